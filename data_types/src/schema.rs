@@ -91,7 +91,8 @@ impl TryFrom<ArrowSchemaRef> for Schema {
     }
 }
 
-const MEASUREMENT_METADATA_KEY: &str = "iox::measurement::name";
+/// The key in an arrow schema's metadata holding the measurement name.
+pub const MEASUREMENT_METADATA_KEY: &str = "iox::measurement::name";
 
 impl Schema {
     /// Create a new Schema wrapper over the schema
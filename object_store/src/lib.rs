@@ -306,6 +306,11 @@ pub enum Error {
         source: azure_sdk_core::errors::AzureError,
     },
 
+    #[snafu(display("Unrecognized file extension: {}", extension))]
+    UnrecognizedFileExtension {
+        extension: String,
+    },
+
     #[snafu(display("Unable to create file {}: {}", path.display(), err))]
     UnableToCreateFile {
         err: io::Error,
@@ -344,6 +349,35 @@ pub enum Error {
     UnableToCopyDataToFile {
         source: io::Error,
     },
+
+    #[snafu(display("Cloud storage location is not valid UTF-8: {}", source))]
+    InvalidCloudLocationEncoding {
+        source: std::str::Utf8Error,
+    },
+
+    #[snafu(display("Invalid percent-encoding in path part {}", encoded))]
+    InvalidPathPartEncoding {
+        encoded: String,
+    },
+
+    #[snafu(display("Path {} is {} bytes long, exceeding the limit of {}", path, len, max))]
+    PathTooLong {
+        path: String,
+        len: usize,
+        max: usize,
+    },
+
+    #[snafu(display(
+        "Path part {} is {} bytes long, exceeding the limit of {}",
+        part,
+        len,
+        max
+    ))]
+    PathPartTooLong {
+        part: String,
+        len: usize,
+        max: usize,
+    },
 }
 
 #[cfg(test)]
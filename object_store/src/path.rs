@@ -1,7 +1,15 @@
 //! This module contains code for abstracting object locations that work
 //! across different backing implementations and platforms.
 
-use std::{mem, path::PathBuf};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    mem,
+    path::PathBuf,
+};
+
+use snafu::ResultExt;
 
 /// Paths that came from or are to be used in cloud-based object storage
 pub mod cloud;
@@ -14,7 +22,7 @@ pub mod parsed;
 use parsed::DirsAndFileName;
 
 mod parts;
-use parts::PathPart;
+pub use parts::{EncodeSet, PathPart};
 
 /// Universal interface for handling paths and locations for objects and
 /// directories in the object store.
@@ -24,7 +32,11 @@ use parts::PathPart;
 ///
 /// Deliberately does not implement `Display` or `ToString`! Use one of the
 /// converters.
-#[derive(Default, Clone, PartialEq, Eq, Debug)]
+///
+/// Equality, ordering and hashing are all based on the parsed directories and
+/// file name of the path, so equivalent paths in different representations
+/// can be used interchangeably as map keys.
+#[derive(Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct ObjectStorePath {
     inner: PathRepresentation,
 }
@@ -41,6 +53,49 @@ impl ObjectStorePath {
         }
     }
 
+    /// As `from_cloud_unchecked`, but for a location received as raw bytes,
+    /// such as a key from an object store listing.
+    ///
+    /// The bytes are not converted lossily, as that could silently change the
+    /// location: an error is returned if they aren't valid UTF-8.
+    pub fn from_cloud_bytes(path: &[u8]) -> crate::Result<Self> {
+        let path = std::str::from_utf8(path).context(crate::InvalidCloudLocationEncoding)?;
+        Ok(Self::from_cloud_unchecked(path))
+    }
+
+    /// As `from_cloud_unchecked`, but for the location of an object, whose last
+    /// part is always treated as a file name rather than only if it has an
+    /// extension. For example, the object `data/2021` has the file name `2021`
+    /// in the directory `data`.
+    ///
+    /// A path ending in `DELIMITER` has no last part, so it is still treated as
+    /// a directory.
+    pub fn from_cloud_with_file_name(path: impl Into<String>) -> Self {
+        let path = path.into();
+        let is_dir = path.ends_with(DELIMITER);
+        let mut parts: DirsAndFileName = PathRepresentation::RawCloud(path).into();
+        if !is_dir && parts.file_name.is_none() {
+            parts.file_name = parts.directories.pop();
+        }
+        parts.into()
+    }
+
+    /// Joins a key returned by an object store, relative to the known prefix
+    /// `base`, onto that prefix. For use when a store's listing returns keys
+    /// relative to the prefix being listed, such as across pages of results.
+    ///
+    /// `relative_key` is treated as already encoded, as with
+    /// `from_cloud_with_file_name`: its parts are separated by `DELIMITER` and
+    /// are not encoded again, and its last part is the file name unless the key
+    /// ends in `DELIMITER`. Leading delimiters are ignored, and any file name
+    /// of `base` is replaced as it is by `push_path`.
+    pub fn extend_from_relative(base: &Self, relative_key: &str) -> Self {
+        let relative = Self::from_cloud_with_file_name(relative_key.trim_start_matches(DELIMITER));
+        let mut path = base.clone();
+        path.push_path(&relative);
+        path
+    }
+
     /// For use when receiving a path from a filesystem directly, not
     /// when building a path. Uses the standard library's path splitting
     /// implementation to separate into parts.
@@ -82,34 +137,148 @@ impl ObjectStorePath {
         self.inner = mem::take(&mut self.inner).push_all_dirs(parts);
     }
 
+    /// As `push_dir`, but returns an error if the resulting path would exceed
+    /// `limits`, in which case the path is unchanged.
+    pub fn try_push_dir(
+        &mut self,
+        part: impl Into<String>,
+        limits: &PathLimits,
+    ) -> crate::Result<()> {
+        self.try_update(limits, |path| path.push_dir(part))
+    }
+
+    /// As `set_file_name`, but returns an error if the resulting path would
+    /// exceed `limits`, in which case the path is unchanged.
+    pub fn try_set_file_name(
+        &mut self,
+        part: impl Into<String>,
+        limits: &PathLimits,
+    ) -> crate::Result<()> {
+        self.try_update(limits, |path| path.set_file_name(part))
+    }
+
+    /// As `push_all_dirs`, but returns an error if the resulting path would
+    /// exceed `limits`, in which case the path is unchanged.
+    pub fn try_push_all_dirs<'a>(
+        &mut self,
+        parts: impl AsRef<[&'a str]>,
+        limits: &PathLimits,
+    ) -> crate::Result<()> {
+        self.try_update(limits, |path| path.push_all_dirs(parts))
+    }
+
     /// Pops a part from the path and returns it, or `None` if it's empty.
     pub fn pop(&mut self) -> Option<&PathPart> {
         unimplemented!()
     }
 
+    /// Returns the path with its last part removed: the file name if there is
+    /// one, otherwise the last directory. Returns `None` if the path is empty.
+    ///
+    /// Unlike `pop`, `self` is not modified.
+    pub fn parent(&self) -> Option<Self> {
+        let mut parts = self.parts().into_owned();
+        if parts.file_name.take().is_none() {
+            parts.directories.pop()?;
+        }
+        Some(parts.into())
+    }
+
     /// Returns true if the directories in `prefix` are the same as the starting
     /// directories of `self`.
+    ///
+    /// A raw `prefix` ending in `DELIMITER` is always treated as a directory,
+    /// so `foo/bar` and `foo/bar/` match the same paths.
     pub fn prefix_matches(&self, prefix: &Self) -> bool {
-        use PathRepresentation::*;
-        match (&self.inner, &prefix.inner) {
-            (Parts(self_parts), Parts(other_parts)) => self_parts.prefix_matches(&other_parts),
-            (Parts(self_parts), _) => {
-                let prefix_parts: DirsAndFileName = prefix.into();
-                self_parts.prefix_matches(&prefix_parts)
-            }
-            (_, Parts(prefix_parts)) => {
-                let self_parts: DirsAndFileName = self.into();
-                self_parts.prefix_matches(&prefix_parts)
-            }
+        self.parts().prefix_matches(&prefix.parts())
+    }
+
+    /// Returns true if `self` and `other` denote the same directory, treating
+    /// any file name as the last directory of its path. For example,
+    /// `foo/bar.json` and `foo/bar.json/` are equal as directories, but not
+    /// according to `PartialEq`, as only the first has a file name.
+    pub fn eq_as_directory(&self, other: &Self) -> bool {
+        let (parts, other_parts) = (self.parts(), other.parts());
+        parts
+            .directories
+            .iter()
+            .chain(parts.file_name.iter())
+            .eq(other_parts
+                .directories
+                .iter()
+                .chain(other_parts.file_name.iter()))
+    }
+
+    /// A storage-independent form of the path, for use as a stable key in
+    /// caches and logs. Encoded parts are always joined with `DELIMITER`, and
+    /// any directory path ends with it, so equivalent paths from different
+    /// object stores have the same canonical form.
+    ///
+    /// Unlike `display`, parts aren't decoded, so different paths never have
+    /// the same canonical form.
+    pub fn canonical(&self) -> String {
+        let parts = self.parts();
+        let mut canonical = String::new();
+        for dir in &parts.directories {
+            canonical.push_str(dir.encoded());
+            canonical.push_str(DELIMITER);
+        }
+        if let Some(file_name) = &parts.file_name {
+            canonical.push_str(file_name.encoded());
+        }
+        canonical
+    }
+
+    /// A human-readable form of the path with all parts decoded, for use in
+    /// logs and error messages.
+    ///
+    /// Paths that came from a filesystem are joined with the platform's path
+    /// separator, and all other paths are joined with `DELIMITER`. The result
+    /// is not suitable for use as a location; use one of the converters for
+    /// that.
+    pub fn display(&self) -> String {
+        let parts = self.parts();
+        let decoded = parts
+            .directories
+            .iter()
+            .chain(parts.file_name.iter())
+            .map(|part| part.to_string());
+
+        match &self.inner {
+            PathRepresentation::RawPathBuf(_) => decoded.collect::<PathBuf>().display().to_string(),
             _ => {
-                let self_parts: DirsAndFileName = self.into();
-                let prefix_parts: DirsAndFileName = prefix.into();
-                self_parts.prefix_matches(&prefix_parts)
+                let mut path = decoded.collect::<Vec<_>>().join(DELIMITER);
+                if parts.file_name.is_none() && !parts.directories.is_empty() {
+                    path.push_str(DELIMITER);
+                }
+                path
             }
         }
     }
 }
 
+impl ObjectStorePath {
+    // Applies `update` to a copy of the path, only replacing the path with
+    // the copy if it is within `limits`.
+    fn try_update(
+        &mut self,
+        limits: &PathLimits,
+        update: impl FnOnce(&mut Self),
+    ) -> crate::Result<()> {
+        let mut path = self.clone();
+        update(&mut path);
+        limits.check(&path)?;
+        *self = path;
+        Ok(())
+    }
+
+    // The parsed directories and file name of this path, only converting if
+    // the path isn't already parsed.
+    fn parts(&self) -> Cow<'_, DirsAndFileName> {
+        self.inner.parts()
+    }
+}
+
 impl From<&'_ DirsAndFileName> for ObjectStorePath {
     fn from(other: &'_ DirsAndFileName) -> Self {
         other.clone().into()
@@ -190,6 +359,36 @@ impl PathRepresentation {
         dirs_and_file_name.file_name = Some((&*part).into());
         Self::Parts(dirs_and_file_name)
     }
+
+    // The parsed directories and file name of this path, only converting if
+    // the path isn't already parsed.
+    fn parts(&self) -> Cow<'_, DirsAndFileName> {
+        match self {
+            Self::Parts(dirs_and_file_name) => Cow::Borrowed(dirs_and_file_name),
+            _ => Cow::Owned(self.to_owned().into()),
+        }
+    }
+}
+
+// Hashing and ordering are based on the parsed parts, consistent with
+// `PartialEq`, so raw cloud and file paths compare equal to the equivalent
+// built path.
+impl Hash for PathRepresentation {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.parts().hash(state)
+    }
+}
+
+impl PartialOrd for PathRepresentation {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PathRepresentation {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.parts().cmp(&other.parts())
+    }
 }
 
 impl PartialEq for PathRepresentation {
@@ -217,6 +416,98 @@ impl PartialEq for PathRepresentation {
 /// The delimiter to separate object namespaces, creating a directory structure.
 pub const DELIMITER: &str = "/";
 
+/// Limits on the length of paths, such as those object stores impose on their
+/// keys. For example, S3 keys can be at most 1024 bytes long.
+///
+/// Lengths are measured in bytes of the encoded form of the path, as given by
+/// `ObjectStorePath::canonical`, as that is what is sent to the object store.
+/// No limits are enforced by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PathLimits {
+    /// The maximum length of the whole path.
+    pub max_len: Option<usize>,
+
+    /// The maximum length of each directory and of the file name.
+    pub max_part_len: Option<usize>,
+}
+
+impl PathLimits {
+    /// Returns an error if `path` or any of its parts is longer than allowed.
+    pub fn check(&self, path: &ObjectStorePath) -> crate::Result<()> {
+        if let Some(max) = self.max_part_len {
+            let parts = path.parts();
+            for part in parts.directories.iter().chain(parts.file_name.iter()) {
+                let len = part.encoded().len();
+                snafu::ensure!(
+                    len <= max,
+                    crate::PathPartTooLong {
+                        part: part.encoded(),
+                        len,
+                        max,
+                    }
+                );
+            }
+        }
+
+        if let Some(max) = self.max_len {
+            let canonical = path.canonical();
+            let len = canonical.len();
+            snafu::ensure!(
+                len <= max,
+                crate::PathTooLong {
+                    path: canonical,
+                    len,
+                    max,
+                }
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The file extensions recognised as denoting the file name part of a path.
+pub const FILE_EXTENSIONS: &[&str] = &["json", "parquet", "segment"];
+
+/// Builds the path of a chunk's persisted table data, of the form
+/// `<partition_key>/<chunk_id>/<table_name>.<extension>`.
+///
+/// All components are encoded, so partition keys and table names may contain
+/// the delimiter. Returns an error if `extension` is not one of
+/// `FILE_EXTENSIONS`.
+pub fn chunk_path(
+    partition_key: &str,
+    chunk_id: u32,
+    table_name: &str,
+    extension: &str,
+) -> crate::Result<ObjectStorePath> {
+    snafu::ensure!(
+        FILE_EXTENSIONS.contains(&extension),
+        crate::UnrecognizedFileExtension { extension }
+    );
+
+    let mut path = ObjectStorePath::default();
+    path.push_dir(partition_key);
+    path.push_dir(chunk_id.to_string());
+    path.set_file_name(format!("{}.{}", table_name, extension));
+    Ok(path)
+}
+
+/// Returns the keys that match `prefix`, as determined by
+/// `ObjectStorePath::prefix_matches`, in their original order.
+///
+/// Object stores that only provide a flat list of keys can use this to
+/// implement listing, so that partial directory and file name prefixes are
+/// matched consistently across implementations.
+pub fn list_under_prefix(
+    keys: &[ObjectStorePath],
+    prefix: &ObjectStorePath,
+) -> Vec<ObjectStorePath> {
+    keys.iter()
+        .filter(|key| key.prefix_matches(prefix))
+        .cloned()
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -349,6 +640,152 @@ mod tests {
         );
     }
 
+    #[test]
+    fn prefix_matches_with_trailing_delimiter() {
+        let objects = [
+            "foo/bar/baz.json",
+            "foo/bar/v1.0/data.segment",
+            "foo/qux/baz.json",
+            "foo/bar.json",
+        ];
+        let matching = |prefix: &str| {
+            let prefix = ObjectStorePath::from_cloud_unchecked(prefix);
+            objects
+                .iter()
+                .filter(|&&o| ObjectStorePath::from_cloud_unchecked(o).prefix_matches(&prefix))
+                .copied()
+                .collect::<Vec<_>>()
+        };
+
+        let expected = vec!["foo/bar/baz.json", "foo/bar/v1.0/data.segment"];
+        assert_eq!(matching("foo/bar"), expected);
+        assert_eq!(matching("foo/bar/"), expected);
+
+        // without the trailing delimiter the last part is parsed as a file name
+        // and matches `foo/bar.json` too.
+        assert_eq!(matching("foo/bar.json/"), Vec::<&str>::new());
+        assert_eq!(matching("foo/bar.json"), vec!["foo/bar.json"]);
+
+        assert_eq!(matching("foo/bar/v1.0/"), vec!["foo/bar/v1.0/data.segment"]);
+    }
+
+    #[test]
+    fn list_under_prefix_filters_keys() {
+        let keys: Vec<_> = [
+            "foo/bar/data.segment",
+            "foo/bar/baz/data.segment",
+            "foo/barn/data.segment",
+            "foo/baz.json",
+            "other/bar/data.segment",
+        ]
+        .iter()
+        .map(|&key| ObjectStorePath::from_cloud_unchecked(key))
+        .collect();
+
+        let matching = |prefix: &str| {
+            let prefix = ObjectStorePath::from_cloud_unchecked(prefix);
+            list_under_prefix(&keys, &prefix)
+                .iter()
+                .map(cloud::CloudConverter::convert)
+                .collect::<Vec<_>>()
+        };
+
+        // a directory prefix also matches directories it partially names.
+        let expected = vec![
+            "foo/bar/data.segment",
+            "foo/bar/baz/data.segment",
+            "foo/barn/data.segment",
+        ];
+        assert_eq!(matching("foo/bar/"), expected);
+        assert_eq!(matching("foo/bar"), expected);
+        assert_eq!(matching("foo/barn"), vec!["foo/barn/data.segment"]);
+        assert_eq!(matching("fo").len(), 4);
+
+        // a partial file name matches only files in the same directory.
+        assert_eq!(matching("foo/baz.j"), vec!["foo/baz.json"]);
+
+        assert!(matching("missing").is_empty());
+        assert_eq!(matching("").len(), keys.len());
+    }
+
+    #[test]
+    fn display_decodes_parts() {
+        let mut path = ObjectStorePath::default();
+        path.push_all_dirs(&["foo/bar", "baz%2Ftest"]);
+        path.set_file_name("my file.json");
+        assert_eq!(path.display(), "foo/bar/baz%2Ftest/my file.json");
+
+        let mut dir = ObjectStorePath::default();
+        dir.push_dir("a#b");
+        assert_eq!(dir.display(), "a#b/");
+
+        let cloud = ObjectStorePath::from_cloud_unchecked("foo%2Fbar/data%23.segment");
+        assert_eq!(cloud.display(), "foo/bar/data#.segment");
+
+        let file = ObjectStorePath::from_path_buf_unchecked("foo/a%b/data.segment");
+        let expected: PathBuf = ["foo", "a%b", "data.segment"].iter().collect();
+        assert_eq!(file.display(), expected.display().to_string());
+    }
+
+    #[test]
+    fn file_and_cloud_conversions_preserve_file_name() {
+        let file_path = ObjectStorePath::from_path_buf_unchecked("foo/bar/data.segment");
+        let cloud = cloud::CloudConverter::convert(&file_path);
+        assert_eq!(cloud, "foo/bar/data.segment");
+
+        let cloud_path = ObjectStorePath::from_cloud_unchecked(cloud);
+        assert_eq!(cloud_path, file_path);
+        assert_eq!(
+            file::FileConverter::convert(&cloud_path),
+            PathBuf::from("foo/bar/data.segment")
+        );
+        let parts: DirsAndFileName = cloud_path.into();
+        assert_eq!(parts.file_name.unwrap().encoded(), "data.segment");
+
+        // A directory containing a dot isn't a file name in either form.
+        let file_path = ObjectStorePath::from_path_buf_unchecked("foo/v1.0");
+        let cloud = cloud::CloudConverter::convert(&file_path);
+        assert_eq!(cloud, "foo/v1.0/");
+
+        let cloud_path = ObjectStorePath::from_cloud_unchecked(cloud);
+        assert_eq!(cloud_path, file_path);
+        assert_eq!(
+            file::FileConverter::convert(&cloud_path),
+            PathBuf::from("foo/v1.0")
+        );
+        let parts: DirsAndFileName = cloud_path.into();
+        assert!(parts.file_name.is_none());
+    }
+
+    #[test]
+    fn canonical_is_storage_independent() {
+        let cases = &[
+            (vec!["foo", "bar", "data.segment"], "foo/bar/data.segment"),
+            (vec!["foo", "v1.0"], "foo/v1.0/"),
+            (vec!["foo%2Fbar", "blah.json"], "foo%2Fbar/blah.json"),
+        ];
+        for (file_parts, cloud) in cases {
+            let file_path =
+                ObjectStorePath::from_path_buf_unchecked(file_parts.iter().collect::<PathBuf>());
+            let cloud_path = ObjectStorePath::from_cloud_unchecked(*cloud);
+            assert_eq!(file_path.canonical(), *cloud);
+            assert_eq!(cloud_path.canonical(), *cloud);
+        }
+
+        // a raw cloud directory without a trailing delimiter is normalised.
+        let cloud_path = ObjectStorePath::from_cloud_unchecked("foo/bar");
+        assert_eq!(cloud_path.canonical(), "foo/bar/");
+
+        // built paths have their parts encoded, unlike `display`.
+        let mut path = ObjectStorePath::default();
+        path.push_dir("foo/bar");
+        path.set_file_name("blah.json");
+        assert_eq!(path.canonical(), "foo%2Fbar/blah.json");
+        assert_eq!(path.display(), "foo/bar/blah.json");
+
+        assert_eq!(ObjectStorePath::default().canonical(), "");
+    }
+
     #[test]
     fn convert_raw_before_partial_eq() {
         // dir and file_name
@@ -380,6 +817,274 @@ mod tests {
         assert_eq!(built, cloud);
     }
 
+    #[test]
+    fn equivalent_paths_as_map_keys() {
+        use std::collections::{BTreeMap, HashMap};
+
+        let cloud = ObjectStorePath::from_cloud_unchecked("foo/bar/blah.json");
+        let mut built = ObjectStorePath::default();
+        built.push_all_dirs(&["foo", "bar"]);
+        built.set_file_name("blah.json");
+
+        let mut hash_map = HashMap::new();
+        hash_map.insert(cloud.clone(), 1);
+        hash_map.insert(built.clone(), 2);
+        assert_eq!(hash_map.len(), 1);
+        assert_eq!(hash_map[&cloud], 2);
+
+        let mut btree_map = BTreeMap::new();
+        btree_map.insert(cloud.clone(), 1);
+        btree_map.insert(built.clone(), 2);
+        assert_eq!(btree_map.len(), 1);
+        assert_eq!(cloud.cmp(&built), Ordering::Equal);
+
+        // Ordering is by parts, regardless of representation.
+        let other = ObjectStorePath::from_cloud_unchecked("foo/baz/blah.json");
+        assert!(built < other);
+        assert!(other > cloud);
+
+        // Paths from the filesystem are keyed by their parts too.
+        let file = ObjectStorePath::from_path_buf_unchecked("foo/bar/blah.json");
+        hash_map.insert(file.clone(), 3);
+        assert_eq!(hash_map.len(), 1);
+        assert_eq!(hash_map[&cloud], 3);
+        btree_map.insert(file.clone(), 3);
+        assert_eq!(btree_map.len(), 1);
+        assert_eq!(file.cmp(&cloud), Ordering::Equal);
+        assert!(file < other);
+    }
+
+    #[test]
+    fn chunk_path_encodes_components() {
+        let path = chunk_path("2021-01-01", 3, "cpu", "parquet").unwrap();
+        assert_eq!(
+            cloud::CloudConverter::convert(&path),
+            "2021-01-01/3/cpu.parquet"
+        );
+
+        let path = chunk_path("region/west", 42, "mem/free", "json").unwrap();
+        assert_eq!(
+            cloud::CloudConverter::convert(&path),
+            "region%2Fwest/42/mem%2Ffree.json"
+        );
+
+        let parts: DirsAndFileName = path.into();
+        assert_eq!(parts.directories.len(), 2);
+        assert_eq!(parts.directories[0].to_string(), "region/west");
+        assert_eq!(parts.file_name.unwrap().to_string(), "mem/free.json");
+
+        // the path is recognised as having a file name when read back.
+        let path = chunk_path("1970-01-01T00", 1, "h2o", "segment").unwrap();
+        let round_trip =
+            ObjectStorePath::from_cloud_unchecked(cloud::CloudConverter::convert(&path));
+        assert_eq!(round_trip, path);
+    }
+
+    #[test]
+    fn cloud_location_from_bytes() {
+        let path = ObjectStorePath::from_cloud_bytes("foo/bär/blah.json".as_bytes()).unwrap();
+        assert_eq!(
+            path,
+            ObjectStorePath::from_cloud_unchecked("foo/bär/blah.json")
+        );
+
+        // invalid UTF-8 is rejected rather than replaced.
+        let result = ObjectStorePath::from_cloud_bytes(b"foo/b\xFFr/blah.json");
+        assert!(matches!(
+            result,
+            Err(crate::Error::InvalidCloudLocationEncoding { .. })
+        ));
+
+        // a truncated multi-byte sequence
+        let result = ObjectStorePath::from_cloud_bytes(&"foo/ä".as_bytes()[..5]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn cloud_file_name_without_extension() {
+        // without the file name being asserted, the last part is a directory.
+        let dir = ObjectStorePath::from_cloud_unchecked("data/2021");
+        let parts: DirsAndFileName = dir.clone().into();
+        assert_eq!(parts.directories.len(), 2);
+        assert!(parts.file_name.is_none());
+
+        let mut path = ObjectStorePath::from_cloud_with_file_name("data/2021");
+        assert_ne!(path, dir);
+        let parts: DirsAndFileName = path.clone().into();
+        assert_eq!(parts.directories, vec![PathPart::from("data")]);
+        assert_eq!(parts.file_name, Some(PathPart::from("2021")));
+
+        // converters preserve the file name.
+        assert_eq!(cloud::CloudConverter::convert(&path), "data/2021");
+        assert_eq!(
+            file::FileConverter::convert(&path),
+            ["data", "2021"].iter().collect::<PathBuf>()
+        );
+        assert_eq!(path.display(), "data/2021");
+
+        // pushing a directory keeps the file name at the end of the path.
+        path.push_dir("archive");
+        assert_eq!(cloud::CloudConverter::convert(&path), "data/archive/2021");
+
+        // file names with extensions and directories are unaffected.
+        assert_eq!(
+            ObjectStorePath::from_cloud_with_file_name("data/blah.json"),
+            ObjectStorePath::from_cloud_unchecked("data/blah.json")
+        );
+        let dir = ObjectStorePath::from_cloud_with_file_name("data/2021/");
+        assert_eq!(dir, ObjectStorePath::from_cloud_unchecked("data/2021/"));
+        assert_eq!(
+            ObjectStorePath::from_cloud_with_file_name(""),
+            ObjectStorePath::default()
+        );
+    }
+
+    #[test]
+    fn extend_from_relative_key() {
+        let mut base = ObjectStorePath::default();
+        base.push_all_dirs(&["my/db", "data"]);
+
+        // the relative key's delimiters separate parts, and its last part is
+        // the file name.
+        let path = ObjectStorePath::extend_from_relative(&base, "2021/01/blah.json");
+        assert_eq!(
+            cloud::CloudConverter::convert(&path),
+            "my%2Fdb/data/2021/01/blah.json"
+        );
+        assert!(path.prefix_matches(&base));
+
+        let path = ObjectStorePath::extend_from_relative(&base, "2021/01");
+        let parts: DirsAndFileName = path.clone().into();
+        assert_eq!(parts.directories.len(), 3);
+        assert_eq!(parts.file_name, Some(PathPart::from("01")));
+
+        // encoded characters in the key aren't encoded again.
+        let path = ObjectStorePath::extend_from_relative(&base, "foo%2Fbar/a%25b.json");
+        assert_eq!(
+            cloud::CloudConverter::convert(&path),
+            "my%2Fdb/data/foo%2Fbar/a%25b.json"
+        );
+        assert_eq!(path.display(), "my/db/data/foo/bar/a%b.json");
+
+        // leading delimiters are ignored, and a trailing delimiter denotes a
+        // directory.
+        let path = ObjectStorePath::extend_from_relative(&base, "/2021/");
+        assert_eq!(cloud::CloudConverter::convert(&path), "my%2Fdb/data/2021/");
+
+        // an empty key is the base itself.
+        assert_eq!(ObjectStorePath::extend_from_relative(&base, ""), base);
+    }
+
+    #[test]
+    fn path_limits() {
+        let limits = PathLimits {
+            max_len: Some(16),
+            max_part_len: Some(5),
+        };
+
+        let mut path = ObjectStorePath::default();
+        path.try_push_all_dirs(&["foo", "bar"], &limits).unwrap();
+        path.try_set_file_name("a.json", &limits).unwrap_err();
+        path.try_set_file_name("b.rs", &limits).unwrap();
+        assert_eq!(path.canonical(), "foo/bar/b.rs");
+
+        // parts are measured once encoded: "a/b" is "a%2Fb".
+        let err = path.try_push_dir("a/b/c", &limits).unwrap_err();
+        assert!(
+            matches!(err, crate::Error::PathPartTooLong { len: 9, max: 5, .. }),
+            "{}",
+            err
+        );
+        assert_eq!(path.canonical(), "foo/bar/b.rs");
+
+        // "foo/bar/baz/b.rs" is 16 bytes, but another directory is too many.
+        path.try_push_dir("baz", &limits).unwrap();
+        let err = path.try_push_dir("qux", &limits).unwrap_err();
+        assert!(
+            matches!(err, crate::Error::PathTooLong { len: 20, max: 16, .. }),
+            "{}",
+            err
+        );
+        assert_eq!(path.canonical(), "foo/bar/baz/b.rs");
+
+        // without limits any path can be built.
+        let mut path = ObjectStorePath::default();
+        path.try_push_dir("a".repeat(2000), &PathLimits::default())
+            .unwrap();
+        assert!(PathLimits::default().check(&path).is_ok());
+        assert!(limits.check(&path).is_err());
+    }
+
+    #[test]
+    fn eq_as_directory() {
+        let cases = &[
+            ("foo/bar.json", "foo/bar.json/"),
+            ("foo/bar/", "foo/bar"),
+            ("", ""),
+        ];
+        for (a, b) in cases {
+            let a = ObjectStorePath::from_cloud_unchecked(*a);
+            let b = ObjectStorePath::from_cloud_unchecked(*b);
+            assert!(a.eq_as_directory(&b), "{:?} should equal {:?}", a, b);
+            assert!(b.eq_as_directory(&a), "{:?} should equal {:?}", b, a);
+        }
+
+        // strict equality distinguishes file names from directories.
+        let file = ObjectStorePath::from_cloud_unchecked("foo/bar.json");
+        let dir = ObjectStorePath::from_cloud_unchecked("foo/bar.json/");
+        assert_ne!(file, dir);
+
+        let file = ObjectStorePath::from_cloud_with_file_name("foo/bar");
+        let mut dir = ObjectStorePath::default();
+        dir.push_all_dirs(&["foo", "bar"]);
+        assert_ne!(file, dir);
+        assert!(file.eq_as_directory(&dir));
+
+        // paths with different parts are never equal.
+        let cases = &[
+            ("foo/bar/", "foo/baz/"),
+            ("foo/", "foo/bar/"),
+            ("foo/bar.json", "foo/"),
+            ("foo%2Fbar/", "foo/bar/"),
+        ];
+        for (a, b) in cases {
+            let a = ObjectStorePath::from_cloud_unchecked(*a);
+            let b = ObjectStorePath::from_cloud_unchecked(*b);
+            assert!(!a.eq_as_directory(&b), "{:?} shouldn't equal {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn parent() {
+        // the parent of a file is its directory.
+        let path = ObjectStorePath::from_cloud_unchecked("foo/bar/blah.json");
+        let mut expected = ObjectStorePath::default();
+        expected.push_all_dirs(&["foo", "bar"]);
+        assert_eq!(path.parent(), Some(expected.clone()));
+
+        // the parent of a directory is the directory containing it.
+        let parent = expected.parent().unwrap();
+        assert_eq!(parent.display(), "foo/");
+
+        // a top-level directory's parent is the empty path, which has no parent.
+        let parent = parent.parent().unwrap();
+        assert_eq!(parent, ObjectStorePath::default());
+        assert!(parent.parent().is_none());
+
+        // a top-level file's parent is also the empty path.
+        let path = ObjectStorePath::from_cloud_unchecked("blah.json");
+        assert_eq!(path.parent(), Some(ObjectStorePath::default()));
+    }
+
+    #[test]
+    fn chunk_path_unrecognized_extension() {
+        let err = chunk_path("2021-01-01", 3, "cpu", "txt").unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::UnrecognizedFileExtension { extension } if extension == "txt"
+        ));
+    }
+
     #[test]
     fn path_rep_conversions() {
         // dir and file name
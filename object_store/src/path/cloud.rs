@@ -1,38 +1,122 @@
-use super::{ObjectStorePath, PathPart, PathRepresentation, DELIMITER};
+use super::{DirsAndFileName, ObjectStorePath, PathPart, PathRepresentation, DELIMITER};
 
 use itertools::Itertools;
 
 /// Converts `ObjectStorePath`s to `String`s that are appropriate for use as
 /// locations in cloud storage.
-#[derive(Debug, Clone, Copy)]
-pub struct CloudConverter {}
+///
+/// The associated `convert` function always separates parts with `DELIMITER`.
+/// A converter instance can be configured with a different delimiter for
+/// locations that use another separator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CloudConverter {
+    delimiter: char,
+}
+
+impl Default for CloudConverter {
+    fn default() -> Self {
+        Self {
+            delimiter: DELIMITER.as_bytes()[0] as char,
+        }
+    }
+}
 
 impl CloudConverter {
+    /// Creates a converter that separates the parts of cloud storage locations
+    /// with `delimiter` rather than `DELIMITER`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delimiter` is alphanumeric or `%`, as those characters are
+    /// used when encoding parts.
+    pub fn with_delimiter(delimiter: char) -> Self {
+        assert!(
+            !delimiter.is_alphanumeric() && delimiter != '%',
+            "invalid cloud path delimiter {:?}",
+            delimiter
+        );
+        Self { delimiter }
+    }
+
+    /// Creates a cloud storage location by joining this `ObjectStorePath`'s
+    /// parts with the converter's delimiter. Any occurrences of the delimiter
+    /// within a part are encoded.
+    pub fn location(&self, object_store_path: &ObjectStorePath) -> String {
+        let location = Self::convert(object_store_path);
+        if self.is_default() {
+            return location;
+        }
+
+        let escaped = self.escaped_delimiter();
+        location
+            .split(DELIMITER)
+            .map(|part| part.replace(self.delimiter, &escaped))
+            .join(&self.delimiter.to_string())
+    }
+
+    /// Parses a cloud storage location whose parts are separated by the
+    /// converter's delimiter, decoding any encoded occurrences of the delimiter
+    /// within a part.
+    pub fn parse(&self, location: &str) -> ObjectStorePath {
+        if self.is_default() {
+            return ObjectStorePath::from_cloud_unchecked(location);
+        }
+
+        let escaped = self.escaped_delimiter();
+        let location = location
+            .split(self.delimiter)
+            .map(|part| {
+                part.replace(DELIMITER, "%2F")
+                    .replace(&escaped, &self.delimiter.to_string())
+            })
+            .join(DELIMITER);
+        ObjectStorePath::from_cloud_unchecked(location)
+    }
+
+    fn is_default(&self) -> bool {
+        self.delimiter.to_string() == DELIMITER
+    }
+
+    // The percent-encoded form of the delimiter.
+    fn escaped_delimiter(&self) -> String {
+        let mut buf = [0; 4];
+        self.delimiter
+            .encode_utf8(&mut buf)
+            .bytes()
+            .map(|b| format!("%{:02X}", b))
+            .collect()
+    }
+
     /// Creates a cloud storage location by joining this `ObjectStorePath`'s
     /// parts with `DELIMITER`
+    ///
+    /// Filesystem paths are parsed first, so whether the last part is a file
+    /// name or a directory is decided by the filesystem rules and preserved.
     pub fn convert(object_store_path: &ObjectStorePath) -> String {
         match &object_store_path.inner {
             PathRepresentation::RawCloud(path) => path.to_owned(),
-            PathRepresentation::RawPathBuf(_path) => {
-                todo!("convert");
-            }
+            PathRepresentation::RawPathBuf(_) => Self::convert_parts(&object_store_path.into()),
             PathRepresentation::Parts(dirs_and_file_name) => {
-                let mut path = dirs_and_file_name
-                    .directories
-                    .iter()
-                    .map(PathPart::encoded)
-                    .join(DELIMITER);
-
-                if !path.is_empty() {
-                    path.push_str(DELIMITER);
-                }
-                if let Some(file_name) = &dirs_and_file_name.file_name {
-                    path.push_str(file_name.encoded());
-                }
-                path
+                Self::convert_parts(dirs_and_file_name)
             }
         }
     }
+
+    fn convert_parts(dirs_and_file_name: &DirsAndFileName) -> String {
+        let mut path = dirs_and_file_name
+            .directories
+            .iter()
+            .map(PathPart::encoded)
+            .join(DELIMITER);
+
+        if !path.is_empty() {
+            path.push_str(DELIMITER);
+        }
+        if let Some(file_name) = &dirs_and_file_name.file_name {
+            path.push_str(file_name.encoded());
+        }
+        path
+    }
 }
 
 #[cfg(test)]
@@ -73,6 +157,56 @@ mod tests {
         assert_eq!(converted, "foo%2Fbar/baz%252Ftest/");
     }
 
+    #[test]
+    fn custom_delimiter_location() {
+        let converter = CloudConverter::with_delimiter(':');
+
+        let mut location = ObjectStorePath::default();
+        location.push_all_dirs(&["foo:bar", "baz/test"]);
+        location.set_file_name("data.segment");
+        assert_eq!(
+            converter.location(&location),
+            "foo%3Abar:baz%2Ftest:data.segment"
+        );
+
+        let mut location = ObjectStorePath::default();
+        location.push_dir("test");
+        assert_eq!(converter.location(&location), "test:");
+    }
+
+    #[test]
+    fn custom_delimiter_parse() {
+        let converter = CloudConverter::with_delimiter(':');
+
+        let mut expected = ObjectStorePath::default();
+        expected.push_all_dirs(&["foo:bar", "baz/test"]);
+        expected.set_file_name("data.segment");
+
+        let parsed = converter.parse("foo%3Abar:baz/test:data.segment");
+        assert_eq!(parsed, expected);
+        assert_eq!(
+            CloudConverter::convert(&parsed),
+            "foo:bar/baz%2Ftest/data.segment"
+        );
+
+        // locations round trip through the converter
+        assert_eq!(converter.parse(&converter.location(&expected)), expected);
+
+        let mut dir = ObjectStorePath::default();
+        dir.push_dir("v1.0");
+        assert_eq!(converter.parse("v1.0:"), dir);
+    }
+
+    #[test]
+    fn default_delimiter_is_unchanged() {
+        let converter = CloudConverter::default();
+
+        let mut location = ObjectStorePath::default();
+        location.push_all_dirs(&["foo/bar", "baz"]);
+        assert_eq!(converter.location(&location), "foo%2Fbar/baz/");
+        assert_eq!(converter.parse("foo%2Fbar/baz/"), location);
+    }
+
     #[test]
     fn push_all_encodes() {
         let mut location = ObjectStorePath::default();
@@ -81,4 +215,24 @@ mod tests {
         let converted = CloudConverter::convert(&location);
         assert_eq!(converted, "foo%2Fbar/baz%252Ftest/");
     }
+
+    #[test]
+    fn build_on_cloud_location() {
+        // Paths received from cloud storage can be extended with the same
+        // methods used to build any other path.
+        let mut location = ObjectStorePath::from_cloud_unchecked("mydb/wal/");
+        location.push_all_dirs(&["000", "001"]);
+        location.set_file_name("002.segment");
+
+        assert_eq!(
+            CloudConverter::convert(&location),
+            "mydb/wal/000/001/002.segment"
+        );
+        assert_eq!(location.display(), "mydb/wal/000/001/002.segment");
+
+        let mut expected = ObjectStorePath::default();
+        expected.push_all_dirs(&["mydb", "wal", "000", "001"]);
+        expected.set_file_name("002.segment");
+        assert_eq!(location, expected);
+    }
 }
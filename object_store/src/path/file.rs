@@ -1,33 +1,38 @@
-use super::{ObjectStorePath, PathPart, PathRepresentation};
+use super::{DirsAndFileName, ObjectStorePath, PathPart, PathRepresentation};
 
 use std::path::PathBuf;
 
 /// Converts `ObjectStorePath`s to `String`s that are appropriate for use as
 /// locations in filesystem storage.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FileConverter {}
 
 impl FileConverter {
     /// Creates a filesystem `PathBuf` location by using the standard library's
     /// `PathBuf` building implementation appropriate for the current
     /// platform.
+    ///
+    /// Cloud storage locations are parsed first, so whether the last part is a
+    /// file name or a directory is decided by the cloud rules and preserved.
     pub fn convert(object_store_path: &ObjectStorePath) -> PathBuf {
         match &object_store_path.inner {
-            PathRepresentation::RawCloud(_path) => {
-                todo!("convert");
-            }
+            PathRepresentation::RawCloud(_) => Self::convert_parts(&object_store_path.into()),
             PathRepresentation::RawPathBuf(path) => path.to_owned(),
             PathRepresentation::Parts(dirs_and_file_name) => {
-                let mut path: PathBuf = dirs_and_file_name
-                    .directories
-                    .iter()
-                    .map(PathPart::encoded)
-                    .collect();
-                if let Some(file_name) = &dirs_and_file_name.file_name {
-                    path.push(file_name.encoded());
-                }
-                path
+                Self::convert_parts(dirs_and_file_name)
             }
         }
     }
+
+    fn convert_parts(dirs_and_file_name: &DirsAndFileName) -> PathBuf {
+        let mut path: PathBuf = dirs_and_file_name
+            .directories
+            .iter()
+            .map(PathPart::encoded)
+            .collect();
+        if let Some(file_name) = &dirs_and_file_name.file_name {
+            path.push(file_name.encoded());
+        }
+        path
+    }
 }
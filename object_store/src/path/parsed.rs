@@ -1,6 +1,6 @@
-use super::{ObjectStorePath, PathPart, PathRepresentation, DELIMITER};
+use super::{ObjectStorePath, PathPart, PathRepresentation, DELIMITER, FILE_EXTENSIONS};
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Hash)]
 pub(crate) struct DirsAndFileName {
     pub(crate) directories: Vec<PathPart>,
     pub(crate) file_name: Option<PathPart>,
@@ -105,12 +105,14 @@ impl From<PathRepresentation> for DirsAndFileName {
     fn from(path_rep: PathRepresentation) -> Self {
         match path_rep {
             PathRepresentation::RawCloud(path) => {
+                // A path ending in the delimiter is a directory.
+                let is_dir = path.ends_with(DELIMITER);
                 let mut parts: Vec<PathPart> = path
                     .split_terminator(DELIMITER)
                     .map(|s| PathPart(s.to_string()))
                     .collect();
                 let maybe_file_name = match parts.pop() {
-                    Some(file) if file.encoded().contains('.') => Some(file),
+                    Some(file) if !is_dir && file.encoded().contains('.') => Some(file),
                     Some(dir) => {
                         parts.push(dir);
                         None
@@ -123,6 +125,8 @@ impl From<PathRepresentation> for DirsAndFileName {
                 }
             }
             PathRepresentation::RawPathBuf(path) => {
+                // A path ending in a separator is a directory.
+                let is_dir = path.to_string_lossy().ends_with(std::path::is_separator);
                 let mut parts: Vec<PathPart> = path
                     .iter()
                     .flat_map(|s| s.to_os_string().into_string().map(PathPart))
@@ -130,10 +134,11 @@ impl From<PathRepresentation> for DirsAndFileName {
 
                 let maybe_file_name = match parts.pop() {
                     Some(file)
-                        if !file.encoded().starts_with('.')
-                            && (file.encoded().ends_with(".json")
-                                || file.encoded().ends_with(".parquet")
-                                || file.encoded().ends_with(".segment")) =>
+                        if !is_dir
+                            && !file.encoded().starts_with('.')
+                            && FILE_EXTENSIONS
+                                .iter()
+                                .any(|ext| file.encoded().ends_with(&format!(".{}", ext))) =>
                     {
                         Some(file)
                     }
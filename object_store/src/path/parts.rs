@@ -1,6 +1,9 @@
+use std::collections::BTreeSet;
+
 use percent_encoding::{percent_decode_str, percent_encode, AsciiSet, CONTROLS};
 
 use super::DELIMITER;
+use crate::{Error, Result};
 
 // percent_encode's API needs this as a byte
 const DELIMITER_BYTE: u8 = DELIMITER.as_bytes()[0];
@@ -9,8 +12,14 @@ const DELIMITER_BYTE: u8 = DELIMITER.as_bytes()[0];
 /// of a path.
 ///
 /// A PathPart instance is guaranteed to contain no `/` characters as it can
-/// only be constructed by going through the `try_from` impl.
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+/// only be constructed by going through the `from` impl, which percent-encodes
+/// its input, or `from_encoded`, which rejects input containing a `/`.
+///
+/// The inner string is always the *encoded* form. Converting with `from` is
+/// therefore not idempotent: a value that already contains `%` is encoded
+/// again (`%2F` becomes `%252F`). Values read back from an object store are
+/// already encoded and should be wrapped with `from_encoded` instead.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Hash)]
 pub struct PathPart(pub(super) String);
 
 /// Characters we want to encode.
@@ -43,29 +52,156 @@ const INVALID: &AsciiSet = &CONTROLS
 
 impl From<&str> for PathPart {
     fn from(v: &str) -> Self {
-        match v {
-            // We don't want to encode `.` generally, but we do want to disallow parts of paths
-            // to be equal to `.` or `..` to prevent file system traversal shenanigans.
-            "." => Self(String::from("%2E")),
-            ".." => Self(String::from("%2E%2E")),
-            other => Self(percent_encode(other.as_bytes(), INVALID).to_string()),
-        }
+        encode(v, INVALID)
+    }
+}
+
+fn encode(v: &str, invalid: &AsciiSet) -> PathPart {
+    match v {
+        // We don't want to encode `.` generally, but we do want to disallow parts of paths
+        // to be equal to `.` or `..` to prevent file system traversal shenanigans.
+        "." => PathPart(String::from("%2E")),
+        ".." => PathPart(String::from("%2E%2E")),
+        other => PathPart(percent_encode(other.as_bytes(), invalid).to_string()),
     }
 }
 
+/// The set of characters that are percent-encoded when creating a `PathPart`
+/// from an unencoded string.
+///
+/// By default this contains the delimiter, control characters, and the
+/// characters that the supported object stores recommend avoiding. Non-ASCII
+/// characters are always encoded. For stores with stricter naming rules the
+/// set can be extended, e.g. to also encode spaces, and parts created with
+/// `encode` then added to a path with `push_part_as_dir`.
+///
+/// Decoding doesn't depend on the set used for encoding: every
+/// percent-encoded character is decoded, and `%` itself is always encoded.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct EncodeSet {
+    extra: BTreeSet<u8>,
+}
+
+impl EncodeSet {
+    /// The default set of encoded characters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also encode `c`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `c` isn't an ASCII character, as those are always encoded.
+    pub fn with_char(mut self, c: char) -> Self {
+        assert!(c.is_ascii(), "only ASCII characters can be added: {:?}", c);
+        self.extra.insert(c as u8);
+        self
+    }
+
+    /// Returns true if `c` is encoded.
+    pub fn contains(&self, c: char) -> bool {
+        let mut buf = [0; 4];
+        let s = c.encode_utf8(&mut buf);
+        percent_encode(s.as_bytes(), &self.ascii_set()).to_string() != *s
+    }
+
+    /// The ASCII characters that are encoded, in ascending order.
+    pub fn chars(&self) -> Vec<char> {
+        (0..128_u8)
+            .map(char::from)
+            .filter(|&c| self.contains(c))
+            .collect()
+    }
+
+    /// Creates a `PathPart` from `v`, encoding the characters in this set.
+    pub fn encode(&self, v: &str) -> PathPart {
+        encode(v, &self.ascii_set())
+    }
+
+    fn ascii_set(&self) -> AsciiSet {
+        self.extra
+            .iter()
+            .fold(INVALID.add(DELIMITER_BYTE), |set, &b| set.add(b))
+    }
+}
+
+/// Displays the decoded part. If the part's encoding is invalid, see
+/// `PathPart::decoded`, each invalid escape sequence or byte is displayed as
+/// the replacement character `U+FFFD`.
 impl std::fmt::Display for PathPart {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        percent_decode_str(&self.0)
-            .decode_utf8()
-            .expect("Valid UTF-8 that came from String")
-            .fmt(f)
+        match self.decoded() {
+            Ok(decoded) => decoded.fmt(f),
+            Err(_) => {
+                // Invalid escapes are replaced with the encoded replacement
+                // character so they are flagged rather than kept verbatim.
+                let mut encoded = String::with_capacity(self.0.len());
+                for (i, c) in self.0.char_indices() {
+                    if c == '%' && !is_valid_escape(&self.0.as_bytes()[i..]) {
+                        encoded.push_str("%EF%BF%BD");
+                    } else {
+                        encoded.push(c);
+                    }
+                }
+                percent_decode_str(&encoded).decode_utf8_lossy().fmt(f)
+            }
+        }
     }
 }
 
+// Returns true if `escape` starts with a `%` followed by two hex digits.
+fn is_valid_escape(escape: &[u8]) -> bool {
+    escape.len() >= 3 && escape[1].is_ascii_hexdigit() && escape[2].is_ascii_hexdigit()
+}
+
 impl PathPart {
+    /// Creates a PathPart from a string that is already percent-encoded, such
+    /// as a component of a key listed from an object store, without encoding
+    /// it again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `encoded` contains the delimiter, as that can never be the
+    /// output of encoding a single part.
+    pub fn from_encoded(encoded: &str) -> Self {
+        assert!(
+            !encoded.contains(DELIMITER),
+            "encoded path part must not contain the delimiter: {}",
+            encoded
+        );
+        Self(encoded.to_string())
+    }
+
     pub fn encoded(&self) -> &str {
         &self.0
     }
+
+    /// Returns the decoded part.
+    ///
+    /// Parts created from unencoded strings always decode successfully, but
+    /// parts created with `from_encoded` may be invalid. An error is returned
+    /// if the part contains a `%` that isn't followed by two hex digits, or if
+    /// the decoded bytes aren't valid UTF-8.
+    pub fn decoded(&self) -> Result<String> {
+        let invalid = || Error::InvalidPathPartEncoding {
+            encoded: self.0.clone(),
+        };
+
+        let bytes = self.0.as_bytes();
+        let valid_escapes = bytes
+            .iter()
+            .enumerate()
+            .all(|(i, &b)| b != b'%' || is_valid_escape(&bytes[i..]));
+        if !valid_escapes {
+            return Err(invalid());
+        }
+
+        percent_decode_str(&self.0)
+            .decode_utf8()
+            .map(|decoded| decoded.into_owned())
+            .map_err(|_| invalid())
+    }
 }
 
 #[cfg(test)]
@@ -91,6 +227,82 @@ mod tests {
         assert_eq!(part.to_string(), "foo%2Fbar");
     }
 
+    #[test]
+    fn path_part_from_encoded_is_not_reencoded() {
+        let encoded: PathPart = "foo%2Fbar".into();
+        assert_eq!(encoded.encoded(), "foo%252Fbar");
+
+        let part = PathPart::from_encoded("foo%2Fbar");
+        assert_eq!(part.encoded(), "foo%2Fbar");
+        assert_eq!(part.to_string(), "foo/bar");
+
+        // round-tripping the encoded form of a part yields the same part
+        let original: PathPart = "baz%2Ftest".into();
+        assert_eq!(PathPart::from_encoded(original.encoded()), original);
+        assert_eq!(PathPart::from(original.to_string().as_str()), original);
+    }
+
+    #[test]
+    fn path_part_with_invalid_encoding() {
+        let part = PathPart::from_encoded("foo%2Fbar");
+        assert_eq!(part.decoded().unwrap(), "foo/bar");
+
+        // a truncated escape sequence
+        let part = PathPart::from_encoded("foo%A");
+        assert!(matches!(
+            part.decoded(),
+            Err(Error::InvalidPathPartEncoding { encoded }) if encoded == "foo%A"
+        ));
+        assert_eq!(part.to_string(), "foo\u{FFFD}A");
+
+        let part = PathPart::from_encoded("%zzfoo%");
+        assert!(part.decoded().is_err());
+        assert_eq!(part.to_string(), "\u{FFFD}zzfoo\u{FFFD}");
+
+        // an escape sequence that doesn't decode to UTF-8
+        let part = PathPart::from_encoded("foo%FF");
+        assert!(part.decoded().is_err());
+        assert_eq!(part.to_string(), "foo\u{FFFD}");
+    }
+
+    #[test]
+    #[should_panic(expected = "must not contain the delimiter")]
+    fn path_part_from_encoded_rejects_delimiter() {
+        PathPart::from_encoded("foo/bar");
+    }
+
+    #[test]
+    fn encode_set_can_be_extended() {
+        let default = EncodeSet::new();
+        assert!(default.contains('/'));
+        assert!(default.contains('%'));
+        assert!(!default.contains(' '));
+        assert!(!default.contains('a'));
+        assert!(default.chars().contains(&'#'));
+
+        let part = default.encode("my file+1");
+        assert_eq!(part, PathPart::from("my file+1"));
+        assert_eq!(part.encoded(), "my file+1");
+
+        let extended = EncodeSet::new().with_char(' ').with_char('+');
+        assert!(extended.contains(' '));
+        assert_eq!(extended.chars().len(), default.chars().len() + 2);
+
+        let part = extended.encode("my file+1/%");
+        assert_eq!(part.encoded(), "my%20file%2B1%2F%25");
+        assert_eq!(part.to_string(), "my file+1/%");
+
+        // parts are decoded the same way regardless of the set used.
+        assert_eq!(PathPart::from_encoded(part.encoded()), part);
+        assert_eq!(extended.encode(".."), PathPart::from(".."));
+    }
+
+    #[test]
+    #[should_panic(expected = "only ASCII characters can be added")]
+    fn encode_set_rejects_non_ascii() {
+        EncodeSet::new().with_char('é');
+    }
+
     #[test]
     fn path_part_cant_be_one_dot() {
         let part: PathPart = ".".into();
@@ -27,7 +27,7 @@ fn read_group(c: &mut Criterion) {
 fn read_group_predicate_all_time(c: &mut Criterion, row_group: &RowGroup, rng: &mut ThreadRng) {
     // This benchmark fixes the number of rows in the `RowGroup` (500K), and
     // varies the cardinality of the group keys.
-    let time_pred = Predicate::with_time_range(&[], i64::MIN, i64::MAX);
+    let time_pred = all_time_predicate(row_group);
     benchmark_read_group_vary_cardinality(
         c,
         "row_group_read_group_all_time_vary_cardinality",
@@ -68,7 +68,7 @@ fn read_group_predicate_all_time(c: &mut Criterion, row_group: &RowGroup, rng: &
         c,
         "row_group_read_group_all_time_vary_rows",
         &[250_000, 500_000, 750_000, 1_000_000], //  `RowGroup` row sizes to vary
-        &time_pred,
+        &all_time_predicate,
         (vec!["node_id", "pod_id"], 20000),
         rng,
     );
@@ -119,12 +119,58 @@ fn read_group_pre_computed_groups(c: &mut Criterion, row_group: &RowGroup, rng:
         c,
         "row_group_read_group_pre_computed_groups_vary_rows",
         &[250_000, 500_000, 750_000, 1_000_000], // `RowGroup` row sizes to vary
-        &Predicate::default(),
+        &|_| Predicate::default(),
         (vec!["data_centre", "cluster"], 200),
         rng,
     );
 }
 
+// These benchmarks compare the single pass implementation of
+// read_window_aggregate, used when there are no predicates or group columns,
+// with the general implementation, which is forced by applying a time
+// predicate that covers the `RowGroup`.
+fn read_window_aggregate(c: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+
+    let rows = 1_000_000;
+    let row_group = generate_single_series_row_group(rows, &mut rng);
+    let aggregates = [
+        ("counter", AggregateType::Count),
+        ("counter", AggregateType::Sum),
+    ];
+    let window = 60 * 60 * 1000 * ONE_MS; // one hour
+
+    let mut group = c.benchmark_group("row_group_read_window_aggregate");
+    group.throughput(Throughput::Elements(rows as u64));
+
+    let predicates = vec![
+        ("no_predicate", Predicate::default()),
+        ("all_time", all_time_predicate(&row_group)),
+    ];
+    for (name, predicate) in &predicates {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{}_rows_{:?}", name, rows)),
+            predicate,
+            |b, predicate| {
+                b.iter(|| {
+                    let result =
+                        row_group.read_window_aggregate(predicate, &[], &aggregates, window);
+                    assert!(!result.is_empty());
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+// A time predicate covering every row in the `RowGroup`. Its bounds are
+// finite, because unbounded ends add no expression to the predicate, so it
+// avoids the optimised no predicate implementations.
+fn all_time_predicate(row_group: &RowGroup) -> Predicate {
+    let (min, max) = row_group.time_range();
+    Predicate::with_time_range(&[], min, max + 1)
+}
+
 // This benchmarks the impact that the cardinality of group keys has on the
 // performance of read_group.
 fn benchmark_read_group_vary_cardinality(
@@ -171,7 +217,7 @@ fn benchmark_read_group_vary_rows(
     c: &mut Criterion,
     benchmark_group_name: &str,
     row_sizes: &[usize],
-    predicate: &Predicate,
+    predicate: &dyn Fn(&RowGroup) -> Predicate,
     group_columns: (Vec<&str>, usize),
     rng: &mut ThreadRng,
 ) {
@@ -179,6 +225,7 @@ fn benchmark_read_group_vary_rows(
 
     for num_rows in row_sizes {
         let row_group = generate_row_group(*num_rows, rng);
+        let predicate = &predicate(&row_group);
 
         // benchmark measures the throughput of group creation.
         group.throughput(Throughput::Elements(group_columns.1 as u64));
@@ -340,6 +387,33 @@ fn generate_row_group(rows: usize, rng: &mut ThreadRng) -> RowGroup {
     RowGroup::new(rows as u32, columns)
 }
 
+// Generates a `RowGroup` containing a single series with a point every ten
+// seconds.
+fn generate_single_series_row_group(rows: usize, rng: &mut ThreadRng) -> RowGroup {
+    let start = 1351700038292387000_i64;
+    let times = (0..rows as i64)
+        .map(|i| start + i * 10_000 * ONE_MS)
+        .collect::<Vec<_>>();
+    let counters = (0..rows)
+        .map(|_| rng.gen_range(0, 10_000))
+        .collect::<Vec<i64>>();
+
+    let columns = vec![
+        (
+            "counter".to_string(),
+            ColumnType::Field(Column::from(counters.as_slice())),
+        ),
+        (
+            "time".to_string(),
+            ColumnType::Time(Column::from(times.as_slice())),
+        ),
+    ]
+    .into_iter()
+    .collect::<BTreeMap<_, _>>();
+
+    RowGroup::new(rows as u32, columns)
+}
+
 fn generate_trace_for_row_group(
     spans_per_trace: usize,
     timestamp: i64,
@@ -453,5 +527,5 @@ fn generate_trace_for_row_group(
     column_packers
 }
 
-criterion_group!(benches, read_group);
+criterion_group!(benches, read_group, read_window_aggregate);
 criterion_main!(benches);
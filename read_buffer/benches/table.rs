@@ -0,0 +1,154 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use read_buffer::benchmarks::{Column, ColumnType, RowGroup, Table};
+use read_buffer::{ColumnSelection, Predicate};
+
+const ROW_GROUPS: [usize; 3] = [1, 10, 100];
+const ROWS_PER_ROW_GROUP: usize = 1_000;
+
+// An allocator that counts the allocations made through it, so the benchmarks
+// can report how much each implementation allocates as well as timing it.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Returns the number of allocations, and the total bytes allocated, by `f`.
+fn count_allocations(f: impl FnOnce()) -> (usize, usize) {
+    let (allocations, bytes) = (
+        ALLOCATIONS.load(Ordering::Relaxed),
+        ALLOCATED_BYTES.load(Ordering::Relaxed),
+    );
+    f();
+    (
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes,
+    )
+}
+
+// These benchmarks compare running the same query repeatedly with `select`,
+// which allocates a new vector of results for each query, and `select_into`,
+// which reuses one vector that is cleared between queries. The allocations
+// made by a single query with each are reported before they are timed.
+fn select(c: &mut Criterion) {
+    let mut group = c.benchmark_group("table_select");
+
+    for &row_groups in &ROW_GROUPS {
+        let table = generate_table(row_groups);
+        let columns = ColumnSelection::Some(&["region", "counter", "time"]);
+        let predicate = Predicate::default();
+
+        let select_allocations = count_allocations(|| {
+            table.select(&columns, &[], &predicate).unwrap();
+        });
+        // The vector has already held the results of a query, as it would
+        // when it is reused.
+        let mut batches = table.select(&columns, &[], &predicate).unwrap();
+        let select_into_allocations = count_allocations(|| {
+            batches.clear();
+            table
+                .select_into(&columns, &[], &predicate, &mut batches)
+                .unwrap();
+        });
+        for (name, (allocations, bytes)) in &[
+            ("select", select_allocations),
+            ("select_into", select_into_allocations),
+        ] {
+            println!(
+                "table_select/{}/{}: {} allocations, {} bytes allocated per query",
+                name, row_groups, allocations, bytes
+            );
+        }
+        assert!(select_into_allocations.0 < select_allocations.0);
+
+        group.throughput(Throughput::Elements(
+            (row_groups * ROWS_PER_ROW_GROUP) as u64,
+        ));
+
+        group.bench_with_input(
+            BenchmarkId::new("select", row_groups),
+            &row_groups,
+            |b, _| {
+                b.iter(|| {
+                    let batches = table.select(&columns, &[], &predicate).unwrap();
+                    assert_eq!(batches.len(), row_groups);
+                });
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("select_into", row_groups),
+            &row_groups,
+            |b, _| {
+                let mut batches = Vec::with_capacity(row_groups);
+                b.iter(|| {
+                    batches.clear();
+                    table
+                        .select_into(&columns, &[], &predicate, &mut batches)
+                        .unwrap();
+                    assert_eq!(batches.len(), row_groups);
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+// Generates a table with the provided number of row groups, each containing
+// `ROWS_PER_ROW_GROUP` rows.
+fn generate_table(row_groups: usize) -> Table {
+    let mut table: Option<Table> = None;
+    for i in 0..row_groups {
+        let offset = (i * ROWS_PER_ROW_GROUP) as i64;
+        let times = (offset..offset + ROWS_PER_ROW_GROUP as i64).collect::<Vec<_>>();
+        let counters = times.iter().map(|t| t % 100).collect::<Vec<_>>();
+        let regions = times
+            .iter()
+            .map(|t| if t % 2 == 0 { "west" } else { "east" })
+            .collect::<Vec<_>>();
+
+        let mut columns = BTreeMap::new();
+        columns.insert(
+            "time".to_string(),
+            ColumnType::Time(Column::from(times.as_slice())),
+        );
+        columns.insert(
+            "counter".to_string(),
+            ColumnType::Field(Column::from(counters.as_slice())),
+        );
+        columns.insert(
+            "region".to_string(),
+            ColumnType::Tag(Column::from(regions.as_slice())),
+        );
+        let row_group = RowGroup::new(ROWS_PER_ROW_GROUP as u32, columns);
+
+        match &mut table {
+            Some(table) => table.add_row_group(row_group).unwrap(),
+            None => table = Some(Table::new("cpu".to_owned(), row_group)),
+        }
+    }
+    table.unwrap()
+}
+
+criterion_group!(benches, select);
+criterion_main!(benches);
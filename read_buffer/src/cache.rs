@@ -0,0 +1,396 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use arrow_deps::arrow::array::{Array, ArrayRef};
+
+/// The caches enabled on the chunks and tables of a database as they are
+/// created.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// The byte budget of each table's `DecodeCache`, if enabled.
+    pub decode_cache_budget: Option<usize>,
+
+    /// The capacity of each table's `PruneCache`, if enabled.
+    pub prune_cache_capacity: Option<usize>,
+
+    /// The capacity of each chunk's cache of `table_names` results, if
+    /// enabled.
+    pub table_names_cache_capacity: Option<usize>,
+}
+
+/// The key for a cached column: the index of the row group within the table
+/// and the name of the column.
+type CacheKey = (usize, String);
+
+/// A size-bounded, least-recently-used cache of fully materialised (decoded)
+/// columns.
+///
+/// Decoding a column from its encoded representation can be expensive, and
+/// when the same columns are repeatedly selected (e.g., a dashboard
+/// refreshing) the cost can be avoided by keeping recently decoded columns
+/// around. Entries are evicted in least-recently-used order when the total
+/// size of the cached columns would exceed the configured byte budget.
+#[derive(Debug)]
+pub struct DecodeCache {
+    // The maximum total size in bytes of all cached columns.
+    budget: usize,
+
+    // The current total size in bytes of all cached columns.
+    size: usize,
+
+    entries: HashMap<CacheKey, CachedColumn>,
+
+    // Keys ordered by recency of use, keyed by the tick at which they were
+    // last used. The first key is the least recently used entry.
+    recency: BTreeMap<u64, CacheKey>,
+
+    // Incremented every time an entry is used.
+    tick: u64,
+
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Debug)]
+struct CachedColumn {
+    arr: ArrayRef,
+
+    // The tick at which the column was last used.
+    last_used: u64,
+}
+
+impl DecodeCache {
+    /// Create a new cache that will hold at most `budget` bytes of decoded
+    /// columns.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            size: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the decoded column for the row group and column name if it is
+    /// present in the cache. Looking up an entry marks it as the most recently
+    /// used entry.
+    pub fn get(&mut self, row_group: usize, column_name: &str) -> Option<ArrayRef> {
+        let key = (row_group, column_name.to_owned());
+        match self.entries.get_mut(&key) {
+            Some(entry) => {
+                self.hits += 1;
+
+                // mark the entry as the most recently used.
+                self.recency.remove(&entry.last_used);
+                self.tick += 1;
+                entry.last_used = self.tick;
+                self.recency.insert(self.tick, key);
+
+                Some(ArrayRef::clone(&entry.arr))
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Adds a decoded column to the cache, evicting least recently used
+    /// entries until it fits within the budget. Columns larger than the entire
+    /// budget are not cached.
+    pub fn insert(&mut self, row_group: usize, column_name: &str, arr: ArrayRef) {
+        let arr_size = arr.get_array_memory_size();
+        if arr_size > self.budget {
+            return;
+        }
+
+        let key = (row_group, column_name.to_owned());
+        if let Some(existing) = self.entries.remove(&key) {
+            self.size -= existing.arr.get_array_memory_size();
+            self.recency.remove(&existing.last_used);
+        }
+
+        while self.size + arr_size > self.budget {
+            let least_recent = match self.recency.keys().next() {
+                Some(&tick) => tick,
+                None => break,
+            };
+            let evict = self.recency.remove(&least_recent).unwrap();
+            let evicted = self.entries.remove(&evict).unwrap();
+            self.size -= evicted.arr.get_array_memory_size();
+        }
+
+        self.size += arr_size;
+        self.tick += 1;
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            CachedColumn {
+                arr,
+                last_used: self.tick,
+            },
+        );
+    }
+
+    /// Removes all cached columns for the row group at the provided index.
+    ///
+    /// Row groups are identified by their position within the table, so the
+    /// entries for all row groups after the removed one are re-keyed to
+    /// account for their new positions.
+    pub fn remove_row_group(&mut self, row_group: usize) {
+        let entries = std::mem::take(&mut self.entries);
+        self.recency.clear();
+        self.size = 0;
+        for ((rg, column_name), entry) in entries {
+            let rg = match rg {
+                rg if rg == row_group => continue,
+                rg if rg > row_group => rg - 1,
+                rg => rg,
+            };
+
+            self.size += entry.arr.get_array_memory_size();
+            self.recency
+                .insert(entry.last_used, (rg, column_name.clone()));
+            self.entries.insert((rg, column_name), entry);
+        }
+    }
+
+    /// Removes all cached columns.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.size = 0;
+    }
+
+    /// The number of cached columns.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if there are no cached columns.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The total size in bytes of all cached columns.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Statistics about the effectiveness of the cache.
+    pub fn stats(&self) -> DecodeCacheStats {
+        DecodeCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+            size: self.size,
+        }
+    }
+}
+
+/// Hit and miss counters, along with current usage, for a `DecodeCache`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DecodeCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub size: usize,
+}
+
+/// A bounded, least-recently-used cache of the row groups in a table that
+/// could satisfy a predicate, keyed by the predicate's expressions.
+///
+/// Determining which row groups could satisfy a predicate requires checking
+/// every expression against the meta data of every row group. When the same
+/// predicate is repeatedly evaluated against an unchanged table (e.g., a
+/// dashboard refreshing) the result can be reused. Row groups are identified
+/// by their position within the table, so the cache must be cleared whenever
+/// the table's row groups change.
+///
+/// Chunks similarly cache the positions of the tables with rows satisfying a
+/// predicate, which must be cleared whenever the chunk's tables change.
+#[derive(Debug)]
+pub struct PruneCache {
+    // The maximum number of cached predicates.
+    capacity: usize,
+
+    entries: HashMap<String, Vec<usize>>,
+
+    // Keys ordered by recency of use. The front of the queue is the least
+    // recently used entry.
+    recency: VecDeque<String>,
+
+    hits: u64,
+    misses: u64,
+}
+
+impl PruneCache {
+    /// Create a new cache that will hold the pruning results of at most
+    /// `capacity` predicates.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            recency: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the positions of the row groups that could satisfy the
+    /// predicate if present in the cache. Looking up an entry marks it as the
+    /// most recently used entry.
+    pub fn get(&mut self, predicate: &str) -> Option<Vec<usize>> {
+        match self.entries.get(predicate) {
+            Some(row_groups) => {
+                let row_groups = row_groups.clone();
+                self.hits += 1;
+                if let Some(i) = self.recency.iter().position(|k| k == predicate) {
+                    self.recency.remove(i);
+                }
+                self.recency.push_back(predicate.to_owned());
+                Some(row_groups)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Adds the positions of the row groups that could satisfy the predicate,
+    /// evicting the least recently used entry if the cache is full.
+    pub fn insert(&mut self, predicate: &str, row_groups: Vec<usize>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self
+            .entries
+            .insert(predicate.to_owned(), row_groups)
+            .is_some()
+        {
+            self.recency.retain(|k| k != predicate);
+        } else if self.entries.len() > self.capacity {
+            if let Some(evict) = self.recency.pop_front() {
+                self.entries.remove(&evict);
+            }
+        }
+        self.recency.push_back(predicate.to_owned());
+    }
+
+    /// Removes all cached pruning results.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    /// Statistics about the effectiveness of the cache.
+    pub fn stats(&self) -> PruneCacheStats {
+        PruneCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+        }
+    }
+}
+
+/// Hit and miss counters, along with current usage, for a `PruneCache`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PruneCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use arrow_deps::arrow::array::Int64Array;
+    use std::sync::Arc;
+
+    fn array(n: i64) -> ArrayRef {
+        Arc::new(Int64Array::from((0..n).collect::<Vec<_>>()))
+    }
+
+    #[test]
+    fn lru_eviction() {
+        let one = array(10).get_array_memory_size();
+        let mut cache = DecodeCache::new(one * 2);
+
+        cache.insert(0, "a", array(10));
+        cache.insert(0, "b", array(10));
+        assert_eq!(cache.len(), 2);
+
+        // make "a" the most recently used.
+        assert!(cache.get(0, "a").is_some());
+
+        // "b" should be evicted to make room.
+        cache.insert(1, "a", array(10));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(0, "b").is_none());
+        assert!(cache.get(0, "a").is_some());
+        assert!(cache.get(1, "a").is_some());
+        assert!(cache.size() <= one * 2);
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 1);
+
+        // columns larger than the budget are not cached.
+        cache.insert(2, "a", array(1000));
+        assert!(cache.get(2, "a").is_none());
+    }
+
+    #[test]
+    fn remove_row_group() {
+        let mut cache = DecodeCache::new(usize::MAX);
+        cache.insert(0, "a", array(10));
+        cache.insert(1, "a", array(20));
+        cache.insert(2, "a", array(30));
+
+        cache.remove_row_group(1);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(0, "a").unwrap().len(), 10);
+        assert_eq!(cache.get(1, "a").unwrap().len(), 30);
+        assert!(cache.get(2, "a").is_none());
+        assert_eq!(
+            cache.size(),
+            array(10).get_array_memory_size() + array(30).get_array_memory_size()
+        );
+    }
+
+    #[test]
+    fn prune_cache_eviction() {
+        let mut cache = PruneCache::new(2);
+        cache.insert("a", vec![0]);
+        cache.insert("b", vec![1]);
+
+        // make "a" the most recently used.
+        assert_eq!(cache.get("a"), Some(vec![0]));
+
+        // "b" should be evicted to make room.
+        cache.insert("c", vec![0, 1]);
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a"), Some(vec![0]));
+        assert_eq!(cache.get("c"), Some(vec![0, 1]));
+        assert_eq!(
+            cache.stats(),
+            PruneCacheStats {
+                hits: 3,
+                misses: 1,
+                entries: 2
+            }
+        );
+
+        // replacing an entry doesn't evict anything.
+        cache.insert("a", vec![]);
+        assert_eq!(cache.get("a"), Some(vec![]));
+        assert_eq!(cache.stats().entries, 2);
+
+        cache.clear();
+        assert!(cache.get("c").is_none());
+    }
+}
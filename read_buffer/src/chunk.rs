@@ -1,5 +1,10 @@
 use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
+use std::sync::Mutex;
 
+use arrow_deps::arrow::record_batch::RecordBatch;
+use snafu::{ensure, OptionExt};
+
+use crate::cache::{CacheConfig, PruneCache, PruneCacheStats};
 use crate::row_group::RowGroup;
 use crate::row_group::{ColumnName, Predicate};
 use crate::schema::AggregateType;
@@ -21,15 +26,34 @@ pub struct Chunk {
     // The set of tables within this chunk. Each table is identified by a
     // measurement name.
     tables: BTreeMap<TableName, Table>,
+
+    // An optional cache of the tables, by position in `tables`, that satisfy
+    // recently evaluated predicates and time ranges in `table_names`.
+    table_names_cache: Option<Mutex<PruneCache>>,
+
+    // The caches enabled on the chunk and on each of its tables.
+    caches: CacheConfig,
 }
 
 impl Chunk {
     pub fn new(id: u32, table: Table) -> Self {
+        Self::with_caches(id, table, CacheConfig::default())
+    }
+
+    /// Create a new chunk with the provided table, enabling the caches in
+    /// `caches` on the chunk, the table, and any tables added later.
+    pub fn with_caches(id: u32, mut table: Table, caches: CacheConfig) -> Self {
+        enable_table_caches(&mut table, &caches);
         let mut p = Self {
             id,
             meta: MetaData::new(&table),
             tables: BTreeMap::new(),
+            table_names_cache: None,
+            caches,
         };
+        if let Some(capacity) = caches.table_names_cache_capacity {
+            p.enable_table_names_cache(capacity);
+        }
         p.tables.insert(table.name().to_owned(), table);
         p
     }
@@ -39,6 +63,31 @@ impl Chunk {
         self.id
     }
 
+    /// Enables caching of the results of `table_names`, for at most
+    /// `capacity` distinct predicate and time range combinations. Any
+    /// previously cached results are discarded.
+    ///
+    /// When a call's predicate and time range are found in the cache the
+    /// tables are not checked against them again. The cache is cleared
+    /// whenever data is added to the chunk or a table is renamed.
+    pub fn enable_table_names_cache(&mut self, capacity: usize) {
+        self.table_names_cache = Some(Mutex::new(PruneCache::new(capacity)));
+    }
+
+    /// Hit and miss counters for the chunk's table names cache, if enabled.
+    pub fn table_names_cache_stats(&self) -> Option<PruneCacheStats> {
+        self.table_names_cache
+            .as_ref()
+            .map(|cache| cache.lock().unwrap().stats())
+    }
+
+    // Discards any cached `table_names` results.
+    fn invalidate_table_names_cache(&mut self) {
+        if let Some(cache) = &self.table_names_cache {
+            cache.lock().unwrap().clear();
+        }
+    }
+
     /// The total size in bytes of all row groups in all tables in this chunk.
     pub fn size(&self) -> u64 {
         self.meta.size
@@ -49,6 +98,25 @@ impl Chunk {
         self.meta.rows
     }
 
+    /// The number of rows in each table in this chunk, keyed by table name.
+    /// Row counts are maintained as data is added, so no data is scanned.
+    pub fn table_row_counts(&self) -> BTreeMap<TableName, u64> {
+        self.tables
+            .iter()
+            .map(|(table_name, table)| (table_name.clone(), table.rows()))
+            .collect()
+    }
+
+    /// Eagerly computes any metadata in the chunk's tables that is otherwise
+    /// built on demand. Returns the number of columns whose metadata was
+    /// computed, which is zero if the chunk is already warm.
+    pub fn warm_metadata(&self) -> usize {
+        self.tables
+            .values()
+            .map(|table| table.warm_metadata())
+            .sum()
+    }
+
     /// The total number of row groups in all tables in this chunk.
     pub fn row_groups(&self) -> usize {
         self.meta.row_groups
@@ -59,30 +127,130 @@ impl Chunk {
         self.tables.len()
     }
 
+    /// The total time range of all data within this chunk, if any.
+    pub fn time_range(&self) -> Option<(i64, i64)> {
+        self.meta.time_range
+    }
+
     /// Returns true if the chunk contains data for this table.
-    pub fn has_table(&self, table_name: &str) -> bool {
+    pub fn contains_table(&self, table_name: &str) -> bool {
         self.tables.contains_key(table_name)
     }
 
+    /// Returns the table with the provided name, if it exists in the chunk.
+    pub fn table(&self, table_name: &str) -> Option<&Table> {
+        self.tables.get(table_name)
+    }
+
+    // Returns the table with the provided name or a `TableNotFound` error.
+    fn table_or_err(&self, table_name: &str) -> Result<&Table, Error> {
+        self.table(table_name)
+            .context(crate::TableNotFound { table_name })
+    }
+
     /// Returns true if there are no tables under this chunk.
     pub fn is_empty(&self) -> bool {
         self.tables() == 0
     }
 
+    /// Checks that the row group can be added to the table in the chunk. Any
+    /// row group can be added if the table doesn't exist.
+    pub fn validate_row_group(&self, table_name: &str, row_group: &RowGroup) -> Result<(), Error> {
+        match self.table(table_name) {
+            Some(table) => table.validate_row_group(row_group),
+            None => Ok(()),
+        }
+    }
+
     /// Add a row_group to a table in the chunk, updating all Chunk meta data.
-    pub fn upsert_table(&mut self, table_name: String, row_group: RowGroup) {
-        // update meta data
-        self.meta.update(&row_group);
+    /// An error is returned, and the chunk is unchanged, if the row group's
+    /// schema conflicts with the table's.
+    pub fn upsert_table(&mut self, table_name: String, row_group: RowGroup) -> Result<(), Error> {
+        self.validate_row_group(&table_name, &row_group)?;
+        self.invalidate_table_names_cache();
+
+        // The meta data is only updated once the row group has been added,
+        // so it never counts data that isn't stored.
+        let mut meta = self.meta.clone();
+        meta.update(&row_group);
 
         match self.tables.entry(table_name.to_owned()) {
             Entry::Occupied(mut e) => {
                 let table = e.get_mut();
-                table.add_row_group(row_group);
+                table.add_row_group(row_group)?;
             }
             Entry::Vacant(e) => {
-                e.insert(Table::new(table_name, row_group));
+                let mut table = Table::new(table_name, row_group);
+                enable_table_caches(&mut table, &self.caches);
+                e.insert(table);
             }
         };
+        self.meta = meta;
+        Ok(())
+    }
+
+    /// Determines, using only meta data, whether the table could have rows in
+    /// this chunk that fall within the time range `[min, max)`, if provided,
+    /// and satisfy the predicate.
+    pub fn could_pass_predicate(
+        &self,
+        table_name: &str,
+        time_range: Option<(i64, i64)>,
+        predicate: &Predicate,
+    ) -> bool {
+        let table = match self.table(table_name) {
+            Some(table) => table,
+            None => return false,
+        };
+
+        if let Some((min, max)) = time_range {
+            if !overlaps_time_range(table.time_range(), min, max) {
+                return false;
+            }
+        }
+
+        table.could_pass_predicate(predicate)
+    }
+
+    /// Renames the table `old_name` to `new_name`.
+    ///
+    /// Returns an error if `old_name` does not exist in the chunk or if a table
+    /// called `new_name` already exists. The chunk's meta data is unaffected.
+    pub fn rename_table(&mut self, old_name: &str, new_name: &str) -> Result<(), Error> {
+        self.table_or_err(old_name)?;
+        ensure!(
+            !self.contains_table(new_name),
+            crate::TableAlreadyExists {
+                table_name: new_name
+            }
+        );
+
+        let mut table = self.tables.remove(old_name).unwrap();
+        table.rename(new_name.to_owned());
+        self.tables.insert(new_name.to_owned(), table);
+        self.invalidate_table_names_cache();
+        Ok(())
+    }
+
+    /// Replaces all of the row groups of the table, which must contain the
+    /// same rows as the table's existing row groups, e.g., when compacting the
+    /// table. `row_groups` must not be empty.
+    pub fn replace_row_groups(
+        &mut self,
+        table_name: &str,
+        row_groups: Vec<RowGroup>,
+    ) -> Result<(), Error> {
+        let table = self
+            .tables
+            .get_mut(table_name)
+            .context(crate::TableNotFound { table_name })?;
+        let (prev_size, prev_row_groups) = (table.size(), table.len());
+        table.replace_row_groups(row_groups)?;
+
+        // The table's rows, and therefore its time range, are unchanged.
+        self.meta.size = self.meta.size - prev_size + table.size();
+        self.meta.row_groups = self.meta.row_groups - prev_row_groups + table.len();
+        Ok(())
     }
 
     /// Returns an iterator of lazily executed `read_filter` operations on the
@@ -104,20 +272,37 @@ impl Chunk {
         select_columns: &ColumnSelection<'_>,
     ) -> Result<table::ReadFilterResults<'_>, Error> {
         // Lookup table by name and dispatch execution.
-        match self.tables.get(table_name) {
-            Some(table) => Ok(table.read_filter(select_columns, predicate)),
-            None => crate::TableNotFound {
-                table_name: table_name.to_owned(),
-            }
-            .fail(),
-        }
+        let table = self.table_or_err(table_name)?;
+        Ok(table.read_filter(select_columns, predicate))
+    }
+
+    /// Reads the selected columns from every table in the chunk, returning the
+    /// record batches for each table keyed by table name, in table name
+    /// order. Each table's batches have that table's schema, and columns that
+    /// don't exist in a table are not included in its batches.
+    ///
+    /// This allows the entire chunk to be exported in one call.
+    pub fn scan_all(&self, columns: &ColumnSelection<'_>) -> Vec<(TableName, Vec<RecordBatch>)> {
+        self.tables
+            .iter()
+            .map(|(table_name, table)| {
+                let mut results = table.read_filter(columns, &Predicate::default());
+                let mut batches = vec![];
+                while let Some(rb) = results.next_record_batch() {
+                    batches.push(rb);
+                }
+                (table_name.clone(), batches)
+            })
+            .collect()
     }
 
     /// Returns an iterable collection of data in group columns and aggregate
     /// columns, optionally filtered by the provided predicate. Results are
     /// merged across all row groups within the returned table.
     ///
-    /// Returns `None` if the table no longer exists within the chunk.
+    /// Returns an error if the table no longer exists within the chunk, or if
+    /// any of the group, aggregate or predicate columns do not exist within
+    /// the table.
     ///
     /// Note: `read_aggregate` currently only supports grouping on "tag"
     /// columns.
@@ -127,11 +312,23 @@ impl Chunk {
         predicate: Predicate,
         group_columns: &ColumnSelection<'_>,
         aggregates: &[(ColumnName<'_>, AggregateType)],
-    ) -> Option<table::ReadAggregateResults<'_>> {
+    ) -> Result<table::ReadAggregateResults<'_>, Error> {
         // Lookup table by name and dispatch execution.
-        self.tables
-            .get(table_name)
-            .map(|table| table.read_aggregate(predicate, group_columns, aggregates))
+        self.table_or_err(table_name)?
+            .read_aggregate(predicate, group_columns, aggregates)
+    }
+
+    /// Validates that the table exists within the chunk and that all of the
+    /// columns referenced by a `read_aggregate` call exist within that table.
+    pub fn validate_aggregate(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        group_columns: &ColumnSelection<'_>,
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+    ) -> Result<(), Error> {
+        self.table_or_err(table_name)?
+            .validate_aggregate(predicate, group_columns, aggregates)
     }
 
     //
@@ -140,8 +337,76 @@ impl Chunk {
 
     /// Returns the distinct set of table names that contain data that satisfies
     /// the time range and predicates.
-    pub fn table_names(&self, predicate: &Predicate) -> BTreeSet<&String> {
-        self.tables.keys().collect::<BTreeSet<&String>>()
+    ///
+    /// If a time range `[min, max)` is provided then only tables with data
+    /// overlapping that range are returned. If the predicate isn't empty then
+    /// only tables with rows satisfying it, within the time range, are
+    /// returned.
+    ///
+    /// The result is served from, and stored in, the table names cache if
+    /// enabled.
+    pub fn table_names(
+        &self,
+        predicate: &Predicate,
+        time_range: Option<(i64, i64)>,
+    ) -> BTreeSet<&String> {
+        if time_range.is_none() && predicate.is_empty() {
+            return self.tables.keys().collect::<BTreeSet<&String>>();
+        }
+
+        let cache = match &self.table_names_cache {
+            Some(cache) => cache,
+            None => return self.filter_table_names(predicate, time_range),
+        };
+
+        // The debug representation distinguishes all properties of the
+        // expressions, such as case-insensitivity.
+        let key = format!("{:?} {:?}", predicate.expressions(), time_range);
+        if let Some(positions) = cache.lock().unwrap().get(&key) {
+            let names = self.tables.keys().collect::<Vec<_>>();
+            return positions.into_iter().map(|i| names[i]).collect();
+        }
+
+        let names = self.filter_table_names(predicate, time_range);
+        let positions = self
+            .tables
+            .keys()
+            .enumerate()
+            .filter(|(_, name)| names.contains(name))
+            .map(|(i, _)| i)
+            .collect();
+        cache.lock().unwrap().insert(&key, positions);
+        names
+    }
+
+    // Checks every table against the time range and predicate.
+    fn filter_table_names(
+        &self,
+        predicate: &Predicate,
+        time_range: Option<(i64, i64)>,
+    ) -> BTreeSet<&String> {
+        // Rows satisfying the predicate must also be within the time range.
+        let time_predicate = match time_range {
+            Some((min, max)) => {
+                // Skip the tables entirely if the chunk doesn't contain any
+                // data for the time range.
+                if !overlaps_time_range(self.time_range(), min, max) {
+                    return BTreeSet::new();
+                }
+                Predicate::with_time_range(predicate.expressions(), min, max)
+            }
+            None => predicate.clone(),
+        };
+
+        self.tables
+            .iter()
+            .filter(|(_, table)| match time_range {
+                Some((min, max)) => overlaps_time_range(table.time_range(), min, max),
+                None => true,
+            })
+            .filter(|(_, table)| predicate.is_empty() || table.satisfies_predicate(&time_predicate))
+            .map(|(name, _)| name)
+            .collect::<BTreeSet<&String>>()
     }
 
     /// Returns the distinct set of tag keys (column names) matching the
@@ -158,6 +423,23 @@ impl Chunk {
         todo!();
     }
 
+    /// Returns the distinct set of field keys (field column names) in the
+    /// table that have not already been found in `found_keys`, where the table
+    /// has rows matching the predicate.
+    ///
+    /// An empty set is returned if the table does not exist in the chunk.
+    pub fn field_keys(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        found_keys: &BTreeSet<String>,
+    ) -> BTreeSet<ColumnName<'_>> {
+        match self.table(table_name) {
+            Some(table) => table.field_keys(predicate, found_keys),
+            None => BTreeSet::new(),
+        }
+    }
+
     /// Returns the distinct set of tag values (column values) for each provided
     /// tag key, where each returned value lives in a row matching the provided
     /// optional predicates and time range.
@@ -181,8 +463,29 @@ impl Chunk {
     }
 }
 
+// Determines if the inclusive time range `[range_min, range_max]` overlaps
+// the half-open time range `[min, max)`, where a `max` of `i64::MAX` is an
+// open bound. A missing time range never overlaps.
+fn overlaps_time_range(range: Option<(i64, i64)>, min: i64, max: i64) -> bool {
+    match range {
+        Some((range_min, range_max)) => (max == i64::MAX || range_min < max) && range_max >= min,
+        None => false,
+    }
+}
+
+// Enables the table caches configured in `caches` on the table.
+fn enable_table_caches(table: &mut Table, caches: &CacheConfig) {
+    if let Some(budget) = caches.decode_cache_budget {
+        table.enable_decode_cache(budget);
+    }
+    if let Some(capacity) = caches.prune_cache_capacity {
+        table.enable_prune_cache(capacity);
+    }
+}
+
 // `Chunk` metadata that is used to track statistics about the chunk and
 // whether it could contain data necessary to execute a query.
+#[derive(Clone)]
 struct MetaData {
     size: u64, // size in bytes of the chunk
     rows: u64, // Total number of rows across all tables
@@ -228,3 +531,193 @@ impl MetaData {
         todo!()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::column::Column;
+    use crate::row_group::{BinaryExpr, ColumnType};
+
+    fn build_row_group(region: &str) -> RowGroup {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&[region, region][..]));
+        columns.insert("region".to_string(), rc);
+        RowGroup::new(2, columns)
+    }
+
+    #[test]
+    fn contains_table() {
+        let mut chunk = Chunk::new(22, Table::new("cpu".to_owned(), build_row_group("west")));
+        chunk
+            .upsert_table("mem".to_owned(), build_row_group("east"))
+            .unwrap();
+
+        assert!(chunk.contains_table("cpu"));
+        assert!(chunk.contains_table("mem"));
+        assert!(!chunk.contains_table("disk"));
+
+        assert_eq!(chunk.table("mem").unwrap().name(), "mem");
+        assert!(chunk.table("disk").is_none());
+
+        assert!(matches!(
+            chunk.read_filter("disk", &Predicate::default(), &ColumnSelection::All),
+            Err(Error::TableNotFound { table_name }) if table_name == "disk"
+        ));
+    }
+
+    #[test]
+    fn table_row_counts() {
+        let mut chunk = Chunk::new(22, Table::new("cpu".to_owned(), build_row_group("west")));
+        chunk
+            .upsert_table("cpu".to_owned(), build_row_group("north"))
+            .unwrap();
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[100_i64, 200, 300][..]));
+        columns.insert("time".to_string(), tc);
+        chunk
+            .upsert_table("mem".to_owned(), RowGroup::new(3, columns))
+            .unwrap();
+
+        let counts = chunk.table_row_counts();
+        assert_eq!(
+            counts
+                .iter()
+                .map(|(k, v)| (k.as_str(), *v))
+                .collect::<Vec<_>>(),
+            vec![("cpu", 4), ("mem", 3)]
+        );
+        assert_eq!(counts.values().sum::<u64>(), chunk.rows());
+    }
+
+    #[test]
+    fn scan_all() {
+        let mut chunk = Chunk::new(22, Table::new("cpu".to_owned(), build_row_group("west")));
+        chunk
+            .upsert_table("cpu".to_owned(), build_row_group("north"))
+            .unwrap();
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[100_i64, 200, 300][..]));
+        columns.insert("time".to_string(), tc);
+        let fc = ColumnType::Field(Column::from(&[1.5, 2.5, 3.5][..]));
+        columns.insert("used".to_string(), fc);
+        chunk
+            .upsert_table("mem".to_owned(), RowGroup::new(3, columns))
+            .unwrap();
+
+        let results = chunk.scan_all(&ColumnSelection::All);
+        let summary = results
+            .iter()
+            .map(|(table_name, batches)| {
+                let schema = batches[0].schema();
+                let column_names = schema
+                    .fields()
+                    .iter()
+                    .map(|f| f.name().as_str())
+                    .collect::<Vec<_>>();
+                let rows = batches.iter().map(|rb| rb.num_rows()).collect::<Vec<_>>();
+                (table_name.as_str(), column_names.join(","), rows)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("cpu", "region,time".to_owned(), vec![2, 2]),
+                ("mem", "time,used".to_owned(), vec![3]),
+            ]
+        );
+
+        // Tables without any of the selected columns have no batches.
+        let results = chunk.scan_all(&ColumnSelection::Some(&["used"]));
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "cpu");
+        assert!(results[0].1.is_empty());
+        assert_eq!(results[1].1[0].num_columns(), 1);
+        assert_eq!(results[1].1[0].num_rows(), 3);
+    }
+
+    #[test]
+    fn table_names_time_range() {
+        let mut chunk = Chunk::new(22, Table::new("cpu".to_owned(), build_row_group("west")));
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[100_i64, 200][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["east", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        chunk
+            .upsert_table("mem".to_owned(), RowGroup::new(2, columns))
+            .unwrap();
+        assert_eq!(chunk.time_range(), Some((1, 200)));
+
+        let table_names = |time_range| {
+            chunk
+                .table_names(&Predicate::default(), time_range)
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(table_names(None), vec!["cpu", "mem"]);
+        assert_eq!(table_names(Some((0, 1000))), vec!["cpu", "mem"]);
+        assert_eq!(table_names(Some((2, 100))), vec!["cpu"]);
+        assert_eq!(table_names(Some((3, 100))), Vec::<String>::new());
+        assert_eq!(table_names(Some((200, 201))), vec!["mem"]);
+        assert_eq!(table_names(Some((201, 1000))), Vec::<String>::new());
+    }
+
+    #[test]
+    fn table_names_predicate_cache() {
+        let mut chunk = Chunk::new(22, Table::new("cpu".to_owned(), build_row_group("west")));
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[100_i64, 200][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["east", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        chunk
+            .upsert_table("mem".to_owned(), RowGroup::new(2, columns))
+            .unwrap();
+        chunk.enable_table_names_cache(10);
+
+        let table_names = |chunk: &Chunk, time_range| {
+            let predicate = Predicate::new(vec![BinaryExpr::from(("region", "=", "east"))]);
+            chunk
+                .table_names(&predicate, time_range)
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(table_names(&chunk, None), vec!["mem"]);
+        let stats = chunk.table_names_cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses), (0, 1));
+
+        // the second identical call doesn't evaluate the predicate again.
+        assert_eq!(table_names(&chunk, None), vec!["mem"]);
+        let stats = chunk.table_names_cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+
+        // rows must satisfy the predicate within the time range.
+        assert_eq!(table_names(&chunk, Some((0, 150))), vec!["mem"]);
+        assert_eq!(table_names(&chunk, Some((150, 160))), Vec::<String>::new());
+        let stats = chunk.table_names_cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 3, 3));
+
+        // adding data invalidates the cache.
+        chunk
+            .upsert_table("cpu".to_owned(), build_row_group("east"))
+            .unwrap();
+        assert_eq!(chunk.table_names_cache_stats().unwrap().entries, 0);
+        assert_eq!(table_names(&chunk, None), vec!["cpu", "mem"]);
+
+        // as does renaming a table.
+        chunk.rename_table("mem", "disk").unwrap();
+        assert_eq!(table_names(&chunk, None), vec!["cpu", "disk"]);
+        let stats = chunk.table_names_cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses), (1, 5));
+    }
+}
@@ -1,19 +1,25 @@
 pub mod cmp;
+pub mod delta;
 pub mod dictionary;
 pub mod fixed;
 pub mod fixed_null;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod strategy;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use arrow::array;
 use croaring::Bitmap;
 use either::Either;
+use snafu::{ensure, Snafu};
 
 use arrow_deps::{arrow, arrow::array::Array};
 
-use crate::schema::{AggregateType, LogicalDataType};
+use crate::schema::{AggregateType, LogicalDataType, NanHandling};
+use strategy::EncodingKind;
 
 // Edd's totally made up magic constant. This determines whether we would use
 // a run-length encoded dictionary encoding or just a plain dictionary encoding.
@@ -25,6 +31,25 @@ use crate::schema::{AggregateType, LogicalDataType};
 // compression is worth the memory and compute costs to work on it.
 pub const TEMP_CARDINALITY_DICTIONARY_ENCODING_LIMIT: usize = 100_000;
 
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("unsupported arrow type for value: {:?}", data_type))]
+    UnsupportedArrowType {
+        data_type: arrow::datatypes::DataType,
+    },
+
+    #[snafu(display("row {} out of range for array with {} rows", index, len))]
+    RowOutOfRange { index: usize, len: usize },
+
+    #[snafu(display("cannot cast {:?} column to {:?}", from, to))]
+    UnsupportedCast {
+        from: LogicalDataType,
+        to: LogicalDataType,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
 /// The possible logical types that column values can have. All values in a
 /// column have the same physical type.
 pub enum Column {
@@ -48,9 +73,11 @@ pub enum Column {
     // type probably needs some thought.
     Unsigned(MetaData<u64>, IntegerEncoding), // TODO - 64-bit unsigned integers
 
+    // A column of arbitrary bytes, e.g., serialized protobuf messages.
+    ByteArray(MetaData<Vec<u8>>, ByteArrayEncoding),
+
     // These are TODO
-    Bool,                                         // TODO - booleans
-    ByteArray(MetaData<Vec<u8>>, StringEncoding), // TODO - arbitrary bytes
+    Bool, // TODO - booleans
 }
 
 impl Column {
@@ -80,8 +107,26 @@ impl Column {
         }
     }
 
+    /// The total size in bytes of the encoded column data.
     pub fn size(&self) -> u64 {
-        0
+        match &self {
+            Column::String(meta, _) => meta.size,
+            Column::Float(meta, _) => meta.size,
+            Column::Integer(meta, _) => meta.size,
+            Column::Unsigned(meta, _) => meta.size,
+            Column::Bool => 0, // boolean columns don't store any data yet
+            Column::ByteArray(meta, _) => meta.size,
+        }
+    }
+
+    /// The kind of encoding used to store the column's data.
+    pub fn encoding_kind(&self) -> EncodingKind {
+        match &self {
+            Column::String(_, StringEncoding::RLEDictionary(_)) => EncodingKind::RLE,
+            Column::String(_, StringEncoding::Dictionary(_)) => EncodingKind::Dictionary,
+            Column::Integer(_, IntegerEncoding::I64Delta(_)) => EncodingKind::Delta,
+            _ => EncodingKind::Plain,
+        }
     }
 
     /// Returns the (min, max)  values stored in this column
@@ -116,7 +161,13 @@ impl Column {
                 None => None,
             },
             Column::Bool => todo!(),
-            Column::ByteArray(_, _) => todo!(),
+            Column::ByteArray(meta, _) => match &meta.range {
+                Some(range) => Some((
+                    OwnedValue::ByteArray(range.0.clone()),
+                    OwnedValue::ByteArray(range.1.clone()),
+                )),
+                None => None,
+            },
         }
     }
 
@@ -160,7 +211,7 @@ impl Column {
             Column::Integer(_, data) => data.value(row_id),
             Column::Unsigned(_, data) => data.value(row_id),
             Column::Bool => todo!(),
-            Column::ByteArray(_, _) => todo!(),
+            Column::ByteArray(_, data) => data.value(row_id),
         }
     }
 
@@ -181,7 +232,7 @@ impl Column {
             Column::Integer(_, data) => data.values(row_ids),
             Column::Unsigned(_, data) => data.values(row_ids),
             Column::Bool => todo!(),
-            Column::ByteArray(_, _) => todo!(),
+            Column::ByteArray(_, data) => data.values(row_ids),
         }
     }
 
@@ -193,7 +244,48 @@ impl Column {
             Column::Integer(_, data) => data.all_values(),
             Column::Unsigned(_, data) => data.all_values(),
             Column::Bool => todo!(),
-            Column::ByteArray(_, _) => todo!(),
+            Column::ByteArray(_, data) => data.all_values(),
+        }
+    }
+
+    /// Returns a new column containing the values of this column converted to
+    /// the `target` logical type, e.g., so that columns with different types
+    /// in different chunks can be combined.
+    ///
+    /// Only widening casts, from integer and unsigned columns to float
+    /// columns, are supported, along with casts to the column's own type. An
+    /// error is returned for other casts, as they could lose data. Note that
+    /// integers with a magnitude larger than 2^53 can't be represented exactly
+    /// as floats, and are rounded to the nearest float.
+    pub fn cast(&self, target: LogicalDataType) -> Result<Self> {
+        let from = self.logical_datatype();
+        let arr = array::ArrayRef::from(self.all_values());
+        let any = arr.as_any();
+        match (from, target) {
+            (LogicalDataType::Integer, LogicalDataType::Integer) => {
+                Ok(Self::from(any.downcast_ref::<array::Int64Array>().unwrap()))
+            }
+            (LogicalDataType::Unsigned, LogicalDataType::Unsigned) => Ok(Self::from(
+                any.downcast_ref::<array::UInt64Array>().unwrap(),
+            )),
+            (LogicalDataType::Float, LogicalDataType::Float) => Ok(Self::from(
+                any.downcast_ref::<array::Float64Array>().unwrap(),
+            )),
+            (LogicalDataType::Integer, LogicalDataType::Float) => {
+                let arr = any.downcast_ref::<array::Int64Array>().unwrap();
+                let values = (0..arr.len())
+                    .map(|i| Some(arr.value(i) as f64).filter(|_| arr.is_valid(i)))
+                    .collect::<Vec<_>>();
+                Ok(Self::from(array::Float64Array::from(values)))
+            }
+            (LogicalDataType::Unsigned, LogicalDataType::Float) => {
+                let arr = any.downcast_ref::<array::UInt64Array>().unwrap();
+                let values = (0..arr.len())
+                    .map(|i| Some(arr.value(i) as f64).filter(|_| arr.is_valid(i)))
+                    .collect::<Vec<_>>();
+                Ok(Self::from(array::Float64Array::from(values)))
+            }
+            (from, to) => UnsupportedCast { from, to }.fail(),
         }
     }
 
@@ -207,7 +299,7 @@ impl Column {
     }
 
     // The distinct set of values found at the logical row ids.
-    pub fn distinct_values(&self, row_ids: &[u32]) -> ValueSet<'_> {
+    pub fn distinct_values_at(&self, row_ids: &[u32]) -> ValueSet<'_> {
         assert!(
             row_ids.len() as u32 <= self.num_rows(),
             format!(
@@ -219,11 +311,26 @@ impl Column {
 
         match &self {
             Column::String(_, data) => data.distinct_values(row_ids),
-            Column::ByteArray(_, _) => todo!(),
+            Column::ByteArray(_, data) => data.distinct_values(row_ids),
             _ => unimplemented!("distinct values is not implemented for this type"),
         }
     }
 
+    /// The distinct set of non-null values in the entire column, or `None` for
+    /// column types without distinct value sets.
+    ///
+    /// For dictionary encoded columns this is answered directly from the
+    /// column's dictionary and does not require scanning any rows. Other
+    /// columns are scanned the first time their distinct values are needed,
+    /// and the result is cached.
+    pub fn distinct_values(&self) -> Option<ValueSet<'_>> {
+        match &self {
+            Column::String(_, data) => Some(data.all_distinct_values()),
+            Column::ByteArray(_, data) => Some(data.all_distinct_values()),
+            _ => None,
+        }
+    }
+
     //
     // Methods for getting encoded (compressed) values.
     //
@@ -309,6 +416,39 @@ impl Column {
         RowIDsOption::Some(row_ids)
     }
 
+    /// Determine the set of row ids that satisfy the predicate, comparing
+    /// string values without regard to case.
+    ///
+    /// Only `=` and `!=` predicates on string columns are matched
+    /// case-insensitively; all other predicates are evaluated as they would be
+    /// by `row_ids_filter`.
+    ///
+    /// Note: case-folded values don't map onto the column's min/max range, so
+    /// the column meta data can't be used to short-circuit evaluation. Every
+    /// distinct value in the column is lower-cased and compared to the
+    /// lower-cased predicate value, and the row ids for each matching value
+    /// are then combined. This makes case-insensitive matching more expensive
+    /// than an exact match, particularly on high-cardinality columns.
+    pub fn row_ids_filter_case_insensitive(
+        &self,
+        op: &cmp::Operator,
+        value: &Value<'_>,
+        dst: RowIDs,
+    ) -> RowIDsOption {
+        let row_ids = match (&self, op) {
+            (Column::String(_, data), cmp::Operator::Equal)
+            | (Column::String(_, data), cmp::Operator::NotEqual) => {
+                data.row_ids_filter_case_insensitive(op, value.string(), dst)
+            }
+            _ => return self.row_ids_filter(op, value, dst),
+        };
+
+        if row_ids.is_empty() {
+            return RowIDsOption::None(row_ids);
+        }
+        RowIDsOption::Some(row_ids)
+    }
+
     /// Determine the set of row ids that satisfy both of the predicates.
     ///
     /// Note: this method is a special case for common range-based predicates
@@ -537,7 +677,7 @@ impl Column {
 
         match &self {
             Column::String(_, data) => data.min(row_ids),
-            Column::Float(_, data) => data.min(row_ids),
+            Column::Float(meta, data) => data.min(row_ids, meta.properties.contains_nan),
             Column::Integer(_, data) => data.min(row_ids),
             Column::Unsigned(_, data) => data.min(row_ids),
             Column::Bool => todo!(),
@@ -551,7 +691,7 @@ impl Column {
 
         match &self {
             Column::String(_, data) => data.max(row_ids),
-            Column::Float(_, data) => data.max(row_ids),
+            Column::Float(meta, data) => data.max(row_ids, meta.properties.contains_nan),
             Column::Integer(_, data) => data.max(row_ids),
             Column::Unsigned(_, data) => data.max(row_ids),
             Column::Bool => todo!(),
@@ -568,13 +708,22 @@ impl Column {
         assert!(row_ids.len() as u32 <= self.num_rows());
 
         match &self {
-            Column::Float(_, data) => data.sum(row_ids),
+            Column::Float(meta, data) => data.sum(row_ids, meta.properties.contains_nan),
             Column::Integer(_, data) => data.sum(row_ids),
             Column::Unsigned(_, data) => data.sum(row_ids),
             _ => panic!("cannot sum non-numerical column type"),
         }
     }
 
+    /// Determines if there are any NaN values located at the provided rows.
+    /// Only float columns can contain NaN values.
+    pub fn contains_nan(&self, row_ids: &[u32]) -> bool {
+        match &self {
+            Column::Float(meta, data) => meta.properties.contains_nan && data.contains_nan(row_ids),
+            _ => false,
+        }
+    }
+
     /// The count of all non-null values located at the provided rows.
     pub fn count(&self, row_ids: &[u32]) -> u32 {
         assert!(row_ids.len() as u32 <= self.num_rows());
@@ -585,7 +734,7 @@ impl Column {
             Column::Integer(_, data) => data.count(row_ids),
             Column::Unsigned(_, data) => data.count(row_ids),
             Column::Bool => todo!(),
-            Column::ByteArray(_, _) => todo!(),
+            Column::ByteArray(_, data) => data.count(row_ids),
         }
     }
 
@@ -609,6 +758,10 @@ impl Column {
 #[derive(Default, Debug, PartialEq)]
 pub struct ColumnProperties {
     pub has_pre_computed_row_ids: bool,
+
+    // Whether a float column contains any NaN values, which aggregates have to
+    // treat specially.
+    pub contains_nan: bool,
 }
 
 #[derive(Default, Debug, PartialEq)]
@@ -769,6 +922,18 @@ impl StringEncoding {
         }
     }
 
+    /// Returns the distinct set of non-null values in the column.
+    ///
+    /// Both encodings maintain a dictionary containing exactly the distinct
+    /// values encoded in the column, so no rows need to be scanned.
+    pub fn all_distinct_values(&self) -> ValueSet<'_> {
+        let dictionary = match &self {
+            Self::RLEDictionary(c) => c.dictionary(),
+            Self::Dictionary(c) => c.dictionary(),
+        };
+        ValueSet::String(dictionary.into_iter().map(Some).collect())
+    }
+
     /// Returns the row ids that satisfy the provided predicate.
     pub fn row_ids_filter(&self, op: &cmp::Operator, value: &str, dst: RowIDs) -> RowIDs {
         match &self {
@@ -777,6 +942,57 @@ impl StringEncoding {
         }
     }
 
+    /// Returns the row ids that satisfy the provided `=` or `!=` predicate,
+    /// where values are compared without regard to case.
+    pub fn row_ids_filter_case_insensitive(
+        &self,
+        op: &cmp::Operator,
+        value: &str,
+        mut dst: RowIDs,
+    ) -> RowIDs {
+        let value = value.to_lowercase();
+        let dictionary = match &self {
+            Self::RLEDictionary(c) => c.dictionary(),
+            Self::Dictionary(c) => c.dictionary(),
+        };
+
+        // All distinct values in the column that match `value` once case is
+        // ignored.
+        let matching = dictionary
+            .into_iter()
+            .filter(|v| v.to_lowercase() == value)
+            .collect::<Vec<_>>();
+
+        match op {
+            // x = 'a' OR x = 'A' OR ...
+            cmp::Operator::Equal => {
+                dst.clear();
+                let mut other = RowIDs::new_bitmap();
+                for v in matching {
+                    other = self.row_ids_filter(op, v, other);
+                    dst.union(&other);
+                }
+                dst
+            }
+            // x != 'a' AND x != 'A' AND ...
+            cmp::Operator::NotEqual => match matching.split_first() {
+                // no value in the column matches, so all non-null rows satisfy
+                // the predicate.
+                None => self.row_ids_filter(op, &value, dst),
+                Some((first, rest)) => {
+                    dst = self.row_ids_filter(op, first, dst);
+                    let mut other = RowIDs::new_bitmap();
+                    for v in rest {
+                        other = self.row_ids_filter(op, v, other);
+                        dst.intersect(&other);
+                    }
+                    dst
+                }
+            },
+            _ => unreachable!("case-insensitive matching only supports = and !="),
+        }
+    }
+
     /// The lexicographic minimum non-null value at the rows specified, or the
     /// NULL value if the column only contains NULL values at the provided row
     /// ids.
@@ -826,6 +1042,16 @@ impl StringEncoding {
     }
 
     fn from_arrow_string_array(arr: &arrow::array::StringArray) -> Self {
+        Self::from_arrow_string_array_with_rle(arr, None)
+    }
+
+    // Encodes the array as a run-length encoded dictionary if `rle` is
+    // `Some(true)`, or a plain dictionary if it's `Some(false)`. Otherwise
+    // the encoding is chosen based on the cardinality of the array.
+    fn from_arrow_string_array_with_rle(
+        arr: &arrow::array::StringArray,
+        rle: Option<bool>,
+    ) -> Self {
         // build a sorted dictionary.
         let mut dictionary = BTreeSet::new();
 
@@ -835,12 +1061,12 @@ impl StringEncoding {
             }
         }
 
-        let mut data: dictionary::Encoding =
-            if dictionary.len() > TEMP_CARDINALITY_DICTIONARY_ENCODING_LIMIT {
-                dictionary::Encoding::Plain(dictionary::Plain::with_dictionary(dictionary))
-            } else {
-                dictionary::Encoding::RLE(dictionary::RLE::with_dictionary(dictionary))
-            };
+        let rle = rle.unwrap_or(dictionary.len() <= TEMP_CARDINALITY_DICTIONARY_ENCODING_LIMIT);
+        let mut data: dictionary::Encoding = if rle {
+            dictionary::Encoding::RLE(dictionary::RLE::with_dictionary(dictionary))
+        } else {
+            dictionary::Encoding::Plain(dictionary::Plain::with_dictionary(dictionary))
+        };
 
         let mut prev = if !arr.is_null(0) {
             Some(arr.value(0))
@@ -1028,6 +1254,7 @@ impl StringEncoding {
                 };
 
                 MetaData {
+                    size: data.size(),
                     rows: data.num_rows(),
                     range,
                     ..MetaData::default()
@@ -1065,6 +1292,10 @@ pub enum IntegerEncoding {
     // Nullable encodings - TODO, add variants for smaller physical types.
     I64I64N(fixed_null::FixedNull<arrow::datatypes::Int64Type>),
     U64U64N(fixed_null::FixedNull<arrow::datatypes::UInt64Type>),
+
+    // Non-decreasing signed 64-bit values stored as the differences between
+    // consecutive values.
+    I64Delta(delta::Delta),
 }
 
 impl IntegerEncoding {
@@ -1105,6 +1336,8 @@ impl IntegerEncoding {
                 Some(v) => Value::Scalar(Scalar::U64(v)),
                 None => Value::Null,
             },
+
+            Self::I64Delta(c) => Value::Scalar(Scalar::I64(c.value(row_id))),
         }
     }
 
@@ -1131,6 +1364,8 @@ impl IntegerEncoding {
 
             Self::I64I64N(c) => Values::I64N(c.values(row_ids, vec![])),
             Self::U64U64N(c) => Values::U64N(c.values(row_ids, vec![])),
+
+            Self::I64Delta(c) => Values::I64(c.values(row_ids, vec![])),
         }
     }
 
@@ -1157,6 +1392,8 @@ impl IntegerEncoding {
 
             Self::I64I64N(c) => Values::I64N(c.all_values(vec![])),
             Self::U64U64N(c) => Values::U64N(c.all_values(vec![])),
+
+            Self::I64Delta(c) => Values::I64(c.all_values(vec![])),
         }
     }
 
@@ -1175,6 +1412,7 @@ impl IntegerEncoding {
                 Self::I64U16(data) => EncodedValues::I64(data.values(row_ids, dst)),
                 Self::I64I8(data) => EncodedValues::I64(data.values(row_ids, dst)),
                 Self::I64U8(data) => EncodedValues::I64(data.values(row_ids, dst)),
+                Self::I64Delta(data) => EncodedValues::I64(data.values(row_ids, dst)),
                 _ => unreachable!("encoded values on encoding type not currently supported"),
             },
             _ => unreachable!("currently only support encoded values as i64"),
@@ -1196,6 +1434,7 @@ impl IntegerEncoding {
                 Self::I64U16(data) => EncodedValues::I64(data.all_values(dst)),
                 Self::I64I8(data) => EncodedValues::I64(data.all_values(dst)),
                 Self::I64U8(data) => EncodedValues::I64(data.all_values(dst)),
+                Self::I64Delta(data) => EncodedValues::I64(data.all_values(dst)),
                 _ => unreachable!("encoded values on encoding type not supported"),
             },
             _ => unreachable!("currently only support encoded values as i64"),
@@ -1224,6 +1463,8 @@ impl IntegerEncoding {
 
             Self::I64I64N(c) => c.row_ids_filter(value.as_i64(), op, dst),
             Self::U64U64N(c) => c.row_ids_filter(value.as_u64(), op, dst),
+
+            Self::I64Delta(c) => c.row_ids_filter(value.as_i64(), op, dst),
         }
     }
 
@@ -1276,6 +1517,10 @@ impl IntegerEncoding {
 
             Self::I64I64N(c) => todo!(),
             Self::U64U64N(c) => todo!(),
+
+            Self::I64Delta(c) => {
+                c.row_ids_filter_range((low.1.as_i64(), low.0), (high.1.as_i64(), high.0), dst)
+            }
         }
     }
 
@@ -1300,6 +1545,7 @@ impl IntegerEncoding {
                 Some(v) => Value::Scalar(Scalar::U64(v)),
                 None => Value::Null,
             },
+            IntegerEncoding::I64Delta(c) => Value::Scalar(Scalar::I64(c.min(row_ids))),
         }
     }
 
@@ -1324,6 +1570,7 @@ impl IntegerEncoding {
                 Some(v) => Value::Scalar(Scalar::U64(v)),
                 None => Value::Null,
             },
+            IntegerEncoding::I64Delta(c) => Value::Scalar(Scalar::I64(c.max(row_ids))),
         }
     }
 
@@ -1348,6 +1595,7 @@ impl IntegerEncoding {
                 Some(v) => Scalar::U64(v),
                 None => Scalar::Null,
             },
+            IntegerEncoding::I64Delta(c) => Scalar::I64(c.sum(row_ids)),
         }
     }
 
@@ -1366,6 +1614,7 @@ impl IntegerEncoding {
             IntegerEncoding::U64U8(c) => c.count(row_ids),
             IntegerEncoding::I64I64N(c) => c.count(row_ids),
             IntegerEncoding::U64U64N(c) => c.count(row_ids),
+            IntegerEncoding::I64Delta(c) => c.count(row_ids),
         }
     }
 }
@@ -1445,40 +1694,220 @@ impl FloatEncoding {
         }
     }
 
-    pub fn min(&self, row_ids: &[u32]) -> Value<'_> {
+    // Returns the value at the provided row id, or `None` if the value is
+    // either NULL or NaN.
+    fn non_nan_value(&self, row_id: u32) -> Option<f64> {
+        let v: Option<f64> = match &self {
+            FloatEncoding::Fixed64(c) => Some(c.value(row_id)),
+            FloatEncoding::FixedNull64(c) => c.value(row_id),
+        };
+        v.filter(|v| !v.is_nan())
+    }
+
+    /// Determines if there are any NaN values at the provided row ids.
+    pub fn contains_nan(&self, row_ids: &[u32]) -> bool {
+        row_ids.iter().any(|&row_id| match &self {
+            FloatEncoding::Fixed64(c) => c.value::<f64>(row_id).is_nan(),
+            FloatEncoding::FixedNull64(c) => c.value(row_id).map_or(false, f64::is_nan),
+        })
+    }
+
+    /// The minimum non-null value at the provided row ids. NaN values are not
+    /// considered, and `-inf` is smaller than any finite value. Returns NULL
+    /// if there are only NULL or NaN values at the row ids.
+    ///
+    /// Values are only checked for NaN if `contains_nan` is set, i.e., if the
+    /// column may contain NaN values.
+    pub fn min(&self, row_ids: &[u32], contains_nan: bool) -> Value<'_> {
+        if !contains_nan {
+            return match &self {
+                FloatEncoding::Fixed64(c) => Value::Scalar(Scalar::F64(c.min(row_ids))),
+                FloatEncoding::FixedNull64(c) => match c.min(row_ids) {
+                    Some(v) => Value::Scalar(Scalar::F64(v)),
+                    None => Value::Null,
+                },
+            };
+        }
+
+        let min = row_ids
+            .iter()
+            .filter_map(|&row_id| self.non_nan_value(row_id))
+            .fold(None, |min: Option<f64>, v| match min {
+                Some(min) if min <= v => Some(min),
+                _ => Some(v),
+            });
+
+        match min {
+            Some(v) => Value::Scalar(Scalar::F64(v)),
+            None => Value::Null,
+        }
+    }
+
+    /// The maximum non-null value at the provided row ids. NaN values are not
+    /// considered, and `+inf` is larger than any finite value. Returns NULL
+    /// if there are only NULL or NaN values at the row ids.
+    ///
+    /// Values are only checked for NaN if `contains_nan` is set.
+    pub fn max(&self, row_ids: &[u32], contains_nan: bool) -> Value<'_> {
+        if !contains_nan {
+            return match &self {
+                FloatEncoding::Fixed64(c) => Value::Scalar(Scalar::F64(c.max(row_ids))),
+                FloatEncoding::FixedNull64(c) => match c.max(row_ids) {
+                    Some(v) => Value::Scalar(Scalar::F64(v)),
+                    None => Value::Null,
+                },
+            };
+        }
+
+        let max = row_ids
+            .iter()
+            .filter_map(|&row_id| self.non_nan_value(row_id))
+            .fold(None, |max: Option<f64>, v| match max {
+                Some(max) if max >= v => Some(max),
+                _ => Some(v),
+            });
+
+        match max {
+            Some(v) => Value::Scalar(Scalar::F64(v)),
+            None => Value::Null,
+        }
+    }
+
+    /// The sum of the non-null values at the provided row ids. NaN values are
+    /// not considered, and the sum is NULL if there are only NULL or NaN
+    /// values at the row ids.
+    ///
+    /// Values are only checked for NaN if `contains_nan` is set.
+    pub fn sum(&self, row_ids: &[u32], contains_nan: bool) -> Scalar {
+        if !contains_nan {
+            return match &self {
+                FloatEncoding::Fixed64(c) => Scalar::F64(c.sum(row_ids)),
+                FloatEncoding::FixedNull64(c) => match c.sum(row_ids) {
+                    Some(v) => Scalar::F64(v),
+                    None => Scalar::Null,
+                },
+            };
+        }
+
+        row_ids
+            .iter()
+            .filter_map(|&row_id| self.non_nan_value(row_id))
+            .fold(Scalar::Null, |sum, v| match sum {
+                Scalar::F64(sum) => Scalar::F64(sum + v),
+                _ => Scalar::F64(v),
+            })
+    }
+
+    pub fn count(&self, row_ids: &[u32]) -> u32 {
         match &self {
-            FloatEncoding::Fixed64(c) => Value::Scalar(Scalar::F64(c.min(row_ids))),
-            FloatEncoding::FixedNull64(c) => match c.min(row_ids) {
-                Some(v) => Value::Scalar(Scalar::F64(v)),
-                None => Value::Null,
-            },
+            FloatEncoding::Fixed64(c) => c.count(row_ids),
+            FloatEncoding::FixedNull64(c) => c.count(row_ids),
         }
     }
+}
 
-    pub fn max(&self, row_ids: &[u32]) -> Value<'_> {
+pub enum ByteArrayEncoding {
+    // The row ids of an occurrence of each distinct non-null value, in value
+    // order, are cached once the column has been scanned for them.
+    Arrow(arrow::array::BinaryArray, Mutex<Option<Vec<u32>>>),
+}
+
+impl ByteArrayEncoding {
+    pub fn num_rows(&self) -> u32 {
         match &self {
-            FloatEncoding::Fixed64(c) => Value::Scalar(Scalar::F64(c.max(row_ids))),
-            FloatEncoding::FixedNull64(c) => match c.max(row_ids) {
-                Some(v) => Value::Scalar(Scalar::F64(v)),
-                None => Value::Null,
-            },
+            Self::Arrow(arr, _) => arr.len() as u32,
         }
     }
 
-    pub fn sum(&self, row_ids: &[u32]) -> Scalar {
+    /// Determines if the column contains a NULL value.
+    pub fn contains_null(&self) -> bool {
         match &self {
-            FloatEncoding::Fixed64(c) => Scalar::F64(c.sum(row_ids)),
-            FloatEncoding::FixedNull64(c) => match c.sum(row_ids) {
-                Some(v) => Scalar::F64(v),
-                None => Scalar::Null,
+            Self::Arrow(arr, _) => arr.null_count() > 0,
+        }
+    }
+
+    /// Returns the logical value found at the provided row id.
+    pub fn value(&self, row_id: u32) -> Value<'_> {
+        match &self {
+            Self::Arrow(arr, _) => match arr.is_null(row_id as usize) {
+                true => Value::Null,
+                false => Value::ByteArray(arr.value(row_id as usize)),
             },
         }
     }
 
+    /// Returns the logical values found at the provided row ids.
+    pub fn values(&self, row_ids: &[u32]) -> Values<'_> {
+        match &self {
+            Self::Arrow(arr, _) => Values::ByteArray(
+                row_ids
+                    .iter()
+                    .map(|&row_id| match arr.is_null(row_id as usize) {
+                        true => None,
+                        false => Some(arr.value(row_id as usize)),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns all logical values in the column.
+    pub fn all_values(&self) -> Values<'_> {
+        let row_ids = (0..self.num_rows()).collect::<Vec<_>>();
+        self.values(&row_ids)
+    }
+
+    /// Returns the distinct set of values found at the provided row ids.
+    pub fn distinct_values(&self, row_ids: &[u32]) -> ValueSet<'_> {
+        match &self {
+            Self::Arrow(arr, _) => ValueSet::ByteArray(
+                row_ids
+                    .iter()
+                    .map(|&row_id| match arr.is_null(row_id as usize) {
+                        true => None,
+                        false => Some(arr.value(row_id as usize)),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns the distinct set of non-null values in the column.
+    ///
+    /// The column is scanned the first time this is called, and the row ids
+    /// of the distinct values are cached so that later calls don't need to
+    /// scan it.
+    pub fn all_distinct_values(&self) -> ValueSet<'_> {
+        match &self {
+            Self::Arrow(arr, distinct_row_ids) => {
+                let mut distinct_row_ids = distinct_row_ids.lock().unwrap();
+                let row_ids = distinct_row_ids.get_or_insert_with(|| {
+                    let mut first_row_ids = BTreeMap::new();
+                    for i in (0..arr.len()).filter(|&i| !arr.is_null(i)) {
+                        first_row_ids.entry(arr.value(i)).or_insert(i as u32);
+                    }
+                    first_row_ids
+                        .into_iter()
+                        .map(|(_, row_id)| row_id)
+                        .collect()
+                });
+                ValueSet::ByteArray(
+                    row_ids
+                        .iter()
+                        .map(|&row_id| Some(arr.value(row_id as usize)))
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// The count of non-null values at the provided row ids.
     pub fn count(&self, row_ids: &[u32]) -> u32 {
         match &self {
-            FloatEncoding::Fixed64(c) => c.count(row_ids),
-            FloatEncoding::FixedNull64(c) => c.count(row_ids),
+            Self::Arrow(arr, _) => row_ids
+                .iter()
+                .filter(|&&row_id| !arr.is_null(row_id as usize))
+                .count() as u32,
         }
     }
 }
@@ -1525,6 +1954,33 @@ impl From<&[&str]> for Column {
     }
 }
 
+/// Converts an Arrow `BinaryArray` into a column backed by the Arrow array.
+impl From<&arrow::array::BinaryArray> for Column {
+    fn from(arr: &arrow::array::BinaryArray) -> Self {
+        let range = (0..arr.len())
+            .filter(|&i| !arr.is_null(i))
+            .map(|i| arr.value(i))
+            .fold(None, |range: Option<(&[u8], &[u8])>, v| match range {
+                Some((min, max)) => Some((min.min(v), max.max(v))),
+                None => Some((v, v)),
+            })
+            .map(|(min, max)| (min.to_vec(), max.to_vec()));
+
+        let meta = MetaData {
+            size: arr.get_array_memory_size() as u64,
+            rows: arr.len() as u32,
+            range,
+            ..MetaData::default()
+        };
+
+        let data = ByteArrayEncoding::Arrow(
+            arrow::array::BinaryArray::from(arr.data()),
+            Mutex::new(None),
+        );
+        Column::ByteArray(meta, data)
+    }
+}
+
 /// Converts a slice of u64 values into the most compact fixed-width physical
 /// encoding.
 impl From<&[u64]> for Column {
@@ -1873,6 +2329,20 @@ impl From<&[i64]> for Column {
     }
 }
 
+/// Converts a delta encoding of non-decreasing values into a column.
+impl From<delta::Delta> for Column {
+    fn from(data: delta::Delta) -> Self {
+        let rows = data.num_rows();
+        let meta = MetaData {
+            size: data.size(),
+            rows,
+            range: Some((data.value(0), data.value(rows - 1))),
+            ..MetaData::default()
+        };
+        Column::Integer(meta, IntegerEncoding::I64Delta(data))
+    }
+}
+
 impl From<arrow::array::Int64Array> for Column {
     fn from(arr: arrow::array::Int64Array) -> Self {
         if arr.null_count() == 0 {
@@ -2108,7 +2578,10 @@ impl From<&[f64]> for Column {
             size: data.size(),
             rows: data.num_rows(),
             range: Some((min, max)),
-            ..MetaData::default()
+            properties: ColumnProperties {
+                contains_nan: arr.iter().any(|v| v.is_nan()),
+                ..ColumnProperties::default()
+            },
         };
 
         Column::Float(meta, FloatEncoding::Fixed64(data))
@@ -2156,12 +2629,16 @@ impl From<arrow::array::Float64Array> for Column {
             _ => unreachable!("min/max must both be Some or None"),
         };
 
+        let contains_nan = (0..arr.len()).any(|i| !arr.is_null(i) && arr.value(i).is_nan());
         let data = fixed_null::FixedNull::<arrow::datatypes::Float64Type>::from(arr);
         let meta = MetaData {
             size: data.size(),
             rows: data.num_rows(),
             range,
-            ..MetaData::default()
+            properties: ColumnProperties {
+                contains_nan,
+                ..ColumnProperties::default()
+            },
         };
 
         // TODO(edd): currently fixed null only supports 64-bit logical/physical
@@ -2208,12 +2685,40 @@ pub enum AggregateResult<'a> {
 
 #[allow(unused_assignments)]
 impl<'a> AggregateResult<'a> {
+    /// Updates the aggregate with the provided value. NaN values are skipped.
     pub fn update(&mut self, other: Value<'a>) {
+        self.update_with_nan_handling(other, NanHandling::Skip);
+    }
+
+    /// Updates the aggregate with the provided value, treating NaN values
+    /// according to `nan_handling`.
+    pub fn update_with_nan_handling(&mut self, other: Value<'a>, nan_handling: NanHandling) {
         if other.is_null() {
             // a NULL value has no effect on aggregates
             return;
         }
 
+        if other.is_nan() {
+            match self {
+                Self::Count(_) => {} // NaN is not NULL so it is counted
+                Self::Min(v) | Self::Max(v) => {
+                    if let NanHandling::Propagate = nan_handling {
+                        *v = other;
+                    }
+                    return;
+                }
+                Self::Sum(v) => {
+                    if let NanHandling::Propagate = nan_handling {
+                        *v = *other.scalar();
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        // Note: once a `Min`, `Max` or `Sum` aggregate is NaN it will remain NaN
+        // because all comparisons with NaN are false and NaN + x is NaN.
         match self {
             Self::Count(v) => {
                 if !other.is_null() {
@@ -2302,21 +2807,60 @@ impl<'a> AggregateResult<'a> {
         }
     }
 
+    /// Updates the aggregate with the value of a row at `time`.
+    ///
+    /// `First` and `Last` aggregates take the value, which may be NULL, at the
+    /// earliest and latest time respectively. Ties are won by the value already
+    /// held, so rows should be provided in ascending row id order. All other
+    /// aggregates are updated as by `update`.
+    pub fn update_at(&mut self, time: i64, other: Value<'a>) {
+        match self {
+            Self::First(first) => match first {
+                Some((first_time, _)) if *first_time <= time => {}
+                _ => *first = Some((time, other)),
+            },
+            Self::Last(last) => match last {
+                Some((last_time, _)) if *last_time >= time => {}
+                _ => *last = Some((time, other)),
+            },
+            _ => self.update(other),
+        }
+    }
+
     /// Merge `other` into `self`
     pub fn merge(&mut self, other: &AggregateResult<'a>) {
         match (self, other) {
             (AggregateResult::Count(this), AggregateResult::Count(that)) => *this += *that,
             (AggregateResult::Sum(this), AggregateResult::Sum(that)) => *this += that,
+            // NaN aggregates are only produced when NaN values are being
+            // propagated, in which case they must propagate through merges.
             (AggregateResult::Min(this), AggregateResult::Min(that)) => {
-                if *this > *that {
+                if that.is_nan() || *this > *that {
                     *this = *that;
                 }
             }
             (AggregateResult::Max(this), AggregateResult::Max(that)) => {
-                if *this < *that {
+                if that.is_nan() || *this < *that {
                     *this = *that;
                 }
             }
+            // `self` holds the aggregate of earlier rows, so it wins ties.
+            (AggregateResult::First(this), AggregateResult::First(that)) => {
+                if let Some((that_time, _)) = that {
+                    match this {
+                        Some((this_time, _)) if *this_time <= *that_time => {}
+                        _ => *this = *that,
+                    }
+                }
+            }
+            (AggregateResult::Last(this), AggregateResult::Last(that)) => {
+                if let Some((that_time, _)) = that {
+                    match this {
+                        Some((this_time, _)) if *this_time >= *that_time => {}
+                        _ => *this = *that,
+                    }
+                }
+            }
             (a, b) => unimplemented!("merging {:?} into {:?} not yet implemented", b, a),
         }
     }
@@ -2504,6 +3048,51 @@ impl std::fmt::Display for AggregateResult<'_> {
     }
 }
 
+/// An owned copy of an `AggregateResult`, which holds the partial state of an
+/// aggregate independently of the column data it was computed from.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "type", content = "value", rename_all = "snake_case")
+)]
+pub enum OwnedAggregateResult {
+    Count(u64),
+    Sum(Scalar),
+    Min(OwnedValue),
+    Max(OwnedValue),
+    First(Option<(i64, OwnedValue)>),
+    Last(Option<(i64, OwnedValue)>),
+}
+
+impl OwnedAggregateResult {
+    /// Borrows the aggregate as an `AggregateResult`, e.g., so that it can be
+    /// merged with other aggregates.
+    pub fn as_aggregate(&self) -> AggregateResult<'_> {
+        match self {
+            Self::Count(v) => AggregateResult::Count(*v),
+            Self::Sum(v) => AggregateResult::Sum(*v),
+            Self::Min(v) => AggregateResult::Min(v.as_value()),
+            Self::Max(v) => AggregateResult::Max(v.as_value()),
+            Self::First(v) => AggregateResult::First(v.as_ref().map(|(t, v)| (*t, v.as_value()))),
+            Self::Last(v) => AggregateResult::Last(v.as_ref().map(|(t, v)| (*t, v.as_value()))),
+        }
+    }
+}
+
+impl From<&AggregateResult<'_>> for OwnedAggregateResult {
+    fn from(result: &AggregateResult<'_>) -> Self {
+        match result {
+            AggregateResult::Count(v) => Self::Count(*v),
+            AggregateResult::Sum(v) => Self::Sum(*v),
+            AggregateResult::Min(v) => Self::Min((*v).into()),
+            AggregateResult::Max(v) => Self::Max((*v).into()),
+            AggregateResult::First(v) => Self::First(v.map(|(t, v)| (t, v.into()))),
+            AggregateResult::Last(v) => Self::Last(v.map(|(t, v)| (t, v.into()))),
+        }
+    }
+}
+
 /// A scalar is a numerical value that can be aggregated.
 #[derive(Debug, PartialEq, PartialOrd, Copy, Clone)]
 pub enum Scalar {
@@ -2542,6 +3131,10 @@ impl Scalar {
         matches!(self, Self::Null)
     }
 
+    pub fn is_nan(&self) -> bool {
+        matches!(self, Self::F64(v) if v.is_nan())
+    }
+
     // Implementations of all the accessors for the variants of `Scalar`.
     typed_scalar_converters! {
         (as_i64, try_as_i64, i64),
@@ -2567,6 +3160,17 @@ impl Scalar {
             _ => unimplemented!("converting integer Scalar to f64 unsupported"),
         }
     }
+
+    /// Returns a single-element Arrow array containing the scalar. A NULL
+    /// scalar is returned as an array of type `Null` with a NULL element.
+    pub fn to_array(&self) -> array::ArrayRef {
+        match self {
+            Scalar::Null => Arc::new(arrow::array::NullArray::new(1)),
+            Scalar::I64(v) => Arc::new(arrow::array::Int64Array::from(vec![*v])),
+            Scalar::U64(v) => Arc::new(arrow::array::UInt64Array::from(vec![*v])),
+            Scalar::F64(v) => Arc::new(arrow::array::Float64Array::from(vec![*v])),
+        }
+    }
 }
 
 impl std::ops::AddAssign<&Scalar> for Scalar {
@@ -2661,6 +3265,31 @@ pub enum OwnedValue {
     Scalar(Scalar),
 }
 
+impl OwnedValue {
+    /// Borrows the value as a `Value`.
+    pub fn as_value(&self) -> Value<'_> {
+        match self {
+            Self::Null => Value::Null,
+            Self::String(v) => Value::String(v.as_str()),
+            Self::ByteArray(v) => Value::ByteArray(v.as_slice()),
+            Self::Boolean(v) => Value::Boolean(*v),
+            Self::Scalar(v) => Value::Scalar(*v),
+        }
+    }
+}
+
+impl From<Value<'_>> for OwnedValue {
+    fn from(value: Value<'_>) -> Self {
+        match value {
+            Value::Null => Self::Null,
+            Value::String(v) => Self::String(v.to_owned()),
+            Value::ByteArray(v) => Self::ByteArray(v.to_vec()),
+            Value::Boolean(v) => Self::Boolean(v),
+            Value::Scalar(v) => Self::Scalar(v),
+        }
+    }
+}
+
 impl PartialEq<Value<'_>> for OwnedValue {
     fn eq(&self, other: &Value<'_>) -> bool {
         match (&self, other) {
@@ -2700,11 +3329,51 @@ pub enum Value<'a> {
     Scalar(Scalar),
 }
 
-impl Value<'_> {
+impl<'a> Value<'a> {
+    /// The value at row `index` of an Arrow array. String, binary, boolean,
+    /// and 64-bit integer and float arrays are supported.
+    pub fn from_arrow(arr: &'a array::ArrayRef, index: usize) -> Result<Self> {
+        ensure!(
+            index < arr.len(),
+            RowOutOfRange {
+                index,
+                len: arr.len()
+            }
+        );
+
+        if arr.is_null(index) {
+            return Ok(Self::Null);
+        }
+
+        let any = arr.as_any();
+        if let Some(arr) = any.downcast_ref::<array::StringArray>() {
+            Ok(Self::String(arr.value(index)))
+        } else if let Some(arr) = any.downcast_ref::<array::BinaryArray>() {
+            Ok(Self::ByteArray(arr.value(index)))
+        } else if let Some(arr) = any.downcast_ref::<array::BooleanArray>() {
+            Ok(Self::Boolean(arr.value(index)))
+        } else if let Some(arr) = any.downcast_ref::<array::Int64Array>() {
+            Ok(Self::from(arr.value(index)))
+        } else if let Some(arr) = any.downcast_ref::<array::UInt64Array>() {
+            Ok(Self::from(arr.value(index)))
+        } else if let Some(arr) = any.downcast_ref::<array::Float64Array>() {
+            Ok(Self::from(arr.value(index)))
+        } else {
+            UnsupportedArrowType {
+                data_type: arr.data_type().clone(),
+            }
+            .fail()
+        }
+    }
+
     pub fn is_null(&self) -> bool {
         matches!(self, Self::Null)
     }
 
+    pub fn is_nan(&self) -> bool {
+        matches!(self, Self::Scalar(s) if s.is_nan())
+    }
+
     pub fn scalar(&self) -> &Scalar {
         if let Self::Scalar(s) = self {
             return s;
@@ -2793,6 +3462,18 @@ pub enum Values<'a> {
 }
 
 impl<'a> Values<'a> {
+    /// `rows` NULL values of the provided logical type.
+    pub fn nulls(logical_data_type: LogicalDataType, rows: usize) -> Self {
+        match logical_data_type {
+            LogicalDataType::Integer => Self::I64N(vec![None; rows]),
+            LogicalDataType::Unsigned => Self::U64N(vec![None; rows]),
+            LogicalDataType::Float => Self::F64N(vec![None; rows]),
+            LogicalDataType::String => Self::String(vec![None; rows]),
+            LogicalDataType::Binary => Self::ByteArray(vec![None; rows]),
+            LogicalDataType::Boolean => Self::Bool(vec![None; rows]),
+        }
+    }
+
     pub fn len(&self) -> usize {
         match &self {
             Self::String(c) => c.len(),
@@ -2811,6 +3492,20 @@ impl<'a> Values<'a> {
         self.len() == 0
     }
 
+    /// Returns true if any of the values are NULL. Variants that cannot
+    /// represent NULL values never contain them.
+    pub fn contains_null(&self) -> bool {
+        match &self {
+            Self::String(c) => c.iter().any(Option::is_none),
+            Self::I64(_) | Self::U64(_) | Self::F64(_) => false,
+            Self::I64N(c) => c.iter().any(Option::is_none),
+            Self::U64N(c) => c.iter().any(Option::is_none),
+            Self::F64N(c) => c.iter().any(Option::is_none),
+            Self::Bool(c) => c.iter().any(Option::is_none),
+            Self::ByteArray(c) => c.iter().any(Option::is_none),
+        }
+    }
+
     pub fn value(&self, i: usize) -> Value<'a> {
         match &self {
             Self::String(c) => match c[i] {
@@ -2895,6 +3590,20 @@ pub enum ValueSet<'a> {
     ByteArray(BTreeSet<Option<&'a [u8]>>),
 }
 
+impl ValueSet<'_> {
+    /// The number of distinct values in the set.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::String(set) => set.len(),
+            Self::ByteArray(set) => set.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 #[derive(Debug, PartialEq)]
 /// A representation of encoded values for a column.
 pub enum EncodedValues {
@@ -3111,7 +3820,7 @@ impl RowIDs {
 #[cfg(test)]
 mod test {
     use super::*;
-    use arrow_deps::arrow::array::{Int64Array, StringArray};
+    use arrow_deps::arrow::array::{Float64Array, Int64Array, StringArray};
 
     #[test]
     fn row_ids_intersect() {
@@ -3547,7 +4256,52 @@ mod test {
         exp.insert(None);
 
         let col = Column::from(&input[..]);
-        assert_eq!(col.distinct_values(&[0, 1, 2, 3, 4]), ValueSet::String(exp));
+        assert_eq!(
+            col.distinct_values_at(&[0, 1, 2, 3, 4]),
+            ValueSet::String(exp)
+        );
+    }
+
+    #[test]
+    fn distinct_values_all_rows() {
+        let input = &[
+            Some("hello"),
+            None,
+            Some("world"),
+            Some("hello"),
+            Some("world"),
+        ];
+
+        let hello = "hello".to_string();
+        let world = "world".to_string();
+        let exp = ValueSet::String(vec![Some(&hello), Some(&world)].into_iter().collect());
+
+        let rle = Column::from(&input[..]);
+        assert_eq!(rle.distinct_values(), Some(exp));
+
+        let data = StringEncoding::Dictionary(dictionary::Plain::from(input.to_vec()));
+        let plain = Column::String(StringEncoding::meta_from_data(&data), data);
+        assert_eq!(plain.distinct_values(), rle.distinct_values());
+
+        // binary columns are scanned once, and their distinct values cached.
+        let arr = arrow::array::BinaryArray::from(vec![
+            Some(&b"b"[..]),
+            None,
+            Some(&b"a"[..]),
+            Some(&b"b"[..]),
+        ]);
+        let col = Column::from(&arr);
+        let exp = ValueSet::ByteArray(vec![Some(&b"a"[..]), Some(&b"b"[..])].into_iter().collect());
+        assert_eq!(col.distinct_values(), Some(exp));
+        match &col {
+            Column::ByteArray(_, ByteArrayEncoding::Arrow(_, cached)) => {
+                assert_eq!(*cached.lock().unwrap(), Some(vec![2, 0]));
+            }
+            _ => panic!("expected binary column"),
+        }
+        assert_eq!(col.distinct_values(), col.distinct_values());
+
+        assert!(Column::from(&[1.2, 3.3][..]).distinct_values().is_none());
     }
 
     #[test]
@@ -4261,4 +5015,188 @@ mod test {
         res.update(Value::Scalar(Scalar::Null));
         assert!(matches!(res, AggregateResult::Sum(Scalar::I64(15))));
     }
+
+    #[test]
+    fn float_aggregates_nan_and_inf() {
+        let input = &[f64::NAN, 10.5, f64::INFINITY, -2.5, f64::NEG_INFINITY, 3.0];
+        let col = Column::from(&input[..]);
+
+        // NaN values are skipped, and infinities are ordered.
+        assert_eq!(col.min(&[0, 1, 3][..]), Value::from(-2.5));
+        assert_eq!(col.max(&[0, 1, 3][..]), Value::from(10.5));
+        assert_eq!(
+            col.min(&[0, 1, 2, 3, 4][..]),
+            Value::from(f64::NEG_INFINITY)
+        );
+        assert_eq!(col.max(&[0, 1, 2, 3, 4][..]), Value::from(f64::INFINITY));
+        assert_eq!(col.sum(&[0, 1, 5][..]), Scalar::F64(13.5));
+        assert_eq!(col.sum(&[0, 1, 2][..]), Scalar::F64(f64::INFINITY));
+
+        // Only NaN values
+        assert_eq!(col.min(&[0][..]), Value::Null);
+        assert_eq!(col.max(&[0][..]), Value::Null);
+        assert_eq!(col.sum(&[0][..]), Scalar::Null);
+
+        assert!(col.contains_nan(&[0, 1][..]));
+        assert!(!col.contains_nan(&[1, 2, 3, 4, 5][..]));
+
+        // Columns without NaN values are aggregated without checking for them.
+        let col = Column::from(&input[1..]);
+        assert!(!col.properties().contains_nan);
+        assert!(!col.contains_nan(&[0, 1, 2, 3, 4][..]));
+        assert_eq!(
+            col.min(&[0, 1, 2, 3, 4][..]),
+            Value::from(f64::NEG_INFINITY)
+        );
+        assert_eq!(col.max(&[0, 2, 4][..]), Value::from(10.5));
+        assert_eq!(col.sum(&[0, 2, 4][..]), Scalar::F64(11.0));
+
+        let arr = Float64Array::from(vec![Some(f64::NAN), None, Some(1.5), Some(-0.5)]);
+        let col = Column::from(arr);
+        assert_eq!(col.min(&[0, 1, 2, 3][..]), Value::from(-0.5));
+        assert_eq!(col.max(&[0, 1, 2, 3][..]), Value::from(1.5));
+        assert_eq!(col.sum(&[0, 1, 2, 3][..]), Scalar::F64(1.0));
+        assert_eq!(col.sum(&[0, 1][..]), Scalar::Null);
+        assert_eq!(col.count(&[0, 1, 2, 3][..]), 3);
+    }
+
+    #[test]
+    fn cast() {
+        let ints = Column::from(&[1_i64, -2, 3][..]);
+        let floats = Column::from(&[0.5_f64, 1.5][..]);
+
+        let cast = ints.cast(LogicalDataType::Float).unwrap();
+        assert_eq!(cast.logical_datatype(), LogicalDataType::Float);
+        assert_eq!(cast.num_rows(), 3);
+        assert_eq!(cast.all_values(), Values::F64(vec![1.0, -2.0, 3.0]));
+
+        // the cast column can be merged with an existing float column.
+        let arrays = vec![
+            array::ArrayRef::from(cast.all_values()),
+            array::ArrayRef::from(floats.all_values()),
+        ];
+        let merged = arrow::compute::kernels::concat::concat(
+            &arrays.iter().map(|a| a.as_ref()).collect::<Vec<_>>(),
+        )
+        .unwrap();
+        let merged = Column::from(merged.as_any().downcast_ref::<Float64Array>().unwrap());
+        assert_eq!(
+            merged.all_values(),
+            Values::F64(vec![1.0, -2.0, 3.0, 0.5, 1.5])
+        );
+        assert_eq!(merged.min(&[0, 1, 2, 3, 4][..]), Value::from(-2.0));
+
+        // NULL values are preserved.
+        let col = Column::from(Int64Array::from(vec![Some(1), None, Some(3)]));
+        let cast = col.cast(LogicalDataType::Float).unwrap();
+        assert_eq!(
+            cast.all_values(),
+            Values::F64N(vec![Some(1.0), None, Some(3.0)])
+        );
+
+        let col = Column::from(&[u64::MAX, 2][..]);
+        let cast = col.cast(LogicalDataType::Float).unwrap();
+        assert_eq!(cast.all_values(), Values::F64(vec![u64::MAX as f64, 2.0]));
+
+        // casts to the same type are supported.
+        let cast = floats.cast(LogicalDataType::Float).unwrap();
+        assert_eq!(cast.all_values(), floats.all_values());
+
+        // narrowing casts are rejected.
+        assert!(matches!(
+            floats.cast(LogicalDataType::Integer),
+            Err(Error::UnsupportedCast {
+                from: LogicalDataType::Float,
+                to: LogicalDataType::Integer,
+            })
+        ));
+        assert!(ints.cast(LogicalDataType::Unsigned).is_err());
+        let col = Column::from(&["a", "b"][..]);
+        assert!(col.cast(LogicalDataType::Float).is_err());
+    }
+
+    #[test]
+    fn scalar_to_array() {
+        let arr = Scalar::I64(-10).to_array();
+        let arr = arr.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(arr.len(), 1);
+        assert_eq!(arr.value(0), -10);
+
+        let arr = Scalar::U64(10).to_array();
+        let arr = arr
+            .as_any()
+            .downcast_ref::<arrow::array::UInt64Array>()
+            .unwrap();
+        assert_eq!(arr.len(), 1);
+        assert_eq!(arr.value(0), 10);
+
+        let arr = Scalar::F64(1.5).to_array();
+        let arr = arr.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(arr.len(), 1);
+        assert!((arr.value(0) - 1.5).abs() < f64::EPSILON);
+
+        let arr = Scalar::Null.to_array();
+        assert_eq!(arr.data_type(), &arrow::datatypes::DataType::Null);
+        assert_eq!(arr.len(), 1);
+        assert!(arr.is_null(0));
+    }
+
+    #[test]
+    fn aggregate_result_nan() {
+        let nan = Value::Scalar(Scalar::F64(f64::NAN));
+        let values = vec![
+            Value::Scalar(Scalar::F64(2.0)),
+            nan,
+            Value::Scalar(Scalar::F64(f64::NEG_INFINITY)),
+            Value::Scalar(Scalar::F64(f64::INFINITY)),
+        ];
+
+        // NaN values are skipped by default.
+        let mut count = AggregateResult::Count(0);
+        let mut min = AggregateResult::Min(Value::Null);
+        let mut max = AggregateResult::Max(Value::Null);
+        for v in &values {
+            count.update(*v);
+            min.update(*v);
+            max.update(*v);
+        }
+        assert!(matches!(count, AggregateResult::Count(4)));
+        assert_eq!(
+            min,
+            AggregateResult::Min(Value::Scalar(Scalar::F64(f64::NEG_INFINITY)))
+        );
+        assert_eq!(
+            max,
+            AggregateResult::Max(Value::Scalar(Scalar::F64(f64::INFINITY)))
+        );
+
+        let mut sum = AggregateResult::Sum(Scalar::Null);
+        for v in &values[..2] {
+            sum.update(*v);
+        }
+        assert_eq!(sum, AggregateResult::Sum(Scalar::F64(2.0)));
+
+        // A group of only NaN values has a NULL aggregate.
+        let mut min = AggregateResult::Min(Value::Null);
+        min.update(nan);
+        assert_eq!(min, AggregateResult::Min(Value::Null));
+
+        // NaN values can be propagated.
+        let mut min = AggregateResult::Min(Value::Null);
+        let mut max = AggregateResult::Max(Value::Null);
+        let mut sum = AggregateResult::Sum(Scalar::Null);
+        for v in &values {
+            min.update_with_nan_handling(*v, NanHandling::Propagate);
+            max.update_with_nan_handling(*v, NanHandling::Propagate);
+            sum.update_with_nan_handling(*v, NanHandling::Propagate);
+        }
+        assert!(matches!(min, AggregateResult::Min(v) if v.is_nan()));
+        assert!(matches!(max, AggregateResult::Max(v) if v.is_nan()));
+        assert!(matches!(sum, AggregateResult::Sum(v) if v.is_nan()));
+
+        // NaN aggregates propagate through merges.
+        let mut other = AggregateResult::Min(Value::Scalar(Scalar::F64(1.0)));
+        other.merge(&min);
+        assert!(matches!(other, AggregateResult::Min(v) if v.is_nan()));
+    }
 }
@@ -0,0 +1,492 @@
+//! An encoding for non-decreasing integer values, such as the timestamps in a
+//! row group containing a single series.
+//!
+//! Rather than storing every value, the encoding stores the difference between
+//! each value and the one before it. The smallest difference is stored once,
+//! and each difference is stored relative to it using the smallest unsigned
+//! physical type that can represent the largest of them. For example,
+//! timestamps that are roughly ten seconds apart, give or take a few
+//! milliseconds, need four bytes per value rather than eight.
+//!
+//! Regularly spaced values, e.g., timestamps written by an agent on a fixed
+//! interval, all have the same difference, so no per-row data is stored at
+//! all.
+//!
+//! To avoid decoding from the start of the column whenever a value is read,
+//! the absolute value of every `CHECKPOINT_INTERVAL`th row is also stored.
+//! Because values are non-decreasing, the rows satisfying a predicate always
+//! form a contiguous range that can be found by searching the checkpoints,
+//! without decoding the column.
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::mem::size_of;
+
+use crate::column::{cmp, RowIDs};
+
+// The number of rows between stored absolute values.
+const CHECKPOINT_INTERVAL: usize = 128;
+
+// The differences between consecutive values, relative to the smallest
+// difference, stored in the smallest physical type that can represent all of
+// them.
+#[derive(Debug)]
+enum Deltas {
+    // All differences are the same; only the number of rows is stored.
+    Constant(usize),
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Deltas {
+    fn get(&self, i: usize) -> i64 {
+        match self {
+            Self::Constant(_) => 0,
+            Self::U8(deltas) => i64::from(deltas[i]),
+            Self::U16(deltas) => i64::from(deltas[i]),
+            Self::U32(deltas) => i64::from(deltas[i]),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Constant(rows) => *rows,
+            Self::U8(deltas) => deltas.len(),
+            Self::U16(deltas) => deltas.len(),
+            Self::U32(deltas) => deltas.len(),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Self::Constant(_) => size_of::<usize>(),
+            Self::U8(deltas) => size_of::<Vec<u8>>() + deltas.len(),
+            Self::U16(deltas) => size_of::<Vec<u16>>() + size_of::<u16>() * deltas.len(),
+            Self::U32(deltas) => size_of::<Vec<u32>>() + size_of::<u32>() * deltas.len(),
+        }
+    }
+}
+
+/// A Delta encoding stores a column of non-decreasing `i64` values as the
+/// differences between consecutive values. Delta encodings do not support NULL
+/// values.
+#[derive(Debug)]
+pub struct Delta {
+    // The smallest difference between two consecutive values.
+    reference: i64,
+
+    // `deltas[i]` is the difference between the values at rows `i` and `i-1`,
+    // less `reference`. The delta for the first row is never read.
+    deltas: Deltas,
+
+    // `checkpoints[i]` is the value at row `i * CHECKPOINT_INTERVAL`.
+    checkpoints: Vec<i64>,
+}
+
+impl std::fmt::Display for Delta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[Delta] rows: {:?}, size: {}",
+            self.num_rows(),
+            self.size()
+        )
+    }
+}
+
+impl Delta {
+    /// Encodes the values, returning `None` if they are empty, are not
+    /// non-decreasing, if the smallest difference between consecutive values
+    /// can't be represented as an `i64`, or if the spread of differences
+    /// can't be represented with 32 bits.
+    pub fn try_from_values(values: &[i64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let mut deltas = Vec::with_capacity(values.len());
+        for pair in values.windows(2) {
+            let delta = i128::from(pair[1]) - i128::from(pair[0]);
+            if delta < 0 {
+                return None;
+            }
+            deltas.push(delta);
+        }
+
+        // the smallest difference may not fit in an i64 if the values span
+        // most of the range of i64.
+        let reference = deltas.iter().min().copied().unwrap_or(0);
+        let reference_i64 = i64::try_from(reference).ok()?;
+        let spread = deltas.iter().max().copied().unwrap_or(0) - reference;
+        // the first row has no previous value, so its delta is never read.
+        let relative = std::iter::once(0).chain(deltas.iter().map(|&d| d - reference));
+        let deltas = if spread == 0 {
+            Deltas::Constant(values.len())
+        } else if spread <= i128::from(u8::MAX) {
+            Deltas::U8(relative.map(|d| d as u8).collect())
+        } else if spread <= i128::from(u16::MAX) {
+            Deltas::U16(relative.map(|d| d as u16).collect())
+        } else if spread <= i128::from(u32::MAX) {
+            Deltas::U32(relative.map(|d| d as u32).collect())
+        } else {
+            return None;
+        };
+
+        let checkpoints = values
+            .iter()
+            .step_by(CHECKPOINT_INTERVAL)
+            .copied()
+            .collect();
+        Some(Self {
+            reference: reference_i64,
+            deltas,
+            checkpoints,
+        })
+    }
+
+    pub fn num_rows(&self) -> u32 {
+        self.deltas.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.len() == 0
+    }
+
+    /// Returns the total size in bytes of the encoded data, including the
+    /// stored checkpoint values.
+    pub fn size(&self) -> u64 {
+        (size_of::<i64>()
+            + self.deltas.size()
+            + size_of::<Vec<i64>>()
+            + size_of::<i64>() * self.checkpoints.len()) as u64
+    }
+
+    /// Returns the logical (decoded) value at the provided row id.
+    pub fn value(&self, row_id: u32) -> i64 {
+        let row_id = row_id as usize;
+        let checkpoint = row_id / CHECKPOINT_INTERVAL;
+        self.advance(
+            checkpoint * CHECKPOINT_INTERVAL,
+            self.checkpoints[checkpoint],
+            row_id,
+        )
+    }
+
+    /// Returns the logical (decoded) values for the provided row ids.
+    ///
+    /// Row ids are typically ascending, in which case each value is decoded
+    /// from the value before it.
+    pub fn values(&self, row_ids: &[u32], mut dst: Vec<i64>) -> Vec<i64> {
+        dst.clear();
+        dst.reserve(row_ids.len());
+
+        let mut prev: Option<(usize, i64)> = None;
+        for &row_id in row_ids {
+            let row_id = row_id as usize;
+            let v = match prev {
+                Some((prev_row, prev_value))
+                    if prev_row <= row_id && row_id - prev_row <= CHECKPOINT_INTERVAL =>
+                {
+                    self.advance(prev_row, prev_value, row_id)
+                }
+                _ => self.value(row_id as u32),
+            };
+            dst.push(v);
+            prev = Some((row_id, v));
+        }
+        dst
+    }
+
+    /// Returns all logical (decoded) values in the column.
+    pub fn all_values(&self, mut dst: Vec<i64>) -> Vec<i64> {
+        dst.clear();
+        dst.reserve(self.deltas.len());
+
+        let mut v = self.checkpoints[0];
+        dst.push(v);
+        for i in 1..self.deltas.len() {
+            v += self.reference + self.deltas.get(i);
+            dst.push(v);
+        }
+        dst
+    }
+
+    /// Returns the number of rows in the provided row ids. There are no NULL
+    /// values in a Delta encoding.
+    pub fn count(&self, row_ids: &[u32]) -> u32 {
+        row_ids.len() as u32
+    }
+
+    /// Returns the sum of the logical values at the provided row ids.
+    pub fn sum(&self, row_ids: &[u32]) -> i64 {
+        self.values(row_ids, vec![]).iter().sum()
+    }
+
+    /// Returns the minimum logical value at the provided row ids. Because
+    /// values are non-decreasing this is the value of the smallest row id.
+    pub fn min(&self, row_ids: &[u32]) -> i64 {
+        self.value(*row_ids.iter().min().unwrap())
+    }
+
+    /// Returns the maximum logical value at the provided row ids. Because
+    /// values are non-decreasing this is the value of the largest row id.
+    pub fn max(&self, row_ids: &[u32]) -> i64 {
+        self.value(*row_ids.iter().max().unwrap())
+    }
+
+    /// Returns the set of row ids that satisfy a binary operator on a logical
+    /// value.
+    ///
+    /// Matching rows are found by searching the stored checkpoints, so only
+    /// the values around the boundaries of the matching rows are decoded.
+    pub fn row_ids_filter(&self, value: i64, op: &cmp::Operator, mut dst: RowIDs) -> RowIDs {
+        dst.clear();
+
+        let rows = self.num_rows();
+        let (first_gte, first_gt) = self.bounds(value);
+        let ranges = match op {
+            cmp::Operator::Equal => vec![(first_gte, first_gt)],
+            cmp::Operator::NotEqual => vec![(0, first_gte), (first_gt, rows)],
+            op => vec![self.ordered_range(value, op)],
+        };
+
+        for (from, to) in ranges {
+            if from < to {
+                dst.add_range(from, to);
+            }
+        }
+        dst
+    }
+
+    /// Returns the set of row ids that satisfy a pair of binary operators on
+    /// logical values, e.g., `x >= 100 AND x < 200`.
+    ///
+    /// As with `row_ids_filter` only values around the boundaries of the
+    /// matching rows are decoded.
+    pub fn row_ids_filter_range(
+        &self,
+        left: (i64, &cmp::Operator),
+        right: (i64, &cmp::Operator),
+        mut dst: RowIDs,
+    ) -> RowIDs {
+        dst.clear();
+
+        let (left_from, left_to) = self.ordered_range(left.0, left.1);
+        let (right_from, right_to) = self.ordered_range(right.0, right.1);
+        let (from, to) = (left_from.max(right_from), left_to.min(right_to));
+        if from < to {
+            dst.add_range(from, to);
+        }
+        dst
+    }
+
+    // Returns the value at row `to` given the value at row `from`, which must
+    // not be after `to`.
+    fn advance(&self, from: usize, value: i64, to: usize) -> i64 {
+        if let Deltas::Constant(_) = self.deltas {
+            return value + self.reference * (to - from) as i64;
+        }
+
+        let mut value = value;
+        for i in from + 1..=to {
+            value += self.reference + self.deltas.get(i);
+        }
+        value
+    }
+
+    // Returns the half-open range of rows satisfying `x op value` for one of
+    // the ordering operators `>`, `>=`, `<` or `<=`.
+    fn ordered_range(&self, value: i64, op: &cmp::Operator) -> (u32, u32) {
+        let rows = self.num_rows();
+        let (first_gte, first_gt) = self.bounds(value);
+        match op {
+            cmp::Operator::GT => (first_gt, rows),
+            cmp::Operator::GTE => (first_gte, rows),
+            cmp::Operator::LT => (0, first_gte),
+            cmp::Operator::LTE => (0, first_gt),
+            op => panic!("unsupported operator provided: {:?}", op),
+        }
+    }
+
+    // Returns the first row with a value greater than or equal to `value` and
+    // the first row with a value greater than `value`.
+    fn bounds(&self, value: i64) -> (u32, u32) {
+        (
+            self.partition_point(|v| v < value) as u32,
+            self.partition_point(|v| v <= value) as u32,
+        )
+    }
+
+    // Returns the first row whose value does not satisfy `pred`, which must be
+    // satisfied by all of the rows before it.
+    fn partition_point<F>(&self, pred: F) -> usize
+    where
+        F: Fn(i64) -> bool,
+    {
+        // the index of the first checkpoint not satisfying the predicate.
+        let checkpoint = match self.checkpoints.binary_search_by(|&v| {
+            if pred(v) {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        }) {
+            Ok(i) | Err(i) => i,
+        };
+        if checkpoint == 0 {
+            return 0;
+        }
+
+        // the partition point lies between the previous checkpoint and this
+        // one.
+        let end = (checkpoint * CHECKPOINT_INTERVAL).min(self.deltas.len());
+        let mut row = (checkpoint - 1) * CHECKPOINT_INTERVAL;
+        let mut value = self.checkpoints[checkpoint - 1];
+        while pred(value) {
+            row += 1;
+            if row == end {
+                break;
+            }
+            value = self.advance(row - 1, value, row);
+        }
+        row
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::column::fixed::Fixed;
+
+    #[test]
+    fn try_from_values() {
+        assert!(Delta::try_from_values(&[]).is_none());
+        assert!(Delta::try_from_values(&[1, 3, 2]).is_none());
+        assert!(Delta::try_from_values(&[0, 0, i64::from(u32::MAX) + 1]).is_none());
+        // the difference between the values doesn't fit in an i64.
+        assert!(Delta::try_from_values(&[i64::MIN, i64::MAX]).is_none());
+
+        let enc = Delta::try_from_values(&[10, 12, 12, 300]).unwrap();
+        assert!(matches!(enc.deltas, Deltas::U16(_)));
+        assert_eq!(enc.num_rows(), 4);
+        assert_eq!(enc.all_values(vec![]), vec![10, 12, 12, 300]);
+    }
+
+    #[test]
+    fn value() {
+        let values = (0..1000).map(|i| -500 + i * 3).collect::<Vec<i64>>();
+        let enc = Delta::try_from_values(&values).unwrap();
+        assert!(matches!(enc.deltas, Deltas::Constant(_)));
+
+        for (i, &v) in values.iter().enumerate() {
+            assert_eq!(enc.value(i as u32), v);
+        }
+
+        let row_ids = [0, 1, 127, 128, 129, 700, 3, 999];
+        assert_eq!(
+            enc.values(&row_ids, vec![]),
+            row_ids
+                .iter()
+                .map(|&i| values[i as usize])
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(enc.all_values(vec![]), values);
+    }
+
+    #[test]
+    fn aggregates() {
+        let enc = Delta::try_from_values(&[1, 2, 4, 8, 16]).unwrap();
+        assert_eq!(enc.count(&[0, 2, 4]), 3);
+        assert_eq!(enc.sum(&[0, 2, 4]), 21);
+        assert_eq!(enc.min(&[4, 2]), 4);
+        assert_eq!(enc.max(&[1, 3, 2]), 8);
+    }
+
+    #[test]
+    fn row_ids_filter() {
+        let enc = Delta::try_from_values(&[1, 2, 4, 4, 16]).unwrap();
+
+        let row_ids = enc.row_ids_filter(4, &cmp::Operator::Equal, RowIDs::new_vector());
+        assert_eq!(row_ids.to_vec(), vec![2, 3]);
+
+        let row_ids = enc.row_ids_filter_range(
+            (2, &cmp::Operator::GTE),
+            (16, &cmp::Operator::LT),
+            RowIDs::new_vector(),
+        );
+        assert_eq!(row_ids.to_vec(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn row_ids_filter_checkpoints() {
+        // values span several checkpoints and repeat across their boundaries.
+        let values = (0..1000).map(|i| i / 3).collect::<Vec<i64>>();
+        let enc = Delta::try_from_values(&values).unwrap();
+
+        let cases = vec![
+            (-1, cmp::Operator::GT),
+            (42, cmp::Operator::Equal),
+            (42, cmp::Operator::NotEqual),
+            (100, cmp::Operator::GT),
+            (100, cmp::Operator::GTE),
+            (100, cmp::Operator::LT),
+            (100, cmp::Operator::LTE),
+            (333, cmp::Operator::GTE),
+            (334, cmp::Operator::GTE),
+        ];
+
+        for (v, op) in cases {
+            let exp = values
+                .iter()
+                .enumerate()
+                .filter(|(_, &x)| match op {
+                    cmp::Operator::Equal => x == v,
+                    cmp::Operator::NotEqual => x != v,
+                    cmp::Operator::GT => x > v,
+                    cmp::Operator::GTE => x >= v,
+                    cmp::Operator::LT => x < v,
+                    cmp::Operator::LTE => x <= v,
+                })
+                .map(|(i, _)| i as u32)
+                .collect::<Vec<_>>();
+
+            let row_ids = enc.row_ids_filter(v, &op, RowIDs::new_vector());
+            assert_eq!(row_ids.to_vec(), exp, "{:?} {}", op, v);
+        }
+
+        let row_ids = enc.row_ids_filter_range(
+            (42, &cmp::Operator::GT),
+            (300, &cmp::Operator::LTE),
+            RowIDs::new_bitmap(),
+        );
+        assert_eq!(row_ids.to_vec(), (129..903).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn evenly_spaced_size() {
+        // 10,000 timestamps ten seconds apart.
+        let values = (0..10_000)
+            .map(|i| 1_600_000_000_000_000_000 + i * 10_000_000_000)
+            .collect::<Vec<i64>>();
+        let enc = Delta::try_from_values(&values).unwrap();
+        assert!(matches!(enc.deltas, Deltas::Constant(_)));
+        assert_eq!(enc.all_values(vec![]), values);
+
+        // the encoding is more than 50x smaller than plain i64 storage.
+        let plain = Fixed::<i64>::from(values.as_slice());
+        assert!(enc.size() * 50 < plain.size());
+
+        let row_ids = enc.row_ids_filter_range(
+            (
+                1_600_000_000_000_000_000 + 95_000_000_000,
+                &cmp::Operator::GTE,
+            ),
+            (
+                1_600_000_000_000_000_000 + 150_000_000_000,
+                &cmp::Operator::LT,
+            ),
+            RowIDs::new_vector(),
+        );
+        assert_eq!(row_ids.to_vec(), (10..15).collect::<Vec<u32>>());
+    }
+}
@@ -0,0 +1,341 @@
+//! `serde` support for `Scalar`, `Value` and `OwnedValue`, enabled by the
+//! `serde` feature.
+//!
+//! Each value is encoded as an object whose `type` field names its variant
+//! and whose `value` field holds its data, so that integer and float types
+//! remain distinguishable once encoded. In JSON:
+//!
+//! ```text
+//! {"type":"null"}
+//! {"type":"string","value":"west"}
+//! {"type":"byte_array","value":[104,105]}
+//! {"type":"boolean","value":true}
+//! {"type":"i64","value":-42}
+//! {"type":"u64","value":42}
+//! {"type":"f64","value":1.5}
+//! ```
+//!
+//! Numbers that JSON consumers can't represent exactly are encoded as strings:
+//! integers outside the range `±(2^53 - 1)`, e.g.,
+//! `{"type":"u64","value":"18446744073709551615"}`, and non-finite floats,
+//! i.e., `"NaN"`, `"inf"` and `"-inf"`. Either form is accepted when
+//! decoding.
+//!
+//! Numeric scalars are encoded the same way whether or not they are wrapped
+//! in a `Value::Scalar`, so `Scalar::Null` and `Value::Null` are both encoded
+//! as `{"type":"null"}`, and `OwnedValue::Scalar(Scalar::Null)` decodes as
+//! `OwnedValue::Null`.
+//!
+//! `Value` borrows its data and can only be encoded; decode into an
+//! `OwnedValue` instead.
+use std::borrow::Cow;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{OwnedValue, Scalar, Value};
+
+// The largest integer magnitude that can be represented exactly by an IEEE
+// 754 double, which is how many JSON implementations store numbers.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+// The encoded form shared by all of the value types.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+enum Repr<'a> {
+    Null,
+    String(Cow<'a, str>),
+    ByteArray(Cow<'a, [u8]>),
+    Boolean(bool),
+    I64(#[serde(with = "i64_number")] i64),
+    U64(#[serde(with = "u64_number")] u64),
+    F64(#[serde(with = "f64_number")] f64),
+}
+
+impl From<Scalar> for Repr<'_> {
+    fn from(scalar: Scalar) -> Self {
+        match scalar {
+            Scalar::Null => Self::Null,
+            Scalar::I64(v) => Self::I64(v),
+            Scalar::U64(v) => Self::U64(v),
+            Scalar::F64(v) => Self::F64(v),
+        }
+    }
+}
+
+impl<'a> From<Value<'a>> for Repr<'a> {
+    fn from(value: Value<'a>) -> Self {
+        match value {
+            Value::Null => Self::Null,
+            Value::String(v) => Self::String(Cow::Borrowed(v)),
+            Value::ByteArray(v) => Self::ByteArray(Cow::Borrowed(v)),
+            Value::Boolean(v) => Self::Boolean(v),
+            Value::Scalar(v) => v.into(),
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedValue> for Repr<'a> {
+    fn from(value: &'a OwnedValue) -> Self {
+        match value {
+            OwnedValue::Null => Self::Null,
+            OwnedValue::String(v) => Self::String(Cow::Borrowed(v)),
+            OwnedValue::ByteArray(v) => Self::ByteArray(Cow::Borrowed(v)),
+            OwnedValue::Boolean(v) => Self::Boolean(*v),
+            OwnedValue::Scalar(v) => (*v).into(),
+        }
+    }
+}
+
+impl Serialize for Scalar {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Repr::from(*self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Scalar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Repr::deserialize(deserializer)? {
+            Repr::Null => Ok(Self::Null),
+            Repr::I64(v) => Ok(Self::I64(v)),
+            Repr::U64(v) => Ok(Self::U64(v)),
+            Repr::F64(v) => Ok(Self::F64(v)),
+            Repr::String(_) | Repr::ByteArray(_) | Repr::Boolean(_) => {
+                Err(de::Error::custom("expected a null, i64, u64 or f64 scalar"))
+            }
+        }
+    }
+}
+
+impl Serialize for Value<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Repr::from(*self).serialize(serializer)
+    }
+}
+
+impl Serialize for OwnedValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Repr::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for OwnedValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Null => Self::Null,
+            Repr::String(v) => Self::String(v.into_owned()),
+            Repr::ByteArray(v) => Self::ByteArray(v.into_owned()),
+            Repr::Boolean(v) => Self::Boolean(v),
+            Repr::I64(v) => Self::Scalar(Scalar::I64(v)),
+            Repr::U64(v) => Self::Scalar(Scalar::U64(v)),
+            Repr::F64(v) => Self::Scalar(Scalar::F64(v)),
+        })
+    }
+}
+
+// A number that is encoded as a string when it can't be represented exactly
+// as a JSON number.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString<T> {
+    Number(T),
+    String(String),
+}
+
+impl<T: std::str::FromStr> NumberOrString<T> {
+    fn into_number<E: de::Error>(self) -> Result<T, E> {
+        match self {
+            Self::Number(v) => Ok(v),
+            Self::String(s) => s
+                .parse()
+                .map_err(|_| E::custom(format!("invalid number: {:?}", s))),
+        }
+    }
+}
+
+mod i64_number {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        let max = MAX_SAFE_INTEGER as i64;
+        if (-max..=max).contains(v) {
+            serializer.serialize_i64(*v)
+        } else {
+            serializer.collect_str(v)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        NumberOrString::deserialize(deserializer)?.into_number()
+    }
+}
+
+mod u64_number {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        if *v <= MAX_SAFE_INTEGER {
+            serializer.serialize_u64(*v)
+        } else {
+            serializer.collect_str(v)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        NumberOrString::deserialize(deserializer)?.into_number()
+    }
+}
+
+mod f64_number {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if v.is_finite() {
+            serializer.serialize_f64(*v)
+        } else {
+            // `Display` produces "NaN", "inf" or "-inf", which `parse` accepts.
+            serializer.collect_str(v)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        NumberOrString::deserialize(deserializer)?.into_number()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(value: OwnedValue, expected_json: &str) {
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, expected_json);
+        let decoded: OwnedValue = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn owned_value_round_trip() {
+        round_trip(OwnedValue::Null, r#"{"type":"null"}"#);
+        round_trip(
+            OwnedValue::String("west \"1\"".to_owned()),
+            r#"{"type":"string","value":"west \"1\""}"#,
+        );
+        round_trip(
+            OwnedValue::ByteArray(b"hi".to_vec()),
+            r#"{"type":"byte_array","value":[104,105]}"#,
+        );
+        round_trip(
+            OwnedValue::Boolean(true),
+            r#"{"type":"boolean","value":true}"#,
+        );
+        round_trip(
+            OwnedValue::Scalar(Scalar::I64(-42)),
+            r#"{"type":"i64","value":-42}"#,
+        );
+        round_trip(
+            OwnedValue::Scalar(Scalar::U64(42)),
+            r#"{"type":"u64","value":42}"#,
+        );
+        round_trip(
+            OwnedValue::Scalar(Scalar::F64(1.5)),
+            r#"{"type":"f64","value":1.5}"#,
+        );
+
+        // integers that can't be represented exactly by a double are strings.
+        round_trip(
+            OwnedValue::Scalar(Scalar::I64(MAX_SAFE_INTEGER as i64)),
+            r#"{"type":"i64","value":9007199254740991}"#,
+        );
+        round_trip(
+            OwnedValue::Scalar(Scalar::I64(i64::MIN)),
+            r#"{"type":"i64","value":"-9223372036854775808"}"#,
+        );
+        round_trip(
+            OwnedValue::Scalar(Scalar::U64(MAX_SAFE_INTEGER + 1)),
+            r#"{"type":"u64","value":"9007199254740992"}"#,
+        );
+        round_trip(
+            OwnedValue::Scalar(Scalar::U64(u64::MAX)),
+            r#"{"type":"u64","value":"18446744073709551615"}"#,
+        );
+
+        // as are non-finite floats.
+        round_trip(
+            OwnedValue::Scalar(Scalar::F64(f64::INFINITY)),
+            r#"{"type":"f64","value":"inf"}"#,
+        );
+        round_trip(
+            OwnedValue::Scalar(Scalar::F64(f64::NEG_INFINITY)),
+            r#"{"type":"f64","value":"-inf"}"#,
+        );
+        let json = serde_json::to_string(&OwnedValue::Scalar(Scalar::F64(f64::NAN))).unwrap();
+        assert_eq!(json, r#"{"type":"f64","value":"NaN"}"#);
+        match serde_json::from_str::<OwnedValue>(&json).unwrap() {
+            OwnedValue::Scalar(Scalar::F64(v)) => assert!(v.is_nan()),
+            v => panic!("unexpected value {:?}", v),
+        }
+
+        // a NULL scalar is indistinguishable from a NULL value.
+        let json = serde_json::to_string(&OwnedValue::Scalar(Scalar::Null)).unwrap();
+        assert_eq!(json, r#"{"type":"null"}"#);
+        let decoded: OwnedValue = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, OwnedValue::Null);
+    }
+
+    #[test]
+    fn scalar_round_trip() {
+        let scalars = vec![
+            (Scalar::Null, r#"{"type":"null"}"#),
+            (Scalar::I64(-1), r#"{"type":"i64","value":-1}"#),
+            (
+                Scalar::U64(u64::MAX),
+                r#"{"type":"u64","value":"18446744073709551615"}"#,
+            ),
+            (Scalar::F64(-0.5), r#"{"type":"f64","value":-0.5}"#),
+        ];
+
+        for (scalar, expected_json) in scalars {
+            let json = serde_json::to_string(&scalar).unwrap();
+            assert_eq!(json, expected_json);
+            let decoded: Scalar = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded, scalar);
+        }
+
+        // small integers may also be encoded as strings.
+        let decoded: Scalar = serde_json::from_str(r#"{"type":"u64","value":"10"}"#).unwrap();
+        assert_eq!(decoded, Scalar::U64(10));
+
+        // non-numeric values aren't scalars.
+        assert!(serde_json::from_str::<Scalar>(r#"{"type":"boolean","value":true}"#).is_err());
+        assert!(serde_json::from_str::<Scalar>(r#"{"type":"u64","value":"-1"}"#).is_err());
+        assert!(serde_json::from_str::<Scalar>(r#"{"type":"i64","value":1.5}"#).is_err());
+    }
+
+    #[test]
+    fn value_serialize() {
+        let values = vec![
+            (Value::Null, r#"{"type":"null"}"#),
+            (Value::String("east"), r#"{"type":"string","value":"east"}"#),
+            (
+                Value::ByteArray(&[0, 255]),
+                r#"{"type":"byte_array","value":[0,255]}"#,
+            ),
+            (Value::Boolean(false), r#"{"type":"boolean","value":false}"#),
+            (Value::Scalar(Scalar::I64(3)), r#"{"type":"i64","value":3}"#),
+            (Value::Scalar(Scalar::U64(3)), r#"{"type":"u64","value":3}"#),
+            (
+                Value::Scalar(Scalar::F64(3.0)),
+                r#"{"type":"f64","value":3.0}"#,
+            ),
+        ];
+
+        for (value, expected_json) in values {
+            let json = serde_json::to_string(&value).unwrap();
+            assert_eq!(json, expected_json);
+
+            // values decode into their owned equivalent.
+            let decoded: OwnedValue = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded, OwnedValue::from(value));
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Choosing how the columns of a row group are encoded.
+use std::collections::BTreeMap;
+
+use arrow_deps::arrow::{self, array::Array};
+
+use crate::column::{delta::Delta, Column, StringEncoding};
+
+// The number of leading values inspected when deciding whether a column is
+// worth trying to delta encode.
+const SAMPLE_ROWS: usize = 1024;
+
+/// The kinds of encoding that can be used to store a column's data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EncodingKind {
+    /// Values are stored as they are, using the smallest physical type that
+    /// can represent them.
+    Plain,
+
+    /// Each distinct value is stored once in a sorted dictionary, and each
+    /// row stores the id of its value. Only supported for string columns.
+    Dictionary,
+
+    /// As `Dictionary`, but runs of rows with the same value are stored once.
+    /// Only supported for string columns.
+    RLE,
+
+    /// Values are stored as the differences between consecutive values. Only
+    /// supported for integer columns whose values are non-decreasing and
+    /// non-NULL.
+    Delta,
+}
+
+/// Determines how the columns of new row groups are encoded, based on the
+/// type of each column and a sample of its data.
+///
+/// By default:
+///
+///   * tag columns use `RLE`, or `Dictionary` if they have a very large number
+///     of distinct values;
+///   * time columns use `Delta` if their values are non-decreasing, which is
+///     typical for a row group containing a single series;
+///   * all other columns use `Plain`.
+///
+/// The encoding can be overridden for columns with a particular name. If the
+/// chosen encoding can't store the column's data, e.g., `Delta` for values
+/// that aren't non-decreasing, then the default encoding is used instead.
+#[derive(Debug, Default, Clone)]
+pub struct EncodingStrategy {
+    overrides: BTreeMap<String, EncodingKind>,
+}
+
+impl EncodingStrategy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses `encoding` for all columns called `column_name`, if it can store
+    /// their data.
+    pub fn with_column_encoding(mut self, column_name: &str, encoding: EncodingKind) -> Self {
+        self.overrides.insert(column_name.to_owned(), encoding);
+        self
+    }
+
+    /// Encodes a tag column.
+    pub fn tag_column(&self, column_name: &str, arr: &arrow::array::StringArray) -> Column {
+        let rle = match self.overrides.get(column_name) {
+            Some(EncodingKind::RLE) => Some(true),
+            Some(EncodingKind::Dictionary) => Some(false),
+            _ => None,
+        };
+
+        let data = StringEncoding::from_arrow_string_array_with_rle(arr, rle);
+        Column::String(StringEncoding::meta_from_data(&data), data)
+    }
+
+    /// Encodes an integer field column.
+    pub fn field_column(&self, column_name: &str, arr: &arrow::array::Int64Array) -> Column {
+        match self.overrides.get(column_name) {
+            Some(EncodingKind::Delta) => integer_column(arr, true),
+            _ => integer_column(arr, false),
+        }
+    }
+
+    /// Encodes the time column.
+    pub fn time_column(&self, column_name: &str, arr: &arrow::array::Int64Array) -> Column {
+        let delta = match self.overrides.get(column_name) {
+            Some(EncodingKind::Delta) => true,
+            Some(_) => false,
+            None => {
+                let sample = &arr.values()[..arr.len().min(SAMPLE_ROWS)];
+                sample.windows(2).all(|pair| pair[0] <= pair[1])
+            }
+        };
+        integer_column(arr, delta)
+    }
+}
+
+// Encodes the integer array, using a delta encoding if `delta` is true and
+// the values can be delta encoded.
+fn integer_column(arr: &arrow::array::Int64Array, delta: bool) -> Column {
+    if delta && arr.null_count() == 0 {
+        if let Some(data) = Delta::try_from_values(arr.values()) {
+            return Column::from(data);
+        }
+    }
+    Column::from(arr)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::column::IntegerEncoding;
+
+    #[test]
+    fn time_column_delta_encoding() {
+        let times = (0..10_000)
+            .map(|i| 1_600_000_000_000_000_000 + i * 1_000_000)
+            .collect::<Vec<i64>>();
+        let arr = arrow::array::Int64Array::from(times);
+
+        let strategy = EncodingStrategy::default();
+        let col = strategy.time_column("time", &arr);
+        assert!(matches!(
+            col,
+            Column::Integer(_, IntegerEncoding::I64Delta(_))
+        ));
+        assert_eq!(col.column_range(), Column::from(&arr).column_range());
+
+        // the encoded column is smaller than storing plain i64 values.
+        let plain = EncodingStrategy::new()
+            .with_column_encoding("time", EncodingKind::Plain)
+            .time_column("time", &arr);
+        assert!(matches!(
+            plain,
+            Column::Integer(_, IntegerEncoding::I64I64(_))
+        ));
+        assert!(col.size() < plain.size() * 6 / 10);
+
+        // values that aren't non-decreasing can't be delta encoded.
+        let arr = arrow::array::Int64Array::from(vec![3, 2, 1]);
+        let col = strategy.time_column("time", &arr);
+        assert!(!matches!(
+            col,
+            Column::Integer(_, IntegerEncoding::I64Delta(_))
+        ));
+    }
+
+    #[test]
+    fn column_encoding_overrides() {
+        let strategy = EncodingStrategy::new()
+            .with_column_encoding("region", EncodingKind::Dictionary)
+            .with_column_encoding("counter", EncodingKind::Delta)
+            .with_column_encoding("load", EncodingKind::Delta);
+
+        let arr = arrow::array::StringArray::from(vec!["east", "east", "west"]);
+        assert!(matches!(
+            strategy.tag_column("region", &arr),
+            Column::String(_, StringEncoding::Dictionary(_))
+        ));
+        assert!(matches!(
+            strategy.tag_column("host", &arr),
+            Column::String(_, StringEncoding::RLEDictionary(_))
+        ));
+
+        let arr = arrow::array::Int64Array::from(vec![10, 20, 30]);
+        assert!(matches!(
+            strategy.field_column("counter", &arr),
+            Column::Integer(_, IntegerEncoding::I64Delta(_))
+        ));
+        assert!(!matches!(
+            strategy.field_column("other", &arr),
+            Column::Integer(_, IntegerEncoding::I64Delta(_))
+        ));
+
+        // falls back to the default encoding.
+        let arr = arrow::array::Int64Array::from(vec![30, 20, 10]);
+        assert!(!matches!(
+            strategy.field_column("load", &arr),
+            Column::Integer(_, IntegerEncoding::I64Delta(_))
+        ));
+    }
+}
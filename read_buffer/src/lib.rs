@@ -2,6 +2,7 @@
 #![allow(dead_code)]
 #![allow(clippy::too_many_arguments)]
 #![allow(unused_variables)]
+pub(crate) mod cache;
 pub(crate) mod chunk;
 pub(crate) mod column;
 pub(crate) mod row_group;
@@ -9,27 +10,167 @@ mod schema;
 pub(crate) mod table;
 
 use std::{
-    collections::{btree_map::Entry, BTreeMap, BTreeSet},
-    convert::TryInto,
+    cmp::Reverse,
+    collections::{btree_map::Entry, BTreeMap, BTreeSet, BinaryHeap},
+    convert::{TryFrom, TryInto},
     fmt,
+    ops::Bound,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
-use arrow_deps::{arrow::record_batch::RecordBatch, util::str_iter_to_batch};
+use arrow_deps::{
+    arrow::{
+        array::{
+            Array, ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, StringArray,
+            UInt64Array,
+        },
+        compute::kernels::{
+            concat::concat,
+            sort::{lexsort_to_indices, SortColumn},
+            take::take,
+        },
+        datatypes::{DataType, Field, Schema as ArrowSchema, SchemaRef},
+        record_batch::RecordBatch,
+    },
+    util::str_iter_to_batch,
+};
+use data_types::schema::{InfluxColumnType, InfluxFieldType, Schema, MEASUREMENT_METADATA_KEY};
 use snafu::{ensure, OptionExt, ResultExt, Snafu};
 
 // Identifiers that are exported as part of the public API.
-pub use row_group::{BinaryExpr, Predicate};
+pub use column::strategy::{EncodingKind, EncodingStrategy};
+pub use column::{OwnedValue, Scalar, Value};
+pub use row_group::{BinaryExpr, Literal, Predicate, PredicatePushdown};
 pub use schema::*;
-pub use table::ColumnSelection;
+pub use table::{ArithmeticOperator, ColumnSelection, Operand, Projection};
 
+use cache::CacheConfig;
 use chunk::Chunk;
-use row_group::{ColumnName, RowGroup};
+use column::{AggregateResult, OwnedAggregateResult};
+use row_group::{ColumnMeta, ColumnName, RowGroup, TIME_COLUMN_NAME};
 use table::Table;
 
 /// The name of the column containing table names returned by a call to
 /// `table_names`.
 pub const TABLE_NAMES_COLUMN_NAME: &str = "table";
 
+/// The name of the column containing field keys returned by a call to
+/// `field_keys`.
+pub const FIELD_KEYS_COLUMN_NAME: &str = "field";
+
+/// The column type metadata values, as stored in the metadata of a record
+/// batch's schema, of the columns that can be added with `upsert_partition`.
+/// String and boolean field columns are not supported.
+pub const VALID_COLUMN_TYPES: [&str; 5] = [
+    "iox::column_type::tag",
+    "iox::column_type::field::float",
+    "iox::column_type::field::integer",
+    "iox::column_type::field::uinteger",
+    "iox::column_type::timestamp",
+];
+
+/// Determines if `column_type` is the column type metadata value of a column
+/// that can be added with `upsert_partition`, allowing record batches to be
+/// validated as they are built.
+pub fn is_valid_column_type(column_type: &str) -> bool {
+    match InfluxColumnType::try_from(column_type) {
+        Ok(InfluxColumnType::Tag) | Ok(InfluxColumnType::Timestamp) => true,
+        Ok(InfluxColumnType::Field(field_type)) => matches!(
+            field_type,
+            InfluxFieldType::Float | InfluxFieldType::Integer | InfluxFieldType::UInteger
+        ),
+        Err(_) => false,
+    }
+}
+
+/// Validates the IOx metadata of a record batch, so that producers can check
+/// record batches before they are added with `upsert_partition` without
+/// needing a `Database`.
+///
+/// Column names must be unique, and the schema's metadata must map each
+/// column to a valid column type (see `is_valid_column_type`), with no entries
+/// for unknown columns other than the measurement name. Binary columns have no
+/// column type, and are added as fields of arbitrary bytes. There must be
+/// exactly one timestamp column, called "time", with `Int64` values.
+pub fn validate_iox_record_batch(rb: &RecordBatch) -> Result<()> {
+    let schema = rb.schema();
+    let metadata = schema.metadata();
+
+    let mut column_names = BTreeSet::new();
+    let mut time_columns = vec![];
+    for field in schema.fields() {
+        let name = field.name();
+        ensure!(
+            column_names.insert(name.as_str()),
+            InvalidRecordBatch {
+                msg: format!("duplicate column {:?}", name),
+            }
+        );
+
+        let column_type = match metadata.get(name) {
+            Some(column_type) => column_type,
+            None if is_untyped_column(field) => continue,
+            None => {
+                return InvalidRecordBatch {
+                    msg: format!("missing column type for column {:?}", name),
+                }
+                .fail()
+            }
+        };
+        ensure!(
+            is_valid_column_type(column_type),
+            InvalidRecordBatch {
+                msg: format!(
+                    "invalid column type {:?} for column {:?}",
+                    column_type, name
+                ),
+            }
+        );
+        if matches!(
+            InfluxColumnType::try_from(column_type.as_str()),
+            Ok(InfluxColumnType::Timestamp)
+        ) {
+            time_columns.push(field);
+        }
+    }
+
+    for key in metadata.keys() {
+        ensure!(
+            key == MEASUREMENT_METADATA_KEY || column_names.contains(key.as_str()),
+            InvalidRecordBatch {
+                msg: format!("column type for unknown column {:?}", key),
+            }
+        );
+    }
+
+    match time_columns.as_slice() {
+        [field] => ensure!(
+            field.name() == TIME_COLUMN_NAME && field.data_type() == &DataType::Int64,
+            InvalidRecordBatch {
+                msg: format!(
+                    "timestamp column {:?} with type {:?} must be called {:?} with type Int64",
+                    field.name(),
+                    field.data_type(),
+                    TIME_COLUMN_NAME,
+                ),
+            }
+        ),
+        _ => {
+            return InvalidRecordBatch {
+                msg: format!(
+                    "expected one timestamp column, found {}",
+                    time_columns.len()
+                ),
+            }
+            .fail()
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("arrow conversion error: {}", source))]
@@ -46,17 +187,107 @@ pub enum Error {
     #[snafu(display("table does not exist: {}", table_name))]
     TableNotFound { table_name: String },
 
+    #[snafu(display("table already exists: {}", table_name))]
+    TableAlreadyExists { table_name: String },
+
+    #[snafu(display("invalid data for table {}: {}", table_name, msg))]
+    InvalidTableData { table_name: String, msg: String },
+
+    #[snafu(display("invalid record batch: {}", msg))]
+    InvalidRecordBatch { msg: String },
+
+    #[snafu(display("schema conversion error: {}", source))]
+    SchemaError {
+        source: data_types::schema::builder::Error,
+    },
+
+    #[snafu(display("column has different types across chunks: {}", column_name))]
+    ColumnTypeMismatch { column_name: String },
+
     #[snafu(display("unsupported operation: {}", msg))]
     UnsupportedOperation { msg: String },
 
+    #[snafu(display("invalid partial aggregate: {}", msg))]
+    InvalidPartialAggregate { msg: String },
+
     #[snafu(display("unsupported aggregate: {}", agg))]
     UnsupportedAggregate { agg: AggregateType },
+
+    #[snafu(display("{} column does not exist: {}", role, column_name))]
+    ColumnNotFound {
+        column_name: String,
+        role: ColumnRole,
+    },
+
+    #[snafu(display(
+        "row group index {} out of range for table with {} row groups",
+        index,
+        row_groups
+    ))]
+    RowGroupNotFound { index: usize, row_groups: usize },
+
+    #[snafu(display("query cancelled"))]
+    Cancelled,
+
+    #[snafu(display(
+        "tag column {} in table {} would have more than {} distinct values",
+        column_name,
+        table_name,
+        limit
+    ))]
+    TagCardinalityExceeded {
+        table_name: String,
+        column_name: String,
+        limit: usize,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// The role a column plays within a query. Used to describe which part of a
+/// query referenced a column that does not exist.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnRole {
+    Group,
+    Aggregate,
+    Predicate,
+    Projection,
+    Select,
+    Having,
+}
+
+impl fmt::Display for ColumnRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Group => write!(f, "group"),
+            Self::Aggregate => write!(f, "aggregate"),
+            Self::Predicate => write!(f, "predicate"),
+            Self::Projection => write!(f, "projection"),
+            Self::Select => write!(f, "select"),
+            Self::Having => write!(f, "having"),
+        }
+    }
+}
+
+/// Determines what happens when data added to a table would result in a tag
+/// column with more distinct values than the database's tag cardinality limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagCardinalityAction {
+    /// The data is rejected with an error and none of it is added.
+    Reject,
+
+    /// The column's values are stored in a string field column instead of a
+    /// tag column.
+    ConvertToField,
+}
+
 // A database is scoped to a single tenant. Within a database there exists
 // partitions, chunks, tables and row groups.
+//
+// A `Database` is `Send + Sync` so that it can be shared across threads, for
+// example behind an `RwLock`. Any state mutated through a shared reference,
+// such as a table's decode cache, must therefore use thread-safe interior
+// mutability (e.g., `Mutex`) rather than `Cell`/`RefCell` or `Rc`.
 #[derive(Default)]
 pub struct Database {
     // The collection of partitions for the database. Each partition is uniquely
@@ -68,6 +299,27 @@ pub struct Database {
 
     // Total number of rows in the database.
     rows: u64,
+
+    // Sentinel values, by column name, that represent missing values in
+    // integer field columns. Matching values are stored as NULL on ingest.
+    integer_null_sentinels: BTreeMap<String, i64>,
+
+    // The maximum number of rows in a single row group. Larger record batches
+    // are split into multiple row groups on ingest.
+    max_row_group_rows: Option<usize>,
+
+    // Determines how the columns of new row groups are encoded.
+    encoding_strategy: EncodingStrategy,
+
+    // The maximum number of distinct values in a tag column of a table, and
+    // what to do with data that would exceed it.
+    tag_cardinality_limit: Option<(usize, TagCardinalityAction)>,
+
+    // Whether row groups identical to one already in the table are ignored.
+    deduplicate_row_groups: bool,
+
+    // The caches enabled on new chunks and tables.
+    caches: CacheConfig,
 }
 
 impl Database {
@@ -75,48 +327,297 @@ impl Database {
         Self::default()
     }
 
+    /// Treats `sentinel` as a missing value when ingesting data for integer
+    /// field columns called `column_name`, storing NULL in its place.
+    ///
+    /// Only data added after calling this method is affected.
+    pub fn with_integer_null_sentinel(mut self, column_name: &str, sentinel: i64) -> Self {
+        self.integer_null_sentinels
+            .insert(column_name.to_owned(), sentinel);
+        self
+    }
+
+    /// Limits row groups to at most `rows` rows. Record batches containing
+    /// more rows are split into multiple row groups when they are added,
+    /// which allows finer grained pruning of row groups during queries.
+    ///
+    /// Only data added after calling this method is affected.
+    pub fn with_max_row_group_rows(mut self, rows: usize) -> Self {
+        assert!(rows > 0, "row groups must contain at least one row");
+        self.max_row_group_rows = Some(rows);
+        self
+    }
+
+    /// Encodes the columns of new row groups according to `strategy`.
+    ///
+    /// Only data added after calling this method is affected.
+    pub fn with_encoding_strategy(mut self, strategy: EncodingStrategy) -> Self {
+        self.encoding_strategy = strategy;
+        self
+    }
+
+    /// Limits each tag column of a table within a chunk to at most
+    /// `max_values` distinct values, counting both the values already in the
+    /// table and those being added. Data that would exceed the limit is
+    /// handled according to `action`.
+    ///
+    /// A column that is already a tag column in the table can't be converted
+    /// into a field, so data exceeding the limit for such a column is always
+    /// rejected. Once converted, all later data for the column in the table is
+    /// stored as a field.
+    ///
+    /// Only data added after calling this method is affected.
+    pub fn with_tag_cardinality_limit(
+        mut self,
+        max_values: usize,
+        action: TagCardinalityAction,
+    ) -> Self {
+        self.tag_cardinality_limit = Some((max_values, action));
+        self
+    }
+
+    /// Ignores new row groups containing the same data as a row group already
+    /// in the same table, chunk and partition, e.g., because a write was
+    /// retried. Without this, upserting the same data twice stores its rows
+    /// twice.
+    ///
+    /// Row groups are compared by content hash, so data is only considered a
+    /// duplicate if it has the same columns and values in the same order. If
+    /// a maximum row group size is set then each resulting row group is
+    /// deduplicated independently.
+    ///
+    /// Only data added after calling this method is affected.
+    pub fn with_row_group_deduplication(mut self) -> Self {
+        self.deduplicate_row_groups = true;
+        self
+    }
+
+    /// Caches the decoded columns read by `read_filter` in each table, holding
+    /// at most `budget` bytes of decoded column data per table. Columns are
+    /// only cached for row groups whose rows all satisfy the predicate.
+    ///
+    /// Only tables created after calling this method are affected.
+    pub fn with_decode_cache(mut self, budget: usize) -> Self {
+        self.caches.decode_cache_budget = Some(budget);
+        self
+    }
+
+    /// Caches the row groups of each table that could satisfy a predicate,
+    /// for at most `capacity` distinct predicates per table, so that repeated
+    /// queries don't check every row group against the predicate again.
+    ///
+    /// Only tables created after calling this method are affected.
+    pub fn with_prune_cache(mut self, capacity: usize) -> Self {
+        self.caches.prune_cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Caches the results of `table_names` in each chunk, for at most
+    /// `capacity` distinct predicate and time range combinations per chunk.
+    ///
+    /// Only chunks created after calling this method are affected.
+    pub fn with_table_names_cache(mut self, capacity: usize) -> Self {
+        self.caches.table_names_cache_capacity = Some(capacity);
+        self
+    }
+
     /// Adds new data for a chunk.
     ///
     /// Data should be provided as a single row group for a table within the
     /// chunk. If the `Table` or `Chunk` does not exist they will be created,
     /// otherwise relevant structures will be updated.
+    ///
+    /// If a maximum row group size has been set then the data may be split
+    /// into several row groups.
+    ///
+    /// Adding a record batch without any rows is a no-op: no partition, chunk,
+    /// table or row group is created.
+    ///
+    /// An error is returned if the data doesn't contain exactly one timestamp
+    /// column, named "time", with `Int64` values, if any of its columns have
+    /// a type the read buffer doesn't support, or if any of its columns have
+    /// a different type from the existing table's columns. Data may have
+    /// columns that the existing table doesn't, and vice versa: reads of the
+    /// table return NULL values for the columns its rows don't have.
     pub fn upsert_partition(
         &mut self,
         partition_key: &str,
         chunk_id: u32,
         table_name: &str,
         table_data: RecordBatch,
-    ) {
+    ) -> Result<()> {
+        let row_groups =
+            self.row_groups_for_upsert(partition_key, chunk_id, table_name, table_data)?;
+        for row_group in row_groups {
+            self.upsert_row_group(partition_key, chunk_id, table_name, row_group)?;
+        }
+        Ok(())
+    }
+
+    // Validates the table data and converts it into the row groups to add to
+    // the table within the chunk, without adding them.
+    fn row_groups_for_upsert(
+        &self,
+        partition_key: &str,
+        chunk_id: u32,
+        table_name: &str,
+        table_data: RecordBatch,
+    ) -> Result<Vec<RowGroup>> {
         // validate table data contains appropriate meta data.
         let schema = table_data.schema();
-        if schema.fields().len() != schema.metadata().len() {
-            todo!("return error with missing column types for fields")
+        for field in schema.fields() {
+            ensure!(
+                schema.metadata().contains_key(field.name()) || is_untyped_column(field),
+                InvalidTableData {
+                    table_name,
+                    msg: format!("missing column type for column {:?}", field.name()),
+                }
+            );
+        }
+        validate_table_data(table_name, &table_data)?;
+
+        // A row group without rows has no time range.
+        if table_data.num_rows() == 0 {
+            return Ok(vec![]);
+        }
+
+        let chunk = self
+            .partitions
+            .get(partition_key)
+            .and_then(|partition| partition.chunks.get(&chunk_id));
+
+        let table_data = match self.tag_cardinality_limit {
+            Some((max_values, action)) => {
+                let table = chunk.and_then(|chunk| chunk.table(table_name));
+                limit_tag_cardinality(table_name, table, table_data, max_values, action)?
+            }
+            None => table_data,
+        };
+
+        let batches = match self.max_row_group_rows {
+            Some(max_rows) => split_record_batch(table_data, max_rows),
+            None => vec![table_data],
+        };
+
+        let mut row_groups = Vec::with_capacity(batches.len());
+        for batch in batches {
+            let row_group = RowGroup::from_record_batch(
+                batch,
+                &self.integer_null_sentinels,
+                &self.encoding_strategy,
+            );
+            if let Some(chunk) = chunk {
+                chunk.validate_row_group(table_name, &row_group)?;
+            }
+            row_groups.push(row_group);
         }
+        Ok(row_groups)
+    }
 
-        let row_group = RowGroup::from(table_data);
-        self.size += row_group.size();
-        self.rows += row_group.rows() as u64;
+    // Adds a row group to a table within a chunk, creating the partition,
+    // chunk and table as necessary.
+    fn upsert_row_group(
+        &mut self,
+        partition_key: &str,
+        chunk_id: u32,
+        table_name: &str,
+        row_group: RowGroup,
+    ) -> Result<()> {
+        let (size, rows) = (row_group.size(), row_group.rows() as u64);
 
         // create a new chunk if one doesn't exist, or add the table data to
         // the existing chunk.
         match self.partitions.entry(partition_key.to_owned()) {
             Entry::Occupied(mut e) => {
                 let partition = e.get_mut();
-                partition.upsert_chunk(chunk_id, table_name.to_owned(), row_group);
+                let added = partition.upsert_chunk(
+                    chunk_id,
+                    table_name.to_owned(),
+                    row_group,
+                    self.deduplicate_row_groups,
+                    self.caches,
+                )?;
+                if !added {
+                    return Ok(());
+                }
             }
             Entry::Vacant(e) => {
                 e.insert(Partition::new(
                     partition_key,
-                    Chunk::new(chunk_id, Table::new(table_name.to_owned(), row_group)),
+                    Chunk::with_caches(
+                        chunk_id,
+                        Table::new(table_name.to_owned(), row_group),
+                        self.caches,
+                    ),
                 ));
             }
         };
+
+        self.size += size;
+        self.rows += rows;
+        Ok(())
+    }
+
+    /// Adds new data for multiple tables within a chunk.
+    ///
+    /// Each table's data is added as it would be by `upsert_partition`, and
+    /// the same table may appear more than once. All of the provided data is
+    /// validated before any of it is added, both against the existing tables
+    /// and against the other data for the same table, so if the data for any
+    /// table is invalid then an error is returned and none of the tables are
+    /// updated.
+    pub fn upsert_partition_many(
+        &mut self,
+        partition_key: &str,
+        chunk_id: u32,
+        tables: Vec<(String, RecordBatch)>,
+    ) -> Result<()> {
+        // The columns of the row groups to add to each table, which must not
+        // conflict with each other either.
+        let mut table_columns: BTreeMap<String, BTreeMap<String, ColumnMeta>> = BTreeMap::new();
+
+        let mut all_row_groups = Vec::with_capacity(tables.len());
+        for (table_name, table_data) in tables {
+            let row_groups =
+                self.row_groups_for_upsert(partition_key, chunk_id, &table_name, table_data)?;
+
+            let columns = table_columns.entry(table_name.clone()).or_default();
+            for row_group in &row_groups {
+                for (column_name, column_meta) in &row_group.metadata().columns {
+                    match columns.get(column_name) {
+                        Some(curr_meta) => ensure!(
+                            curr_meta == column_meta,
+                            InvalidTableData {
+                                table_name: table_name.as_str(),
+                                msg: format!(
+                                    "column {} has a different type from the table's column",
+                                    column_name
+                                ),
+                            }
+                        ),
+                        None => {
+                            columns.insert(column_name.clone(), column_meta.clone());
+                        }
+                    }
+                }
+            }
+            all_row_groups.push((table_name, row_groups));
+        }
+
+        for (table_name, row_groups) in all_row_groups {
+            for row_group in row_groups {
+                self.upsert_row_group(partition_key, chunk_id, &table_name, row_group)?;
+            }
+        }
+        Ok(())
     }
 
     /// Remove all row groups, tables and chunks within the specified partition
     /// key.
     pub fn drop_partition(&mut self, partition_key: &str) -> Result<()> {
-        if self.partitions.remove(partition_key).is_some() {
+        if let Some(partition) = self.partitions.remove(partition_key) {
+            self.size -= partition.size;
+            self.rows -= partition.rows;
             return Ok(());
         }
 
@@ -134,13 +635,183 @@ impl Database {
                 key: partition_key.to_owned(),
             })?;
 
-        if partition.chunks.remove(&chunk_id).is_some() {
+        if let Some(chunk) = partition.chunks.remove(&chunk_id) {
+            partition.size -= chunk.size();
+            partition.rows -= chunk.rows();
+            self.size -= chunk.size();
+            self.rows -= chunk.rows();
             return Ok(());
         }
 
         Err(Error::ChunkNotFound { id: chunk_id })
     }
 
+    /// Removes all partitions, and all data within them, from the database.
+    ///
+    /// Configuration such as integer null sentinels and the encoding strategy
+    /// is kept, so the database can be reused without being reconstructed.
+    pub fn clear(&mut self) {
+        self.partitions.clear();
+        self.size = 0;
+        self.rows = 0;
+    }
+
+    /// Compacts every table in every chunk of the partition, replacing the
+    /// table's row groups with as few row groups as possible, e.g., after many
+    /// small writes. Chunks are not merged, so chunk ids remain valid.
+    ///
+    /// Compacted row groups are re-encoded with the database's encoding
+    /// strategy and are limited to its maximum row group size, if set. Tables
+    /// with a single row group are left as they are. The rows of each table,
+    /// and so the results of queries, are unchanged.
+    pub fn compact_partition(&mut self, partition_key: &str) -> Result<CompactionReport> {
+        let partition = self
+            .partitions
+            .get_mut(partition_key)
+            .context(PartitionNotFound { key: partition_key })?;
+
+        let mut report = CompactionReport {
+            row_groups_before: partition.row_groups(),
+            size_before: partition.size,
+            ..CompactionReport::default()
+        };
+
+        // All of the compacted row groups are built before any are replaced,
+        // so that the partition is left unchanged if an error is returned.
+        let mut compacted = vec![];
+        for (&chunk_id, chunk) in &partition.chunks {
+            for (table_name, batches) in chunk.scan_all(&ColumnSelection::All) {
+                if batches.len() < 2 {
+                    continue;
+                }
+
+                // The batches all have the table's schema: columns that a
+                // row group doesn't have are NULL in its batch.
+                let table_data = concat_record_batches(batches)?;
+                let batches = match self.max_row_group_rows {
+                    Some(max_rows) => split_record_batch(table_data, max_rows),
+                    None => vec![table_data],
+                };
+                // The data is already stored, so null sentinels set since it
+                // was added are not applied to it.
+                let row_groups = batches
+                    .into_iter()
+                    .map(|batch| {
+                        RowGroup::from_record_batch(
+                            batch,
+                            &BTreeMap::new(),
+                            &self.encoding_strategy,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                compacted.push((chunk_id, table_name, row_groups));
+            }
+        }
+
+        for (chunk_id, table_name, row_groups) in compacted {
+            let chunk = partition.chunks.get_mut(&chunk_id).unwrap();
+            chunk.replace_row_groups(&table_name, row_groups)?;
+        }
+
+        let size_after = partition.chunks.values().map(|chunk| chunk.size()).sum();
+        self.size = self.size - partition.size + size_after;
+        partition.size = size_after;
+
+        report.row_groups_after = partition.row_groups();
+        report.size_after = size_after;
+        Ok(report)
+    }
+
+    /// Renames the table `old_name` to `new_name` within the specified chunk
+    /// and partition.
+    ///
+    /// Returns an error if the table does not exist in the chunk, or if a
+    /// table called `new_name` already exists in the chunk.
+    pub fn rename_table(
+        &mut self,
+        partition_key: &str,
+        chunk_id: u32,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<()> {
+        let partition = self
+            .partitions
+            .get_mut(partition_key)
+            .ok_or(Error::PartitionNotFound {
+                key: partition_key.to_owned(),
+            })?;
+
+        let chunk = partition
+            .chunks
+            .get_mut(&chunk_id)
+            .ok_or(Error::ChunkNotFound { id: chunk_id })?;
+
+        chunk.rename_table(old_name, new_name)
+    }
+
+    /// Returns the partition key and chunk id of every chunk that could
+    /// contain rows for the table that fall within the time range `[min,
+    /// max)`, if provided, and satisfy the predicate.
+    ///
+    /// Chunks are pruned using only their meta data, so no data is read. The
+    /// returned chunks are the ones a query with the same table, time range
+    /// and predicate would need to execute against.
+    pub fn matching_chunks(
+        &self,
+        table_name: &str,
+        time_range: Option<(i64, i64)>,
+        predicate: &Predicate,
+    ) -> Vec<(String, u32)> {
+        self.partitions
+            .iter()
+            .flat_map(|(partition_key, partition)| {
+                partition
+                    .chunks
+                    .values()
+                    .filter(|chunk| chunk.could_pass_predicate(table_name, time_range, predicate))
+                    .map(move |chunk| (partition_key.clone(), chunk.id()))
+            })
+            .collect()
+    }
+
+    /// Returns the minimum and maximum timestamps of the rows that a query
+    /// against the table, with the same time range `[min, max)` and predicate,
+    /// could return, or `None` if no rows could be returned.
+    ///
+    /// The bounds are determined using only the meta data of chunks and row
+    /// groups, so no data is read. They contain the times of all matching
+    /// rows, but may be wider than them.
+    pub fn result_time_bounds(
+        &self,
+        table_name: &str,
+        time_range: Option<(i64, i64)>,
+        predicate: &Predicate,
+    ) -> Option<(i64, i64)> {
+        let (mut min, mut max) = self
+            .partitions
+            .values()
+            .flat_map(|partition| partition.chunks.values())
+            .filter(|chunk| chunk.could_pass_predicate(table_name, time_range, predicate))
+            .filter_map(|chunk| chunk.table(table_name)?.time_range_for_predicate(predicate))
+            .fold(None, |acc, (min, max)| match acc {
+                Some((acc_min, acc_max)) => Some((min.min(acc_min), max.max(acc_max))),
+                None => Some((min, max)),
+            })?;
+
+        if let Some((range_min, range_max)) = time_range {
+            min = min.max(range_min);
+            if range_max != i64::MAX {
+                // No rows are before a `max` of `i64::MIN`.
+                max = max.min(range_max.checked_sub(1)?);
+            }
+        }
+
+        if min > max {
+            return None;
+        }
+        Some((min, max))
+    }
+
     // Lists all partition keys with data for this database.
     pub fn partition_keys(&self) -> Vec<&String> {
         self.partitions.keys().collect()
@@ -163,6 +834,21 @@ impl Database {
         self.rows
     }
 
+    /// Eagerly computes any metadata that is otherwise built on demand by
+    /// queries, such as the distinct counts of column statistics. Column and
+    /// time ranges are always computed as data is added.
+    ///
+    /// This is intended to be called after a bulk load, and is cheap if the
+    /// database is already warm. Returns the number of table columns whose
+    /// metadata was computed.
+    pub fn warm_metadata(&self) -> usize {
+        self.partitions
+            .values()
+            .flat_map(|partition| partition.chunks.values())
+            .map(|chunk| chunk.warm_metadata())
+            .sum()
+    }
+
     /// Determines the total number of tables under all partitions within the
     /// database.
     pub fn tables(&self) -> usize {
@@ -181,6 +867,25 @@ impl Database {
             .sum()
     }
 
+    /// Consumes the database, returning its final statistics.
+    ///
+    /// This allows applications to account for the database's contents when
+    /// shutting it down, and to confirm that it has been torn down.
+    pub fn shutdown_summary(self) -> DatabaseStats {
+        DatabaseStats {
+            size: self.size,
+            rows: self.rows,
+            partitions: self.partitions.len(),
+            chunks: self
+                .partitions
+                .values()
+                .map(|partition| partition.chunks.len())
+                .sum(),
+            tables: self.tables(),
+            row_groups: self.row_groups(),
+        }
+    }
+
     /// Returns rows for the specified columns in the provided table, for the
     /// specified partition key and chunks within that partition.
     ///
@@ -195,6 +900,11 @@ impl Database {
     ///
     /// `read_filter` is lazy - it does not execute against the next chunk until
     /// the results for the previous one have been emitted.
+    ///
+    /// When all columns are selected, the table may have different columns in
+    /// each chunk, e.g., because fields were added over time. All record
+    /// batches then share the union of the chunks' schemas, and columns missing
+    /// from a chunk are filled with NULL values.
     pub fn read_filter<'a>(
         &self,
         partition_key: &str,
@@ -205,32 +915,8 @@ impl Database {
     ) -> Result<ReadFilterResults<'a, '_>> {
         match self.partitions.get(partition_key) {
             Some(partition) => {
-                let mut chunks = vec![];
-                for chunk_id in chunk_ids {
-                    let chunk = partition
-                        .chunks
-                        .get(chunk_id)
-                        .context(ChunkNotFound { id: *chunk_id })?;
-
-                    ensure!(chunk.has_table(table_name), TableNotFound { table_name });
-
-                    chunks.push(
-                        partition
-                            .chunks
-                            .get(chunk_id)
-                            .ok_or_else(|| Error::ChunkNotFound { id: *chunk_id })?,
-                    )
-                }
-
-                // TODO(edd): encapsulate execution of `read_filter` on each chunk
-                // into an anonymous function, rather than having to store all
-                // the input context arguments in the iterator state.
-                Ok(ReadFilterResults::new(
-                    chunks,
-                    table_name,
-                    predicate,
-                    select_columns,
-                ))
+                let chunks = partition.chunks_by_ids(chunk_ids)?;
+                Self::read_filter_chunks(chunks, table_name, predicate, select_columns)
             }
             None => Err(Error::PartitionNotFound {
                 key: partition_key.to_owned(),
@@ -238,42 +924,243 @@ impl Database {
         }
     }
 
-    /// Returns aggregates for each group specified by the values of the
-    /// grouping keys, limited to the specified partition key table name and
-    /// chunk ids.
-    ///
-    /// Results may be filtered by conjunctive predicates.
-    /// Whilst the `ReadBuffer` will carry out the most optimal execution
-    /// possible by pruning columns, row groups and tables, it is assumed
-    /// that the caller has already provided an appropriately pruned
-    /// collection of chunks.
-    ///
-    /// Currently, only grouping by string (tag key) columns is supported.
-    /// Required aggregates are specified via a tuple comprising a column name
-    /// and the type of aggregation required. Multiple aggregations can be
-    /// applied to the same column.
+    /// Returns rows for the specified columns in the provided table, as
+    /// `read_filter` does, but for all chunks in all partitions whose keys
+    /// fall within the [key_from, key_to) range.
     ///
-    /// This method might be deprecated in the future, replaced by a call to
-    /// `read_aggregate_window` with a `window` of `0`.
-    pub fn read_aggregate<'input>(
+    /// This is useful when partition keys sort in time order, e.g.,
+    /// `2021-01-01T00`, as it allows all partitions within a time span to be
+    /// queried without first listing them. Chunks without the table are
+    /// skipped, and no results are returned if the range contains no
+    /// partitions.
+    pub fn read_filter_partition_range<'a>(
         &self,
-        partition_key: &str,
-        table_name: &'input str,
-        chunk_ids: &[u32],
+        key_from: &str,
+        key_to: &str,
+        table_name: &'a str,
         predicate: Predicate,
-        group_columns: ColumnSelection<'input>,
-        aggregates: Vec<(ColumnName<'input>, AggregateType)>,
-    ) -> Result<ReadAggregateResults<'input, '_>> {
-        match self.partitions.get(partition_key) {
-            Some(partition) => {
-                let mut chunks = vec![];
-                for chunk_id in chunk_ids {
-                    let chunk = partition
-                        .chunks
+        select_columns: ColumnSelection<'a>,
+    ) -> Result<ReadFilterResults<'a, '_>> {
+        ensure!(
+            key_from <= key_to,
+            UnsupportedOperation {
+                msg: "partition key range must not end before it starts"
+            }
+        );
+
+        let chunks = self
+            .partitions
+            .range::<str, _>((Bound::Included(key_from), Bound::Excluded(key_to)))
+            .flat_map(|(_, partition)| partition.chunks.values())
+            .filter(|chunk| chunk.contains_table(table_name))
+            .collect();
+        Self::read_filter_chunks(chunks, table_name, predicate, select_columns)
+    }
+
+    // Executes `read_filter` against the table in each of the chunks, all of
+    // which must contain the table.
+    fn read_filter_chunks<'a, 'chunk>(
+        chunks: Vec<&'chunk Chunk>,
+        table_name: &'a str,
+        predicate: Predicate,
+        select_columns: ColumnSelection<'a>,
+    ) -> Result<ReadFilterResults<'a, 'chunk>> {
+        let mut tables = vec![];
+        let mut pushdown: Option<PredicatePushdown> = None;
+        for chunk in &chunks {
+            let table = chunk
+                .table(table_name)
+                .context(TableNotFound { table_name })?;
+
+            let table_pushdown = table.predicate_pushdown(&predicate);
+            match &mut pushdown {
+                Some(pushdown) => pushdown.merge(&table_pushdown),
+                None => pushdown = Some(table_pushdown),
+            }
+            tables.push(table);
+        }
+
+        let pushdown = pushdown.unwrap_or_else(|| PredicatePushdown {
+            applied: predicate.expressions().to_vec(),
+            residual: vec![],
+        });
+
+        // The results of a single table already have the union schema, so
+        // there is no need to build it and conform each result to it.
+        let schema = match select_columns {
+            ColumnSelection::All if tables.len() > 1 => Some(union_schema(&tables)?),
+            ColumnSelection::All | ColumnSelection::Some(_) => None,
+        };
+
+        // TODO(edd): encapsulate execution of `read_filter` on each chunk
+        // into an anonymous function, rather than having to store all
+        // the input context arguments in the iterator state.
+        Ok(ReadFilterResults::new(
+            chunks,
+            table_name,
+            predicate,
+            select_columns,
+            pushdown,
+            schema,
+        ))
+    }
+
+    /// Returns an estimate of the total number of bytes that `read_filter`
+    /// would materialise for the same arguments, so that queries likely to
+    /// produce very large results can be rejected before they are run. Time
+    /// ranges are expressed in the predicate, as for `read_filter`.
+    ///
+    /// The estimate is computed from column meta data and estimates of the
+    /// number of rows satisfying the predicate; no rows are read. It is not
+    /// exact, and may be smaller or larger than the size of the results.
+    pub fn estimate_select_size(
+        &self,
+        partition_key: &str,
+        table_name: &str,
+        chunk_ids: &[u32],
+        predicate: &Predicate,
+        select_columns: ColumnSelection<'_>,
+    ) -> Result<u64> {
+        let partition = self
+            .partitions
+            .get(partition_key)
+            .context(PartitionNotFound { key: partition_key })?;
+
+        let mut size = 0;
+        for chunk in partition.chunks_by_ids(chunk_ids)? {
+            let table = chunk
+                .table(table_name)
+                .context(TableNotFound { table_name })?;
+            size += table.estimated_select_size(&select_columns, predicate);
+        }
+        Ok(size)
+    }
+
+    /// Returns the names of the columns that a query against the provided
+    /// table and chunks would read, e.g., so that column access can be
+    /// audited. Columns referenced by the predicate, selected, grouped on or
+    /// aggregated are included, as is the time column.
+    ///
+    /// The query is not executed. Referenced columns that don't exist in any
+    /// of the chunks' tables are not included.
+    pub fn accessed_columns(
+        &self,
+        partition_key: &str,
+        table_name: &str,
+        chunk_ids: &[u32],
+        predicate: &Predicate,
+        select_columns: ColumnSelection<'_>,
+        group_columns: ColumnSelection<'_>,
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+    ) -> Result<BTreeSet<String>> {
+        let partition = self
+            .partitions
+            .get(partition_key)
+            .context(PartitionNotFound { key: partition_key })?;
+
+        let mut column_names = BTreeSet::new();
+        for chunk in partition.chunks_by_ids(chunk_ids)? {
+            let table = chunk
+                .table(table_name)
+                .context(TableNotFound { table_name })?;
+            column_names.extend(table.accessed_columns(
+                predicate,
+                &select_columns,
+                &group_columns,
+                aggregates,
+            ));
+        }
+        Ok(column_names)
+    }
+
+    /// Returns rows for the specified columns in the provided table, as
+    /// `read_filter` does, but with all rows ordered by time across all of
+    /// the chunks.
+    ///
+    /// Rather than buffering and sorting all of the results, the rows of each
+    /// row group are sorted by time and then merged with those of the other
+    /// row groups. A row group is only read once the merge reaches the start
+    /// of its time range, and its rows are released once they have been
+    /// emitted, so only row groups overlapping the rows being emitted are held
+    /// in memory.
+    ///
+    /// The time column must be selected. Rows with the same timestamp are
+    /// emitted in an unspecified order.
+    pub fn read_filter_time_ordered<'a>(
+        &self,
+        partition_key: &str,
+        table_name: &'a str,
+        chunk_ids: &[u32],
+        predicate: Predicate,
+        select_columns: ColumnSelection<'a>,
+    ) -> Result<ReadFilterTimeOrderedResults<'a, '_>> {
+        if let ColumnSelection::Some(column_names) = &select_columns {
+            ensure!(
+                column_names.contains(&TIME_COLUMN_NAME),
+                UnsupportedOperation {
+                    msg: "the time column must be selected to order results by time"
+                }
+            );
+        }
+
+        let results = self.read_filter(
+            partition_key,
+            table_name,
+            chunk_ids,
+            predicate,
+            select_columns,
+        )?;
+        Ok(ReadFilterTimeOrderedResults::new(results))
+    }
+
+    /// Returns aggregates for each group specified by the values of the
+    /// grouping keys, limited to the specified partition key table name and
+    /// chunk ids.
+    ///
+    /// Results may be filtered by conjunctive predicates.
+    /// Whilst the `ReadBuffer` will carry out the most optimal execution
+    /// possible by pruning columns, row groups and tables, it is assumed
+    /// that the caller has already provided an appropriately pruned
+    /// collection of chunks.
+    ///
+    /// Currently, only grouping by string (tag key) columns is supported.
+    /// Required aggregates are specified via a tuple comprising a column name
+    /// and the type of aggregation required. Multiple aggregations can be
+    /// applied to the same column.
+    ///
+    /// This method might be deprecated in the future, replaced by a call to
+    /// `read_aggregate_window` with a `window` of `0`.
+    pub fn read_aggregate<'input>(
+        &self,
+        partition_key: &str,
+        table_name: &'input str,
+        chunk_ids: &[u32],
+        predicate: Predicate,
+        group_columns: ColumnSelection<'input>,
+        aggregates: Vec<(ColumnName<'input>, AggregateType)>,
+    ) -> Result<ReadAggregateResults<'input, '_>> {
+        match self.partitions.get(partition_key) {
+            Some(partition) => {
+                let mut chunks = vec![];
+                for chunk_id in chunk_ids {
+                    let chunk = partition
+                        .chunks
                         .get(chunk_id)
                         .context(ChunkNotFound { id: *chunk_id })?;
 
-                    ensure!(chunk.has_table(table_name), TableNotFound { table_name });
+                    ensure!(
+                        chunk.contains_table(table_name),
+                        TableNotFound { table_name }
+                    );
+
+                    // All group, aggregate and predicate columns must exist
+                    // in the table.
+                    chunk.validate_aggregate(
+                        table_name,
+                        &predicate,
+                        &group_columns,
+                        &aggregates,
+                    )?;
 
                     chunks.push(
                         partition
@@ -304,6 +1191,104 @@ impl Database {
         }
     }
 
+    /// Returns a single aggregate, without grouping, of the values in a column
+    /// of the table, limited to the specified partition key and chunk ids.
+    ///
+    /// Results may be filtered by conjunctive predicates and an optional time
+    /// range `[min, max)`. The aggregate is computed directly from the column
+    /// data without building a `RecordBatch`.
+    ///
+    /// `None` is returned if there are no matching rows, or if the aggregate
+    /// is NULL because all matching values are NULL. Only `Count`, `Sum`, `Min`
+    /// and `Max` aggregates on numerical columns are supported.
+    pub fn aggregate_scalar(
+        &self,
+        partition_key: &str,
+        table_name: &str,
+        chunk_ids: &[u32],
+        time_range: Option<(i64, i64)>,
+        predicate: Predicate,
+        column_name: ColumnName<'_>,
+        agg_type: AggregateType,
+    ) -> Result<Option<Scalar>> {
+        if let AggregateType::First | AggregateType::Last = agg_type {
+            return Err(Error::UnsupportedAggregate { agg: agg_type });
+        }
+
+        let partition = self
+            .partitions
+            .get(partition_key)
+            .context(PartitionNotFound { key: partition_key })?;
+
+        let predicate = match time_range {
+            Some((min, max)) => Predicate::with_time_range(predicate.expressions(), min, max),
+            None => predicate,
+        };
+
+        let aggregates = [(column_name, agg_type)];
+        let mut result: Option<AggregateResult<'_>> = None;
+        for chunk in partition.chunks_by_ids(chunk_ids)? {
+            // All aggregate and predicate columns must exist in the table.
+            chunk.validate_aggregate(
+                table_name,
+                &predicate,
+                &ColumnSelection::Some(&[]),
+                &aggregates,
+            )?;
+
+            let table = chunk
+                .table(table_name)
+                .context(TableNotFound { table_name })?;
+            let pushdown = table.predicate_pushdown(&predicate);
+            ensure!(
+                pushdown.is_complete(),
+                UnsupportedOperation {
+                    msg: format!(
+                        "predicate cannot be applied to table {}: {}",
+                        table_name,
+                        Predicate::new(pushdown.residual)
+                    ),
+                }
+            );
+
+            let chunk_result = match table.aggregate_scalar(&predicate, column_name, agg_type) {
+                Some(chunk_result) => chunk_result,
+                None => continue,
+            };
+
+            match (&mut result, chunk_result) {
+                (None, chunk_result) => result = Some(chunk_result),
+                (Some(acc), chunk_result @ AggregateResult::Count(_)) => acc.merge(&chunk_result),
+                (Some(_), AggregateResult::Sum(Scalar::Null)) => {}
+                (Some(acc), AggregateResult::Sum(v)) => acc.update(Value::Scalar(v)),
+                (Some(acc), AggregateResult::Min(v)) => acc.update(v),
+                (Some(acc), AggregateResult::Max(v)) => acc.update(v),
+                (Some(_), AggregateResult::First(_)) | (Some(_), AggregateResult::Last(_)) => {
+                    unreachable!("first and last aggregates are rejected")
+                }
+            }
+        }
+
+        match result {
+            None => Ok(None),
+            Some(AggregateResult::Count(v)) => Ok(Some(Scalar::U64(v))),
+            Some(AggregateResult::Sum(Scalar::Null)) => Ok(None),
+            Some(AggregateResult::Sum(v)) => Ok(Some(v)),
+            Some(AggregateResult::Min(v)) | Some(AggregateResult::Max(v)) => match v {
+                Value::Null => Ok(None),
+                Value::Scalar(v) => Ok(Some(v)),
+                _ => UnsupportedOperation {
+                    msg: format!(
+                        "{} aggregate on non-numerical column {}",
+                        agg_type, column_name
+                    ),
+                }
+                .fail(),
+            },
+            Some(_) => unreachable!("first and last aggregates are rejected"),
+        }
+    }
+
     /// Returns windowed aggregates for each group specified by the values of
     /// the grouping keys and window, limited to the specified partition key
     /// table name and chunk ids.
@@ -371,19 +1356,17 @@ impl Database {
     /// Returns the distinct set of table names that contain data that satisfies
     /// the provided predicate.
     ///
-    /// TODO(edd): Implement predicate support.
+    /// If a time range `[min, max)` is provided then chunks and tables that
+    /// contain no data within that range are excluded. Tables are only
+    /// included if they have rows satisfying the predicate within the time
+    /// range.
     pub fn table_names(
         &self,
         partition_key: &str,
         chunk_ids: &[u32],
+        time_range: Option<(i64, i64)>,
         predicate: Predicate,
     ) -> Result<RecordBatch> {
-        if !predicate.is_empty() {
-            return Err(Error::UnsupportedOperation {
-                msg: "predicate support on `table_names` not implemented".to_owned(),
-            });
-        }
-
         let partition = self
             .partitions
             .get(partition_key)
@@ -395,7 +1378,7 @@ impl Database {
         let names = chunks
             .iter()
             .fold(BTreeSet::new(), |mut names, chunk| {
-                names.append(&mut chunk.table_names(&predicate));
+                names.append(&mut chunk.table_names(&predicate, time_range));
                 names
             })
             // have a BTreeSet here, convert to an iterator of Some(&str)
@@ -405,6 +1388,107 @@ impl Database {
         str_iter_to_batch(TABLE_NAMES_COLUMN_NAME, names).context(ArrowError)
     }
 
+    /// Returns the distinct set of field keys (field column names) for the
+    /// table, across the provided chunks, where the table has rows that
+    /// satisfy the provided predicate.
+    ///
+    /// Chunks that do not contain the table are skipped, and chunks are not
+    /// queried once all of their field columns have already been found. A
+    /// `TableNotFound` error is returned if none of the chunks contain the
+    /// table, whereas an empty batch is returned if the table exists but has
+    /// no rows satisfying the predicate.
+    pub fn field_keys(
+        &self,
+        partition_key: &str,
+        table_name: &str,
+        chunk_ids: &[u32],
+        predicate: Predicate,
+    ) -> Result<RecordBatch> {
+        let partition = self
+            .partitions
+            .get(partition_key)
+            .ok_or(Error::PartitionNotFound {
+                key: partition_key.to_owned(),
+            })?;
+
+        let chunks = partition.chunks_by_ids(chunk_ids)?;
+        ensure!(
+            chunks.iter().any(|chunk| chunk.contains_table(table_name)),
+            TableNotFound { table_name }
+        );
+
+        let mut found_keys = BTreeSet::new();
+        for chunk in chunks {
+            for key in chunk.field_keys(table_name, &predicate, &found_keys) {
+                found_keys.insert(key.to_owned());
+            }
+        }
+
+        str_iter_to_batch(
+            FIELD_KEYS_COLUMN_NAME,
+            found_keys.iter().map(|key| Some(key.as_str())),
+        )
+        .context(ArrowError)
+    }
+
+    /// Returns the distinct non-null values of a field column in the table,
+    /// across the provided chunks, at rows satisfying the predicate and within
+    /// the optional time range `[min, max)`. NULL values are excluded.
+    ///
+    /// The values are returned in ascending order as a single column named
+    /// after the field, in the field's native type. Chunks that do not contain
+    /// the table are skipped, and a `TableNotFound` error is returned if none
+    /// of them do.
+    pub fn field_distinct_values(
+        &self,
+        partition_key: &str,
+        table_name: &str,
+        chunk_ids: &[u32],
+        time_range: Option<(i64, i64)>,
+        predicate: Predicate,
+        column_name: ColumnName<'_>,
+    ) -> Result<RecordBatch> {
+        let partition = self
+            .partitions
+            .get(partition_key)
+            .context(PartitionNotFound { key: partition_key })?;
+
+        let predicate = match time_range {
+            Some((min, max)) => Predicate::with_time_range(predicate.expressions(), min, max),
+            None => predicate,
+        };
+
+        let tables = partition
+            .chunks_by_ids(chunk_ids)?
+            .into_iter()
+            .filter_map(|chunk| chunk.table(table_name))
+            .collect::<Vec<_>>();
+        ensure!(!tables.is_empty(), TableNotFound { table_name });
+
+        let mut logical_data_type = None;
+        let mut values = vec![];
+        for table in tables {
+            let table_values = table.field_distinct_values(column_name, &predicate)?;
+            let table_type = table.column_logical_data_type(column_name);
+            ensure!(
+                logical_data_type.is_none() || logical_data_type == table_type,
+                ColumnTypeMismatch { column_name }
+            );
+            logical_data_type = table_type;
+            values.push(table_values);
+        }
+
+        // Each table has the column, so its logical data type is known.
+        let values = table::distinct_values(values, logical_data_type.unwrap())?;
+        let arr = ArrayRef::from(values);
+        let schema = ArrowSchema::new(vec![Field::new(
+            column_name,
+            arr.data_type().clone(),
+            false,
+        )]);
+        RecordBatch::try_new(Arc::new(schema), vec![arr]).context(ArrowError)
+    }
+
     /// Returns the distinct set of column names (tag keys) that satisfy the
     /// provided predicate.
     pub fn column_names(
@@ -470,21 +1554,46 @@ impl Partition {
     /// Data should be provided as a single row group for a table within the
     /// chunk. If the `Table` or `Chunk` does not exist they will be created,
     /// otherwise relevant structures will be updated.
-    fn upsert_chunk(&mut self, chunk_id: u32, table_name: String, row_group: RowGroup) {
-        self.size += row_group.size();
-        self.rows += row_group.rows() as u64;
+    ///
+    /// When `dedup` is set, a row group with the same content hash as one
+    /// already in the table is ignored. Returns whether the row group was
+    /// added. A new chunk is created with the caches enabled in `caches`.
+    fn upsert_chunk(
+        &mut self,
+        chunk_id: u32,
+        table_name: String,
+        row_group: RowGroup,
+        dedup: bool,
+        caches: CacheConfig,
+    ) -> Result<bool> {
+        let (size, rows) = (row_group.size(), row_group.rows() as u64);
 
         // create a new chunk if one doesn't exist, or add the table data to
         // the existing chunk.
         match self.chunks.entry(chunk_id) {
             Entry::Occupied(mut e) => {
                 let chunk = e.get_mut();
-                chunk.upsert_table(table_name, row_group);
+                let duplicate = dedup
+                    && chunk.table(&table_name).map_or(false, |table| {
+                        table.has_row_group_content(row_group.content_hash())
+                    });
+                if duplicate {
+                    return Ok(false);
+                }
+                chunk.upsert_table(table_name, row_group)?;
             }
             Entry::Vacant(e) => {
-                e.insert(Chunk::new(chunk_id, Table::new(table_name, row_group)));
+                e.insert(Chunk::with_caches(
+                    chunk_id,
+                    Table::new(table_name, row_group),
+                    caches,
+                ));
             }
         };
+
+        self.size += size;
+        self.rows += rows;
+        Ok(true)
     }
 
     /// Return the chunk ids stored in this partition, in order of id
@@ -519,562 +1628,4582 @@ impl Partition {
     pub fn rows(&self) -> u64 {
         self.rows
     }
-}
-
-/// ReadFilterResults implements ...
-pub struct ReadFilterResults<'input, 'chunk> {
-    chunks: Vec<&'chunk Chunk>,
-    next_i: usize,
-    curr_table_results: Option<table::ReadFilterResults<'chunk>>,
 
-    table_name: &'input str,
-    predicate: Predicate,
-    select_columns: table::ColumnSelection<'input>,
-}
+    /// The total size in bytes of all chunks in the partition.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
 
-impl<'input, 'chunk> fmt::Debug for ReadFilterResults<'input, 'chunk> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("ReadFilterResults")
-            .field("chunks.len", &self.chunks.len())
-            .field("next_i", &self.next_i)
-            .field("curr_table_results", &"<OPAQUE>")
-            .field("table_name", &self.table_name)
-            .field("predicate", &self.predicate)
-            .field("select_columns", &self.select_columns)
-            .finish()
+    /// The total time range of all data within the partition, determined
+    /// from the meta data of each chunk.
+    ///
+    /// This is `None` if none of the chunks have a time range.
+    pub fn time_range(&self) -> Option<(i64, i64)> {
+        self.chunks
+            .values()
+            .filter_map(|chunk| chunk.time_range())
+            .fold(None, |acc, (min, max)| match acc {
+                Some((acc_min, acc_max)) => Some((min.min(acc_min), max.max(acc_max))),
+                None => Some((min, max)),
+            })
     }
 }
 
-impl<'input, 'chunk> ReadFilterResults<'input, 'chunk> {
-    fn new(
-        chunks: Vec<&'chunk Chunk>,
-        table_name: &'input str,
-        predicate: Predicate,
-        select_columns: table::ColumnSelection<'input>,
-    ) -> Self {
-        Self {
-            chunks,
-            next_i: 0,
-            curr_table_results: None,
-            table_name,
-            predicate,
-            select_columns,
-        }
+// Splits a record batch into record batches of at most `max_rows` rows. The
+// columns of the returned batches are zero-copy slices of the input columns.
+fn split_record_batch(rb: RecordBatch, max_rows: usize) -> Vec<RecordBatch> {
+    if rb.num_rows() <= max_rows {
+        return vec![rb];
     }
+
+    (0..rb.num_rows())
+        .step_by(max_rows)
+        .map(|offset| {
+            let len = max_rows.min(rb.num_rows() - offset);
+            let columns = rb
+                .columns()
+                .iter()
+                .map(|column| column.slice(offset, len))
+                .collect::<Vec<_>>();
+
+            // The schema and column lengths are the same as the input batch,
+            // which was valid.
+            RecordBatch::try_new(rb.schema(), columns).unwrap()
+        })
+        .collect()
 }
 
-impl<'input, 'chunk> Iterator for ReadFilterResults<'input, 'chunk> {
-    type Item = RecordBatch;
+// Checks the number of distinct values in each tag column of the table data,
+// together with the values already in the table, against `max_values`. Tag
+// columns over the limit are either rejected or converted into string field
+// columns, as are tag columns already stored as fields in the table.
+fn limit_tag_cardinality(
+    table_name: &str,
+    table: Option<&Table>,
+    table_data: RecordBatch,
+    max_values: usize,
+    action: TagCardinalityAction,
+) -> Result<RecordBatch> {
+    let schema = table_schema(table_name, &table_data)?;
+    let table_columns = table
+        .map(|table| table.schema_for_all_columns())
+        .unwrap_or_default();
+
+    let mut field_columns = vec![];
+    for i in 0..schema.len() {
+        let (lp_type, field) = schema.field(i);
+        let column_name = field.name();
+        let arr = match lp_type {
+            Some(InfluxColumnType::Tag) => {
+                match table_data.column(i).as_any().downcast_ref::<StringArray>() {
+                    Some(arr) => arr,
+                    None => continue, // rejected when the row group is built
+                }
+            }
+            _ => continue,
+        };
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.next_i == self.chunks.len() {
-            return None;
+        let is_table_tag = table_columns
+            .iter()
+            .any(|(typ, _)| matches!(typ, ColumnType::Tag(name) if name == column_name));
+        let is_table_field = table_columns
+            .iter()
+            .any(|(typ, _)| matches!(typ, ColumnType::Field(name) if name == column_name));
+        if is_table_field && action == TagCardinalityAction::ConvertToField {
+            field_columns.push(column_name.to_owned());
+            continue;
         }
 
-        // Try next chunk's table.
-        if self.curr_table_results.is_none() {
-            self.curr_table_results = Some(
-                self.chunks[self.next_i]
-                    .read_filter(self.table_name, &self.predicate, &self.select_columns)
-                    .unwrap(),
-            );
+        let mut values = table
+            .and_then(|table| {
+                table
+                    .tag_values(&Predicate::default(), &[column_name.as_str()])
+                    .remove(column_name.as_str())
+            })
+            .unwrap_or_default();
+        values.extend(
+            (0..arr.len())
+                .filter(|&j| arr.is_valid(j))
+                .map(|j| arr.value(j)),
+        );
+        if values.len() <= max_values {
+            continue;
         }
 
-        match &mut self.curr_table_results {
-            // Table potentially has some results.
-            Some(table_results) => {
-                // Table has found results in a row group.
-                if let Some(row_group_result) = table_results.next() {
-                    // it should not be possible for the conversion to record
-                    // batch to fail here
-                    let rb = row_group_result.try_into();
-                    return Some(rb.unwrap());
-                }
+        ensure!(
+            action == TagCardinalityAction::ConvertToField && !is_table_tag,
+            TagCardinalityExceeded {
+                table_name,
+                column_name,
+                limit: max_values,
+            }
+        );
+        field_columns.push(column_name.to_owned());
+    }
 
-                // no more results for row groups in the table. Try next chunk.
-                self.next_i += 1;
-                self.curr_table_results = None;
-                self.next()
-            }
-            // Table does not exist.
-            None => {
-                // Since chunk pruning is the caller's responsibility, I don't
-                // think the caller should request chunk data that does not have
-                // the right tables.
-                todo!(
-                    "What to do about this? Seems like this is an error on the part of the caller"
-                );
+    if field_columns.is_empty() {
+        return Ok(table_data);
+    }
+
+    // Only the column type metadata changes, so the columns remain valid for
+    // the schema.
+    let arrow_schema = table_data.schema();
+    let mut metadata = arrow_schema.metadata().clone();
+    for column_name in field_columns {
+        metadata.insert(
+            column_name,
+            InfluxColumnType::Field(InfluxFieldType::String).to_string(),
+        );
+    }
+    let arrow_schema = ArrowSchema::new_with_metadata(arrow_schema.fields().clone(), metadata);
+    Ok(RecordBatch::try_new(Arc::new(arrow_schema), table_data.columns().to_vec()).unwrap())
+}
+
+// Determines if the table data can be converted into a `RowGroup`. The data
+// must have a valid timeseries schema containing a timestamp column and only
+// column types supported by the read buffer. Data without rows is valid, but
+// isn't added to the table.
+fn validate_table_data(table_name: &str, table_data: &RecordBatch) -> Result<()> {
+    let schema = table_schema(table_name, table_data)?;
+    validate_time_column(table_name, &schema)?;
+
+    for i in 0..schema.len() {
+        let (lp_type, field) = schema.field(i);
+        let supported = match lp_type {
+            Some(InfluxColumnType::Tag) => field.data_type() == &DataType::Utf8,
+            Some(InfluxColumnType::Field(_)) => matches!(
+                field.data_type(),
+                DataType::Int64 | DataType::Float64 | DataType::UInt64
+            ),
+            // validated above.
+            Some(InfluxColumnType::Timestamp) => true,
+            None => is_untyped_column(field),
+        };
+
+        ensure!(
+            supported,
+            InvalidTableData {
+                table_name,
+                msg: format!(
+                    "unsupported column {:?} with type {:?}",
+                    field.name(),
+                    field.data_type()
+                ),
             }
+        );
+    }
+    Ok(())
+}
+
+// Determines if the column can be added without a column type in the schema
+// metadata. Binary columns have no InfluxDB field type, so they are stored as
+// fields of arbitrary bytes without one.
+fn is_untyped_column(field: &Field) -> bool {
+    field.data_type() == &DataType::Binary
+}
+
+// The timeseries schema of the table data.
+fn table_schema(table_name: &str, table_data: &RecordBatch) -> Result<Schema> {
+    table_data
+        .schema()
+        .try_into()
+        .map_err(|e: data_types::schema::Error| Error::InvalidTableData {
+            table_name: table_name.to_owned(),
+            msg: e.to_string(),
+        })
+}
+
+// Determines if the schema contains exactly one timestamp column, which must
+// be called "time" and have `Int64` values. Without it a table's time range
+// is undefined.
+fn validate_time_column(table_name: &str, schema: &Schema) -> Result<()> {
+    let time_columns = (0..schema.len())
+        .map(|i| schema.field(i))
+        .filter(|(lp_type, _)| matches!(lp_type, Some(InfluxColumnType::Timestamp)))
+        .map(|(_, field)| field)
+        .collect::<Vec<_>>();
+
+    match time_columns.as_slice() {
+        [] => InvalidTableData {
+            table_name,
+            msg: "missing timestamp column",
+        }
+        .fail(),
+        [field] => {
+            ensure!(
+                field.name() == TIME_COLUMN_NAME && field.data_type() == &DataType::Int64,
+                InvalidTableData {
+                    table_name,
+                    msg: format!(
+                        "timestamp column {:?} with type {:?} must be called {:?} with type Int64",
+                        field.name(),
+                        field.data_type(),
+                        TIME_COLUMN_NAME,
+                    ),
+                }
+            );
+            Ok(())
+        }
+        _ => InvalidTableData {
+            table_name,
+            msg: format!("{} timestamp columns, expected one", time_columns.len()),
         }
+        .fail(),
     }
 }
 
-/// An iterable set of results for calls to `read_aggregate`.
+/// Describes the effect of compacting a partition with
+/// `Database::compact_partition`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionReport {
+    /// The number of row groups in the partition before compaction.
+    pub row_groups_before: usize,
+
+    /// The number of row groups in the partition after compaction.
+    pub row_groups_after: usize,
+
+    /// The size of the partition in bytes before compaction.
+    pub size_before: u64,
+
+    /// The size of the partition in bytes after compaction.
+    pub size_after: u64,
+}
+
+impl CompactionReport {
+    /// The number of bytes freed by compaction, which is zero if the
+    /// partition grew.
+    pub fn bytes_reclaimed(&self) -> u64 {
+        self.size_before.saturating_sub(self.size_after)
+    }
+}
+
+/// Statistics describing the contents of a `Database`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DatabaseStats {
+    /// The total size of the database in bytes.
+    pub size: u64,
+
+    /// The total number of rows in the database.
+    pub rows: u64,
+
+    /// The number of partitions in the database.
+    pub partitions: usize,
+
+    /// The number of chunks under all partitions.
+    pub chunks: usize,
+
+    /// The number of tables under all chunks.
+    pub tables: usize,
+
+    /// The number of row groups under all tables.
+    pub row_groups: usize,
+}
+
+/// Describes the work carried out when executing a query, as opposed to the
+/// work that was planned.
 ///
-/// The iterator lazily executes against each chunk on a call to `next`.
-/// Currently all row group results inside the chunk's table are merged before
-/// this iterator returns a record batch. Therefore the caller can expect at
-/// most one record batch to be yielded for each chunk.
-pub struct ReadAggregateResults<'input, 'chunk> {
+/// Metrics are accumulated as results are read, so they only describe the
+/// complete query once its results have been exhausted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueryMetrics {
+    /// The total number of rows in all row groups that were read.
+    pub rows_scanned: u64,
+
+    /// The number of row groups that were not read because their metadata
+    /// showed they could not satisfy the predicate.
+    pub row_groups_pruned: usize,
+
+    /// The number of rows in the record batches returned to the caller.
+    pub rows_returned: u64,
+}
+
+/// ReadFilterResults implements ...
+pub struct ReadFilterResults<'input, 'chunk> {
     chunks: Vec<&'chunk Chunk>,
     next_i: usize,
+    curr_table_results: Option<table::ReadFilterResults<'chunk>>,
 
     table_name: &'input str,
     predicate: Predicate,
-    group_columns: table::ColumnSelection<'input>,
-    aggregates: Vec<(ColumnName<'input>, AggregateType)>,
+    select_columns: table::ColumnSelection<'input>,
+
+    // When set, the columns read in place of `select_columns`: the selected
+    // columns preceded by the time column.
+    select_columns_with_time: Option<Vec<&'input str>>,
+
+    // When set, adjacent results are concatenated until they contain up to
+    // this many rows.
+    target_batch_rows: Option<usize>,
+
+    // A result that could not be coalesced into the previously returned
+    // batch.
+    pending: Option<RecordBatch>,
+
+    // The expressions of the predicate that were and were not applied across
+    // all chunks.
+    pushdown: PredicatePushdown,
+
+    // When set, all results are conformed to this schema.
+    schema: Option<SchemaRef>,
+
+    // When set, no further row groups are read.
+    cancellation: Option<Arc<AtomicBool>>,
+
+    // When set, a record batch without rows is returned if no rows match.
+    include_schema_on_empty: bool,
+
+    // Metrics for the chunks that have been fully read.
+    metrics: QueryMetrics,
 }
 
-impl<'input, 'chunk> ReadAggregateResults<'input, 'chunk> {
+impl<'input, 'chunk> fmt::Debug for ReadFilterResults<'input, 'chunk> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadFilterResults")
+            .field("chunks.len", &self.chunks.len())
+            .field("next_i", &self.next_i)
+            .field("curr_table_results", &"<OPAQUE>")
+            .field("table_name", &self.table_name)
+            .field("predicate", &self.predicate)
+            .field("select_columns", &self.select_columns)
+            .field("select_columns_with_time", &self.select_columns_with_time)
+            .field("target_batch_rows", &self.target_batch_rows)
+            .field("pushdown", &self.pushdown)
+            .field("schema", &self.schema)
+            .field("cancellation", &self.cancellation)
+            .field("include_schema_on_empty", &self.include_schema_on_empty)
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+impl<'input, 'chunk> ReadFilterResults<'input, 'chunk> {
     fn new(
         chunks: Vec<&'chunk Chunk>,
         table_name: &'input str,
         predicate: Predicate,
-        group_columns: table::ColumnSelection<'input>,
-        aggregates: Vec<(ColumnName<'input>, AggregateType)>,
+        select_columns: table::ColumnSelection<'input>,
+        pushdown: PredicatePushdown,
+        schema: Option<SchemaRef>,
     ) -> Self {
         Self {
             chunks,
             next_i: 0,
+            curr_table_results: None,
             table_name,
             predicate,
-            group_columns,
-            aggregates,
+            select_columns,
+            select_columns_with_time: None,
+            target_batch_rows: None,
+            pending: None,
+            pushdown,
+            schema,
+            cancellation: None,
+            include_schema_on_empty: false,
+            metrics: QueryMetrics::default(),
         }
     }
-}
 
-impl<'input, 'chunk> Iterator for ReadAggregateResults<'input, 'chunk> {
-    type Item = RecordBatch;
+    /// Returns metrics describing the execution of the query so far.
+    pub fn metrics(&self) -> QueryMetrics {
+        let mut metrics = self.metrics;
+        if let Some(table_results) = &self.curr_table_results {
+            metrics.rows_scanned += table_results.rows_scanned();
+            metrics.row_groups_pruned += table_results.row_groups_pruned();
+        }
+        metrics
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.next_i == self.chunks.len() {
+    /// Describes which expressions of the predicate were applied to the
+    /// results, and which were not and must be re-checked by the caller.
+    ///
+    /// An expression is only considered applied if it was applied to every
+    /// chunk.
+    pub fn pushdown(&self) -> &PredicatePushdown {
+        &self.pushdown
+    }
+
+    /// Coalesce adjacent small results into record batches of up to `rows`
+    /// rows.
+    ///
+    /// By default one record batch is returned for each row group with
+    /// matching rows, which for many small chunks can mean many tiny record
+    /// batches. Results are only coalesced if they have the same schema, and
+    /// results already larger than `rows` are returned as they are.
+    pub fn with_target_batch_rows(mut self, rows: usize) -> Self {
+        self.target_batch_rows = Some(rows);
+        self
+    }
+
+    /// Stops reading row groups once `token` is set, e.g., because the client
+    /// that issued the query has gone away.
+    ///
+    /// The token is checked between row groups. Once it is set the iterator
+    /// ends early, and `check_cancelled` returns an error.
+    pub fn with_cancellation(mut self, token: Arc<AtomicBool>) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Always include the time column in the results, even if it was not
+    /// selected. When it was not selected it is returned as the first column.
+    ///
+    /// By default only the selected columns are returned.
+    pub fn with_time_column(mut self) -> Self {
+        if let table::ColumnSelection::Some(column_names) = &self.select_columns {
+            if !column_names.contains(&TIME_COLUMN_NAME) {
+                let mut columns = Vec::with_capacity(column_names.len() + 1);
+                columns.push(TIME_COLUMN_NAME);
+                columns.extend_from_slice(column_names);
+                self.select_columns_with_time = Some(columns);
+            }
+        }
+        self
+    }
+
+    /// Returns `Error::Cancelled` if the query's cancellation token has been
+    /// set. Callers should check this once iteration ends to determine whether
+    /// the results are complete.
+    pub fn check_cancelled(&self) -> Result<()> {
+        check_cancelled(&self.cancellation)
+    }
+
+    /// Returns a single record batch without any rows when no rows match,
+    /// rather than no record batches, so that the schema of the results is
+    /// still known, e.g., to build a table header.
+    ///
+    /// By default no record batches are returned when no rows match. Nothing
+    /// is returned if none of the chunks contain the table.
+    pub fn with_schema_on_empty(mut self) -> Self {
+        self.include_schema_on_empty = true;
+        self
+    }
+
+    /// Returns the results one row at a time rather than as record batches,
+    /// for consumers that process rows rather than columns.
+    pub fn rows(self) -> ReadFilterRows<'input, 'chunk> {
+        ReadFilterRows {
+            results: self,
+            batch: None,
+            next_row: 0,
+        }
+    }
+
+    // Returns the results for the next row group with matching rows.
+    fn next_batch(&mut self) -> Option<RecordBatch> {
+        if self.next_i == self.chunks.len() || self.check_cancelled().is_err() {
             return None;
         }
 
-        let curr_i = self.next_i;
-        self.next_i += 1;
+        // Try next chunk's table.
+        if self.curr_table_results.is_none() {
+            let select_columns_with_time = self
+                .select_columns_with_time
+                .as_deref()
+                .map(table::ColumnSelection::Some);
+            let select_columns = select_columns_with_time
+                .as_ref()
+                .unwrap_or(&self.select_columns);
+            self.curr_table_results = Some(
+                self.chunks[self.next_i]
+                    .read_filter(self.table_name, &self.predicate, select_columns)
+                    .unwrap(),
+            );
+        }
 
-        // execute against next chunk
-        match &mut self.chunks[curr_i].read_aggregate(
-            self.table_name,
-            self.predicate.clone(),
-            &self.group_columns,
-            &self.aggregates,
-        ) {
-            Some(results_itr) => {
-                let mut row_group_results = results_itr.collect::<Vec<_>>();
-                // table current emits at most one merged result.
-                match row_group_results.len() {
-                    0 => self.next(), // no results try next chunk's table
-                    1 => Some(row_group_results.remove(0).try_into().unwrap()),
-                    _ => panic!("currently expect at most one result"),
+        match &mut self.curr_table_results {
+            // Table potentially has some results.
+            Some(table_results) => {
+                // Table has found results in a row group.
+                if let Some(rb) = table_results.next_record_batch() {
+                    return match &self.schema {
+                        // it should not be possible for conforming a batch to
+                        // the union of the tables' schemas to fail.
+                        Some(schema) => Some(conform_record_batch(rb, schema).unwrap()),
+                        None => Some(rb),
+                    };
                 }
+
+                // no more results for row groups in the table. Try next chunk.
+                self.metrics.rows_scanned += table_results.rows_scanned();
+                self.metrics.row_groups_pruned += table_results.row_groups_pruned();
+                self.next_i += 1;
+                self.curr_table_results = None;
+                self.next_batch()
+            }
+            // Table does not exist.
+            None => {
+                // Since chunk pruning is the caller's responsibility, I don't
+                // think the caller should request chunk data that does not have
+                // the right tables.
+                todo!(
+                    "What to do about this? Seems like this is an error on the part of the caller"
+                );
             }
-            None => self.next(), // try next chunk
         }
     }
 }
 
-/// An iterable set of results for calls to `read_window_aggregate`.
-///
-/// There may be some internal buffering and merging of results before a record
-/// batch is emitted from the iterator.
-pub struct ReadWindowAggregateResults {}
-
-impl Iterator for ReadWindowAggregateResults {
+impl<'input, 'chunk> Iterator for ReadFilterResults<'input, 'chunk> {
     type Item = RecordBatch;
 
     fn next(&mut self) -> Option<Self::Item> {
-        None
+        let rb = match self.target_batch_rows {
+            Some(rows) => self.next_coalesced(rows),
+            None => self.next_batch(),
+        };
+        let rb = match rb {
+            Some(rb) => rb,
+            None => return self.next_empty(),
+        };
+
+        self.metrics.rows_returned += rb.num_rows() as u64;
+        Some(rb)
     }
 }
 
-/// An iterable set of results for calls to `tag_values`.
-///
-/// There may be some internal buffering and merging of results before a record
-/// batch is emitted from the iterator.
-pub struct TagValuesResults {}
+impl<'input, 'chunk> ReadFilterResults<'input, 'chunk> {
+    // Returns a record batch without rows, with the schema of the results,
+    // if one was requested and no rows have been returned. It is returned at
+    // most once.
+    fn next_empty(&mut self) -> Option<RecordBatch> {
+        if !self.include_schema_on_empty
+            || self.metrics.rows_returned > 0
+            || self.check_cancelled().is_err()
+        {
+            return None;
+        }
+        self.include_schema_on_empty = false;
 
-impl Iterator for TagValuesResults {
-    type Item = RecordBatch;
+        let schema = match &self.schema {
+            Some(schema) => Arc::clone(schema),
+            None => {
+                let table = self.chunks.first()?.table(self.table_name)?;
+                let select_columns_with_time = self
+                    .select_columns_with_time
+                    .as_deref()
+                    .map(table::ColumnSelection::Some);
+                let select_columns = select_columns_with_time
+                    .as_ref()
+                    .unwrap_or(&self.select_columns);
+
+                // it should not be possible for converting the schema of a
+                // table's results to fail.
+                let schema: Schema = (&table.read_filter_schema(select_columns))
+                    .try_into()
+                    .unwrap();
+                schema.into()
+            }
+        };
 
-    fn next(&mut self) -> Option<Self::Item> {
-        None
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|field| null_array(field.data_type(), 0))
+            .collect::<Result<Vec<_>>>()
+            .ok()?;
+        RecordBatch::try_new(schema, columns).ok()
+    }
+
+    // Returns the results for the next row groups with matching rows,
+    // concatenated into a record batch of up to `target_rows` rows.
+    fn next_coalesced(&mut self, target_rows: usize) -> Option<RecordBatch> {
+        let first = self.pending.take().or_else(|| self.next_batch())?;
+        let mut rows = first.num_rows();
+        let mut batches = vec![first];
+        while rows < target_rows {
+            match self.next_batch() {
+                Some(rb)
+                    if rb.schema() == batches[0].schema()
+                        && rows + rb.num_rows() <= target_rows =>
+                {
+                    rows += rb.num_rows();
+                    batches.push(rb);
+                }
+                Some(rb) => {
+                    // can't be coalesced into this batch.
+                    self.pending = Some(rb);
+                    break;
+                }
+                None => break,
+            }
+        }
+
+        // it should not be possible for concatenating batches with the same
+        // schema to fail.
+        Some(concat_record_batches(batches).unwrap())
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use std::sync::Arc;
+/// A row of the results of `read_filter`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    schema: SchemaRef,
+    values: Vec<OwnedValue>,
+}
 
-    use arrow_deps::arrow::{
-        array::{
-            ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, StringArray, UInt64Array,
-        },
-        datatypes::DataType::{Float64, UInt64},
-    };
+impl Row {
+    /// The schema of the results the row belongs to.
+    pub fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
 
-    use column::Values;
-    use data_types::schema::builder::SchemaBuilder;
+    /// The values of the row, in the order of the schema's columns.
+    pub fn values(&self) -> &[OwnedValue] {
+        &self.values
+    }
 
-    // helper to make the `database_update_chunk` test simpler to read.
-    fn gen_recordbatch() -> RecordBatch {
-        let schema = SchemaBuilder::new()
-            .non_null_tag("region")
-            .non_null_field("counter", Float64)
-            .timestamp()
-            .build()
-            .unwrap()
-            .into();
+    /// The value of the column at position `index`.
+    pub fn value(&self, index: usize) -> Option<&OwnedValue> {
+        self.values.get(index)
+    }
 
-        let data: Vec<ArrayRef> = vec![
-            Arc::new(StringArray::from(vec!["west", "west", "east"])),
-            Arc::new(Float64Array::from(vec![1.2, 3.3, 45.3])),
-            Arc::new(Int64Array::from(vec![11111111, 222222, 3333])),
-        ];
+    /// The value of the column named `column_name`.
+    pub fn value_by_name(&self, column_name: &str) -> Option<&OwnedValue> {
+        let index = self.schema.index_of(column_name).ok()?;
+        self.value(index)
+    }
+}
 
-        RecordBatch::try_new(schema, data).unwrap()
+/// An iterator over the rows of the results of `read_filter`, returned by
+/// `ReadFilterResults::rows`.
+///
+/// Record batches are read lazily, as with `ReadFilterResults`, and each is
+/// released once all of its rows have been returned.
+pub struct ReadFilterRows<'input, 'chunk> {
+    results: ReadFilterResults<'input, 'chunk>,
+    batch: Option<RecordBatch>,
+    next_row: usize,
+}
+
+impl<'input, 'chunk> ReadFilterRows<'input, 'chunk> {
+    /// Returns metrics describing the execution of the query so far.
+    pub fn metrics(&self) -> QueryMetrics {
+        self.results.metrics()
     }
+}
 
-    #[test]
-    fn database_update_partition() {
-        let mut db = Database::new();
-        db.upsert_partition("hour_1", 22, "a_table", gen_recordbatch());
+impl<'input, 'chunk> fmt::Debug for ReadFilterRows<'input, 'chunk> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadFilterRows")
+            .field("results", &self.results)
+            .field("next_row", &self.next_row)
+            .finish()
+    }
+}
 
-        assert_eq!(db.rows(), 3);
-        assert_eq!(db.tables(), 1);
-        assert_eq!(db.row_groups(), 1);
+impl<'input, 'chunk> Iterator for ReadFilterRows<'input, 'chunk> {
+    type Item = Row;
 
-        let partition = db.partitions.values().next().unwrap();
-        assert_eq!(partition.tables(), 1);
-        assert_eq!(partition.rows(), 3);
-        assert_eq!(partition.row_groups(), 1);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match &self.batch {
+                Some(batch) if self.next_row < batch.num_rows() => {
+                    let values = batch
+                        .columns()
+                        .iter()
+                        .map(|arr| {
+                            Value::from_arrow(arr, self.next_row)
+                                .expect("read buffer results have supported types")
+                                .into()
+                        })
+                        .collect();
+                    self.next_row += 1;
+                    return Some(Row {
+                        schema: batch.schema(),
+                        values,
+                    });
+                }
+                _ => {
+                    self.batch = Some(self.results.next()?);
+                    self.next_row = 0;
+                }
+            }
+        }
+    }
+}
 
-        // Updating the chunk with another row group for the table just adds
-        // that row group to the existing table.
-        db.upsert_partition("hour_1", 22, "a_table", gen_recordbatch());
-        assert_eq!(db.rows(), 6);
-        assert_eq!(db.tables(), 1); // still one table
-        assert_eq!(db.row_groups(), 2);
+/// An iterable set of results for calls to `read_filter_time_ordered`.
+///
+/// The iterator lazily reads each row group once the rows being emitted reach
+/// the start of the row group's time range.
+pub struct ReadFilterTimeOrderedResults<'input, 'chunk> {
+    select_columns: table::ColumnSelection<'input>,
 
-        let partition = db.partitions.values().next().unwrap();
-        assert_eq!(partition.tables(), 1); // it's the same table.
-        assert_eq!(partition.rows(), 6);
-        assert_eq!(partition.row_groups(), 2);
+    // When set, all results are conformed to this schema.
+    schema: Option<SchemaRef>,
 
-        // Adding the same data under another table would increase the table
-        // count.
-        db.upsert_partition("hour_1", 22, "b_table", gen_recordbatch());
-        assert_eq!(db.rows(), 9);
-        assert_eq!(db.tables(), 2);
-        assert_eq!(db.row_groups(), 3);
+    // Every row group in the chunks' tables.
+    sources: Vec<TimeOrderedSource<'chunk>>,
 
-        let partition = db.partitions.values().next().unwrap();
-        assert_eq!(partition.tables(), 2);
-        assert_eq!(partition.rows(), 9);
-        assert_eq!(partition.row_groups(), 3);
+    // The next timestamp of each source with rows remaining, smallest first.
+    // For sources that haven't been read yet this is the start of the row
+    // group's time range.
+    heap: BinaryHeap<Reverse<(i64, usize)>>,
 
-        // Adding the data under another chunk adds a new chunk.
-        db.upsert_partition("hour_1", 29, "a_table", gen_recordbatch());
-        assert_eq!(db.rows(), 12);
-        assert_eq!(db.tables(), 3); // two distinct tables but across two chunks.
-        assert_eq!(db.row_groups(), 4);
+    // The maximum number of rows in each emitted record batch.
+    batch_rows: usize,
+}
 
-        let partition = db.partitions.values().next().unwrap();
-        assert_eq!(partition.tables(), 3);
-        assert_eq!(partition.rows(), 12);
-        assert_eq!(partition.row_groups(), 4);
+// A row group being merged into time ordered results.
+struct TimeOrderedSource<'chunk> {
+    table: &'chunk Table,
+    row_group: usize,
+    predicate: Predicate,
 
-        let chunk_22 = db
-            .partitions
-            .get("hour_1")
-            .unwrap()
-            .chunks
-            .values()
-            .next()
+    // Whether the row group has been read yet.
+    read: bool,
+
+    // The matching rows of the row group sorted by time, which are released
+    // once all of them have been emitted.
+    batch: Option<RecordBatch>,
+    times: Vec<i64>,
+    next_row: usize,
+}
+
+impl<'chunk> TimeOrderedSource<'chunk> {
+    // Reads the matching rows from the row group and sorts them by time.
+    fn read(&mut self, select_columns: &ColumnSelection<'_>, schema: &Option<SchemaRef>) {
+        self.read = true;
+
+        // the row group exists, so reading it can't fail.
+        let result = self
+            .table
+            .select_row_group(self.row_group, select_columns, &self.predicate)
             .unwrap();
-        assert_eq!(chunk_22.tables(), 2);
-        assert_eq!(chunk_22.rows(), 9);
-        assert_eq!(chunk_22.row_groups(), 3);
+        if result.is_empty() {
+            return;
+        }
 
-        let chunk_29 = db
-            .partitions
-            .get("hour_1")
+        // it should not be possible for the conversion to record batch, or
+        // conforming it to the union of the tables' schemas, to fail.
+        let rb: RecordBatch = result.try_into().unwrap();
+        let rb = match schema {
+            Some(schema) => conform_record_batch(rb, schema).unwrap(),
+            None => rb,
+        };
+        let rb = sort_by_time(rb).unwrap();
+
+        let time_column = rb.schema().index_of(TIME_COLUMN_NAME).unwrap();
+        self.times = rb
+            .column(time_column)
+            .as_any()
+            .downcast_ref::<Int64Array>()
             .unwrap()
-            .chunks
             .values()
-            .nth(1)
-            .unwrap();
-        assert_eq!(chunk_29.tables(), 1);
-        assert_eq!(chunk_29.rows(), 3);
-        assert_eq!(chunk_29.row_groups(), 1);
+            .to_vec();
+        self.batch = Some(rb);
     }
+}
 
-    // Helper function to assert the contents of a column on a record batch.
-    fn assert_rb_column_equals(rb: &RecordBatch, col_name: &str, exp: &Values<'_>) {
-        let got_column = rb.column(rb.schema().index_of(col_name).unwrap());
-
-        match exp {
-            Values::String(exp_data) => {
-                let arr: &StringArray = got_column.as_any().downcast_ref::<StringArray>().unwrap();
-                assert_eq!(&arr.iter().collect::<Vec<_>>(), exp_data);
+impl<'input, 'chunk> ReadFilterTimeOrderedResults<'input, 'chunk> {
+    // The default maximum number of rows in each emitted record batch.
+    const DEFAULT_BATCH_ROWS: usize = 1024;
+
+    fn new(results: ReadFilterResults<'input, 'chunk>) -> Self {
+        let table_name = results.table_name;
+        let mut sources = vec![];
+        let mut heap = BinaryHeap::new();
+        for chunk in results.chunks {
+            // the table was found in every chunk by `read_filter`.
+            let table = chunk.table(table_name).unwrap();
+            let predicate = Predicate::new(table.predicate_pushdown(&results.predicate).applied);
+            for row_group in 0..table.len() {
+                let (min, _) = table.row_group_time_range(row_group).unwrap();
+                heap.push(Reverse((min, sources.len())));
+                sources.push(TimeOrderedSource {
+                    table,
+                    row_group,
+                    predicate: predicate.clone(),
+                    read: false,
+                    batch: None,
+                    times: vec![],
+                    next_row: 0,
+                });
             }
-            Values::I64(exp_data) => {
-                let arr: &Int64Array = got_column.as_any().downcast_ref::<Int64Array>().unwrap();
-                assert_eq!(arr.values(), exp_data);
+        }
+
+        Self {
+            select_columns: results.select_columns,
+            schema: results.schema,
+            sources,
+            heap,
+            batch_rows: Self::DEFAULT_BATCH_ROWS,
+        }
+    }
+
+    /// Emit record batches of up to `rows` rows.
+    pub fn with_batch_rows(mut self, rows: usize) -> Self {
+        assert!(rows > 0, "batch rows must be positive");
+        self.batch_rows = rows;
+        self
+    }
+}
+
+impl<'input, 'chunk> Iterator for ReadFilterTimeOrderedResults<'input, 'chunk> {
+    type Item = RecordBatch;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // runs of consecutive rows to emit, as (source, offset, length).
+        let mut runs: Vec<(usize, usize, usize)> = vec![];
+        let mut rows = 0;
+        while rows < self.batch_rows {
+            let i = match self.heap.pop() {
+                Some(Reverse((_, i))) => i,
+                None => break,
+            };
+
+            let source = &mut self.sources[i];
+            if !source.read {
+                source.read(&self.select_columns, &self.schema);
+                if let Some(&time) = source.times.first() {
+                    self.heap.push(Reverse((time, i)));
+                }
+                continue;
             }
-            Values::U64(exp_data) => {
-                let arr: &UInt64Array = got_column.as_any().downcast_ref::<UInt64Array>().unwrap();
-                assert_eq!(arr.values(), exp_data);
+
+            let row = source.next_row;
+            source.next_row += 1;
+            rows += 1;
+            match runs.last_mut() {
+                Some((run_source, offset, len)) if *run_source == i && *offset + *len == row => {
+                    *len += 1
+                }
+                _ => runs.push((i, row, 1)),
             }
-            Values::F64(exp_data) => {
-                let arr: &Float64Array =
-                    got_column.as_any().downcast_ref::<Float64Array>().unwrap();
-                assert_eq!(arr.values(), exp_data);
+
+            if let Some(&time) = source.times.get(source.next_row) {
+                self.heap.push(Reverse((time, i)));
             }
-            Values::I64N(exp_data) => {
-                let arr: &Int64Array = got_column.as_any().downcast_ref::<Int64Array>().unwrap();
-                let got_data = (0..got_column.len())
-                    .map(|i| {
-                        if got_column.is_null(i) {
-                            None
-                        } else {
-                            Some(arr.value(i))
-                        }
+        }
+
+        if runs.is_empty() {
+            return None;
+        }
+
+        let schema = self.sources[runs[0].0].batch.as_ref().unwrap().schema();
+        let columns = (0..schema.fields().len())
+            .map(|c| {
+                let arrays = runs
+                    .iter()
+                    .map(|&(i, offset, len)| {
+                        self.sources[i]
+                            .batch
+                            .as_ref()
+                            .unwrap()
+                            .column(c)
+                            .slice(offset, len)
                     })
                     .collect::<Vec<_>>();
-                assert_eq!(&got_data, exp_data);
+                concat(&arrays.iter().map(|a| a.as_ref()).collect::<Vec<_>>())
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+
+        // release the rows of sources that have been completely emitted.
+        for &(i, _, _) in &runs {
+            let source = &mut self.sources[i];
+            if source.next_row == source.times.len() {
+                source.batch = None;
+                source.times = vec![];
             }
-            Values::U64N(exp_data) => {
-                let arr: &UInt64Array = got_column.as_any().downcast_ref::<UInt64Array>().unwrap();
-                let got_data = (0..got_column.len())
-                    .map(|i| {
-                        if got_column.is_null(i) {
-                            None
-                        } else {
-                            Some(arr.value(i))
+        }
+
+        // it should not be possible for building a batch from columns sliced
+        // from batches with the same schema to fail.
+        Some(RecordBatch::try_new(schema, columns).unwrap())
+    }
+}
+
+// Sorts the rows of the record batch by its time column.
+fn sort_by_time(rb: RecordBatch) -> Result<RecordBatch> {
+    let time_column = rb.schema().index_of(TIME_COLUMN_NAME).context(ArrowError)?;
+    let times = rb.column(time_column);
+    let sorted = times
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .map_or(false, |arr| arr.values().windows(2).all(|w| w[0] <= w[1]));
+    if sorted {
+        return Ok(rb);
+    }
+
+    let indices = lexsort_to_indices(&[SortColumn {
+        values: Arc::clone(times),
+        options: None,
+    }])
+    .context(ArrowError)?;
+    let columns = rb
+        .columns()
+        .iter()
+        .map(|column| take(column, &indices, None))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context(ArrowError)?;
+
+    RecordBatch::try_new(rb.schema(), columns).context(ArrowError)
+}
+
+// Returns `Error::Cancelled` if the cancellation token is set.
+fn check_cancelled(token: &Option<Arc<AtomicBool>>) -> Result<()> {
+    match token {
+        Some(token) if token.load(Ordering::Relaxed) => Cancelled.fail(),
+        _ => Ok(()),
+    }
+}
+
+// Returns the union of the schemas of all columns in the tables, ordered by
+// column name. Columns missing from any of the tables are nullable.
+fn union_schema(tables: &[&Table]) -> Result<SchemaRef> {
+    let mut columns = BTreeMap::new();
+    for table in tables {
+        for (column_type, data_type) in table.schema_for_all_columns() {
+            match columns.entry(column_type.as_str().to_owned()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(((column_type, data_type), 1));
+                }
+                Entry::Occupied(mut entry) => {
+                    ensure!(
+                        entry.get().0 == (column_type, data_type),
+                        ColumnTypeMismatch {
+                            column_name: entry.key()
                         }
-                    })
-                    .collect::<Vec<_>>();
-                assert_eq!(&got_data, exp_data);
+                    );
+                    entry.get_mut().1 += 1;
+                }
             }
-            Values::F64N(exp_data) => {
-                let arr: &Float64Array =
-                    got_column.as_any().downcast_ref::<Float64Array>().unwrap();
-                let got_data = (0..got_column.len())
-                    .map(|i| {
-                        if got_column.is_null(i) {
-                            None
-                        } else {
-                            Some(arr.value(i))
-                        }
-                    })
-                    .collect::<Vec<_>>();
-                assert_eq!(&got_data, exp_data);
+        }
+    }
+
+    let result_schema = ResultSchema {
+        select_columns: columns.values().map(|(schema, _)| schema.clone()).collect(),
+        ..ResultSchema::default()
+    };
+    let schema: Schema = (&result_schema).try_into().context(SchemaError)?;
+
+    let fields = schema
+        .inner()
+        .fields()
+        .iter()
+        .map(|field| {
+            let in_all_tables = columns[field.name()].1 == tables.len();
+            Field::new(
+                field.name(),
+                field.data_type().clone(),
+                field.is_nullable() || !in_all_tables,
+            )
+        })
+        .collect();
+
+    Ok(Arc::new(ArrowSchema::new_with_metadata(
+        fields,
+        schema.inner().metadata().clone(),
+    )))
+}
+
+// Conforms the record batch to `schema`, which must contain all of the
+// batch's columns. Columns missing from the batch are filled with NULL values.
+fn conform_record_batch(rb: RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
+    if rb.schema() == *schema {
+        return Ok(rb);
+    }
+
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match rb.schema().index_of(field.name()) {
+            Ok(i) => Ok(Arc::clone(rb.column(i))),
+            Err(_) => null_array(field.data_type(), rb.num_rows()),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    RecordBatch::try_new(Arc::clone(schema), columns).context(ArrowError)
+}
+
+// Returns an array of `rows` NULL values of the provided type.
+fn null_array(data_type: &DataType, rows: usize) -> Result<ArrayRef> {
+    let array: ArrayRef = match data_type {
+        DataType::Utf8 => Arc::new(StringArray::from(vec![None::<&str>; rows])),
+        DataType::Binary => Arc::new(BinaryArray::from(vec![None::<&[u8]>; rows])),
+        DataType::Int64 => Arc::new(Int64Array::from(vec![None; rows])),
+        DataType::UInt64 => Arc::new(UInt64Array::from(vec![None; rows])),
+        DataType::Float64 => Arc::new(Float64Array::from(vec![None; rows])),
+        DataType::Boolean => Arc::new(BooleanArray::from(vec![None; rows])),
+        data_type => {
+            return UnsupportedOperation {
+                msg: format!("cannot fill missing column of type {:?}", data_type),
             }
-            Values::Bool(exp_data) => {
-                let arr: &BooleanArray =
-                    got_column.as_any().downcast_ref::<BooleanArray>().unwrap();
-                let got_data = (0..got_column.len())
-                    .map(|i| {
-                        if got_column.is_null(i) {
-                            None
-                        } else {
-                            Some(arr.value(i))
-                        }
-                    })
-                    .collect::<Vec<_>>();
-                assert_eq!(&got_data, exp_data);
+            .fail()
+        }
+    };
+    Ok(array)
+}
+
+// Concatenates record batches sharing the same schema into a single record
+// batch.
+fn concat_record_batches(mut batches: Vec<RecordBatch>) -> Result<RecordBatch> {
+    if batches.len() == 1 {
+        return Ok(batches.remove(0));
+    }
+
+    let schema = batches[0].schema();
+    let columns = (0..schema.fields().len())
+        .map(|i| {
+            let arrays = batches
+                .iter()
+                .map(|rb| rb.column(i).as_ref())
+                .collect::<Vec<_>>();
+            concat(&arrays)
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context(ArrowError)?;
+
+    RecordBatch::try_new(schema, columns).context(ArrowError)
+}
+
+/// An iterable set of results for calls to `read_aggregate`.
+///
+/// The iterator lazily executes against each chunk on a call to `next`.
+/// Currently all row group results inside the chunk's table are merged before
+/// this iterator returns a record batch. Therefore the caller can expect at
+/// most one record batch to be yielded for each chunk.
+pub struct ReadAggregateResults<'input, 'chunk> {
+    chunks: Vec<&'chunk Chunk>,
+    next_i: usize,
+
+    table_name: &'input str,
+    predicate: Predicate,
+    group_columns: table::ColumnSelection<'input>,
+    aggregates: Vec<(ColumnName<'input>, AggregateType)>,
+    nan_handling: NanHandling,
+
+    // When set, groups whose aggregates don't satisfy this predicate are
+    // removed from the results.
+    having: Option<Predicate>,
+
+    // When set, no further row groups are read.
+    cancellation: Option<Arc<AtomicBool>>,
+
+    metrics: QueryMetrics,
+}
+
+impl<'input, 'chunk> ReadAggregateResults<'input, 'chunk> {
+    fn new(
+        chunks: Vec<&'chunk Chunk>,
+        table_name: &'input str,
+        predicate: Predicate,
+        group_columns: table::ColumnSelection<'input>,
+        aggregates: Vec<(ColumnName<'input>, AggregateType)>,
+    ) -> Self {
+        Self {
+            chunks,
+            next_i: 0,
+            table_name,
+            predicate,
+            group_columns,
+            aggregates,
+            nan_handling: NanHandling::default(),
+            having: None,
+            cancellation: None,
+            metrics: QueryMetrics::default(),
+        }
+    }
+
+    /// Returns metrics describing the execution of the query so far.
+    pub fn metrics(&self) -> QueryMetrics {
+        self.metrics
+    }
+
+    /// Sets how NaN values in float columns are treated when aggregating. By
+    /// default NaN values are skipped.
+    pub fn with_nan_handling(mut self, nan_handling: NanHandling) -> Self {
+        self.nan_handling = nan_handling;
+        self
+    }
+
+    /// Stops reading row groups once `token` is set, e.g., because the client
+    /// that issued the query has gone away.
+    ///
+    /// The token is checked between row groups. Once it is set the iterator
+    /// ends early, and `check_cancelled` returns an error.
+    pub fn with_cancellation(mut self, token: Arc<AtomicBool>) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Removes the groups whose aggregates don't satisfy `having`, as with a
+    /// SQL `HAVING` clause, before their record batches are built.
+    ///
+    /// The predicate's expressions reference aggregate columns by their
+    /// result column names, e.g., `usage_sum` for the `Sum` of `usage`, and
+    /// a NULL aggregate never satisfies an expression. The predicate applies
+    /// to the final aggregates of each group, so the results of all chunks
+    /// are merged, and emitted as a single record batch.
+    ///
+    /// Returns an error if the predicate references a column that is not an
+    /// aggregate in the results, or if the chunks' tables have columns with
+    /// different types, because their results can't then be merged.
+    pub fn with_having(mut self, having: Predicate) -> Result<Self> {
+        let aggregate_names = self
+            .aggregates
+            .iter()
+            .map(|(column_name, agg_type)| format!("{}_{}", column_name, agg_type))
+            .collect::<Vec<_>>();
+        for expr in having.iter() {
+            ensure!(
+                aggregate_names.iter().any(|name| name == expr.column()),
+                ColumnNotFound {
+                    column_name: expr.column(),
+                    role: ColumnRole::Having,
+                }
+            );
+        }
+
+        let tables = self
+            .chunks
+            .iter()
+            .filter_map(|chunk| chunk.table(self.table_name))
+            .collect::<Vec<_>>();
+        union_schema(&tables)?;
+
+        self.having = Some(having);
+        Ok(self)
+    }
+
+    /// Returns `Error::Cancelled` if the query's cancellation token has been
+    /// set. Callers should check this once iteration ends to determine whether
+    /// the results are complete.
+    pub fn check_cancelled(&self) -> Result<()> {
+        check_cancelled(&self.cancellation)
+    }
+}
+
+impl<'input, 'chunk> ReadAggregateResults<'input, 'chunk> {
+    /// Executes against all remaining chunks and reorganises the results so
+    /// that the rows for each group are contiguous, returning one record
+    /// batch for each group ordered by group key.
+    ///
+    /// Each chunk's results contain at most one row for a group, so a group's
+    /// batch contains a row for each chunk the group appears in, in the order
+    /// the chunks were provided.
+    pub fn into_grouped_batches(mut self) -> Result<Vec<RecordBatch>> {
+        let mut results = vec![];
+        while let Some(result) = self.next_result() {
+            results.push(result);
+        }
+        self.check_cancelled()?;
+
+        let schema = match results.first() {
+            Some(result) => result.schema().clone(),
+            None => return Ok(vec![]),
+        };
+        ensure!(
+            results.iter().all(|result| result.schema() == &schema),
+            UnsupportedOperation {
+                msg: "cannot group results with different schemas across chunks"
             }
-            Values::ByteArray(exp_data) => {
-                let arr: &BinaryArray = got_column.as_any().downcast_ref::<BinaryArray>().unwrap();
-                let got_data = (0..got_column.len())
-                    .map(|i| {
-                        if got_column.is_null(i) {
-                            None
-                        } else {
-                            Some(arr.value(i))
-                        }
-                    })
-                    .collect::<Vec<_>>();
-                assert_eq!(&got_data, exp_data);
+        );
+
+        // A stable sort keeps the rows for each group in chunk order.
+        let mut rows = results
+            .into_iter()
+            .flat_map(|result| result.group_keys.into_iter().zip(result.aggregates))
+            .collect::<Vec<_>>();
+        rows.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut batches = vec![];
+        let mut group = row_group::ReadAggregateResult::default();
+        for (group_key, aggregates) in rows {
+            if matches!(group.group_keys.last(), Some(last) if last != &group_key) {
+                batches.push(group_batch(&schema, std::mem::take(&mut group)));
+            }
+            group.group_keys.push(group_key);
+            group.aggregates.push(aggregates);
+        }
+        if !group.is_empty() {
+            batches.push(group_batch(&schema, group));
+        }
+        Ok(batches)
+    }
+
+    /// Executes against all remaining chunks, merging their results into a
+    /// single `PartialAggregateResult`. This can be merged with the partial
+    /// results of the same query over other chunks, e.g., those computed by
+    /// other processes, before being converted into a record batch.
+    ///
+    /// Returns an error if a `HAVING` predicate has been set, because it
+    /// can only be applied once all partial results have been merged.
+    pub fn into_partial(mut self) -> Result<PartialAggregateResult> {
+        ensure!(
+            self.having.is_none(),
+            UnsupportedOperation {
+                msg: "cannot compute partial aggregates with a having predicate"
+            }
+        );
+
+        let mut partial = PartialAggregateResult::default();
+        while let Some(result) = self.next_result() {
+            partial = partial.merge(PartialAggregateResult::new(result))?;
+        }
+        self.check_cancelled()?;
+        Ok(partial)
+    }
+
+    // Returns the next results to emit. These are the results of the next
+    // chunk with results unless there is a `HAVING` predicate, which must be
+    // applied to the results of all chunks once they have been merged.
+    fn next_result(&mut self) -> Option<row_group::ReadAggregateResult<'chunk>> {
+        let result = match self.having.clone() {
+            None => self.next_chunk_result()?,
+            Some(having) => {
+                let mut merged: Option<row_group::ReadAggregateResult<'chunk>> = None;
+                while let Some(result) = self.next_chunk_result() {
+                    merged = Some(match merged {
+                        Some(merged) => merged.merge(result),
+                        None => result,
+                    });
+                }
+                if self.check_cancelled().is_err() {
+                    return None;
+                }
+
+                let mut result = merged?;
+                result.retain_having(&having);
+                if result.is_empty() {
+                    return None; // all groups removed
+                }
+                result
             }
+        };
+
+        self.metrics.rows_returned += result.cardinality() as u64;
+        Some(result)
+    }
+
+    // Executes against the next chunk with results.
+    fn next_chunk_result(&mut self) -> Option<row_group::ReadAggregateResult<'chunk>> {
+        if self.next_i == self.chunks.len() || self.check_cancelled().is_err() {
+            return None;
+        }
+
+        let curr_i = self.next_i;
+        self.next_i += 1;
+
+        // execute against next chunk. The table and columns have been
+        // validated when the `ReadAggregateResults` was created.
+        let mut results_itr = self.chunks[curr_i]
+            .read_aggregate(
+                self.table_name,
+                self.predicate.clone(),
+                &self.group_columns,
+                &self.aggregates,
+            )
+            .unwrap()
+            .with_nan_handling(self.nan_handling);
+        if let Some(token) = &self.cancellation {
+            results_itr = results_itr.with_cancellation(Arc::clone(token));
+        }
+
+        let mut row_group_results = results_itr.by_ref().collect::<Vec<_>>();
+        self.metrics.rows_scanned += results_itr.rows_scanned();
+        self.metrics.row_groups_pruned += results_itr.row_groups_pruned();
+        if self.check_cancelled().is_err() {
+            return None;
+        }
+
+        // table current emits at most one merged result.
+        match row_group_results.len() {
+            0 => self.next_chunk_result(), // no results try next chunk's table
+            1 => Some(row_group_results.remove(0)),
+            _ => panic!("currently expect at most one result"),
+        }
+    }
+}
+
+// Converts the rows of a single group into a record batch with the provided
+// schema.
+fn group_batch(
+    schema: &ResultSchema,
+    mut group: row_group::ReadAggregateResult<'_>,
+) -> RecordBatch {
+    group.schema = schema.clone();
+    group.group_keys_sorted = true;
+    // it should not be possible for the conversion to record batch to fail
+    // as the schema came from valid results.
+    group.try_into().unwrap()
+}
+
+impl<'input, 'chunk> Iterator for ReadAggregateResults<'input, 'chunk> {
+    type Item = RecordBatch;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // it should not be possible for the conversion to record batch to
+        // fail here.
+        self.next_result().map(|result| result.try_into().unwrap())
+    }
+}
+
+/// The partial state of the aggregates computed by a `read_aggregate` query
+/// over some set of chunks, holding each group's key and aggregates.
+///
+/// Partial results of the same query over disjoint sets of chunks can be
+/// merged, and the merged results converted into a record batch. With the
+/// `serde` feature enabled, partial results can be serialized, e.g., so that
+/// a coordinator can merge those computed by several workers.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PartialAggregateResult {
+    schema: ResultSchema,
+
+    // row-wise group keys and aggregates, ordered by group key.
+    group_keys: Vec<Vec<OwnedValue>>,
+    aggregates: Vec<Vec<OwnedAggregateResult>>,
+}
+
+impl PartialAggregateResult {
+    fn new(mut result: row_group::ReadAggregateResult<'_>) -> Self {
+        if !result.group_keys_sorted {
+            result.sort();
+        }
+
+        let (group_keys, aggregates) = result
+            .groups()
+            .map(|(key, aggs)| {
+                (
+                    key.iter().map(|&v| OwnedValue::from(v)).collect::<Vec<_>>(),
+                    aggs.iter()
+                        .map(OwnedAggregateResult::from)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .unzip();
+        Self {
+            schema: result.schema().clone(),
+            group_keys,
+            aggregates,
+        }
+    }
+
+    // Borrows the partial results as the results of a `read_aggregate` call.
+    fn as_result(&self) -> row_group::ReadAggregateResult<'_> {
+        let mut result = row_group::ReadAggregateResult {
+            schema: self.schema.clone(),
+            group_keys_sorted: true,
+            ..Default::default()
+        };
+        for (key, aggs) in self.group_keys.iter().zip(&self.aggregates) {
+            result.add_row(
+                key.iter().map(OwnedValue::as_value).collect(),
+                aggs.iter()
+                    .map(OwnedAggregateResult::as_aggregate)
+                    .collect(),
+            );
+        }
+        result
+    }
+
+    pub fn schema(&self) -> &ResultSchema {
+        &self.schema
+    }
+
+    /// The number of distinct group keys in the results.
+    pub fn cardinality(&self) -> usize {
+        self.group_keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.group_keys.is_empty()
+    }
+
+    /// Merges `other` into these results, combining the aggregates of the
+    /// groups they have in common.
+    ///
+    /// Returns an error if both results have groups but their schemas differ,
+    /// or if either doesn't match its schema, which is possible if it was
+    /// deserialized from invalid data.
+    pub fn merge(self, other: Self) -> Result<Self> {
+        self.validate()?;
+        other.validate()?;
+        if self.is_empty() {
+            return Ok(other);
+        } else if other.is_empty() {
+            return Ok(self);
+        }
+
+        ensure!(
+            self.schema == other.schema,
+            InvalidPartialAggregate {
+                msg: "cannot merge partial aggregates with different schemas"
+            }
+        );
+        let merged = self.as_result().merge(other.as_result());
+        Ok(Self::new(merged))
+    }
+
+    /// Converts the results into a record batch with a row for each group,
+    /// ordered by group key.
+    pub fn to_record_batch(&self) -> Result<RecordBatch> {
+        self.validate()?;
+        RecordBatch::try_from(self.as_result())
+            .map_err(|e| Error::InvalidPartialAggregate { msg: e.to_string() })
+    }
+
+    // Checks that each group has a value for each group column and aggregate
+    // in the schema, and that the values have the types the schema describes.
+    fn validate(&self) -> Result<()> {
+        let group_columns = self.schema.group_columns.len();
+        let aggregate_columns = self.schema.aggregate_columns.len();
+        ensure!(
+            self.group_keys.len() == self.aggregates.len()
+                && self.group_keys.iter().all(|key| key.len() == group_columns)
+                && self
+                    .aggregates
+                    .iter()
+                    .all(|aggs| aggs.len() == aggregate_columns),
+            InvalidPartialAggregate {
+                msg: "group keys and aggregates do not match the schema"
+            }
+        );
+
+        for key in &self.group_keys {
+            for (v, (column_type, data_type)) in key.iter().zip(&self.schema.group_columns) {
+                // group columns are tags, which may be NULL, apart from the
+                // window start time of windowed aggregates.
+                let valid = match (data_type, v) {
+                    (LogicalDataType::Integer, OwnedValue::Scalar(Scalar::I64(_))) => true,
+                    (LogicalDataType::Integer, _) => false,
+                    (_, OwnedValue::String(_)) | (_, OwnedValue::Null) => true,
+                    _ => false,
+                };
+                ensure!(
+                    valid,
+                    InvalidPartialAggregate {
+                        msg: format!("invalid group key {:?} for column {}", v, column_type),
+                    }
+                );
+            }
+        }
+
+        for aggs in &self.aggregates {
+            for (agg, (column_type, agg_type, data_type)) in
+                aggs.iter().zip(&self.schema.aggregate_columns)
+            {
+                let valid = match (agg_type, agg) {
+                    (AggregateType::Count, OwnedAggregateResult::Count(_)) => true,
+                    (AggregateType::Sum, OwnedAggregateResult::Sum(v)) => {
+                        scalar_has_type(v, data_type)
+                    }
+                    (AggregateType::Min, OwnedAggregateResult::Min(v))
+                    | (AggregateType::Max, OwnedAggregateResult::Max(v)) => {
+                        value_has_type(v, data_type)
+                    }
+                    (AggregateType::First, OwnedAggregateResult::First(v))
+                    | (AggregateType::Last, OwnedAggregateResult::Last(v)) => v
+                        .as_ref()
+                        .map_or(true, |(_, v)| value_has_type(v, data_type)),
+                    _ => false,
+                };
+                ensure!(
+                    valid,
+                    InvalidPartialAggregate {
+                        msg: format!(
+                            "invalid {} aggregate {:?} for column {} with type {:?}",
+                            agg_type, agg, column_type, data_type
+                        ),
+                    }
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+// Determines if an aggregated scalar, which may be NULL, has the logical data
+// type of its aggregate column.
+fn scalar_has_type(v: &Scalar, data_type: &LogicalDataType) -> bool {
+    matches!(
+        (v, data_type),
+        (Scalar::Null, _)
+            | (Scalar::I64(_), LogicalDataType::Integer)
+            | (Scalar::U64(_), LogicalDataType::Unsigned)
+            | (Scalar::F64(_), LogicalDataType::Float)
+    )
+}
+
+// Determines if an aggregated value, which may be NULL, has the logical data
+// type of its aggregate column.
+fn value_has_type(v: &OwnedValue, data_type: &LogicalDataType) -> bool {
+    match (v, data_type) {
+        (OwnedValue::Null, _) => true,
+        (OwnedValue::Scalar(v), data_type) => scalar_has_type(v, data_type),
+        (OwnedValue::String(_), LogicalDataType::String) => true,
+        (OwnedValue::ByteArray(_), LogicalDataType::Binary) => true,
+        (OwnedValue::Boolean(_), LogicalDataType::Boolean) => true,
+        _ => false,
+    }
+}
+
+/// An iterable set of results for calls to `read_window_aggregate`.
+///
+/// There may be some internal buffering and merging of results before a record
+/// batch is emitted from the iterator.
+pub struct ReadWindowAggregateResults {}
+
+impl Iterator for ReadWindowAggregateResults {
+    type Item = RecordBatch;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        None
+    }
+}
+
+/// An iterable set of results for calls to `tag_values`.
+///
+/// There may be some internal buffering and merging of results before a record
+/// batch is emitted from the iterator.
+pub struct TagValuesResults {}
+
+impl Iterator for TagValuesResults {
+    type Item = RecordBatch;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+
+    use arrow_deps::arrow::{
+        array::{
+            ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, StringArray, UInt64Array,
+        },
+        datatypes::DataType::{Binary, Boolean, Float64, Int64, UInt64, Utf8},
+    };
+
+    use column::Values;
+    use data_types::schema::builder::SchemaBuilder;
+
+    // helper to make the `database_update_chunk` test simpler to read.
+    fn gen_recordbatch() -> RecordBatch {
+        let schema = SchemaBuilder::new()
+            .non_null_tag("region")
+            .non_null_field("counter", Float64)
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["west", "west", "east"])),
+            Arc::new(Float64Array::from(vec![1.2, 3.3, 45.3])),
+            Arc::new(Int64Array::from(vec![11111111, 222222, 3333])),
+        ];
+
+        RecordBatch::try_new(schema, data).unwrap()
+    }
+
+    // helper to make a record batch with the same schema as `gen_recordbatch`
+    // and rows at the provided timestamps.
+    fn gen_recordbatch_at(times: &[i64]) -> RecordBatch {
+        let schema = SchemaBuilder::new()
+            .non_null_tag("region")
+            .non_null_field("counter", Float64)
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["west"; times.len()])),
+            Arc::new(Float64Array::from(vec![1.2; times.len()])),
+            Arc::new(Int64Array::from(times.to_vec())),
+        ];
+
+        RecordBatch::try_new(schema, data).unwrap()
+    }
+
+    // Fails to compile if any part of a `Database` is not `Send + Sync`.
+    fn _assert_send_sync()
+    where
+        Database: Send + Sync,
+    {
+    }
+
+    #[test]
+    fn upsert_partition_empty_batch() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 22, "a_table", gen_recordbatch())
+            .unwrap();
+
+        let empty = gen_recordbatch_at(&[]);
+        assert_eq!(empty.num_rows(), 0);
+        db.upsert_partition("hour_1", 22, "a_table", empty.clone())
+            .unwrap();
+        db.upsert_partition("hour_1", 22, "b_table", empty.clone())
+            .unwrap();
+        db.upsert_partition("hour_2", 22, "a_table", empty.clone())
+            .unwrap();
+
+        assert_eq!(db.rows(), 3);
+        assert_eq!(db.row_groups(), 1);
+        assert_eq!(db.tables(), 1);
+        assert_eq!(db.partition_keys(), vec!["hour_1"]);
+
+        // empty batches are skipped when adding many tables too.
+        db.upsert_partition_many(
+            "hour_1",
+            22,
+            vec![
+                ("a_table".to_owned(), empty.clone()),
+                ("b_table".to_owned(), gen_recordbatch()),
+                ("c_table".to_owned(), empty),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(db.rows(), 6);
+        assert_eq!(db.row_groups(), 2);
+        assert_eq!(db.tables(), 2);
+    }
+
+    #[test]
+    fn database_update_partition() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 22, "a_table", gen_recordbatch())
+            .unwrap();
+
+        assert_eq!(db.rows(), 3);
+        assert_eq!(db.tables(), 1);
+        assert_eq!(db.row_groups(), 1);
+
+        let partition = db.partitions.values().next().unwrap();
+        assert_eq!(partition.tables(), 1);
+        assert_eq!(partition.rows(), 3);
+        assert_eq!(partition.row_groups(), 1);
+
+        // Updating the chunk with another row group for the table just adds
+        // that row group to the existing table.
+        db.upsert_partition("hour_1", 22, "a_table", gen_recordbatch())
+            .unwrap();
+        assert_eq!(db.rows(), 6);
+        assert_eq!(db.tables(), 1); // still one table
+        assert_eq!(db.row_groups(), 2);
+
+        let partition = db.partitions.values().next().unwrap();
+        assert_eq!(partition.tables(), 1); // it's the same table.
+        assert_eq!(partition.rows(), 6);
+        assert_eq!(partition.row_groups(), 2);
+
+        // Adding the same data under another table would increase the table
+        // count.
+        db.upsert_partition("hour_1", 22, "b_table", gen_recordbatch())
+            .unwrap();
+        assert_eq!(db.rows(), 9);
+        assert_eq!(db.tables(), 2);
+        assert_eq!(db.row_groups(), 3);
+
+        let partition = db.partitions.values().next().unwrap();
+        assert_eq!(partition.tables(), 2);
+        assert_eq!(partition.rows(), 9);
+        assert_eq!(partition.row_groups(), 3);
+
+        // Adding the data under another chunk adds a new chunk.
+        db.upsert_partition("hour_1", 29, "a_table", gen_recordbatch())
+            .unwrap();
+        assert_eq!(db.rows(), 12);
+        assert_eq!(db.tables(), 3); // two distinct tables but across two chunks.
+        assert_eq!(db.row_groups(), 4);
+
+        let partition = db.partitions.values().next().unwrap();
+        assert_eq!(partition.tables(), 3);
+        assert_eq!(partition.rows(), 12);
+        assert_eq!(partition.row_groups(), 4);
+
+        let chunk_22 = db
+            .partitions
+            .get("hour_1")
+            .unwrap()
+            .chunks
+            .values()
+            .next()
+            .unwrap();
+        assert_eq!(chunk_22.tables(), 2);
+        assert_eq!(chunk_22.rows(), 9);
+        assert_eq!(chunk_22.row_groups(), 3);
+
+        let chunk_29 = db
+            .partitions
+            .get("hour_1")
+            .unwrap()
+            .chunks
+            .values()
+            .nth(1)
+            .unwrap();
+        assert_eq!(chunk_29.tables(), 1);
+        assert_eq!(chunk_29.rows(), 3);
+        assert_eq!(chunk_29.row_groups(), 1);
+    }
+
+    // Helper function to assert the contents of a column on a record batch.
+    fn assert_rb_column_equals(rb: &RecordBatch, col_name: &str, exp: &Values<'_>) {
+        let got_column = rb.column(rb.schema().index_of(col_name).unwrap());
+
+        match exp {
+            Values::String(exp_data) => {
+                let arr: &StringArray = got_column.as_any().downcast_ref::<StringArray>().unwrap();
+                assert_eq!(&arr.iter().collect::<Vec<_>>(), exp_data);
+            }
+            Values::I64(exp_data) => {
+                let arr: &Int64Array = got_column.as_any().downcast_ref::<Int64Array>().unwrap();
+                assert_eq!(arr.values(), exp_data);
+            }
+            Values::U64(exp_data) => {
+                let arr: &UInt64Array = got_column.as_any().downcast_ref::<UInt64Array>().unwrap();
+                assert_eq!(arr.values(), exp_data);
+            }
+            Values::F64(exp_data) => {
+                let arr: &Float64Array =
+                    got_column.as_any().downcast_ref::<Float64Array>().unwrap();
+                assert_eq!(arr.values(), exp_data);
+            }
+            Values::I64N(exp_data) => {
+                let arr: &Int64Array = got_column.as_any().downcast_ref::<Int64Array>().unwrap();
+                let got_data = (0..got_column.len())
+                    .map(|i| {
+                        if got_column.is_null(i) {
+                            None
+                        } else {
+                            Some(arr.value(i))
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(&got_data, exp_data);
+            }
+            Values::U64N(exp_data) => {
+                let arr: &UInt64Array = got_column.as_any().downcast_ref::<UInt64Array>().unwrap();
+                let got_data = (0..got_column.len())
+                    .map(|i| {
+                        if got_column.is_null(i) {
+                            None
+                        } else {
+                            Some(arr.value(i))
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(&got_data, exp_data);
+            }
+            Values::F64N(exp_data) => {
+                let arr: &Float64Array =
+                    got_column.as_any().downcast_ref::<Float64Array>().unwrap();
+                let got_data = (0..got_column.len())
+                    .map(|i| {
+                        if got_column.is_null(i) {
+                            None
+                        } else {
+                            Some(arr.value(i))
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(&got_data, exp_data);
+            }
+            Values::Bool(exp_data) => {
+                let arr: &BooleanArray =
+                    got_column.as_any().downcast_ref::<BooleanArray>().unwrap();
+                let got_data = (0..got_column.len())
+                    .map(|i| {
+                        if got_column.is_null(i) {
+                            None
+                        } else {
+                            Some(arr.value(i))
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(&got_data, exp_data);
+            }
+            Values::ByteArray(exp_data) => {
+                let arr: &BinaryArray = got_column.as_any().downcast_ref::<BinaryArray>().unwrap();
+                let got_data = (0..got_column.len())
+                    .map(|i| {
+                        if got_column.is_null(i) {
+                            None
+                        } else {
+                            Some(arr.value(i))
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(&got_data, exp_data);
+            }
+        }
+    }
+
+    #[test]
+    fn caches() {
+        let mut db = Database::new()
+            .with_decode_cache(1 << 20)
+            .with_prune_cache(10)
+            .with_table_names_cache(10);
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 22, "Wolverine", gen_recordbatch())
+            .unwrap();
+
+        let read = |db: &Database| {
+            db.read_filter(
+                "hour_1",
+                "Wolverine",
+                &[22],
+                Predicate::default(),
+                table::ColumnSelection::Some(&["region", "time"]),
+            )
+            .unwrap()
+            .collect::<Vec<_>>()
+        };
+        // the caches are enabled on the tables added to the chunk after it
+        // was created too.
+        let table = |db: &Database| {
+            let chunk = &db.partitions["hour_1"].chunks[&22];
+            let table = chunk.table("Wolverine").unwrap();
+            (
+                table.decode_cache_stats().unwrap(),
+                table.prune_cache_stats().unwrap(),
+            )
+        };
+
+        let first = read(&db);
+        let (decode, prune) = table(&db);
+        assert_eq!(decode.hits, 0);
+        assert!(decode.misses > 0);
+        assert_eq!(prune.hits, 0);
+        assert!(prune.misses > 0);
+
+        // an identical read is served from the caches.
+        let second = read(&db);
+        assert_eq!(first.len(), second.len());
+        for (first, second) in first.iter().zip(&second) {
+            for i in 0..first.num_columns() {
+                assert_eq!(first.column(i).data(), second.column(i).data());
+            }
+        }
+        let (decode, prune) = table(&db);
+        assert!(decode.hits > 0);
+        assert!(prune.hits > 0);
+
+        for _ in 0..2 {
+            let data = db
+                .table_names(
+                    "hour_1",
+                    &[22],
+                    None,
+                    Predicate::new(vec![BinaryExpr::from(("region", "=", "west"))]),
+                )
+                .unwrap();
+            assert_rb_column_equals(
+                &data,
+                "table",
+                &Values::String(vec![Some("Coolverine"), Some("Wolverine")]),
+            );
+        }
+        let chunk = &db.partitions["hour_1"].chunks[&22];
+        let stats = chunk.table_names_cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+
+        // caches are disabled by default.
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        let chunk = &db.partitions["hour_1"].chunks[&22];
+        assert!(chunk.table_names_cache_stats().is_none());
+        let table = chunk.table("Coolverine").unwrap();
+        assert!(table.decode_cache_stats().is_none());
+        assert!(table.prune_cache_stats().is_none());
+    }
+
+    #[test]
+    fn table_names() {
+        let mut db = Database::new();
+
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        let data = db
+            .table_names("hour_1", &[22], None, Predicate::default())
+            .unwrap();
+        assert_rb_column_equals(&data, "table", &Values::String(vec![Some("Coolverine")]));
+
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        let data = db
+            .table_names("hour_1", &[22], None, Predicate::default())
+            .unwrap();
+        assert_rb_column_equals(&data, "table", &Values::String(vec![Some("Coolverine")]));
+
+        db.upsert_partition("hour_1", 2, "Coolverine", gen_recordbatch())
+            .unwrap();
+        let data = db
+            .table_names("hour_1", &[22], None, Predicate::default())
+            .unwrap();
+        assert_rb_column_equals(&data, "table", &Values::String(vec![Some("Coolverine")]));
+
+        db.upsert_partition("hour_1", 2, "20 Size", gen_recordbatch())
+            .unwrap();
+        let data = db
+            .table_names("hour_1", &[2, 22], None, Predicate::default())
+            .unwrap();
+        assert_rb_column_equals(
+            &data,
+            "table",
+            &Values::String(vec![Some("20 Size"), Some("Coolverine")]),
+        );
+
+        // only tables with rows satisfying the predicate are included.
+        db.upsert_partition(
+            "hour_1",
+            2,
+            "Aggregates",
+            gen_recordbatch_regions(&["north"]),
+        )
+        .unwrap();
+        let data = db
+            .table_names(
+                "hour_1",
+                &[2, 22],
+                None,
+                Predicate::new(vec![BinaryExpr::from(("region", "=", "north"))]),
+            )
+            .unwrap();
+        assert_rb_column_equals(&data, "table", &Values::String(vec![Some("Aggregates")]));
+    }
+
+    #[test]
+    fn upsert_partition_invalid_time_column() {
+        let mut db = Database::new();
+
+        // no timestamp column
+        let schema = SchemaBuilder::new()
+            .non_null_tag("region")
+            .non_null_field("counter", Float64)
+            .build()
+            .unwrap()
+            .into();
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["west"])),
+            Arc::new(Float64Array::from(vec![1.2])),
+        ];
+        let rb = RecordBatch::try_new(schema, data).unwrap();
+        let result = db.upsert_partition("hour_1", 22, "a_table", rb);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTableData { table_name, msg })
+                if table_name == "a_table" && msg == "missing timestamp column"
+        ));
+
+        // a timestamp column with float values
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert("region".to_owned(), InfluxColumnType::Tag.to_string());
+        metadata.insert("time".to_owned(), InfluxColumnType::Timestamp.to_string());
+        let schema = ArrowSchema::new_with_metadata(
+            vec![
+                Field::new("region", DataType::Utf8, false),
+                Field::new("time", Float64, false),
+            ],
+            metadata,
+        );
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["west"])),
+            Arc::new(Float64Array::from(vec![100.0])),
+        ];
+        let rb = RecordBatch::try_new(Arc::new(schema), data).unwrap();
+        let result = db.upsert_partition("hour_1", 22, "a_table", rb);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTableData { table_name, .. }) if table_name == "a_table"
+        ));
+
+        // nothing was added.
+        assert_eq!(db.rows(), 0);
+        assert_eq!(db.tables(), 0);
+        assert!(db.partition_keys().is_empty());
+    }
+
+    #[test]
+    fn upsert_partition_unsupported_column() {
+        let mut db = Database::new();
+
+        // boolean fields are not supported.
+        let schema = SchemaBuilder::new()
+            .non_null_tag("region")
+            .non_null_field("active", Boolean)
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["west"])),
+            Arc::new(BooleanArray::from(vec![true])),
+            Arc::new(Int64Array::from(vec![100])),
+        ];
+        let rb = RecordBatch::try_new(schema, data).unwrap();
+        let result = db.upsert_partition("hour_1", 22, "a_table", rb);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTableData { table_name, .. }) if table_name == "a_table"
+        ));
+
+        // tags must have string values.
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert("region".to_owned(), InfluxColumnType::Tag.to_string());
+        metadata.insert("time".to_owned(), InfluxColumnType::Timestamp.to_string());
+        let schema = ArrowSchema::new_with_metadata(
+            vec![
+                Field::new("region", Int64, false),
+                Field::new("time", Int64, false),
+            ],
+            metadata,
+        );
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![1])),
+            Arc::new(Int64Array::from(vec![100])),
+        ];
+        let rb = RecordBatch::try_new(Arc::new(schema), data).unwrap();
+        let result = db.upsert_partition("hour_1", 22, "a_table", rb);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTableData { table_name, .. }) if table_name == "a_table"
+        ));
+
+        // nothing was added.
+        assert_eq!(db.rows(), 0);
+        assert_eq!(db.tables(), 0);
+        assert!(db.partition_keys().is_empty());
+    }
+
+    #[test]
+    fn shutdown_summary() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 22, "Hulk", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 23, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_2", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+
+        let (size, rows, tables, row_groups) = (db.size(), db.rows(), db.tables(), db.row_groups());
+        assert_eq!(
+            db.shutdown_summary(),
+            DatabaseStats {
+                size,
+                rows,
+                partitions: 2,
+                chunks: 3,
+                tables,
+                row_groups,
+            }
+        );
+        assert_eq!(rows, 12);
+        assert_eq!(tables, 4);
+
+        assert_eq!(Database::new().shutdown_summary(), DatabaseStats::default());
+    }
+
+    #[test]
+    fn drop_and_recreate_partition() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_2", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        let (size, rows) = (db.size(), db.rows());
+
+        db.drop_partition("hour_1").unwrap();
+        assert_eq!(db.size(), size / 2);
+        assert_eq!(db.rows(), 3);
+
+        // only the new data is counted when the partition is recreated.
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch_at(&[1]))
+            .unwrap();
+        let partition = &db.partitions["hour_1"];
+        assert_eq!(partition.rows(), 1);
+        assert_eq!(db.rows(), 4);
+        assert_eq!(db.size(), size / 2 + partition.size());
+
+        db.drop_partition("hour_1").unwrap();
+        db.drop_partition("hour_2").unwrap();
+        assert_eq!(db.size(), 0);
+        assert_eq!(db.rows(), 0);
+        assert!(matches!(
+            db.drop_partition("hour_1"),
+            Err(Error::PartitionNotFound { .. })
+        ));
+
+        // the same applies to chunks.
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 23, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.drop_chunk("hour_1", 22).unwrap();
+        assert_eq!(db.rows(), 3);
+        assert_eq!(db.partitions["hour_1"].rows(), 3);
+
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch_at(&[1]))
+            .unwrap();
+        assert_eq!(db.rows(), 4);
+        assert_eq!(db.size(), db.partitions["hour_1"].size());
+    }
+
+    #[test]
+    fn clear() {
+        let mut db = Database::new().with_max_row_group_rows(2);
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_2", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+
+        db.clear();
+        assert!(db.partition_keys().is_empty());
+        assert_eq!(db.size(), 0);
+        assert_eq!(db.rows(), 0);
+
+        // the database can still be used, with its configuration intact.
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        assert_eq!(db.partition_keys(), vec!["hour_1"]);
+        assert_eq!(db.rows(), 3);
+        assert_eq!(db.row_groups(), 2);
+        assert_eq!(db.size(), db.partitions["hour_1"].size());
+    }
+
+    #[test]
+    fn compact_partition_evolving_columns() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "cpu", gen_recordbatch())
+            .unwrap();
+
+        // "env" is added and "counter" is missing.
+        let schema = SchemaBuilder::new()
+            .non_null_tag("env")
+            .non_null_tag("region")
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["prod", "dev"])),
+            Arc::new(StringArray::from(vec!["north", "south"])),
+            Arc::new(Int64Array::from(vec![100, 200])),
+        ];
+        let rb = RecordBatch::try_new(schema, data).unwrap();
+        db.upsert_partition("hour_1", 1, "cpu", rb).unwrap();
+
+        let read = |db: &Database| {
+            let batches = db
+                .read_filter(
+                    "hour_1",
+                    "cpu",
+                    &[1],
+                    Predicate::default(),
+                    table::ColumnSelection::All,
+                )
+                .unwrap()
+                .collect::<Vec<_>>();
+            concat_record_batches(batches).unwrap()
+        };
+        let before = read(&db);
+
+        let report = db.compact_partition("hour_1").unwrap();
+        assert_eq!(report.row_groups_before, 2);
+        assert_eq!(report.row_groups_after, 1);
+        assert_eq!(db.rows(), 5);
+
+        // the compacted row group has NULL values for the columns each row
+        // didn't have.
+        let after = read(&db);
+        assert_eq!(before.schema(), after.schema());
+        for i in 0..before.num_columns() {
+            assert_eq!(
+                format!("{:?}", before.column(i)),
+                format!("{:?}", after.column(i))
+            );
+        }
+        let env = after.column(after.schema().index_of("env").unwrap());
+        assert_eq!(env.null_count(), 3);
+        let counter = after.column(after.schema().index_of("counter").unwrap());
+        assert_eq!(counter.null_count(), 2);
+    }
+
+    #[test]
+    fn compact_partition() {
+        let mut db = Database::new();
+        for i in 0..10 {
+            db.upsert_partition("hour_1", 1, "Coolverine", gen_recordbatch_at(&[i]))
+                .unwrap();
+        }
+        db.upsert_partition("hour_1", 2, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 2, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_2", 1, "Coolverine", gen_recordbatch_at(&[1]))
+            .unwrap();
+        db.upsert_partition("hour_2", 1, "Coolverine", gen_recordbatch_at(&[2]))
+            .unwrap();
+
+        let read = |db: &Database| {
+            let batches = db
+                .read_filter(
+                    "hour_1",
+                    "Coolverine",
+                    &[1, 2],
+                    Predicate::default(),
+                    table::ColumnSelection::All,
+                )
+                .unwrap()
+                .collect::<Vec<_>>();
+            concat_record_batches(batches).unwrap()
+        };
+        let before = read(&db);
+        let size = db.size();
+
+        let report = db.compact_partition("hour_1").unwrap();
+        assert_eq!(report.row_groups_before, 12);
+        assert_eq!(report.row_groups_after, 2);
+        assert_eq!(
+            report.size_before - report.bytes_reclaimed(),
+            report.size_after
+        );
+        assert_eq!(db.partitions["hour_1"].size(), report.size_after);
+        assert_eq!(db.size(), size - report.bytes_reclaimed());
+        assert_eq!(db.rows(), 18);
+
+        // other partitions are unaffected.
+        assert_eq!(db.partitions["hour_2"].row_groups(), 2);
+
+        // query results are unchanged.
+        let after = read(&db);
+        assert_eq!(before.schema(), after.schema());
+        for i in 0..before.num_columns() {
+            assert_eq!(before.column(i).data(), after.column(i).data());
+        }
+
+        assert!(matches!(
+            db.compact_partition("hour_3"),
+            Err(Error::PartitionNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn row_group_deduplication() {
+        let mut db = Database::new().with_row_group_deduplication();
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        let size = db.size();
+
+        // the same data is ignored.
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        assert_eq!(db.rows(), 3);
+        assert_eq!(db.row_groups(), 1);
+        assert_eq!(db.size(), size);
+        assert_eq!(db.partitions["hour_1"].rows(), 3);
+
+        // different data, or the same data in another chunk, is added.
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch_at(&[1, 2, 3]))
+            .unwrap();
+        db.upsert_partition("hour_1", 23, "Coolverine", gen_recordbatch())
+            .unwrap();
+        assert_eq!(db.rows(), 9);
+        assert_eq!(db.row_groups(), 3);
+
+        // without deduplication the same data is stored twice.
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        assert_eq!(db.rows(), 6);
+    }
+
+    // helper to make a record batch with the same schema as `gen_recordbatch`
+    // and a row for each of the provided regions.
+    fn gen_recordbatch_regions(regions: &[&str]) -> RecordBatch {
+        let schema = SchemaBuilder::new()
+            .non_null_tag("region")
+            .non_null_field("counter", Float64)
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(regions.to_vec())),
+            Arc::new(Float64Array::from(vec![1.2; regions.len()])),
+            Arc::new(Int64Array::from(
+                (0..regions.len() as i64).collect::<Vec<_>>(),
+            )),
+        ];
+
+        RecordBatch::try_new(schema, data).unwrap()
+    }
+
+    #[test]
+    fn tag_cardinality_limit_reject() {
+        let mut db = Database::new().with_tag_cardinality_limit(2, TagCardinalityAction::Reject);
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+
+        // existing values are counted towards the limit.
+        let result = db.upsert_partition(
+            "hour_1",
+            22,
+            "Coolverine",
+            gen_recordbatch_regions(&["north"]),
+        );
+        assert!(matches!(
+            result,
+            Err(Error::TagCardinalityExceeded { column_name, limit: 2, .. }) if column_name == "region"
+        ));
+        assert_eq!(db.rows(), 3);
+
+        // existing values don't count twice.
+        db.upsert_partition(
+            "hour_1",
+            22,
+            "Coolverine",
+            gen_recordbatch_regions(&["east", "west"]),
+        )
+        .unwrap();
+        assert_eq!(db.rows(), 5);
+
+        // the limit applies to each table within a chunk.
+        db.upsert_partition(
+            "hour_1",
+            23,
+            "Coolverine",
+            gen_recordbatch_regions(&["north", "south"]),
+        )
+        .unwrap();
+        let result = db.upsert_partition(
+            "hour_1",
+            24,
+            "Coolverine",
+            gen_recordbatch_regions(&["north", "south", "east"]),
+        );
+        assert!(matches!(result, Err(Error::TagCardinalityExceeded { .. })));
+        assert_eq!(db.rows(), 7);
+    }
+
+    #[test]
+    fn tag_cardinality_limit_convert_to_field() {
+        let mut db =
+            Database::new().with_tag_cardinality_limit(2, TagCardinalityAction::ConvertToField);
+        db.upsert_partition(
+            "hour_1",
+            22,
+            "Coolverine",
+            gen_recordbatch_regions(&["north", "south", "east"]),
+        )
+        .unwrap();
+
+        let table = db.partitions["hour_1"].chunks[&22]
+            .table("Coolverine")
+            .unwrap();
+        let columns = table.schema_for_all_columns();
+        assert!(columns.contains(&(
+            ColumnType::Field("region".to_owned()),
+            LogicalDataType::String
+        )));
+        assert_eq!(db.rows(), 3);
+
+        // later data for the column is also stored as a field, even when under
+        // the limit.
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        assert_eq!(db.rows(), 6);
+
+        // a column that is already a tag can't be converted.
+        db.upsert_partition("hour_1", 23, "Coolverine", gen_recordbatch())
+            .unwrap();
+        let result = db.upsert_partition(
+            "hour_1",
+            23,
+            "Coolverine",
+            gen_recordbatch_regions(&["north"]),
+        );
+        assert!(matches!(result, Err(Error::TagCardinalityExceeded { .. })));
+        assert_eq!(db.rows(), 9);
+    }
+
+    #[test]
+    fn valid_column_types() {
+        for column_type in &VALID_COLUMN_TYPES {
+            assert!(is_valid_column_type(column_type));
+        }
+        assert!(!is_valid_column_type("iox::column_type::field::bytes"));
+        assert!(!is_valid_column_type("tag"));
+
+        // the column types agree with those accepted when upserting.
+        let fields: Vec<(DataType, ArrayRef)> = vec![
+            (Float64, Arc::new(Float64Array::from(vec![1.2]))),
+            (Int64, Arc::new(Int64Array::from(vec![1]))),
+            (UInt64, Arc::new(UInt64Array::from(vec![1]))),
+            (Utf8, Arc::new(StringArray::from(vec!["a"]))),
+            (Boolean, Arc::new(BooleanArray::from(vec![true]))),
+        ];
+        let mut db = Database::new();
+        for (data_type, values) in fields {
+            let schema: SchemaRef = SchemaBuilder::new()
+                .non_null_tag("region")
+                .non_null_field("value", data_type)
+                .timestamp()
+                .build()
+                .unwrap()
+                .into();
+            let data: Vec<ArrayRef> = vec![
+                Arc::new(StringArray::from(vec!["west"])),
+                values,
+                Arc::new(Int64Array::from(vec![100])),
+            ];
+            let rb = RecordBatch::try_new(Arc::clone(&schema), data).unwrap();
+
+            let valid = schema
+                .metadata()
+                .values()
+                .all(|column_type| is_valid_column_type(column_type));
+            let result = db.upsert_partition_many("hour_1", 1, vec![("cpu".to_owned(), rb)]);
+            assert_eq!(result.is_ok(), valid, "{:?}", schema.metadata());
+            db.drop_partition("hour_1").ok();
+        }
+    }
+
+    #[test]
+    fn validate_record_batch_metadata() {
+        assert!(validate_iox_record_batch(&gen_recordbatch()).is_ok());
+
+        // Builds a single row record batch with the provided columns and
+        // schema metadata.
+        let batch = |columns: &[(&str, DataType)], metadata: &[(&str, &str)]| {
+            let fields = columns
+                .iter()
+                .map(|(name, data_type)| Field::new(name, data_type.clone(), false))
+                .collect();
+            let metadata = metadata
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            let data = columns
+                .iter()
+                .map(|(_, data_type)| -> ArrayRef {
+                    match data_type {
+                        Utf8 => Arc::new(StringArray::from(vec!["a"])),
+                        Float64 => Arc::new(Float64Array::from(vec![1.2])),
+                        Binary => Arc::new(BinaryArray::from(vec![&b"a"[..]])),
+                        _ => Arc::new(Int64Array::from(vec![1])),
+                    }
+                })
+                .collect();
+            let schema = ArrowSchema::new_with_metadata(fields, metadata);
+            RecordBatch::try_new(Arc::new(schema), data).unwrap()
+        };
+        let columns = [("region", Utf8), ("counter", Float64), ("time", Int64)];
+        let metadata = [
+            ("region", "iox::column_type::tag"),
+            ("counter", "iox::column_type::field::float"),
+            ("time", "iox::column_type::timestamp"),
+        ];
+        let assert_invalid = |rb: RecordBatch, exp: &str| match validate_iox_record_batch(&rb) {
+            Err(e @ Error::InvalidRecordBatch { .. }) => {
+                assert!(e.to_string().contains(exp), "{}", e)
+            }
+            _ => panic!("expected {:?} to be invalid", rb.schema()),
+        };
+
+        // the measurement name may also be present.
+        let mut with_measurement = metadata.to_vec();
+        with_measurement.push(("iox::measurement::name", "cpu"));
+        assert!(validate_iox_record_batch(&batch(&columns[..], &with_measurement[..])).is_ok());
+
+        // binary columns have no column type, and can be upserted as they are.
+        let mut with_binary = columns.to_vec();
+        with_binary.push(("payload", Binary));
+        let rb = batch(&with_binary[..], &metadata[..]);
+        assert!(validate_iox_record_batch(&rb).is_ok());
+        let mut db = Database::new();
+        assert!(db.upsert_partition("hour_1", 1, "cpu", rb).is_ok());
+
+        // missing metadata entry.
+        assert_invalid(
+            batch(&columns[..], &metadata[..2]),
+            "missing column type for column \"time\"",
+        );
+
+        // metadata entry for an unknown column.
+        let mut extra = metadata.to_vec();
+        extra.push(("host", "iox::column_type::tag"));
+        assert_invalid(
+            batch(&columns[..], &extra[..]),
+            "column type for unknown column \"host\"",
+        );
+
+        // invalid column type.
+        let mut invalid = metadata.to_vec();
+        invalid[1] = ("counter", "iox::column_type::field::bytes");
+        assert_invalid(
+            batch(&columns[..], &invalid[..]),
+            "invalid column type \"iox::column_type::field::bytes\"",
+        );
+
+        // duplicate column names.
+        let duplicate = [("region", Utf8), ("region", Utf8), ("time", Int64)];
+        assert_invalid(
+            batch(&duplicate[..], &metadata[..]),
+            "duplicate column \"region\"",
+        );
+
+        // no timestamp column, or more than one.
+        assert_invalid(
+            batch(&columns[..2], &metadata[..2]),
+            "expected one timestamp column, found 0",
+        );
+        let mut two_times = metadata.to_vec();
+        two_times[1] = ("counter", "iox::column_type::timestamp");
+        assert_invalid(
+            batch(&columns[..], &two_times[..]),
+            "expected one timestamp column, found 2",
+        );
+
+        // timestamp column with the wrong type.
+        let float_time = [("region", Utf8), ("counter", Float64), ("time", Float64)];
+        assert_invalid(
+            batch(&float_time[..], &metadata[..]),
+            "must be called \"time\" with type Int64",
+        );
+    }
+
+    #[test]
+    fn upsert_partition_many() {
+        let mut db = Database::new();
+        db.upsert_partition_many(
+            "hour_1",
+            22,
+            vec![
+                ("Coolverine".to_owned(), gen_recordbatch()),
+                ("20 Size".to_owned(), gen_recordbatch()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(db.rows(), 6);
+        assert_eq!(db.tables(), 2);
+
+        // boolean fields are not supported, so none of the tables should be
+        // updated.
+        let schema = SchemaBuilder::new()
+            .non_null_tag("region")
+            .non_null_field("active", Boolean)
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["west"])),
+            Arc::new(BooleanArray::from(vec![true])),
+            Arc::new(Int64Array::from(vec![100])),
+        ];
+        let invalid = RecordBatch::try_new(schema, data).unwrap();
+
+        let result = db.upsert_partition_many(
+            "hour_1",
+            22,
+            vec![
+                ("Coolverine".to_owned(), gen_recordbatch()),
+                ("Wolverine".to_owned(), gen_recordbatch()),
+                ("Hulk".to_owned(), invalid),
+            ],
+        );
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTableData { table_name, .. }) if table_name == "Hulk"
+        ));
+
+        assert_eq!(db.rows(), 6);
+        assert_eq!(db.tables(), 2);
+        assert_eq!(db.row_groups(), 2);
+        let data = db
+            .table_names("hour_1", &[22], None, Predicate::default())
+            .unwrap();
+        assert_rb_column_equals(
+            &data,
+            "table",
+            &Values::String(vec![Some("20 Size"), Some("Coolverine")]),
+        );
+
+        // the data for the same table must not have conflicting column types,
+        // whether or not the table exists.
+        let with_load = |load: ArrayRef| {
+            let schema = SchemaBuilder::new()
+                .non_null_tag("region")
+                .non_null_field("load", load.data_type().clone())
+                .timestamp()
+                .build()
+                .unwrap()
+                .into();
+            let data: Vec<ArrayRef> = vec![
+                Arc::new(StringArray::from(vec!["west"])),
+                load,
+                Arc::new(Int64Array::from(vec![100])),
+            ];
+            RecordBatch::try_new(schema, data).unwrap()
+        };
+
+        for table_name in &["Coolverine", "Wolverine"] {
+            let result = db.upsert_partition_many(
+                "hour_1",
+                22,
+                vec![
+                    ("Hulk".to_owned(), gen_recordbatch()),
+                    (
+                        table_name.to_string(),
+                        with_load(Arc::new(Float64Array::from(vec![1.5]))),
+                    ),
+                    (
+                        table_name.to_string(),
+                        with_load(Arc::new(Int64Array::from(vec![1]))),
+                    ),
+                ],
+            );
+            assert!(matches!(
+                result,
+                Err(Error::InvalidTableData { table_name: name, .. }) if name == *table_name
+            ));
+
+            assert_eq!(db.rows(), 6);
+            assert_eq!(db.tables(), 2);
+            assert_eq!(db.row_groups(), 2);
+        }
+    }
+
+    #[test]
+    fn upsert_partition_evolving_columns() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 22, "cpu", gen_recordbatch())
+            .unwrap();
+
+        // a superset of the table's columns.
+        let schema = SchemaBuilder::new()
+            .non_null_tag("env")
+            .non_null_tag("region")
+            .non_null_field("counter", Float64)
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["prod"])),
+            Arc::new(StringArray::from(vec!["north"])),
+            Arc::new(Float64Array::from(vec![10.0])),
+            Arc::new(Int64Array::from(vec![100])),
+        ];
+        let superset = RecordBatch::try_new(schema, data).unwrap();
+        db.upsert_partition("hour_1", 22, "cpu", superset).unwrap();
+
+        // a subset of the table's columns.
+        let schema = SchemaBuilder::new()
+            .non_null_tag("region")
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["south"])),
+            Arc::new(Int64Array::from(vec![200])),
+        ];
+        let subset = RecordBatch::try_new(schema, data).unwrap();
+        db.upsert_partition("hour_1", 22, "cpu", subset).unwrap();
+        assert_eq!(db.row_groups(), 3);
+
+        // each row group's results have all of the table's columns, with
+        // NULL values for those the row group doesn't have.
+        let null_counts = db
+            .read_filter(
+                "hour_1",
+                "cpu",
+                &[22],
+                Predicate::default(),
+                ColumnSelection::All,
+            )
+            .unwrap()
+            .map(|rb| {
+                assert_eq!(rb.num_columns(), 4);
+                ["env", "counter"]
+                    .iter()
+                    .map(|&name| rb.column(rb.schema().index_of(name).unwrap()).null_count())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(null_counts, vec![vec![3, 0], vec![0, 0], vec![1, 1]]);
+    }
+
+    #[test]
+    fn upsert_partition_conflicting_column_type() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 22, "cpu", gen_recordbatch())
+            .unwrap();
+
+        // "counter" is a tag rather than a field.
+        let schema = SchemaBuilder::new()
+            .non_null_tag("region")
+            .non_null_tag("counter")
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["west"])),
+            Arc::new(StringArray::from(vec!["high"])),
+            Arc::new(Int64Array::from(vec![100])),
+        ];
+        let drifted = RecordBatch::try_new(schema, data).unwrap();
+
+        let result = db.upsert_partition("hour_1", 22, "cpu", drifted.clone());
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTableData { table_name, .. }) if table_name == "cpu"
+        ));
+
+        // none of the tables are updated when one conflicts.
+        let result = db.upsert_partition_many(
+            "hour_1",
+            22,
+            vec![
+                ("mem".to_owned(), gen_recordbatch()),
+                ("cpu".to_owned(), drifted.clone()),
+            ],
+        );
+        assert!(matches!(result, Err(Error::InvalidTableData { .. })));
+        assert_eq!(db.rows(), 3);
+        assert_eq!(db.tables(), 1);
+        assert_eq!(db.row_groups(), 1);
+
+        // the same data is fine in a different chunk.
+        db.upsert_partition("hour_1", 23, "cpu", drifted).unwrap();
+        assert_eq!(db.rows(), 4);
+    }
+
+    #[test]
+    fn integer_null_sentinel() {
+        let mut db = Database::new().with_integer_null_sentinel("load", -1);
+
+        let schema = SchemaBuilder::new()
+            .non_null_tag("region")
+            .non_null_field("load", Int64)
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec![
+                "east", "east", "west", "west", "west",
+            ])),
+            Arc::new(Int64Array::from(vec![10, -1, -1, 30, 40])),
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4, 5])),
+        ];
+        let rb = RecordBatch::try_new(schema, data).unwrap();
+        db.upsert_partition("hour_1", 1, "table1", rb).unwrap();
+
+        let itr = db
+            .read_aggregate(
+                "hour_1",
+                "table1",
+                &[1],
+                Predicate::default(),
+                table::ColumnSelection::Some(&["region"]),
+                vec![("load", AggregateType::Count)],
+            )
+            .unwrap();
+        let result = itr.collect::<Vec<RecordBatch>>();
+        assert_eq!(result.len(), 1);
+
+        // rows containing the sentinel are not counted.
+        assert_rb_column_equals(
+            &result[0],
+            "region",
+            &Values::String(vec![Some("east"), Some("west")]),
+        );
+        assert_rb_column_equals(&result[0], "load_count", &Values::U64(vec![1, 2]));
+    }
+
+    #[test]
+    fn compact_partition_integer_null_sentinel() {
+        let mut db = Database::new();
+        let gen_load = |load: i64, time: i64| {
+            let schema = SchemaBuilder::new()
+                .non_null_tag("region")
+                .non_null_field("load", Int64)
+                .timestamp()
+                .build()
+                .unwrap()
+                .into();
+            let data: Vec<ArrayRef> = vec![
+                Arc::new(StringArray::from(vec!["west"])),
+                Arc::new(Int64Array::from(vec![load])),
+                Arc::new(Int64Array::from(vec![time])),
+            ];
+            RecordBatch::try_new(schema, data).unwrap()
+        };
+        db.upsert_partition("hour_1", 1, "table1", gen_load(10, 1))
+            .unwrap();
+        db.upsert_partition("hour_1", 1, "table1", gen_load(-1, 2))
+            .unwrap();
+
+        // the sentinel only applies to data added from now on, so compaction
+        // must not turn the stored value into NULL.
+        let mut db = db.with_integer_null_sentinel("load", -1);
+        db.compact_partition("hour_1").unwrap();
+        assert_eq!(db.row_groups(), 1);
+
+        let batches = db
+            .read_filter(
+                "hour_1",
+                "table1",
+                &[1],
+                Predicate::default(),
+                table::ColumnSelection::Some(&["load"]),
+            )
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_rb_column_equals(
+            &concat_record_batches(batches).unwrap(),
+            "load",
+            &Values::I64N(vec![Some(10), Some(-1)]),
+        );
+    }
+
+    #[test]
+    fn read_aggregate_time_column() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "table1", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 2, "table1", gen_recordbatch_at(&[100, 200]))
+            .unwrap();
+
+        let itr = db
+            .read_aggregate(
+                "hour_1",
+                "table1",
+                &[1, 2],
+                Predicate::default(),
+                table::ColumnSelection::Some(&["region"]),
+                vec![
+                    ("time", AggregateType::Min),
+                    ("time", AggregateType::Max),
+                    ("time", AggregateType::Count),
+                ],
+            )
+            .unwrap();
+        let result = itr.collect::<Vec<RecordBatch>>();
+        assert_eq!(result.len(), 2);
+
+        // the first and last observation times of each series in each chunk.
+        assert_rb_column_equals(
+            &result[0],
+            "region",
+            &Values::String(vec![Some("east"), Some("west")]),
+        );
+        assert_rb_column_equals(&result[0], "time_min", &Values::I64(vec![3333, 222222]));
+        assert_rb_column_equals(&result[0], "time_max", &Values::I64(vec![3333, 11111111]));
+        assert_rb_column_equals(&result[0], "time_count", &Values::U64(vec![1, 2]));
+
+        assert_rb_column_equals(&result[1], "region", &Values::String(vec![Some("west")]));
+        assert_rb_column_equals(&result[1], "time_min", &Values::I64(vec![100]));
+        assert_rb_column_equals(&result[1], "time_max", &Values::I64(vec![200]));
+        assert_rb_column_equals(&result[1], "time_count", &Values::U64(vec![2]));
+    }
+
+    #[test]
+    fn binary_field_round_trip() {
+        let mut db = Database::new();
+
+        let schema = SchemaBuilder::new()
+            .non_null_tag("region")
+            .field("payload", Binary)
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+        let payloads: Vec<Option<&[u8]>> =
+            vec![Some(&b"\x08\x01"[..]), None, Some(&b"\x00\xff"[..])];
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["east", "west", "west"])),
+            Arc::new(BinaryArray::from(payloads.clone())),
+            Arc::new(Int64Array::from(vec![1, 2, 3])),
+        ];
+        let rb = RecordBatch::try_new(schema, data).unwrap();
+        db.upsert_partition("hour_1", 1, "table1", rb).unwrap();
+
+        let itr = db
+            .read_filter(
+                "hour_1",
+                "table1",
+                &[1],
+                Predicate::default(),
+                table::ColumnSelection::Some(&["payload", "time"]),
+            )
+            .unwrap();
+        let result = itr.collect::<Vec<RecordBatch>>();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].schema().field(0).data_type(), &Binary);
+
+        assert_rb_column_equals(&result[0], "payload", &Values::ByteArray(payloads));
+        assert_rb_column_equals(&result[0], "time", &Values::I64(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn read_filter_cancellation() {
+        let mut db = Database::new();
+        for chunk_id in 1..=3 {
+            db.upsert_partition("hour_1", chunk_id, "a_table", gen_recordbatch())
+                .unwrap();
+        }
+
+        let token = Arc::new(AtomicBool::new(false));
+        let mut itr = db
+            .read_filter(
+                "hour_1",
+                "a_table",
+                &[1, 2, 3],
+                Predicate::default(),
+                ColumnSelection::All,
+            )
+            .unwrap()
+            .with_cancellation(Arc::clone(&token));
+
+        assert!(itr.next().is_some());
+        assert!(itr.check_cancelled().is_ok());
+
+        // no further row groups are read once the token is set.
+        token.store(true, Ordering::Relaxed);
+        assert!(itr.next().is_none());
+        assert!(matches!(itr.check_cancelled(), Err(Error::Cancelled)));
+    }
+
+    #[test]
+    fn read_aggregate_cancellation() {
+        let mut db = Database::new();
+        for chunk_id in 1..=3 {
+            db.upsert_partition("hour_1", chunk_id, "a_table", gen_recordbatch())
+                .unwrap();
+        }
+
+        let token = Arc::new(AtomicBool::new(false));
+        let mut itr = db
+            .read_aggregate(
+                "hour_1",
+                "a_table",
+                &[1, 2, 3],
+                Predicate::default(),
+                ColumnSelection::Some(&["region"]),
+                vec![("counter", AggregateType::Sum)],
+            )
+            .unwrap()
+            .with_cancellation(Arc::clone(&token));
+
+        assert!(itr.next().is_some());
+
+        token.store(true, Ordering::Relaxed);
+        assert!(itr.next().is_none());
+        assert!(matches!(itr.check_cancelled(), Err(Error::Cancelled)));
+    }
+
+    #[test]
+    fn read_aggregate_having() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "a_table", gen_recordbatch())
+            .unwrap();
+
+        let aggregate = |having: Option<Predicate>| -> Result<Vec<String>> {
+            let mut itr = db
+                .read_aggregate(
+                    "hour_1",
+                    "a_table",
+                    &[1],
+                    Predicate::default(),
+                    ColumnSelection::Some(&["region"]),
+                    vec![("counter", AggregateType::Sum)],
+                )
+                .unwrap();
+            if let Some(having) = having {
+                itr = itr.with_having(having)?;
+            }
+
+            Ok(itr
+                .flat_map(|rb| {
+                    let regions = rb.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+                    (0..rb.num_rows())
+                        .map(|i| regions.value(i).to_owned())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>())
+        };
+
+        assert_eq!(aggregate(None).unwrap(), vec!["east", "west"]);
+
+        // the "west" group's sum is 4.5.
+        let having = Predicate::new(vec![BinaryExpr::from(("counter_sum", ">", 10.0))]);
+        assert_eq!(aggregate(Some(having)).unwrap(), vec!["east"]);
+
+        // no groups satisfy the predicate.
+        let having = Predicate::new(vec![BinaryExpr::from(("counter_sum", ">=", 100.0))]);
+        assert!(aggregate(Some(having)).unwrap().is_empty());
+
+        // the predicate must reference an aggregate column.
+        let having = Predicate::new(vec![BinaryExpr::from(("counter", ">", 10.0))]);
+        assert!(matches!(
+            aggregate(Some(having)),
+            Err(Error::ColumnNotFound {
+                role: ColumnRole::Having,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn read_aggregate_having_multiple_chunks() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "a_table", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 2, "a_table", gen_recordbatch())
+            .unwrap();
+
+        // the "west" group's sum is 4.5 in each chunk, but 9.0 once the
+        // chunks' aggregates are merged.
+        let having = Predicate::new(vec![BinaryExpr::from(("counter_sum", ">", 5.0))]);
+        let results = db
+            .read_aggregate(
+                "hour_1",
+                "a_table",
+                &[1, 2],
+                Predicate::default(),
+                ColumnSelection::Some(&["region"]),
+                vec![("counter", AggregateType::Sum)],
+            )
+            .unwrap()
+            .with_having(having)
+            .unwrap()
+            .collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 1);
+        assert_rb_column_equals(
+            &results[0],
+            "region",
+            &Values::String(vec![Some("east"), Some("west")]),
+        );
+        assert_rb_column_equals(&results[0], "counter_sum", &Values::F64(vec![90.6, 9.0]));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn read_aggregate_merge_serialized_partials() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "a_table", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition(
+            "hour_1",
+            2,
+            "a_table",
+            gen_recordbatch_regions(&["east", "north", "west"]),
+        )
+        .unwrap();
+
+        let partial = |chunk_ids: &[u32]| {
+            db.read_aggregate(
+                "hour_1",
+                "a_table",
+                chunk_ids,
+                Predicate::default(),
+                ColumnSelection::Some(&["region"]),
+                vec![
+                    ("counter", AggregateType::Sum),
+                    ("counter", AggregateType::Count),
+                    ("counter", AggregateType::Min),
+                    ("counter", AggregateType::Max),
+                ],
+            )
+            .unwrap()
+            .into_partial()
+            .unwrap()
+        };
+
+        // each "worker" computes the aggregates over one of the chunks, and
+        // sends its partial results to the coordinator.
+        let serialized = vec![
+            serde_json::to_string(&partial(&[1])).unwrap(),
+            serde_json::to_string(&partial(&[2])).unwrap(),
+        ];
+
+        let merged = serialized
+            .iter()
+            .map(|json| serde_json::from_str::<PartialAggregateResult>(json).unwrap())
+            .fold(PartialAggregateResult::default(), |acc, partial| {
+                acc.merge(partial).unwrap()
+            });
+        assert_eq!(merged, partial(&[1, 2]));
+
+        let rb = merged.to_record_batch().unwrap();
+        assert_rb_column_equals(
+            &rb,
+            "region",
+            &Values::String(vec![Some("east"), Some("north"), Some("west")]),
+        );
+        assert_rb_column_equals(&rb, "counter_count", &Values::U64(vec![2, 1, 3]));
+        assert_rb_column_equals(&rb, "counter_min", &Values::F64(vec![1.2, 1.2, 1.2]));
+        assert_rb_column_equals(&rb, "counter_max", &Values::F64(vec![45.3, 1.2, 3.3]));
+
+        // partial results of different queries can't be merged.
+        let other = db
+            .read_aggregate(
+                "hour_1",
+                "a_table",
+                &[2],
+                Predicate::default(),
+                ColumnSelection::Some(&["region"]),
+                vec![("counter", AggregateType::Sum)],
+            )
+            .unwrap()
+            .into_partial()
+            .unwrap();
+        assert!(matches!(
+            merged.merge(other),
+            Err(Error::InvalidPartialAggregate { .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn read_aggregate_reject_invalid_serialized_partials() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "a_table", gen_recordbatch())
+            .unwrap();
+
+        let partial = db
+            .read_aggregate(
+                "hour_1",
+                "a_table",
+                &[1],
+                Predicate::default(),
+                ColumnSelection::Some(&["region"]),
+                vec![
+                    ("counter", AggregateType::Sum),
+                    ("counter", AggregateType::Min),
+                ],
+            )
+            .unwrap()
+            .into_partial()
+            .unwrap();
+        let json = serde_json::to_value(&partial).unwrap();
+
+        // Deserializes the partial results with the first group's aggregate
+        // for the column at index `i` replaced.
+        let with_aggregate = |i: usize, agg: OwnedAggregateResult| {
+            let mut json = json.clone();
+            json["aggregates"][0][i] = serde_json::to_value(&agg).unwrap();
+            serde_json::from_value::<PartialAggregateResult>(json).unwrap()
+        };
+
+        let valid = with_aggregate(0, OwnedAggregateResult::Sum(Scalar::F64(1.0)));
+        assert!(valid.to_record_batch().is_ok());
+
+        let invalid = vec![
+            // the aggregate is a different type of aggregate.
+            with_aggregate(0, OwnedAggregateResult::Count(2)),
+            with_aggregate(
+                1,
+                OwnedAggregateResult::Max(OwnedValue::Scalar(Scalar::F64(1.0))),
+            ),
+            // the aggregate's value doesn't have the column's type.
+            with_aggregate(0, OwnedAggregateResult::Sum(Scalar::I64(1))),
+            with_aggregate(
+                1,
+                OwnedAggregateResult::Min(OwnedValue::String("a".to_owned())),
+            ),
+        ];
+        for partial in invalid {
+            assert!(matches!(
+                partial.to_record_batch(),
+                Err(Error::InvalidPartialAggregate { .. })
+            ));
+            assert!(matches!(
+                partial.merge(PartialAggregateResult::default()),
+                Err(Error::InvalidPartialAggregate { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn read_aggregate_into_grouped_batches() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "a_table", gen_recordbatch())
+            .unwrap();
+
+        let schema = SchemaBuilder::new()
+            .non_null_tag("region")
+            .non_null_field("counter", Float64)
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["north", "east", "west"])),
+            Arc::new(Float64Array::from(vec![10.0, 20.0, 30.0])),
+            Arc::new(Int64Array::from(vec![100, 200, 300])),
+        ];
+        let rb = RecordBatch::try_new(schema, data).unwrap();
+        db.upsert_partition("hour_1", 2, "a_table", rb).unwrap();
+
+        let batches = db
+            .read_aggregate(
+                "hour_1",
+                "a_table",
+                &[1, 2],
+                Predicate::default(),
+                ColumnSelection::Some(&["region"]),
+                vec![("counter", AggregateType::Sum)],
+            )
+            .unwrap()
+            .into_grouped_batches()
+            .unwrap();
+
+        // each group's rows are in a single batch, in chunk order.
+        let groups = batches
+            .iter()
+            .map(|rb| {
+                let regions = rb.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+                let sums = rb
+                    .column(1)
+                    .as_any()
+                    .downcast_ref::<Float64Array>()
+                    .unwrap();
+                (0..rb.num_rows())
+                    .map(|i| (regions.value(i), sums.value(i)))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            vec![
+                vec![("east", 45.3), ("east", 20.0)],
+                vec![("north", 10.0)],
+                vec![("west", 4.5), ("west", 30.0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn query_metrics() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "a_table", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 2, "a_table", gen_recordbatch())
+            .unwrap();
+        // only contains the "west" region so can be pruned.
+        db.upsert_partition("hour_1", 3, "a_table", gen_recordbatch_at(&[100, 200]))
+            .unwrap();
+
+        let predicate = Predicate::new(vec![BinaryExpr::from(("region", "=", "east"))]);
+        let mut itr = db
+            .read_filter(
+                "hour_1",
+                "a_table",
+                &[1, 2, 3],
+                predicate.clone(),
+                ColumnSelection::All,
+            )
+            .unwrap();
+        let rows = itr.by_ref().map(|rb| rb.num_rows() as u64).sum::<u64>();
+
+        let metrics = itr.metrics();
+        assert_eq!(rows, 2);
+        assert_eq!(metrics.rows_returned, rows);
+        assert_eq!(metrics.rows_scanned, 6);
+        assert_eq!(metrics.row_groups_pruned, 1);
+        assert!(metrics.rows_scanned > metrics.rows_returned);
+
+        let mut itr = db
+            .read_aggregate(
+                "hour_1",
+                "a_table",
+                &[1, 2, 3],
+                predicate,
+                ColumnSelection::Some(&["region"]),
+                vec![("counter", AggregateType::Sum)],
+            )
+            .unwrap();
+        assert_eq!(itr.by_ref().count(), 2);
+        assert_eq!(
+            itr.metrics(),
+            QueryMetrics {
+                rows_scanned: 6,
+                row_groups_pruned: 1,
+                rows_returned: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn partition_size_and_time_range() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "Coolverine", gen_recordbatch_at(&[100, 200]))
+            .unwrap();
+        db.upsert_partition(
+            "hour_1",
+            2,
+            "Coolverine",
+            gen_recordbatch_at(&[1000, 1500, 1200]),
+        )
+        .unwrap();
+
+        let partition = db.partitions.get("hour_1").unwrap();
+        assert_eq!(partition.rows(), 5);
+        assert_eq!(partition.time_range(), Some((100, 1500)));
+
+        let chunk_sizes = partition
+            .chunks
+            .values()
+            .map(|chunk| chunk.size())
+            .collect::<Vec<_>>();
+        assert_eq!(chunk_sizes.len(), 2);
+        assert!(chunk_sizes.iter().all(|&size| size > 0));
+        assert_eq!(partition.size(), chunk_sizes.iter().sum::<u64>());
+        assert_eq!(partition.size(), db.size());
+    }
+
+    #[test]
+    fn upsert_partition_max_row_group_rows() {
+        let mut db = Database::new().with_max_row_group_rows(2000);
+        let times = (0..10_000).collect::<Vec<i64>>();
+        db.upsert_partition("hour_1", 1, "Coolverine", gen_recordbatch_at(&times))
+            .unwrap();
+
+        assert_eq!(db.row_groups(), 5);
+        assert_eq!(db.rows(), 10_000);
+        let partition = db.partitions.get("hour_1").unwrap();
+        assert_eq!(partition.time_range(), Some((0, 9_999)));
+
+        // each row group contains a contiguous range of the input.
+        let itr = db
+            .read_filter(
+                "hour_1",
+                "Coolverine",
+                &[1],
+                Predicate::default(),
+                table::ColumnSelection::Some(&["time"]),
+            )
+            .unwrap();
+        let batches = itr.collect::<Vec<_>>();
+        assert_eq!(batches.len(), 5);
+        for (i, batch) in batches.iter().enumerate() {
+            let exp = (i as i64 * 2000..(i as i64 + 1) * 2000).collect::<Vec<_>>();
+            assert_rb_column_equals(batch, "time", &Values::I64(exp));
+        }
+
+        // row groups outside of the time range are pruned using their meta
+        // data.
+        let itr = db
+            .read_filter(
+                "hour_1",
+                "Coolverine",
+                &[1],
+                Predicate::with_time_range(&[], 2000, 4000),
+                table::ColumnSelection::Some(&["time"]),
+            )
+            .unwrap();
+        let batches = itr.collect::<Vec<_>>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 2000);
+
+        // batches no larger than the limit are not split.
+        db.upsert_partition("hour_1", 1, "Coolverine", gen_recordbatch_at(&[1, 2, 3]))
+            .unwrap();
+        assert_eq!(db.row_groups(), 6);
+    }
+
+    #[test]
+    fn matching_chunks() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "Coolverine", gen_recordbatch_at(&[100, 200]))
+            .unwrap();
+        db.upsert_partition("hour_1", 2, "Coolverine", gen_recordbatch_at(&[1000, 1500]))
+            .unwrap();
+        db.upsert_partition("hour_1", 2, "20 Size", gen_recordbatch_at(&[300]))
+            .unwrap();
+        db.upsert_partition("hour_2", 3, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_2", 4, "20 Size", gen_recordbatch())
+            .unwrap();
+
+        let chunks =
+            |time_range, predicate| db.matching_chunks("Coolverine", time_range, &predicate);
+
+        // chunk 4 doesn't contain the table.
+        assert_eq!(
+            chunks(None, Predicate::default()),
+            vec![
+                ("hour_1".to_owned(), 1),
+                ("hour_1".to_owned(), 2),
+                ("hour_2".to_owned(), 3)
+            ]
+        );
+
+        // chunks 1 and 3 don't overlap the time range.
+        assert_eq!(
+            chunks(Some((1000, 2000)), Predicate::default()),
+            vec![("hour_1".to_owned(), 2)]
+        );
+
+        // only chunk 3 has data for the east region.
+        let predicate = Predicate::new(vec![BinaryExpr::from(("region", "=", "east"))]);
+        assert_eq!(chunks(None, predicate), vec![("hour_2".to_owned(), 3)]);
+
+        let predicate = Predicate::new(vec![BinaryExpr::from(("region", "=", "east"))]);
+        assert!(chunks(Some((1000, 2000)), predicate).is_empty());
+
+        assert!(db
+            .matching_chunks("Hulk", None, &Predicate::default())
+            .is_empty());
+    }
+
+    #[test]
+    fn result_time_bounds() {
+        let mut db = Database::new();
+        db.upsert_partition(
+            "hour_1",
+            22,
+            "Coolverine",
+            gen_recordbatch_regions(&["north", "north"]),
+        )
+        .unwrap();
+        db.upsert_partition("hour_1", 23, "Coolverine", gen_recordbatch())
+            .unwrap();
+
+        let bounds =
+            |time_range, predicate| db.result_time_bounds("Coolverine", time_range, &predicate);
+
+        assert_eq!(bounds(None, Predicate::default()), Some((0, 11111111)));
+
+        // the predicate excludes the earliest chunk.
+        let west = || Predicate::new(vec![BinaryExpr::from(("region", "=", "west"))]);
+        assert_eq!(bounds(None, west()), Some((3333, 11111111)));
+
+        // bounds are limited to the time range.
+        assert_eq!(
+            bounds(Some((5000, i64::MAX)), west()),
+            Some((5000, 11111111))
+        );
+        assert_eq!(
+            bounds(Some((1, 300000)), Predicate::default()),
+            Some((1, 299999))
+        );
+        assert_eq!(bounds(Some((0, 2)), west()), None);
+
+        // a time range between the chunks' time ranges.
+        assert_eq!(bounds(Some((2, 3000)), Predicate::default()), None);
+
+        // an empty time range at the minimum timestamp.
+        assert_eq!(
+            bounds(Some((i64::MIN, i64::MIN)), Predicate::default()),
+            None
+        );
+
+        assert_eq!(
+            db.result_time_bounds("Unknown", None, &Predicate::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn aggregate_scalar() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "a_table", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 2, "a_table", gen_recordbatch_at(&[100, 200]))
+            .unwrap();
+
+        let aggregate = |time_range, predicate, agg| {
+            db.aggregate_scalar(
+                "hour_1",
+                "a_table",
+                &[1, 2],
+                time_range,
+                predicate,
+                "counter",
+                agg,
+            )
+        };
+
+        let result = aggregate(None, Predicate::default(), AggregateType::Count).unwrap();
+        assert_eq!(result, Some(Scalar::U64(5)));
+
+        match aggregate(None, Predicate::default(), AggregateType::Sum).unwrap() {
+            Some(Scalar::F64(v)) => assert!((v - 52.2).abs() < 1e-9),
+            other => panic!("unexpected sum {:?}", other),
+        }
+
+        let result = aggregate(None, Predicate::default(), AggregateType::Min).unwrap();
+        assert_eq!(result, Some(Scalar::F64(1.2)));
+
+        let result = aggregate(None, Predicate::default(), AggregateType::Max).unwrap();
+        assert_eq!(result, Some(Scalar::F64(45.3)));
+
+        // only the third row of the first chunk matches.
+        let predicate = Predicate::new(vec![BinaryExpr::from(("region", "=", "east"))]);
+        let result = aggregate(None, predicate, AggregateType::Sum).unwrap();
+        assert_eq!(result, Some(Scalar::F64(45.3)));
+
+        // only the rows in the second chunk are within the time range.
+        let result = aggregate(Some((0, 1000)), Predicate::default(), AggregateType::Count);
+        assert_eq!(result.unwrap(), Some(Scalar::U64(2)));
+        let result = aggregate(Some((0, 1000)), Predicate::default(), AggregateType::Sum);
+        assert_eq!(result.unwrap(), Some(Scalar::F64(2.4)));
+
+        // no rows match.
+        let predicate = Predicate::new(vec![BinaryExpr::from(("region", "=", "north"))]);
+        let result = aggregate(None, predicate, AggregateType::Max).unwrap();
+        assert_eq!(result, None);
+
+        let result = aggregate(None, Predicate::default(), AggregateType::First);
+        assert!(matches!(result, Err(Error::UnsupportedAggregate { .. })));
+    }
+
+    #[test]
+    fn rename_table() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 22, "20 Size", gen_recordbatch())
+            .unwrap();
+
+        db.rename_table("hour_1", 22, "Coolverine", "Wolverine")
+            .unwrap();
+        let data = db
+            .table_names("hour_1", &[22], None, Predicate::default())
+            .unwrap();
+        assert_rb_column_equals(
+            &data,
+            "table",
+            &Values::String(vec![Some("20 Size"), Some("Wolverine")]),
+        );
+        assert_eq!(db.rows(), 6);
+        assert_eq!(db.tables(), 2);
+
+        // the renamed table can be queried under its new name.
+        let itr = db
+            .read_filter(
+                "hour_1",
+                "Wolverine",
+                &[22],
+                Predicate::default(),
+                table::ColumnSelection::All,
+            )
+            .unwrap();
+        assert_eq!(itr.map(|rb| rb.num_rows()).sum::<usize>(), 3);
+
+        // the old table no longer exists.
+        assert!(matches!(
+            db.rename_table("hour_1", 22, "Coolverine", "Hulk"),
+            Err(Error::TableNotFound { table_name }) if table_name == "Coolverine"
+        ));
+
+        // can't rename over an existing table.
+        assert!(matches!(
+            db.rename_table("hour_1", 22, "Wolverine", "20 Size"),
+            Err(Error::TableAlreadyExists { table_name }) if table_name == "20 Size"
+        ));
+
+        assert!(matches!(
+            db.rename_table("hour_1", 2, "Wolverine", "Hulk"),
+            Err(Error::ChunkNotFound { id: 2 })
+        ));
+    }
+
+    #[test]
+    fn table_names_time_range() {
+        let mut db = Database::new();
+
+        // gen_recordbatch contains data in the time range [3333, 11111111].
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+
+        // "20 Size" only exists in chunk 2, with data far in the future.
+        db.upsert_partition("hour_1", 2, "20 Size", gen_recordbatch_at(&[100000000000]))
+            .unwrap();
+
+        let data = db
+            .table_names("hour_1", &[2, 22], None, Predicate::default())
+            .unwrap();
+        assert_rb_column_equals(
+            &data,
+            "table",
+            &Values::String(vec![Some("20 Size"), Some("Coolverine")]),
+        );
+
+        // chunk 2 does not overlap the time range so its table is omitted.
+        let data = db
+            .table_names(
+                "hour_1",
+                &[2, 22],
+                Some((0, 11111112)),
+                Predicate::default(),
+            )
+            .unwrap();
+        assert_rb_column_equals(&data, "table", &Values::String(vec![Some("Coolverine")]));
+
+        let data = db
+            .table_names(
+                "hour_1",
+                &[2, 22],
+                Some((11111112, i64::MAX)),
+                Predicate::default(),
+            )
+            .unwrap();
+        assert_rb_column_equals(&data, "table", &Values::String(vec![Some("20 Size")]));
+
+        let data = db
+            .table_names("hour_1", &[2, 22], Some((0, 3333)), Predicate::default())
+            .unwrap();
+        assert_rb_column_equals(&data, "table", &Values::String(vec![]));
+    }
+
+    #[test]
+    fn read_filter_single_chunk() {
+        let mut db = Database::new();
+
+        // Add a bunch of row groups to a single table in a single chunk
+        for &i in &[100, 200, 300] {
+            let schema = SchemaBuilder::new()
+                .non_null_tag("env")
+                .non_null_tag("region")
+                .non_null_field("counter", Float64)
+                .timestamp()
+                .build()
+                .unwrap();
+
+            let data: Vec<ArrayRef> = vec![
+                Arc::new(StringArray::from(vec!["us-west", "us-east", "us-west"])),
+                Arc::new(StringArray::from(vec!["west", "west", "east"])),
+                Arc::new(Float64Array::from(vec![1.2, 300.3, 4500.3])),
+                Arc::new(Int64Array::from(vec![i, 2 * i, 3 * i])),
+            ];
+
+            // Add a record batch to a single partition
+            let rb = RecordBatch::try_new(schema.into(), data).unwrap();
+            db.upsert_partition("hour_1", 22, "Coolverine", rb).unwrap();
+        }
+
+        // Build the following query:
+        //
+        //   SELECT * FROM "table_1"
+        //   WHERE "env" = 'us-west' AND
+        //   "time" >= 100 AND  "time" < 205
+        //
+        let predicate = Predicate::with_time_range(
+            &[row_group::BinaryExpr::from(("env", "=", "us-west"))],
+            100,
+            205,
+        ); // filter on time
+
+        let mut itr = db
+            .read_filter(
+                "hour_1",
+                "Coolverine",
+                &[22],
+                predicate,
+                table::ColumnSelection::All,
+            )
+            .unwrap();
+
+        let exp_env_values = Values::String(vec![Some("us-west")]);
+        let exp_region_values = Values::String(vec![Some("west")]);
+        let exp_counter_values = Values::F64(vec![1.2]);
+
+        let first_row_group = itr.next().unwrap();
+        println!("{:?}", first_row_group);
+        assert_rb_column_equals(&first_row_group, "env", &exp_env_values);
+        assert_rb_column_equals(&first_row_group, "region", &exp_region_values);
+        assert_rb_column_equals(&first_row_group, "counter", &exp_counter_values);
+        assert_rb_column_equals(&first_row_group, "time", &Values::I64(vec![100])); // first row from first record batch
+
+        let second_row_group = itr.next().unwrap();
+        println!("{:?}", second_row_group);
+        assert_rb_column_equals(&second_row_group, "env", &exp_env_values);
+        assert_rb_column_equals(&second_row_group, "region", &exp_region_values);
+        assert_rb_column_equals(&second_row_group, "counter", &exp_counter_values);
+        assert_rb_column_equals(&second_row_group, "time", &Values::I64(vec![200])); // first row from second record batch
+
+        // No more data
+        assert!(itr.next().is_none());
+    }
+
+    #[test]
+    fn read_filter_multiple_chunks() {
+        let mut db = Database::new();
+
+        // Add a bunch of row groups to a single table across multiple chunks
+        for &i in &[100, 200, 300] {
+            let schema = SchemaBuilder::new()
+                .non_null_tag("env")
+                .non_null_tag("region")
+                .non_null_field("counter", Float64)
+                .timestamp()
+                .build()
+                .unwrap();
+
+            let data: Vec<ArrayRef> = vec![
+                Arc::new(StringArray::from(vec!["us-west", "us-east", "us-west"])),
+                Arc::new(StringArray::from(vec!["west", "west", "east"])),
+                Arc::new(Float64Array::from(vec![1.2, 300.3, 4500.3])),
+                Arc::new(Int64Array::from(vec![i, 2 * i, 3 * i])),
+            ];
+
+            // Add a record batch to a single partition
+            let rb = RecordBatch::try_new(schema.into(), data).unwrap();
+
+            // The row group gets added to a different chunk each time.
+            db.upsert_partition("hour_1", i as u32, "Coolverine", rb)
+                .unwrap();
+        }
+
+        // Build the following query:
+        //
+        //   SELECT * FROM "table_1"
+        //   WHERE "env" = 'us-west' AND
+        //   "time" >= 100 AND  "time" < 205
+        //
+        let predicate = Predicate::with_time_range(
+            &[row_group::BinaryExpr::from(("env", "=", "us-west"))],
+            100,
+            205,
+        ); // filter on time
+
+        let mut itr = db
+            .read_filter(
+                "hour_1",
+                "Coolverine",
+                &[100, 200, 300],
+                predicate,
+                table::ColumnSelection::Some(&["env", "region", "counter", "time"]),
+            )
+            .unwrap();
+
+        let exp_env_values = Values::String(vec![Some("us-west")]);
+        let exp_region_values = Values::String(vec![Some("west")]);
+        let exp_counter_values = Values::F64(vec![1.2]);
+
+        let first_row_group = itr.next().unwrap();
+        assert_rb_column_equals(&first_row_group, "env", &exp_env_values);
+        assert_rb_column_equals(&first_row_group, "region", &exp_region_values);
+        assert_rb_column_equals(&first_row_group, "counter", &exp_counter_values);
+        assert_rb_column_equals(&first_row_group, "time", &Values::I64(vec![100])); // first row from first record batch
+
+        let second_row_group = itr.next().unwrap();
+        assert_rb_column_equals(&second_row_group, "env", &exp_env_values);
+        assert_rb_column_equals(&second_row_group, "region", &exp_region_values);
+        assert_rb_column_equals(&second_row_group, "counter", &exp_counter_values);
+        assert_rb_column_equals(&second_row_group, "time", &Values::I64(vec![200])); // first row from second record batch
+
+        // No matching data for chunk 3, so iteration ends.
+        assert!(itr.next().is_none());
+    }
+
+    #[test]
+    fn read_filter_union_schema() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "Coolverine", gen_recordbatch())
+            .unwrap();
+
+        // A later chunk with a new "load" field and without the "counter" field.
+        let schema = SchemaBuilder::new()
+            .non_null_tag("region")
+            .non_null_field("load", Int64)
+            .timestamp()
+            .build()
+            .unwrap();
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["east", "north"])),
+            Arc::new(Int64Array::from(vec![10, 20])),
+            Arc::new(Int64Array::from(vec![100, 200])),
+        ];
+        let rb = RecordBatch::try_new(schema.into(), data).unwrap();
+        db.upsert_partition("hour_1", 2, "Coolverine", rb).unwrap();
+
+        let batches = db
+            .read_filter(
+                "hour_1",
+                "Coolverine",
+                &[1, 2],
+                Predicate::default(),
+                table::ColumnSelection::All,
+            )
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].schema(), batches[1].schema());
+
+        let schema = batches[0].schema();
+        let names = schema
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["counter", "load", "region", "time"]);
+        assert!(schema.field_with_name("counter").unwrap().is_nullable());
+        assert!(schema.field_with_name("load").unwrap().is_nullable());
+
+        assert_rb_column_equals(
+            &batches[0],
+            "counter",
+            &Values::F64N(vec![Some(1.2), Some(3.3), Some(45.3)]),
+        );
+        assert_rb_column_equals(&batches[0], "load", &Values::I64N(vec![None; 3]));
+        assert_rb_column_equals(&batches[1], "counter", &Values::F64N(vec![None; 2]));
+        assert_rb_column_equals(&batches[1], "load", &Values::I64N(vec![Some(10), Some(20)]));
+
+        // all batches can be concatenated.
+        let batches = db
+            .read_filter(
+                "hour_1",
+                "Coolverine",
+                &[1, 2],
+                Predicate::default(),
+                table::ColumnSelection::All,
+            )
+            .unwrap()
+            .with_target_batch_rows(10)
+            .collect::<Vec<_>>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 5);
+    }
+
+    #[test]
+    fn read_filter_single_chunk_matches_union() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 2, "Coolverine", gen_recordbatch())
+            .unwrap();
+
+        let single = db
+            .read_filter(
+                "hour_1",
+                "Coolverine",
+                &[1],
+                Predicate::default(),
+                table::ColumnSelection::All,
+            )
+            .unwrap()
+            .collect::<Vec<_>>();
+        let multiple = db
+            .read_filter(
+                "hour_1",
+                "Coolverine",
+                &[1, 2],
+                Predicate::default(),
+                table::ColumnSelection::All,
+            )
+            .unwrap()
+            .collect::<Vec<_>>();
+
+        // The single chunk's results are not conformed to a union schema, but
+        // have the same schema and column order as those of multiple chunks.
+        assert_eq!(single.len(), 1);
+        assert_eq!(multiple.len(), 2);
+        assert_eq!(single[0].schema(), multiple[0].schema());
+        for i in 0..single[0].num_columns() {
+            assert_eq!(
+                single[0].column(i).data(),
+                multiple[0].column(i).data(),
+                "column {} differs",
+                single[0].schema().field(i).name()
+            );
+        }
+    }
+
+    #[test]
+    fn read_filter_partition_range() {
+        let mut db = Database::new();
+        for (i, key) in [
+            "2021-01-01T00",
+            "2021-01-01T01",
+            "2021-01-01T02",
+            "2021-01-01T03",
+        ]
+        .iter()
+        .enumerate()
+        {
+            let time = i as i64 * 100;
+            db.upsert_partition(key, 1, "cpu", gen_recordbatch_at(&[time, time + 1]))
+                .unwrap();
+        }
+        // a chunk without the table is skipped.
+        db.upsert_partition("2021-01-01T02", 2, "mem", gen_recordbatch())
+            .unwrap();
+
+        let read = |key_from: &str, key_to: &str| {
+            let batches = db
+                .read_filter_partition_range(
+                    key_from,
+                    key_to,
+                    "cpu",
+                    Predicate::default(),
+                    table::ColumnSelection::Some(&["time"]),
+                )
+                .unwrap()
+                .collect::<Vec<_>>();
+            let mut times = vec![];
+            for batch in &batches {
+                let column = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap();
+                times.extend(column.values());
+            }
+            times
+        };
+
+        assert_eq!(
+            read("2021-01-01T01", "2021-01-01T03"),
+            vec![100, 101, 200, 201]
+        );
+        assert_eq!(read("2021-01-01T03", "2021-01-02"), vec![300, 301]);
+        assert_eq!(read("2020", "2021"), Vec::<i64>::new());
+
+        let result = db.read_filter_partition_range(
+            "2021-01-01T03",
+            "2021-01-01T01",
+            "cpu",
+            Predicate::default(),
+            table::ColumnSelection::All,
+        );
+        assert!(matches!(result, Err(Error::UnsupportedOperation { .. })));
+    }
+
+    #[test]
+    fn read_filter_rows() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 23, "Coolverine", gen_recordbatch_at(&[1, 2]))
+            .unwrap();
+
+        let read = || {
+            db.read_filter(
+                "hour_1",
+                "Coolverine",
+                &[22, 23],
+                Predicate::default(),
+                table::ColumnSelection::Some(&["region", "counter", "time"]),
+            )
+            .unwrap()
+        };
+
+        let batches = read().collect::<Vec<_>>();
+        let rows = read().rows().collect::<Vec<_>>();
+        assert_eq!(rows.len(), 5);
+
+        // each row holds the values of the columnar results.
+        let mut rows = rows.iter();
+        for batch in &batches {
+            for i in 0..batch.num_rows() {
+                let row = rows.next().unwrap();
+                assert_eq!(row.schema(), &batch.schema());
+                assert_eq!(row.values().len(), 3);
+                for (j, column) in batch.columns().iter().enumerate() {
+                    let expected = OwnedValue::from(Value::from_arrow(column, i).unwrap());
+                    assert_eq!(row.value(j), Some(&expected));
+                }
+            }
+        }
+
+        let row = read().rows().next().unwrap();
+        assert_eq!(
+            row.value_by_name("region"),
+            Some(&OwnedValue::String("west".to_owned()))
+        );
+        assert_eq!(
+            row.value_by_name("counter"),
+            Some(&OwnedValue::Scalar(Scalar::F64(1.2)))
+        );
+        assert_eq!(
+            row.value_by_name("time"),
+            Some(&OwnedValue::Scalar(Scalar::I64(11111111)))
+        );
+        assert_eq!(row.value_by_name("host"), None);
+        assert_eq!(row.value(3), None);
+    }
+
+    #[test]
+    fn read_filter_with_time_column() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+
+        let read = |with_time: bool| {
+            let results = db
+                .read_filter(
+                    "hour_1",
+                    "Coolverine",
+                    &[22],
+                    Predicate::default(),
+                    table::ColumnSelection::Some(&["region", "counter"]),
+                )
+                .unwrap();
+            let results = if with_time {
+                results.with_time_column()
+            } else {
+                results
+            };
+            results.collect::<Vec<_>>()
+        };
+
+        // by default only the selected columns are returned.
+        let batches = read(false);
+        let names = batches[0]
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["region", "counter"]);
+
+        // the time column is returned first.
+        let batches = read(true);
+        let names = batches[0]
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["time", "region", "counter"]);
+        assert_rb_column_equals(
+            &batches[0],
+            "time",
+            &Values::I64(vec![11111111, 222222, 3333]),
+        );
+    }
+
+    #[test]
+    fn read_filter_schema_on_empty() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch())
+            .unwrap();
+
+        let read = |schema_on_empty: bool| {
+            let results = db
+                .read_filter(
+                    "hour_1",
+                    "Coolverine",
+                    &[22],
+                    Predicate::new(vec![BinaryExpr::from(("region", "=", "north"))]),
+                    table::ColumnSelection::Some(&["region", "counter"]),
+                )
+                .unwrap();
+            let results = if schema_on_empty {
+                results.with_schema_on_empty()
+            } else {
+                results
+            };
+            results.collect::<Vec<_>>()
+        };
+
+        // by default no batches are returned when no rows match.
+        assert!(read(false).is_empty());
+
+        // a single batch without rows describes the selected columns.
+        let batches = read(true);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 0);
+        let schema = batches[0].schema();
+        let fields = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec![("region", Utf8), ("counter", Float64)]);
+
+        // no empty batch is returned when rows match.
+        let batches = db
+            .read_filter(
+                "hour_1",
+                "Coolverine",
+                &[22],
+                Predicate::default(),
+                table::ColumnSelection::All,
+            )
+            .unwrap()
+            .with_schema_on_empty()
+            .collect::<Vec<_>>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 3);
+    }
+
+    #[test]
+    fn read_filter_coalesce_batches() {
+        let mut db = Database::new();
+
+        // Add a single row to each of 100 chunks.
+        for i in 0..100 {
+            let schema = SchemaBuilder::new()
+                .non_null_tag("region")
+                .timestamp()
+                .build()
+                .unwrap();
+
+            let data: Vec<ArrayRef> = vec![
+                Arc::new(StringArray::from(vec!["west"])),
+                Arc::new(Int64Array::from(vec![i])),
+            ];
+
+            let rb = RecordBatch::try_new(schema.into(), data).unwrap();
+            db.upsert_partition("hour_1", i as u32, "Coolverine", rb)
+                .unwrap();
         }
+
+        let chunk_ids = (0..100).collect::<Vec<_>>();
+        let itr = db
+            .read_filter(
+                "hour_1",
+                "Coolverine",
+                &chunk_ids,
+                Predicate::default(),
+                table::ColumnSelection::All,
+            )
+            .unwrap();
+        assert_eq!(itr.count(), 100);
+
+        let batches = db
+            .read_filter(
+                "hour_1",
+                "Coolverine",
+                &chunk_ids,
+                Predicate::default(),
+                table::ColumnSelection::All,
+            )
+            .unwrap()
+            .with_target_batch_rows(30)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            batches.iter().map(|rb| rb.num_rows()).collect::<Vec<_>>(),
+            vec![30, 30, 30, 10]
+        );
+        assert_rb_column_equals(
+            &batches[3],
+            "time",
+            &Values::I64((90..100).collect::<Vec<_>>()),
+        );
     }
 
     #[test]
-    fn table_names() {
+    fn read_filter_time_ordered() {
+        // each row's counter is its timestamp, to check rows stay intact.
+        let gen = |times: &[i64]| {
+            let schema = SchemaBuilder::new()
+                .non_null_field("counter", Float64)
+                .timestamp()
+                .build()
+                .unwrap();
+            let data: Vec<ArrayRef> = vec![
+                Arc::new(Float64Array::from(
+                    times.iter().map(|&t| t as f64).collect::<Vec<_>>(),
+                )),
+                Arc::new(Int64Array::from(times.to_vec())),
+            ];
+            RecordBatch::try_new(schema.into(), data).unwrap()
+        };
+
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "Coolverine", gen(&[1, 4, 7]))
+            .unwrap();
+        db.upsert_partition("hour_1", 1, "Coolverine", gen(&[10, 12]))
+            .unwrap();
+        db.upsert_partition("hour_1", 2, "Coolverine", gen(&[2, 5, 8, 11]))
+            .unwrap();
+        // rows within a row group need not be sorted.
+        db.upsert_partition("hour_1", 3, "Coolverine", gen(&[9, 3, 6]))
+            .unwrap();
+
+        let batches = db
+            .read_filter_time_ordered(
+                "hour_1",
+                "Coolverine",
+                &[1, 2, 3],
+                Predicate::default(),
+                table::ColumnSelection::All,
+            )
+            .unwrap()
+            .with_batch_rows(5)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            batches.iter().map(|rb| rb.num_rows()).collect::<Vec<_>>(),
+            vec![5, 5, 2]
+        );
+        let expected = [(0, 1..6), (1, 6..11), (2, 11..13)];
+        for (i, times) in expected.iter().cloned() {
+            assert_rb_column_equals(
+                &batches[i],
+                "time",
+                &Values::I64(times.clone().collect::<Vec<_>>()),
+            );
+            assert_rb_column_equals(
+                &batches[i],
+                "counter",
+                &Values::F64(times.map(|t| t as f64).collect::<Vec<_>>()),
+            );
+        }
+
+        // a predicate is applied to each row group.
+        let batches = db
+            .read_filter_time_ordered(
+                "hour_1",
+                "Coolverine",
+                &[1, 2, 3],
+                Predicate::with_time_range(&[], 4, 9),
+                table::ColumnSelection::Some(&["time"]),
+            )
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(batches.len(), 1);
+        assert_rb_column_equals(&batches[0], "time", &Values::I64(vec![4, 5, 6, 7, 8]));
+
+        // the time column is needed to order the results.
+        assert!(matches!(
+            db.read_filter_time_ordered(
+                "hour_1",
+                "Coolverine",
+                &[1, 2, 3],
+                Predicate::default(),
+                table::ColumnSelection::Some(&["counter"]),
+            ),
+            Err(Error::UnsupportedOperation { .. })
+        ));
+    }
+
+    #[test]
+    fn field_keys() {
         let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition("hour_1", 2, "20 Size", gen_recordbatch())
+            .unwrap();
 
-        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch());
         let data = db
-            .table_names("hour_1", &[22], Predicate::default())
+            .field_keys("hour_1", "Coolverine", &[1, 2], Predicate::default())
             .unwrap();
-        assert_rb_column_equals(&data, "table", &Values::String(vec![Some("Coolverine")]));
+        assert_rb_column_equals(&data, "field", &Values::String(vec![Some("counter")]));
 
-        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch());
+        // the table exists but no rows match.
+        let predicate = Predicate::new(vec![BinaryExpr::from(("region", "=", "north"))]);
         let data = db
-            .table_names("hour_1", &[22], Predicate::default())
+            .field_keys("hour_1", "Coolverine", &[1, 2], predicate)
             .unwrap();
-        assert_rb_column_equals(&data, "table", &Values::String(vec![Some("Coolverine")]));
+        assert_eq!(data.num_rows(), 0);
+
+        // the table doesn't exist in any of the chunks.
+        let result = db.field_keys("hour_1", "20 Size", &[1], Predicate::default());
+        assert!(matches!(
+            result,
+            Err(Error::TableNotFound { table_name }) if table_name == "20 Size"
+        ));
+        let result = db.field_keys("hour_1", "Wolverine", &[1, 2], Predicate::default());
+        assert!(matches!(result, Err(Error::TableNotFound { .. })));
+    }
+
+    #[test]
+    fn field_distinct_values() {
+        let mut db = Database::new();
+        db.upsert_partition("hour_1", 1, "Coolverine", gen_recordbatch())
+            .unwrap();
+        db.upsert_partition(
+            "hour_1",
+            2,
+            "Coolverine",
+            gen_recordbatch_at(&[100, 200, 300]),
+        )
+        .unwrap();
 
-        db.upsert_partition("hour_1", 2, "Coolverine", gen_recordbatch());
         let data = db
-            .table_names("hour_1", &[22], Predicate::default())
+            .field_distinct_values(
+                "hour_1",
+                "Coolverine",
+                &[1, 2],
+                None,
+                Predicate::default(),
+                "counter",
+            )
             .unwrap();
-        assert_rb_column_equals(&data, "table", &Values::String(vec![Some("Coolverine")]));
+        assert_rb_column_equals(&data, "counter", &Values::F64(vec![1.2, 3.3, 45.3]));
 
-        db.upsert_partition("hour_1", 2, "20 Size", gen_recordbatch());
+        // only the east row of the first chunk matches.
         let data = db
-            .table_names("hour_1", &[2, 22], Predicate::default())
+            .field_distinct_values(
+                "hour_1",
+                "Coolverine",
+                &[1, 2],
+                Some((0, 1_000_000)),
+                Predicate::new(vec![BinaryExpr::from(("region", "=", "east"))]),
+                "counter",
+            )
             .unwrap();
-        assert_rb_column_equals(
-            &data,
-            "table",
-            &Values::String(vec![Some("20 Size"), Some("Coolverine")]),
+        assert_rb_column_equals(&data, "counter", &Values::F64(vec![45.3]));
+
+        let result = db.field_distinct_values(
+            "hour_1",
+            "Wolverine",
+            &[1, 2],
+            None,
+            Predicate::default(),
+            "counter",
         );
+        assert!(matches!(result, Err(Error::TableNotFound { .. })));
     }
 
     #[test]
-    fn read_filter_single_chunk() {
+    fn accessed_columns() {
         let mut db = Database::new();
+        let schema = SchemaBuilder::new()
+            .non_null_tag("env")
+            .non_null_tag("region")
+            .non_null_field("counter", Float64)
+            .non_null_field("temp", Float64)
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+        let data: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec!["prod", "dev"])),
+            Arc::new(StringArray::from(vec!["west", "east"])),
+            Arc::new(Float64Array::from(vec![1.0, 2.0])),
+            Arc::new(Float64Array::from(vec![20.5, 21.0])),
+            Arc::new(Int64Array::from(vec![100, 200])),
+        ];
+        let rb = RecordBatch::try_new(schema, data).unwrap();
+        db.upsert_partition("hour_1", 1, "a_table", rb).unwrap();
 
-        // Add a bunch of row groups to a single table in a single chunk
-        for &i in &[100, 200, 300] {
-            let schema = SchemaBuilder::new()
-                .non_null_tag("env")
-                .non_null_tag("region")
-                .non_null_field("counter", Float64)
-                .timestamp()
-                .build()
-                .unwrap();
+        let accessed = |predicate: Predicate, group_columns: ColumnSelection<'_>| {
+            db.accessed_columns(
+                "hour_1",
+                "a_table",
+                &[1],
+                &predicate,
+                ColumnSelection::Some(&["region"]),
+                group_columns,
+                &[("counter", AggregateType::Sum)],
+            )
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>()
+        };
 
-            let data: Vec<ArrayRef> = vec![
-                Arc::new(StringArray::from(vec!["us-west", "us-east", "us-west"])),
-                Arc::new(StringArray::from(vec!["west", "west", "east"])),
-                Arc::new(Float64Array::from(vec![1.2, 300.3, 4500.3])),
-                Arc::new(Int64Array::from(vec![i, 2 * i, 3 * i])),
-            ];
+        let predicate = Predicate::new(vec![BinaryExpr::from(("env", "=", "prod"))]);
+        assert_eq!(
+            accessed(predicate, ColumnSelection::Some(&["region"])),
+            vec!["counter", "env", "region", "time"]
+        );
 
-            // Add a record batch to a single partition
-            let rb = RecordBatch::try_new(schema.into(), data).unwrap();
-            db.upsert_partition("hour_1", 22, "Coolverine", rb);
+        // grouping by all columns groups by all tag columns, and columns that
+        // don't exist are not accessed.
+        let predicate = Predicate::new(vec![BinaryExpr::from(("unknown", "=", "x"))]);
+        assert_eq!(
+            accessed(predicate, ColumnSelection::All),
+            vec!["counter", "env", "region", "time"]
+        );
+
+        assert!(matches!(
+            db.accessed_columns(
+                "hour_1",
+                "b_table",
+                &[1],
+                &Predicate::default(),
+                ColumnSelection::All,
+                ColumnSelection::All,
+                &[],
+            ),
+            Err(Error::TableNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn estimate_select_size() {
+        let mut db = Database::new();
+        for &(id, from) in &[(1, 0), (2, 1000)] {
+            let times = (from..from + 1000).collect::<Vec<i64>>();
+            db.upsert_partition("hour_1", id, "Coolverine", gen_recordbatch_at(&times))
+                .unwrap();
         }
 
-        // Build the following query:
-        //
-        //   SELECT * FROM "table_1"
-        //   WHERE "env" = 'us-west' AND
-        //   "time" >= 100 AND  "time" < 205
-        //
-        let predicate = Predicate::with_time_range(
-            &[row_group::BinaryExpr::from(("env", "=", "us-west"))],
-            100,
-            205,
-        ); // filter on time
+        // half of the rows in each chunk are selected.
+        let predicate = Predicate::with_time_range(&[], 500, 1500);
+        let columns = ["region", "counter", "time"];
+        let estimate = db
+            .estimate_select_size(
+                "hour_1",
+                "Coolverine",
+                &[1, 2],
+                &predicate,
+                table::ColumnSelection::Some(&columns),
+            )
+            .unwrap();
 
-        let mut itr = db
+        let actual = db
             .read_filter(
                 "hour_1",
                 "Coolverine",
-                &[22],
+                &[1, 2],
                 predicate,
-                table::ColumnSelection::All,
+                table::ColumnSelection::Some(&columns),
             )
+            .unwrap()
+            .map(|rb| {
+                rb.columns()
+                    .iter()
+                    .map(|arr| arr.get_array_memory_size())
+                    .sum::<usize>()
+            })
+            .sum::<usize>() as u64;
+        assert!(
+            estimate >= actual / 2 && estimate <= actual * 2,
+            "estimate {} too far from {}",
+            estimate,
+            actual
+        );
+
+        let result = db.estimate_select_size(
+            "hour_1",
+            "Wolverine",
+            &[1, 2],
+            &Predicate::default(),
+            table::ColumnSelection::All,
+        );
+        assert!(matches!(result, Err(Error::TableNotFound { .. })));
+    }
+
+    #[test]
+    fn open_time_range() {
+        let mut db = Database::new();
+        db.upsert_partition(
+            "hour_1",
+            1,
+            "Coolverine",
+            gen_recordbatch_at(&[i64::MIN, 0, i64::MAX]),
+        )
+        .unwrap();
+        db.upsert_partition("hour_1", 2, "Coolverine", gen_recordbatch_at(&[100]))
             .unwrap();
 
-        let exp_env_values = Values::String(vec![Some("us-west")]);
-        let exp_region_values = Values::String(vec![Some("west")]);
-        let exp_counter_values = Values::F64(vec![1.2]);
+        let all_time = (i64::MIN, i64::MAX);
+        let predicate = Predicate::with_time_range(&[], all_time.0, all_time.1);
+        assert!(predicate.is_empty());
 
-        let first_row_group = itr.next().unwrap();
-        println!("{:?}", first_row_group);
-        assert_rb_column_equals(&first_row_group, "env", &exp_env_values);
-        assert_rb_column_equals(&first_row_group, "region", &exp_region_values);
-        assert_rb_column_equals(&first_row_group, "counter", &exp_counter_values);
-        assert_rb_column_equals(&first_row_group, "time", &Values::I64(vec![100])); // first row from first record batch
+        let times = db
+            .read_filter(
+                "hour_1",
+                "Coolverine",
+                &[1, 2],
+                predicate,
+                table::ColumnSelection::Some(&["time"]),
+            )
+            .unwrap()
+            .flat_map(|rb| {
+                let times = rb.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                times.values().to_vec()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(times, vec![i64::MIN, 0, i64::MAX, 100]);
 
-        let second_row_group = itr.next().unwrap();
-        println!("{:?}", second_row_group);
-        assert_rb_column_equals(&second_row_group, "env", &exp_env_values);
-        assert_rb_column_equals(&second_row_group, "region", &exp_region_values);
-        assert_rb_column_equals(&second_row_group, "counter", &exp_counter_values);
-        assert_rb_column_equals(&second_row_group, "time", &Values::I64(vec![200])); // first row from second record batch
+        let count = db
+            .aggregate_scalar(
+                "hour_1",
+                "Coolverine",
+                &[1, 2],
+                Some(all_time),
+                Predicate::default(),
+                "counter",
+                AggregateType::Count,
+            )
+            .unwrap();
+        assert_eq!(count, Some(Scalar::U64(4)));
 
-        // No more data
-        assert!(itr.next().is_none());
+        // only the row at i64::MAX is after 100.
+        let count = db
+            .aggregate_scalar(
+                "hour_1",
+                "Coolverine",
+                &[1, 2],
+                Some((101, i64::MAX)),
+                Predicate::default(),
+                "counter",
+                AggregateType::Count,
+            )
+            .unwrap();
+        assert_eq!(count, Some(Scalar::U64(1)));
+
+        let data = db
+            .table_names(
+                "hour_1",
+                &[1, 2],
+                Some((i64::MAX, i64::MAX)),
+                Predicate::default(),
+            )
+            .unwrap();
+        assert_rb_column_equals(&data, "table", &Values::String(vec![Some("Coolverine")]));
     }
 
     #[test]
-    fn read_filter_multiple_chunks() {
+    fn warm_metadata() {
         let mut db = Database::new();
-
-        // Add a bunch of row groups to a single table across multiple chunks
         for &i in &[100, 200, 300] {
-            let schema = SchemaBuilder::new()
-                .non_null_tag("env")
-                .non_null_tag("region")
-                .non_null_field("counter", Float64)
-                .timestamp()
-                .build()
-                .unwrap();
-
-            let data: Vec<ArrayRef> = vec![
-                Arc::new(StringArray::from(vec!["us-west", "us-east", "us-west"])),
-                Arc::new(StringArray::from(vec!["west", "west", "east"])),
-                Arc::new(Float64Array::from(vec![1.2, 300.3, 4500.3])),
-                Arc::new(Int64Array::from(vec![i, 2 * i, 3 * i])),
-            ];
-
-            // Add a record batch to a single partition
-            let rb = RecordBatch::try_new(schema.into(), data).unwrap();
-
-            // The row group gets added to a different chunk each time.
-            db.upsert_partition("hour_1", i as u32, "Coolverine", rb);
+            db.upsert_partition(
+                "hour_1",
+                i as u32,
+                "Coolverine",
+                gen_recordbatch_at(&[i, 2 * i]),
+            )
+            .unwrap();
         }
 
-        // Build the following query:
-        //
-        //   SELECT * FROM "table_1"
-        //   WHERE "env" = 'us-west' AND
-        //   "time" >= 100 AND  "time" < 205
-        //
-        let predicate = Predicate::with_time_range(
-            &[row_group::BinaryExpr::from(("env", "=", "us-west"))],
-            100,
-            205,
-        ); // filter on time
+        // three columns in each of three chunks are warmed, and nothing is
+        // left to warm the second time.
+        assert_eq!(db.warm_metadata(), 9);
+        assert_eq!(db.warm_metadata(), 0);
 
-        let mut itr = db
+        let table = |id: u32| {
+            db.partitions.get("hour_1").unwrap().chunks[&id]
+                .table("Coolverine")
+                .unwrap()
+        };
+        let scans = || {
+            [100, 200, 300]
+                .iter()
+                .map(|&id| table(id).metadata_scans())
+                .sum::<u64>()
+        };
+        let warm_scans = scans();
+
+        // neither a query pruned by time nor reading column statistics builds
+        // any metadata.
+        let predicate = Predicate::with_time_range(&[], 100, 205);
+        let itr = db
             .read_filter(
                 "hour_1",
                 "Coolverine",
                 &[100, 200, 300],
                 predicate,
-                table::ColumnSelection::Some(&["env", "region", "counter", "time"]),
+                table::ColumnSelection::Some(&["region", "time"]),
             )
             .unwrap();
+        assert_eq!(itr.count(), 2);
 
-        let exp_env_values = Values::String(vec![Some("us-west")]);
-        let exp_region_values = Values::String(vec![Some("west")]);
-        let exp_counter_values = Values::F64(vec![1.2]);
-
-        let first_row_group = itr.next().unwrap();
-        assert_rb_column_equals(&first_row_group, "env", &exp_env_values);
-        assert_rb_column_equals(&first_row_group, "region", &exp_region_values);
-        assert_rb_column_equals(&first_row_group, "counter", &exp_counter_values);
-        assert_rb_column_equals(&first_row_group, "time", &Values::I64(vec![100])); // first row from first record batch
-
-        let second_row_group = itr.next().unwrap();
-        assert_rb_column_equals(&second_row_group, "env", &exp_env_values);
-        assert_rb_column_equals(&second_row_group, "region", &exp_region_values);
-        assert_rb_column_equals(&second_row_group, "counter", &exp_counter_values);
-        assert_rb_column_equals(&second_row_group, "time", &Values::I64(vec![200])); // first row from second record batch
-
-        // No matching data for chunk 3, so iteration ends.
-        assert!(itr.next().is_none());
+        let stats = table(100).column_statistics("region").unwrap();
+        assert_eq!(stats.distinct_count, 1);
+        assert_eq!(scans(), warm_scans);
     }
 
     #[test]
@@ -1104,7 +6233,7 @@ mod test {
             let rb = RecordBatch::try_new(schema.into(), data).unwrap();
             println!("rb {:?} {:?}", i, &rb);
             // The row group gets added to the same chunk each time.
-            db.upsert_partition("hour_1", 1, "table1", rb);
+            db.upsert_partition("hour_1", 1, "table1", rb).unwrap();
         }
 
         // Build the following query:
@@ -1160,4 +6289,5 @@ pub mod benchmarks {
     };
 
     pub use crate::row_group::{ColumnType, RowGroup};
+    pub use crate::table::Table;
 }
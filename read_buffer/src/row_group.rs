@@ -1,22 +1,27 @@
 use std::{
     borrow::Cow,
     cmp::Ordering,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     convert::{TryFrom, TryInto},
+    hash::{Hash, Hasher},
+    mem::size_of,
     sync::Arc,
 };
 
 use arrow::array;
 use hashbrown::{hash_map, HashMap};
 use itertools::Itertools;
-use snafu::{ResultExt, Snafu};
+use snafu::{ensure, ResultExt, Snafu};
 
+use crate::cache::DecodeCache;
 use crate::column::{
-    cmp::Operator, AggregateResult, Column, EncodedValues, OwnedValue, RowIDs, RowIDsOption,
-    Scalar, Value, Values, ValuesIterator,
+    cmp::Operator,
+    strategy::{EncodingKind, EncodingStrategy},
+    AggregateResult, Column, EncodedValues, OwnedValue, RowIDs, RowIDsOption, Scalar, Value,
+    ValueSet, Values, ValuesIterator,
 };
 use crate::schema;
-use crate::schema::{AggregateType, LogicalDataType, ResultSchema};
+use crate::schema::{AggregateType, LogicalDataType, NanHandling, ResultSchema};
 use arrow_deps::arrow::record_batch::RecordBatch;
 use arrow_deps::{
     arrow, datafusion::logical_plan::Expr as DfExpr,
@@ -41,6 +46,9 @@ pub enum Error {
 
     #[snafu(display("unsupported operation: {}", msg))]
     UnsupportedOperation { msg: String },
+
+    #[snafu(display("column {} contains null values but is not nullable", column_name))]
+    ColumnNullabilityMismatch { column_name: String },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -55,6 +63,12 @@ pub struct RowGroup {
     columns: Vec<Column>,
     all_columns_by_name: BTreeMap<String, usize>,
     time_column: usize,
+
+    // Whether the time column is sorted in ascending order.
+    time_sorted: bool,
+
+    // A hash of the row group's column names, types and values.
+    content_hash: u64,
 }
 
 impl RowGroup {
@@ -67,6 +81,7 @@ impl RowGroup {
         let mut all_columns = vec![];
         let mut all_columns_by_name = BTreeMap::new();
         let mut time_column = None;
+        let all_row_ids = (0..rows).collect::<Vec<_>>();
 
         for (name, ct) in columns {
             meta.size += ct.size();
@@ -79,6 +94,7 @@ impl RowGroup {
                         schema::ColumnType::Tag(name.clone()),
                         c.logical_datatype(),
                         c.column_range().unwrap(),
+                        (rows - c.count(&all_row_ids)) as u64,
                     );
 
                     all_columns_by_name.insert(name.clone(), all_columns.len());
@@ -92,6 +108,7 @@ impl RowGroup {
                         schema::ColumnType::Field(name.clone()),
                         c.logical_datatype(),
                         c.column_range().unwrap(),
+                        (rows - c.count(&all_row_ids)) as u64,
                     );
                     all_columns_by_name.insert(name.clone(), all_columns.len());
                     all_columns.push(c);
@@ -113,6 +130,7 @@ impl RowGroup {
                         schema::ColumnType::Timestamp(name.clone()),
                         c.logical_datatype(),
                         c.column_range().unwrap(),
+                        (rows - c.count(&all_row_ids)) as u64,
                     );
 
                     all_columns_by_name.insert(name.clone(), all_columns.len());
@@ -125,11 +143,42 @@ impl RowGroup {
         // Meta data should have same columns for types and ranges.
         assert_eq!(meta.columns.keys().len(), all_columns.len());
 
-        Self {
+        let time_column = time_column.unwrap();
+        let time_sorted = match all_columns[time_column].all_values() {
+            Values::I64(values) => values.windows(2).all(|w| w[0] <= w[1]),
+            _ => false,
+        };
+
+        let mut row_group = Self {
             meta,
             columns: all_columns,
             all_columns_by_name,
-            time_column: time_column.unwrap(),
+            time_column,
+            time_sorted,
+            content_hash: 0,
+        };
+        row_group.content_hash = row_group.compute_content_hash();
+        row_group
+    }
+
+    // Hashes the name, type and values of each column.
+    fn compute_content_hash(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for (name, &i) in &self.all_columns_by_name {
+            name.hash(&mut hasher);
+            std::mem::discriminant(&self.meta.columns[name].typ).hash(&mut hasher);
+            hash_values(&self.columns[i].all_values(), &mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Changes the semantic type of the column, e.g., from a tag to a field.
+    /// The column's data is unchanged. Does nothing if the column doesn't
+    /// exist in the `RowGroup`.
+    pub(crate) fn reclassify_column(&mut self, typ: schema::ColumnType) {
+        if let Some(meta) = self.meta.columns.get_mut(typ.as_str()) {
+            meta.typ = typ;
+            self.content_hash = self.compute_content_hash();
         }
     }
 
@@ -149,6 +198,87 @@ impl RowGroup {
         &self.meta
     }
 
+    /// A hash of the row group's column names, column types and values. Row
+    /// groups containing the same logical data have the same hash, regardless
+    /// of how their columns are encoded.
+    pub fn content_hash(&self) -> u64 {
+        self.content_hash
+    }
+
+    /// The inclusive `(min, max)` range of each column in the `RowGroup`,
+    /// keyed by column name. Ranges only reflect non-null values; a column's
+    /// NULL values never contribute to its range.
+    pub fn column_ranges(&self) -> BTreeMap<ColumnName<'_>, (OwnedValue, OwnedValue)> {
+        self.meta
+            .columns
+            .iter()
+            .map(|(name, meta)| (name.as_str(), meta.range.clone()))
+            .collect()
+    }
+
+    /// All of the materialised values in the column, or `None` if the column
+    /// doesn't exist in the `RowGroup`.
+    pub fn column_all_values(&self, name: ColumnName<'_>) -> Option<Values<'_>> {
+        self.all_columns_by_name
+            .get(name)
+            .map(|&i| self.columns[i].all_values())
+    }
+
+    /// The materialised values in the column at the rows satisfying the
+    /// predicate, or `None` if the column doesn't exist in the `RowGroup` or
+    /// no rows satisfy the predicate.
+    pub fn column_values(&self, name: ColumnName<'_>, predicate: &Predicate) -> Option<Values<'_>> {
+        let &i = self.all_columns_by_name.get(name)?;
+        match self.row_ids_from_predicates(predicate) {
+            RowIDsOption::None(_) => None,
+            RowIDsOption::Some(row_ids) => Some(self.columns[i].values(&row_ids.to_vec())),
+            RowIDsOption::All(_) => Some(self.columns[i].all_values()),
+        }
+    }
+
+    /// The kind of encoding used to store the column, or `None` if the column
+    /// doesn't exist in the `RowGroup`.
+    pub fn column_encoding(&self, name: ColumnName<'_>) -> Option<EncodingKind> {
+        self.all_columns_by_name
+            .get(name)
+            .map(|&i| self.columns[i].encoding_kind())
+    }
+
+    /// The value in the column at the provided row id, which is NULL if the
+    /// column doesn't exist in the `RowGroup`.
+    pub fn column_value(&self, name: ColumnName<'_>, row_id: u32) -> Value<'_> {
+        match self.all_columns_by_name.get(name) {
+            Some(&i) => self.columns[i].value(row_id),
+            None => Value::Null,
+        }
+    }
+
+    /// The timestamp and row id of the earliest row with a timestamp at or
+    /// after `time_lower_bound`. If several rows share that timestamp then the
+    /// row with the smallest row id is returned.
+    pub fn first_row(&self, time_lower_bound: i64) -> Option<(i64, u32)> {
+        self.timestamps()
+            .filter(|&(time, _)| time >= time_lower_bound)
+            .min_by_key(|&(time, row_id)| (time, row_id))
+    }
+
+    /// The timestamp and row id of the latest row with a timestamp before
+    /// `time_upper_bound`. If several rows share that timestamp then the row
+    /// with the smallest row id is returned.
+    pub fn last_row(&self, time_upper_bound: i64) -> Option<(i64, u32)> {
+        self.timestamps()
+            .filter(|&(time, _)| time < time_upper_bound)
+            .min_by_key(|&(time, row_id)| (std::cmp::Reverse(time), row_id))
+    }
+
+    // The non-null timestamps in the `RowGroup`, paired with their row ids.
+    fn timestamps(&self) -> impl Iterator<Item = (i64, u32)> + '_ {
+        (0..self.rows()).filter_map(move |row_id| match self.time_column().value(row_id) {
+            Value::Scalar(Scalar::I64(time)) => Some((time, row_id)),
+            _ => None,
+        })
+    }
+
     // Returns a reference to a column from the column name.
     //
     // It is the caller's responsibility to ensure the column exists in the read
@@ -176,6 +306,16 @@ impl RowGroup {
         self.meta.time_range
     }
 
+    /// Returns true if the rows in the `RowGroup` are ordered by ascending
+    /// time. This is determined when the `RowGroup` is created.
+    ///
+    /// Operations on sorted row groups can take faster paths, e.g., locating
+    /// the rows in a time range via binary search. Out-of-order data falls back
+    /// to the general path.
+    pub fn is_time_sorted(&self) -> bool {
+        self.time_sorted
+    }
+
     /// Efficiently determines if the provided set of binary expressions could
     /// all be satisfied by the `RowGroup` when conjunctively applied.
     pub fn could_satisfy_conjunctive_binary_expressions<'a>(
@@ -192,6 +332,15 @@ impl RowGroup {
     // Methods for reading the `RowGroup`
     //
 
+    /// Returns the ids of the rows that satisfy the predicate.
+    ///
+    /// The predicate only needs to be evaluated once when it is used for
+    /// several operations: the row ids can be passed to `read_filter_rows` and
+    /// `read_aggregate_rows` in place of the predicate.
+    pub fn matching_rows(&self, predicate: &Predicate) -> RowIDsOption {
+        self.row_ids_from_predicates(predicate)
+    }
+
     /// Returns a set of materialised column values that optionally satisfy a
     /// predicate.
     ///
@@ -201,6 +350,18 @@ impl RowGroup {
         &self,
         columns: &[ColumnName<'_>],
         predicates: &Predicate,
+    ) -> ReadFilterResult<'_> {
+        // apply predicates to determine candidate rows.
+        let row_ids = self.row_ids_from_predicates(predicates);
+        self.read_filter_rows(columns, &row_ids)
+    }
+
+    /// As `read_filter`, but for the rows identified by `row_ids`, which
+    /// should be the result of `matching_rows`.
+    pub fn read_filter_rows(
+        &self,
+        columns: &[ColumnName<'_>],
+        row_ids: &RowIDsOption,
     ) -> ReadFilterResult<'_> {
         let select_columns = self.meta.schema_for_column_names(&columns);
         assert_eq!(select_columns.len(), columns.len());
@@ -210,16 +371,116 @@ impl RowGroup {
             ..Default::default()
         };
 
-        // apply predicates to determine candidate rows.
-        let row_ids = self.row_ids_from_predicates(predicates);
-        let col_data = self.materialise_rows(columns, row_ids);
+        self.read_filter_rows_with_schema(&schema, row_ids)
+    }
+
+    /// As `read_filter_rows`, but returns results with the select columns of
+    /// the provided schema, which is typically that of the row group's table.
+    ///
+    /// Selected columns that don't exist in the row group, e.g., because they
+    /// were added to the table after the row group was created, are
+    /// materialised as NULL values of the type given by the schema.
+    pub fn read_filter_rows_with_schema(
+        &self,
+        schema: &ResultSchema,
+        row_ids: &RowIDsOption,
+    ) -> ReadFilterResult<'_> {
+        let rows = match row_ids {
+            RowIDsOption::None(_) => {
+                return ReadFilterResult {
+                    schema: schema.clone(),
+                    data: vec![],
+                }
+            }
+            RowIDsOption::Some(row_ids) => row_ids.len(),
+            RowIDsOption::All(_) => self.rows() as usize,
+        };
+
+        let columns = schema
+            .select_column_names_iter()
+            .map(|name| name.as_str())
+            .filter(|name| self.all_columns_by_name.contains_key(*name))
+            .collect::<Vec<_>>();
+        let mut col_data = self.materialise_rows(&columns, row_ids).into_iter();
+
+        let data = schema
+            .select_columns
+            .iter()
+            .map(|(column_type, logical_data_type)| {
+                if self.all_columns_by_name.contains_key(column_type.as_str()) {
+                    col_data.next().unwrap()
+                } else {
+                    Values::nulls(*logical_data_type, rows)
+                }
+            })
+            .collect();
+
         ReadFilterResult {
-            schema,
-            data: col_data,
+            schema: schema.clone(),
+            data,
+        }
+    }
+
+    /// As `read_filter`, but returns results with the select columns of the
+    /// provided schema as a record batch, or `None` if no rows satisfy the
+    /// predicate. As with `read_filter_rows_with_schema`, selected columns
+    /// that don't exist in the row group are materialised as NULL values.
+    ///
+    /// When all rows satisfy the predicate the fully materialised columns are
+    /// looked up in, and added to, the provided cache, where `index` is the
+    /// position of the `RowGroup` within its table.
+    pub fn read_filter_with_cache(
+        &self,
+        schema: &ResultSchema,
+        predicates: &Predicate,
+        index: usize,
+        cache: &mut DecodeCache,
+    ) -> Result<Option<RecordBatch>> {
+        let row_ids = match self.row_ids_from_predicates(predicates) {
+            RowIDsOption::All(_) => None,
+            row_ids => Some(row_ids),
+        };
+
+        if let Some(row_ids) = row_ids {
+            let result = self.read_filter_rows_with_schema(schema, &row_ids);
+            if result.is_empty() {
+                return Ok(None);
+            }
+            return RecordBatch::try_from(result).map(Some);
+        }
+
+        let mut arrays = Vec::with_capacity(schema.select_columns.len());
+        for (column_type, logical_data_type) in &schema.select_columns {
+            let name = column_type.as_str();
+            if !self.all_columns_by_name.contains_key(name) {
+                let nulls = Values::nulls(*logical_data_type, self.rows() as usize);
+                arrays.push(arrow::array::ArrayRef::from(nulls));
+                continue;
+            }
+
+            let (column_name, col) = self.column_name_and_column(name);
+            let arr = match cache.get(index, column_name) {
+                Some(arr) => arr,
+                None => {
+                    let arr = arrow::array::ArrayRef::from(col.all_values());
+                    cache.insert(index, column_name, Arc::clone(&arr));
+                    arr
+                }
+            };
+            arrays.push(arr);
         }
+
+        let schema = Schema::try_from(schema).context(SchemaError)?;
+        arrow::record_batch::RecordBatch::try_new(schema.into(), arrays)
+            .context(ArrowError)
+            .map(Some)
     }
 
-    fn materialise_rows(&self, names: &[ColumnName<'_>], row_ids: RowIDsOption) -> Vec<Values<'_>> {
+    fn materialise_rows(
+        &self,
+        names: &[ColumnName<'_>],
+        row_ids: &RowIDsOption,
+    ) -> Vec<Values<'_>> {
         let mut col_data = Vec::with_capacity(names.len());
         match row_ids {
             RowIDsOption::None(_) => col_data, // nothing to materialise
@@ -249,6 +510,194 @@ impl RowGroup {
         }
     }
 
+    /// Computes a single aggregate, without grouping, of the values in the
+    /// column from rows satisfying the predicate. Returns `None` if no rows
+    /// satisfy the predicate.
+    ///
+    /// Only `Count`, `Sum`, `Min` and `Max` aggregates are supported.
+    pub fn aggregate_column(
+        &self,
+        predicate: &Predicate,
+        column_name: ColumnName<'_>,
+        agg_type: AggregateType,
+    ) -> Option<AggregateResult<'_>> {
+        let row_ids = match self.row_ids_from_predicates(predicate) {
+            RowIDsOption::None(_) => return None,
+            RowIDsOption::Some(row_ids) => row_ids.to_vec(),
+            RowIDsOption::All(_) => (0..self.rows()).collect::<Vec<_>>(),
+        };
+
+        let col = self.column_by_name(column_name);
+        Some(match agg_type {
+            AggregateType::Count => AggregateResult::Count(col.count(&row_ids) as u64),
+            AggregateType::Sum => AggregateResult::Sum(col.sum(&row_ids)),
+            AggregateType::Min => AggregateResult::Min(col.min(&row_ids)),
+            AggregateType::Max => AggregateResult::Max(col.max(&row_ids)),
+            AggregateType::First | AggregateType::Last => {
+                unimplemented!("{} aggregate not yet supported", agg_type)
+            }
+        })
+    }
+
+    /// Determines if any rows in the `RowGroup` satisfy the predicate.
+    pub fn satisfies_predicate(&self, predicate: &Predicate) -> bool {
+        !matches!(
+            self.row_ids_from_predicates(predicate),
+            RowIDsOption::None(_)
+        )
+    }
+
+    /// Returns an estimate of the number of rows in the `RowGroup` that satisfy
+    /// the predicate, for use when planning queries.
+    ///
+    /// The estimate is derived from column meta-data without evaluating the
+    /// predicate against the column data, so it is not exact. Numerical values
+    /// are assumed to be uniformly distributed between a column's minimum and
+    /// maximum values, each distinct value of a column is assumed to appear
+    /// equally often, and expressions are assumed to be independent.
+    pub fn estimated_matching_rows(&self, predicate: &Predicate) -> u64 {
+        let selectivity = predicate
+            .iter()
+            .map(|expr| self.estimated_selectivity(expr))
+            .product::<f64>();
+        (self.rows() as f64 * selectivity).round() as u64
+    }
+
+    /// Returns an estimate of the number of bytes needed to materialise the
+    /// columns at the rows satisfying the predicate, for use when deciding
+    /// whether to run a query.
+    ///
+    /// The number of matching rows is estimated as in
+    /// `estimated_matching_rows`. Each row is assumed to need the size of the
+    /// column's native type, or the size of the column's longest value and its
+    /// offset for string columns, along with a byte for its validity bitmap.
+    /// Columns that don't exist in the `RowGroup` are ignored.
+    pub fn estimated_select_size(&self, columns: &[ColumnName<'_>], predicate: &Predicate) -> u64 {
+        let row_size = columns
+            .iter()
+            .filter_map(|&name| {
+                let column_meta = self.meta.columns.get(name)?;
+                let value_size = match column_meta.logical_data_type {
+                    LogicalDataType::Integer
+                    | LogicalDataType::Unsigned
+                    | LogicalDataType::Float => 8,
+                    LogicalDataType::Boolean => 1,
+                    LogicalDataType::String => {
+                        let longest = match self.column_by_name(name).distinct_values() {
+                            Some(ValueSet::String(values)) => {
+                                values.into_iter().flatten().map(|v| v.len()).max()
+                            }
+                            _ => None,
+                        };
+                        size_of::<i32>() + longest.unwrap_or_default()
+                    }
+                    LogicalDataType::Binary => size_of::<i32>(),
+                };
+                Some(value_size as u64 + 1)
+            })
+            .sum::<u64>();
+        self.estimated_matching_rows(predicate) * row_size
+    }
+
+    // An estimate of the fraction of rows in the `RowGroup` that satisfy the
+    // expression.
+    fn estimated_selectivity(&self, expr: &BinaryExpr) -> f64 {
+        let column_meta = match self.meta.columns.get(expr.column()) {
+            Some(column_meta) => column_meta,
+            None => return 0.0, // no rows can satisfy an expression on a missing column
+        };
+        if self.rows() == 0 || !self.meta.column_could_satisfy_binary_expr(expr) {
+            return 0.0;
+        }
+
+        let rows = self.rows() as f64;
+        let non_null_rows = rows - column_meta.null_count as f64;
+        if non_null_rows == 0.0 {
+            return 0.0;
+        }
+
+        // Assume each distinct value appears equally often.
+        let distinct = match column_meta.logical_data_type {
+            LogicalDataType::String => self
+                .column_by_name(expr.column())
+                .distinct_values()
+                .map_or(0, |values| values.len()) as f64,
+            // integer columns can't have more distinct values than the span of
+            // their range.
+            LogicalDataType::Integer | LogicalDataType::Unsigned => {
+                match scalar_range_as_f64(&column_meta.range) {
+                    Some((min, max)) => (max - min + 1.0).min(non_null_rows),
+                    None => non_null_rows,
+                }
+            }
+            _ => non_null_rows,
+        };
+        let equal = non_null_rows / distinct.max(1.0) / rows;
+
+        let fraction_in_range = match (
+            scalar_range_as_f64(&column_meta.range),
+            literal_as_f64(expr.literal()),
+        ) {
+            // Assume values are uniformly distributed between min and max.
+            (Some((min, max)), Some(v)) if max > min => match expr.op() {
+                Operator::LT | Operator::LTE => (v - min) / (max - min),
+                Operator::GT | Operator::GTE => (max - v) / (max - min),
+                Operator::Equal | Operator::NotEqual => 1.0,
+            },
+            (Some(_), Some(_)) => 1.0, // all values are the same
+            // there is no sensible distribution to assume for other types.
+            _ => 0.5,
+        };
+
+        match expr.op() {
+            Operator::Equal => equal,
+            Operator::NotEqual => non_null_rows / rows - equal,
+            Operator::LT | Operator::LTE | Operator::GT | Operator::GTE => {
+                non_null_rows / rows * fraction_in_range.max(0.0).min(1.0)
+            }
+        }
+    }
+
+    /// Adds the distinct set of non-null values for each of the provided tag
+    /// columns to `dst`, where each value lives in a row satisfying the
+    /// predicate.
+    ///
+    /// Columns in `tag_keys` that are not tag columns in the `RowGroup` are
+    /// ignored. If all rows satisfy the predicate then the distinct values are
+    /// taken directly from each column's dictionary.
+    pub fn tag_values<'a>(
+        &'a self,
+        predicate: &Predicate,
+        tag_keys: &[ColumnName<'_>],
+        dst: &mut BTreeMap<ColumnName<'a>, BTreeSet<&'a str>>,
+    ) {
+        let row_ids = match self.row_ids_from_predicates(predicate) {
+            RowIDsOption::None(_) => return, // no matching rows
+            RowIDsOption::Some(row_ids) => Some(row_ids.to_vec()),
+            RowIDsOption::All(_) => None,
+        };
+
+        for &name in tag_keys {
+            match self.meta.columns.get(name) {
+                Some(ColumnMeta {
+                    typ: schema::ColumnType::Tag(_),
+                    ..
+                }) => {}
+                _ => continue, // not a tag column in this row group
+            }
+
+            let (column_name, col) = self.column_name_and_column(name);
+            let values = dst.entry(column_name).or_default();
+            let set = match &row_ids {
+                Some(row_ids) => Some(col.distinct_values_at(row_ids)),
+                None => col.distinct_values(),
+            };
+            if let Some(ValueSet::String(set)) = set {
+                values.extend(set.into_iter().flatten().map(|v| v.as_str()));
+            }
+        }
+    }
+
     // Determines the set of row ids that satisfy the provided predicate.
     fn row_ids_from_predicates(&self, predicate: &Predicate) -> RowIDsOption {
         // TODO(edd): perf - potentially pool this so we can re-use it once rows
@@ -297,15 +746,26 @@ impl RowGroup {
         }
 
         for expr in predicate.iter() {
-            // N.B column should always exist because validation of predicates
-            // should happen at the `Table` level.
-            let (col_name, col) = self.column_name_and_column(expr.column());
+            // A column that doesn't exist in the row group, e.g., because it
+            // was added to the table after the row group was created, is
+            // treated as containing only NULL values, which never satisfy the
+            // expression.
+            let col = match self.all_columns_by_name.get(expr.column()) {
+                Some(&i) => &self.columns[i],
+                None => return RowIDsOption::None(dst),
+            };
 
             // Explanation of how this buffer pattern works. The idea is that
             // the buffer should be returned to the caller so it can be re-used
             // on other columns. Each call to `row_ids_filter` returns the
             // buffer back enabling it to be re-used.
-            match col.row_ids_filter(&expr.op, &expr.literal_as_value(), dst) {
+            let row_ids = if expr.is_case_insensitive() {
+                col.row_ids_filter_case_insensitive(&expr.op, &expr.literal_as_value(), dst)
+            } else {
+                col.row_ids_filter(&expr.op, &expr.literal_as_value(), dst)
+            };
+
+            match row_ids {
                 // No rows will be returned for the `RowGroup` because this
                 // column does not match any rows.
                 RowIDsOption::None(_dst) => return RowIDsOption::None(_dst),
@@ -338,8 +798,25 @@ impl RowGroup {
 
     // An optimised function for applying two comparison predicates to a time
     // column at once.
-    fn row_ids_from_time_range(&self, time_range: &[BinaryExpr], dst: RowIDs) -> RowIDsOption {
+    //
+    // If the time column is sorted then the matching rows are contiguous and
+    // can be located via binary search rather than scanning the column.
+    fn row_ids_from_time_range(&self, time_range: &[BinaryExpr], mut dst: RowIDs) -> RowIDsOption {
         assert_eq!(time_range.len(), 2);
+        if self.time_sorted {
+            if let Some((from, to)) = self.sorted_time_range_bounds(time_range) {
+                if from >= to {
+                    return RowIDsOption::None(dst);
+                } else if from == 0 && to == self.rows() {
+                    return RowIDsOption::All(dst);
+                }
+
+                dst.clear();
+                dst.add_range(from, to);
+                return RowIDsOption::Some(dst);
+            }
+        }
+
         self.time_column().row_ids_filter_range(
             &(time_range[0].op, time_range[0].literal_as_value()), // min time
             &(time_range[1].op, time_range[1].literal_as_value()), // max time
@@ -347,6 +824,44 @@ impl RowGroup {
         )
     }
 
+    // Returns the half-open range `[from, to)` of row ids that satisfy all the
+    // provided expressions on the time column, which must be sorted. Returns
+    // `None` if any of the expressions can't be evaluated as a range.
+    fn sorted_time_range_bounds(&self, exprs: &[BinaryExpr]) -> Option<(u32, u32)> {
+        let (mut from, mut to) = (0, self.rows());
+        for expr in exprs {
+            let v = match expr.literal_as_value() {
+                Value::Scalar(Scalar::I64(v)) => v,
+                _ => return None,
+            };
+
+            match expr.op {
+                Operator::GT => from = from.max(self.time_partition_point(|t| t <= v)),
+                Operator::GTE => from = from.max(self.time_partition_point(|t| t < v)),
+                Operator::LT => to = to.min(self.time_partition_point(|t| t < v)),
+                Operator::LTE => to = to.min(self.time_partition_point(|t| t <= v)),
+                Operator::Equal | Operator::NotEqual => return None,
+            }
+        }
+        Some((from, to))
+    }
+
+    // Returns the first row id in the sorted time column for which `pred` is
+    // false, or the number of rows if `pred` holds for all rows.
+    fn time_partition_point(&self, pred: impl Fn(i64) -> bool) -> u32 {
+        let col = self.time_column();
+        let (mut low, mut high) = (0, self.rows());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match col.value(mid) {
+                Value::Scalar(Scalar::I64(t)) if pred(t) => low = mid + 1,
+                Value::Scalar(Scalar::I64(_)) => high = mid,
+                v => unreachable!("unexpected time value {:?}", v),
+            }
+        }
+        low
+    }
+
     /// Materialises a collection of data in group columns and aggregate
     /// columns, optionally filtered by the provided predicate.
     ///
@@ -354,6 +869,8 @@ impl RowGroup {
     /// key", and each value in the same row for the aggregate columns contains
     /// aggregate values for those group keys.
     ///
+    /// NaN values in float columns are skipped when aggregating.
+    ///
     /// Note: `read_aggregate` currently only supports "tag" columns.
     /// Note: `read_aggregate` does not order results.
     pub fn read_aggregate(
@@ -361,6 +878,36 @@ impl RowGroup {
         predicate: &Predicate,
         group_columns: &[ColumnName<'_>],
         aggregates: &[(ColumnName<'_>, AggregateType)],
+    ) -> ReadAggregateResult<'_> {
+        self.read_aggregate_with_nan_handling(
+            predicate,
+            group_columns,
+            aggregates,
+            NanHandling::default(),
+        )
+    }
+
+    /// As `read_aggregate` but NaN values in float columns are treated
+    /// according to `nan_handling`.
+    pub fn read_aggregate_with_nan_handling(
+        &self,
+        predicate: &Predicate,
+        group_columns: &[ColumnName<'_>],
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+        nan_handling: NanHandling,
+    ) -> ReadAggregateResult<'_> {
+        let row_ids = self.row_ids_from_predicates(predicate);
+        self.read_aggregate_rows(&row_ids, group_columns, aggregates, nan_handling)
+    }
+
+    /// As `read_aggregate_with_nan_handling`, but for the rows identified by
+    /// `row_ids`, which should be the result of `matching_rows`.
+    pub fn read_aggregate_rows(
+        &self,
+        row_ids: &RowIDsOption,
+        group_columns: &[ColumnName<'_>],
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+        nan_handling: NanHandling,
     ) -> ReadAggregateResult<'_> {
         let schema = ResultSchema {
             select_columns: vec![],
@@ -373,22 +920,27 @@ impl RowGroup {
             ..ReadAggregateResult::default()
         };
 
-        // Handle case where there are no predicates and all the columns being
-        // grouped support constant-time expression of the row_ids belonging to
-        // each grouped value.
+        // Without group columns all matching rows form a single group.
+        if group_columns.is_empty() {
+            self.read_aggregate_no_group(&mut result, row_ids, nan_handling);
+            return result;
+        }
+
+        // Handle case where all rows match and all the columns being grouped
+        // support constant-time expression of the row_ids belonging to each
+        // grouped value.
         let all_group_cols_pre_computed = result.schema.group_column_names_iter().all(|name| {
             self.column_by_name(name)
                 .properties()
                 .has_pre_computed_row_ids
         });
-        if predicate.is_empty() && all_group_cols_pre_computed {
-            self.read_group_all_rows_all_rle(&mut result);
+        if matches!(row_ids, RowIDsOption::All(_)) && all_group_cols_pre_computed {
+            self.read_group_all_rows_all_rle(&mut result, nan_handling);
             return result;
         }
 
-        // There are predicates. The next stage is apply them and determine the
-        // intermediate set of row ids.
-        let row_ids = self.row_ids_from_predicates(predicate);
+        // Only some rows match, so the group and aggregate columns are
+        // materialised for the intermediate set of row ids.
         let filter_row_ids = match row_ids {
             RowIDsOption::None(_) => {
                 return result;
@@ -456,15 +1008,178 @@ impl RowGroup {
                 &mut result,
                 &groupby_encoded_ids[0],
                 aggregate_columns_data,
+                nan_handling,
             );
             return result;
         }
 
         // Perform the group by using a hashmap
-        self.read_group_with_hashing(&mut result, &groupby_encoded_ids, aggregate_columns_data);
+        self.read_group_with_hashing(
+            &mut result,
+            &groupby_encoded_ids,
+            aggregate_columns_data,
+            nan_handling,
+        );
+        result
+    }
+
+    /// Returns aggregates segmented by the group columns and windowed by time.
+    ///
+    /// Each row satisfying the predicate is assigned to the window starting at
+    /// `window_start(t, window)` for its timestamp `t`. The time column is
+    /// appended to the group columns of the result and holds the start of each
+    /// window.
+    ///
+    /// Results are ordered by group key and then by window start, both
+    /// ascending, so each series is emitted as a contiguous run of windows.
+    ///
+    /// When there are no predicates or group columns and only `Count` and
+    /// `Sum` aggregates are requested, windows are computed in a single pass
+    /// over the time and aggregate columns rather than by building group keys.
+    ///
+    /// `window` must be positive.
+    pub fn read_window_aggregate(
+        &self,
+        predicate: &Predicate,
+        group_columns: &[ColumnName<'_>],
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+        window: i64,
+    ) -> ReadAggregateResult<'_> {
+        let mut group_and_time_columns = group_columns.to_vec();
+        group_and_time_columns.push(TIME_COLUMN_NAME);
+
+        let mut result = ReadAggregateResult {
+            schema: ResultSchema {
+                select_columns: vec![],
+                group_columns: self.meta.schema_for_column_names(&group_and_time_columns),
+                aggregate_columns: self.meta.schema_for_aggregate_column_names(aggregates),
+            },
+            ..ReadAggregateResult::default()
+        };
+
+        if predicate.is_empty()
+            && group_columns.is_empty()
+            && aggregates
+                .iter()
+                .all(|(_, agg_type)| matches!(agg_type, AggregateType::Count | AggregateType::Sum))
+        {
+            self.read_window_aggregate_ordered(&mut result, aggregates, window);
+            return result;
+        }
+
+        let row_ids = match self.row_ids_from_predicates(predicate) {
+            RowIDsOption::None(_) => return result, // no matching rows
+            RowIDsOption::Some(row_ids) => row_ids.to_vec(),
+            RowIDsOption::All(_) => (0..self.rows()).collect::<Vec<_>>(),
+        };
+
+        let group_columns_data = group_columns
+            .iter()
+            .map(|&name| self.column_by_name(name).values(&row_ids))
+            .collect::<Vec<_>>();
+        let aggregate_columns_data = aggregates
+            .iter()
+            .map(|(name, _)| self.column_by_name(name).values(&row_ids))
+            .collect::<Vec<_>>();
+        let times = match self.time_column().values(&row_ids) {
+            Values::I64(times) => times,
+            _ => unreachable!("time column must contain non-null i64 values"),
+        };
+
+        // Group keys are kept sorted so that the windows of each group are
+        // emitted in ascending order of their start time.
+        let mut groups: BTreeMap<GroupKey<'_>, Vec<AggregateResult<'_>>> = BTreeMap::new();
+        for (row, &t) in times.iter().enumerate() {
+            let mut group_key = group_columns_data
+                .iter()
+                .map(|values| values.value(row))
+                .collect::<Vec<_>>();
+            group_key.push(Value::Scalar(Scalar::I64(window_start(t, window))));
+
+            let group_aggs = groups.entry(GroupKey(group_key)).or_insert_with(|| {
+                aggregates
+                    .iter()
+                    .map(|(_, agg_type)| AggregateResult::from(agg_type))
+                    .collect()
+            });
+            // Rows are visited in ascending row id order, so `First` and
+            // `Last` ties within the window are won by the earliest row.
+            for (agg, values) in group_aggs.iter_mut().zip(&aggregate_columns_data) {
+                agg.update_at(t, values.value(row));
+            }
+        }
+
+        for (group_key, aggs) in groups {
+            result.group_keys.push(group_key);
+            result.aggregates.push(AggregateResults(aggs));
+        }
+        result.group_keys_sorted = true;
         result
     }
 
+    // An optimised implementation of `read_window_aggregate` for when all rows
+    // are aggregated into a single series of windows using only `Count` and
+    // `Sum` aggregates.
+    //
+    // Rows are bucketed into windows on the fly in a single pass over the time
+    // and aggregate columns. Because rows are typically ordered by time, runs
+    // of consecutive rows usually fall into the same window and only require a
+    // single lookup of that window's aggregates.
+    fn read_window_aggregate_ordered<'a>(
+        &'a self,
+        dst: &mut ReadAggregateResult<'a>,
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+        window: i64,
+    ) {
+        let aggregate_columns_data = aggregates
+            .iter()
+            .map(|(name, _)| self.column_by_name(name).all_values())
+            .collect::<Vec<_>>();
+        let times = match self.time_column().all_values() {
+            Values::I64(times) => times,
+            _ => unreachable!("time column must contain non-null i64 values"),
+        };
+
+        let new_aggregates = || {
+            aggregates
+                .iter()
+                .map(|(_, agg_type)| AggregateResult::from(agg_type))
+                .collect::<Vec<_>>()
+        };
+
+        let mut windows: BTreeMap<i64, Vec<AggregateResult<'_>>> = BTreeMap::new();
+        let mut current: Option<(i64, Vec<AggregateResult<'_>>)> = None;
+        for (row, &t) in times.iter().enumerate() {
+            let start = window_start(t, window);
+            let (_, aggs) = match current.take() {
+                Some((current_start, aggs)) if current_start == start => {
+                    current.get_or_insert((start, aggs))
+                }
+                other => {
+                    if let Some((prev_start, prev_aggs)) = other {
+                        windows.insert(prev_start, prev_aggs);
+                    }
+                    let aggs = windows.remove(&start).unwrap_or_else(new_aggregates);
+                    current.get_or_insert((start, aggs))
+                }
+            };
+
+            for (agg, values) in aggs.iter_mut().zip(&aggregate_columns_data) {
+                agg.update(values.value(row));
+            }
+        }
+        if let Some((start, aggs)) = current {
+            windows.insert(start, aggs);
+        }
+
+        for (start, aggs) in windows {
+            dst.group_keys
+                .push(GroupKey(vec![Value::Scalar(Scalar::I64(start))]));
+            dst.aggregates.push(AggregateResults(aggs));
+        }
+        dst.group_keys_sorted = true;
+    }
+
     // read_group_hash executes a read-group-aggregate operation on the
     // `RowGroup` using a hashmap to build up a collection of group keys and
     // aggregates.
@@ -475,16 +1190,27 @@ impl RowGroup {
         dst: &mut ReadAggregateResult<'a>,
         groupby_encoded_ids: &[Vec<u32>],
         aggregate_columns_data: Vec<Values<'a>>,
+        nan_handling: NanHandling,
     ) {
         // An optimised approach to building the hashmap of group keys using a
         // single 128-bit integer as the group key. If grouping is on more than
         // four columns then a fallback to using an vector as a key will happen.
         if dst.schema.group_columns.len() <= 4 {
-            self.read_group_hash_with_u128_key(dst, &groupby_encoded_ids, &aggregate_columns_data);
+            self.read_group_hash_with_u128_key(
+                dst,
+                &groupby_encoded_ids,
+                &aggregate_columns_data,
+                nan_handling,
+            );
             return;
         }
 
-        self.read_group_hash_with_vec_key(dst, &groupby_encoded_ids, &aggregate_columns_data);
+        self.read_group_hash_with_vec_key(
+            dst,
+            &groupby_encoded_ids,
+            &aggregate_columns_data,
+            nan_handling,
+        );
     }
 
     // This function is used with `read_group_hash` when the number of columns
@@ -495,6 +1221,7 @@ impl RowGroup {
         dst: &mut ReadAggregateResult<'a>,
         groupby_encoded_ids: &[Vec<u32>],
         aggregate_columns_data: &[Values<'a>],
+        nan_handling: NanHandling,
     ) {
         // Now begin building the group keys.
         let mut groups: HashMap<Vec<u32>, Vec<AggregateResult<'_>>> = HashMap::default();
@@ -516,7 +1243,8 @@ impl RowGroup {
                 // them
                 hash_map::RawEntryMut::Occupied(mut entry) => {
                     for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        entry.get_mut()[i].update(values.value(row));
+                        entry.get_mut()[i]
+                            .update_with_nan_handling(values.value(row), nan_handling);
                     }
                 }
                 // group key does not exist, so create it.
@@ -527,7 +1255,7 @@ impl RowGroup {
                     }
 
                     for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        group_key_aggs[i].update(values.value(row));
+                        group_key_aggs[i].update_with_nan_handling(values.value(row), nan_handling);
                     }
 
                     entry.insert(key_buf.clone(), group_key_aggs);
@@ -573,6 +1301,7 @@ impl RowGroup {
         dst: &mut ReadAggregateResult<'a>,
         groupby_encoded_ids: &[Vec<u32>],
         aggregate_columns_data: &[Values<'a>],
+        nan_handling: NanHandling,
     ) {
         let total_rows = groupby_encoded_ids[0].len();
         assert!(groupby_encoded_ids.iter().all(|x| x.len() == total_rows));
@@ -594,7 +1323,8 @@ impl RowGroup {
                 // them
                 hash_map::RawEntryMut::Occupied(mut entry) => {
                     for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        entry.get_mut()[i].update(values.value(row));
+                        entry.get_mut()[i]
+                            .update_with_nan_handling(values.value(row), nan_handling);
                     }
                 }
                 // group key does not exist, so create it.
@@ -605,7 +1335,7 @@ impl RowGroup {
                     }
 
                     for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        group_key_aggs[i].update(values.value(row));
+                        group_key_aggs[i].update_with_nan_handling(values.value(row), nan_handling);
                     }
 
                     entry.insert(group_key_packed, group_key_aggs);
@@ -647,7 +1377,11 @@ impl RowGroup {
     //
     // In this case all the grouping columns pre-computed bitsets for each
     // distinct value.
-    fn read_group_all_rows_all_rle<'a>(&'a self, dst: &mut ReadAggregateResult<'a>) {
+    fn read_group_all_rows_all_rle<'a>(
+        &'a self,
+        dst: &mut ReadAggregateResult<'a>,
+        nan_handling: NanHandling,
+    ) {
         let group_columns = dst
             .schema
             .group_column_names_iter()
@@ -737,6 +1471,23 @@ impl RowGroup {
 
             let mut aggregates = Vec::with_capacity(aggregate_columns_typ.len());
             for (agg_col, typ) in &aggregate_columns_typ {
+                // Column aggregates skip NaN values, so when NaN values should
+                // propagate the aggregate is NaN if any exist for the group.
+                if let NanHandling::Propagate = nan_handling {
+                    let nan = Value::Scalar(Scalar::F64(f64::NAN));
+                    match typ {
+                        AggregateType::Min | AggregateType::Max | AggregateType::Sum
+                            if agg_col.contains_nan(&aggregate_row_ids.to_vec()) =>
+                        {
+                            let mut agg = AggregateResult::from(typ);
+                            agg.update_with_nan_handling(nan, nan_handling);
+                            aggregates.push(agg);
+                            continue;
+                        }
+                        _ => {}
+                    }
+                }
+
                 aggregates.push(match typ {
                     AggregateType::Count => {
                         AggregateResult::Count(agg_col.count(&aggregate_row_ids.to_vec()) as u64)
@@ -768,6 +1519,7 @@ impl RowGroup {
         dst: &mut ReadAggregateResult<'a>,
         groupby_encoded_ids: &[u32],
         aggregate_columns_data: Vec<Values<'a>>,
+        nan_handling: NanHandling,
     ) {
         assert_eq!(dst.schema().group_columns.len(), 1);
         let column = self.column_by_name(dst.schema.group_column_names_iter().next().unwrap());
@@ -786,7 +1538,7 @@ impl RowGroup {
                 Some(group_key_aggs) => {
                     // Update all aggregates for the group key
                     for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        group_key_aggs[i].update(values.value(row));
+                        group_key_aggs[i].update_with_nan_handling(values.value(row), nan_handling);
                     }
                 }
                 None => {
@@ -798,7 +1550,7 @@ impl RowGroup {
                         .collect::<Vec<_>>();
 
                     for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        group_key_aggs[i].update(values.value(row));
+                        group_key_aggs[i].update_with_nan_handling(values.value(row), nan_handling);
                     }
 
                     groups[idx] = Some(group_key_aggs);
@@ -822,6 +1574,39 @@ impl RowGroup {
         dst.aggregates = aggregate_vec;
     }
 
+    // Aggregates all rows satisfying the predicate into a single row with an
+    // empty group key. There are no results if no rows satisfy the predicate.
+    fn read_aggregate_no_group<'a>(
+        &'a self,
+        dst: &mut ReadAggregateResult<'a>,
+        row_ids: &RowIDsOption,
+        nan_handling: NanHandling,
+    ) {
+        let filter_row_ids = match row_ids {
+            RowIDsOption::None(_) => return, // no matching rows
+            RowIDsOption::Some(row_ids) => Some(row_ids.to_vec()),
+            RowIDsOption::All(_) => None,
+        };
+
+        let mut aggregates = Vec::with_capacity(dst.schema.aggregate_columns.len());
+        for (col_type, agg_type, _) in &dst.schema.aggregate_columns {
+            let col = self.column_by_name(col_type.as_str());
+            let values = match &filter_row_ids {
+                Some(row_ids) => col.values(row_ids),
+                None => col.all_values(),
+            };
+
+            let mut aggregate = AggregateResult::from(agg_type);
+            for row in 0..values.len() {
+                aggregate.update_with_nan_handling(values.value(row), nan_handling);
+            }
+            aggregates.push(aggregate);
+        }
+
+        dst.add_row(vec![], aggregates);
+        dst.group_keys_sorted = true;
+    }
+
     // Optimised `read_group` method for cases where the columns being grouped
     // are already totally ordered in the `RowGroup`.
     //
@@ -845,6 +1630,24 @@ impl RowGroup {
 /// column.
 impl From<RecordBatch> for RowGroup {
     fn from(rb: RecordBatch) -> Self {
+        Self::from_record_batch(rb, &BTreeMap::new(), &EncodingStrategy::default())
+    }
+}
+
+impl RowGroup {
+    /// Initialise a `RowGroup` from an Arrow RecordBatch, as with the `From`
+    /// implementation.
+    ///
+    /// `integer_null_sentinels` maps integer field column names to a sentinel
+    /// value that represents a missing value in that column. Values equal to
+    /// the sentinel are stored as NULL.
+    ///
+    /// `strategy` determines how each column is encoded.
+    pub fn from_record_batch(
+        rb: RecordBatch,
+        integer_null_sentinels: &BTreeMap<String, i64>,
+        strategy: &EncodingStrategy,
+    ) -> Self {
         let rows = rb.num_rows();
         // TODO proper error handling here if the input schema is bad
         let schema: Schema = rb
@@ -865,18 +1668,25 @@ impl From<RecordBatch> for RowGroup {
                         .downcast_ref::<arrow::array::StringArray>()
                         .unwrap();
 
-                    let column_data = Column::from(arr);
+                    let column_data = strategy.tag_column(col_name, arr);
 
                     columns.insert(col_name.to_owned(), ColumnType::Tag(column_data));
                 }
                 Some(InfluxColumnType::Field(_)) => {
                     let column_data = match arrow_column.data_type() {
-                        arrow::datatypes::DataType::Int64 => Column::from(
-                            arrow_column
+                        arrow::datatypes::DataType::Int64 => {
+                            let arr = arrow_column
                                 .as_any()
                                 .downcast_ref::<arrow::array::Int64Array>()
-                                .unwrap(),
-                        ),
+                                .unwrap();
+
+                            match integer_null_sentinels.get(col_name) {
+                                Some(&sentinel) => {
+                                    Column::from(null_sentinel_values(arr, sentinel))
+                                }
+                                None => strategy.field_column(col_name, arr),
+                            }
+                        }
                         arrow::datatypes::DataType::Float64 => Column::from(
                             arrow_column
                                 .as_any()
@@ -889,6 +1699,14 @@ impl From<RecordBatch> for RowGroup {
                                 .downcast_ref::<arrow::array::UInt64Array>()
                                 .unwrap(),
                         ),
+                        // String fields are only created from tag columns over
+                        // the tag cardinality limit.
+                        arrow::datatypes::DataType::Utf8 => Column::from(
+                            arrow_column
+                                .as_any()
+                                .downcast_ref::<arrow::array::StringArray>()
+                                .unwrap(),
+                        ),
                         dt => unimplemented!(
                             "data type {:?} currently not supported for field columns",
                             dt
@@ -900,15 +1718,30 @@ impl From<RecordBatch> for RowGroup {
                 Some(InfluxColumnType::Timestamp) => {
                     assert_eq!(col_name, TIME_COLUMN_NAME);
 
-                    let column_data = Column::from(match arrow_column.data_type() {
-                        arrow::datatypes::DataType::Int64 => arrow_column
+                    let column_data = strategy.time_column(
+                        col_name,
+                        match arrow_column.data_type() {
+                            arrow::datatypes::DataType::Int64 => arrow_column
+                                .as_any()
+                                .downcast_ref::<arrow::array::Int64Array>()
+                                .unwrap(),
+                            dt => panic!("{:?} column with {:?} must have type i64", col_name, dt),
+                        },
+                    );
+
+                    columns.insert(col_name.to_owned(), ColumnType::Time(column_data));
+                }
+                // Binary columns have no InfluxDB field type, so they are
+                // stored as fields of arbitrary bytes.
+                None if arrow_column.data_type() == &arrow::datatypes::DataType::Binary => {
+                    let column_data = Column::from(
+                        arrow_column
                             .as_any()
-                            .downcast_ref::<arrow::array::Int64Array>()
+                            .downcast_ref::<arrow::array::BinaryArray>()
                             .unwrap(),
-                        dt => panic!("{:?} column with {:?} must have type i64", col_name, dt),
-                    });
+                    );
 
-                    columns.insert(col_name.to_owned(), ColumnType::Time(column_data));
+                    columns.insert(col_name.to_owned(), ColumnType::Field(column_data));
                 }
                 _ => panic!("unknown column type"),
             }
@@ -918,6 +1751,150 @@ impl From<RecordBatch> for RowGroup {
     }
 }
 
+// Returns the range of a numerical column as `f64` values, or `None` if the
+// column isn't numerical.
+fn scalar_range_as_f64(range: &(OwnedValue, OwnedValue)) -> Option<(f64, f64)> {
+    fn as_f64(v: &OwnedValue) -> Option<f64> {
+        match v {
+            OwnedValue::Scalar(Scalar::I64(v)) => Some(*v as f64),
+            OwnedValue::Scalar(Scalar::U64(v)) => Some(*v as f64),
+            OwnedValue::Scalar(Scalar::F64(v)) => Some(*v),
+            _ => None,
+        }
+    }
+
+    Some((as_f64(&range.0)?, as_f64(&range.1)?))
+}
+
+// Determines if the aggregate satisfies the expression. Numerical aggregates
+// are compared with numerical literals and string aggregates with string
+// literals, and all other comparisons are unsatisfied.
+fn aggregate_satisfies_expr(result: &AggregateResult<'_>, expr: &BinaryExpr) -> bool {
+    let value = match result {
+        AggregateResult::Count(v) => Value::Scalar(Scalar::U64(*v)),
+        AggregateResult::Sum(v) => Value::Scalar(*v),
+        AggregateResult::Min(v)
+        | AggregateResult::Max(v)
+        | AggregateResult::First(Some((_, v)))
+        | AggregateResult::Last(Some((_, v))) => *v,
+        AggregateResult::First(None) | AggregateResult::Last(None) => Value::Null,
+    };
+
+    let ordering = match (value, expr.literal()) {
+        (Value::String(v), Literal::String(literal)) => Some(v.cmp(literal.as_str())),
+        (Value::Scalar(scalar), literal) => {
+            let v = match scalar {
+                Scalar::I64(v) => Number::Int(i128::from(v)),
+                Scalar::U64(v) => Number::Int(i128::from(v)),
+                Scalar::F64(v) => Number::Float(v),
+                Scalar::Null => return false,
+            };
+            let literal = match literal {
+                Literal::Integer(v) => Number::Int(i128::from(*v)),
+                Literal::Unsigned(v) => Number::Int(i128::from(*v)),
+                Literal::Float(v) => Number::Float(*v),
+                Literal::String(_) | Literal::Boolean(_) => return false,
+            };
+            v.partial_cmp(&literal)
+        }
+        _ => None,
+    };
+
+    match ordering {
+        Some(ordering) => match expr.op() {
+            Operator::Equal => ordering == Ordering::Equal,
+            Operator::NotEqual => ordering != Ordering::Equal,
+            Operator::GT => ordering == Ordering::Greater,
+            Operator::GTE => ordering != Ordering::Less,
+            Operator::LT => ordering == Ordering::Less,
+            Operator::LTE => ordering != Ordering::Greater,
+        },
+        None => false,
+    }
+}
+
+// A numerical value, which can be compared with other numerical values
+// without losing precision: integers are held exactly, rather than as floats.
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Int(a), Self::Int(b)) => Some(a.cmp(b)),
+            (Self::Float(a), Self::Float(b)) => a.partial_cmp(b),
+            (Self::Int(a), Self::Float(b)) => cmp_int_float(*a, *b),
+            (Self::Float(a), Self::Int(b)) => cmp_int_float(*b, *a).map(Ordering::reverse),
+        }
+    }
+}
+
+// Compares an integer with a float exactly. Converting the integer to the
+// nearest float preserves its order relative to any other float, so the
+// integers only need comparing when the converted integer equals the float,
+// which is then a whole number that converts to an `i128` exactly.
+fn cmp_int_float(a: i128, b: f64) -> Option<Ordering> {
+    match (a as f64).partial_cmp(&b)? {
+        Ordering::Equal => Some(a.cmp(&(b as i128))),
+        ordering => Some(ordering),
+    }
+}
+
+// Returns a numerical literal as an `f64` value.
+fn literal_as_f64(literal: &Literal) -> Option<f64> {
+    match literal {
+        Literal::Integer(v) => Some(*v as f64),
+        Literal::Unsigned(v) => Some(*v as f64),
+        Literal::Float(v) => Some(*v),
+        Literal::String(_) | Literal::Boolean(_) => None,
+    }
+}
+
+// Feeds the values into `state`. Floats are hashed by their bit patterns.
+fn hash_values<H: Hasher>(values: &Values<'_>, state: &mut H) {
+    match values {
+        Values::String(values) => values.hash(state),
+        Values::I64(values) => values.hash(state),
+        Values::U64(values) => values.hash(state),
+        Values::F64(values) => {
+            for v in values {
+                v.to_bits().hash(state);
+            }
+        }
+        Values::I64N(values) => values.hash(state),
+        Values::U64N(values) => values.hash(state),
+        Values::F64N(values) => {
+            for v in values {
+                v.map(f64::to_bits).hash(state);
+            }
+        }
+        Values::Bool(values) => values.hash(state),
+        Values::ByteArray(values) => values.hash(state),
+    }
+}
+
+// Returns a copy of the array where all values equal to `sentinel` are NULL.
+fn null_sentinel_values(arr: &arrow::array::Int64Array, sentinel: i64) -> arrow::array::Int64Array {
+    use arrow::array::Array;
+
+    (0..arr.len())
+        .map(|i| match arr.is_null(i) {
+            false if arr.value(i) != sentinel => Some(arr.value(i)),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
 // Packs an encoded values into a `u128` at `pos`, which must be `[0,4)`.
 #[inline(always)]
 fn pack_u32_in_u128(packed_value: u128, encoded_id: u32, pos: usize) -> u128 {
@@ -952,11 +1929,19 @@ impl Predicate {
     /// expressions, which result in the `Predicate` expressing the following:
     ///
     /// time >= from AND time < to
+    ///
+    /// A `from` of `i64::MIN` or a `to` of `i64::MAX` is an unbounded end, and
+    /// adds no expression to the predicate. So `(i64::MIN, i64::MAX)` matches
+    /// all rows, including any with a timestamp of `i64::MAX`, and without
+    /// other expressions produces an empty predicate.
     pub fn with_time_range(exprs: &[BinaryExpr], from: i64, to: i64) -> Self {
-        let mut time_exprs = vec![
-            BinaryExpr::from((TIME_COLUMN_NAME, ">=", from)),
-            BinaryExpr::from((TIME_COLUMN_NAME, "<", to)),
-        ];
+        let mut time_exprs = vec![];
+        if from != i64::MIN {
+            time_exprs.push(BinaryExpr::from((TIME_COLUMN_NAME, ">=", from)));
+        }
+        if to != i64::MAX {
+            time_exprs.push(BinaryExpr::from((TIME_COLUMN_NAME, "<", to)));
+        }
 
         time_exprs.extend_from_slice(exprs);
         Self(time_exprs)
@@ -1001,6 +1986,52 @@ impl Predicate {
     }
 }
 
+/// Displays the conjunction of the predicate's expressions, e.g.,
+/// `time >= 100 AND time < 200 AND region = 'west'`. An empty predicate is
+/// displayed as an empty string.
+impl std::fmt::Display for Predicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, expr) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " AND ")?;
+            }
+            write!(f, "{}", expr)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describes which expressions of a `Predicate` were applied when reading
+/// data, and which were not and must be re-checked by the caller.
+///
+/// Expressions are only applied if the read buffer can evaluate them against
+/// the column they reference, e.g., a string literal compared with a numeric
+/// column cannot be evaluated. Results may contain rows that do not satisfy
+/// the `residual` expressions.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct PredicatePushdown {
+    pub applied: Vec<BinaryExpr>,
+    pub residual: Vec<BinaryExpr>,
+}
+
+impl PredicatePushdown {
+    /// Returns true if every expression in the predicate was applied.
+    pub fn is_complete(&self) -> bool {
+        self.residual.is_empty()
+    }
+
+    /// Combines the pushdown of the same predicate against different data.
+    /// An expression is only considered applied if it was applied by both.
+    pub fn merge(&mut self, other: &Self) {
+        for expr in &other.residual {
+            self.applied.retain(|e| e != expr);
+            if !self.residual.contains(expr) {
+                self.residual.push(expr.clone());
+            }
+        }
+    }
+}
+
 /// Supported literal values for expressions. These map to a sub-set of logical
 /// datatypes supported by the `ReadBuffer`.
 #[derive(Clone, Debug, PartialEq)]
@@ -1042,6 +2073,39 @@ impl<'a> TryFrom<&DFScalarValue> for Literal {
     }
 }
 
+/// Converts a value read from a column or record batch, allowing predicates to
+/// be built from query results.
+impl TryFrom<&Value<'_>> for Literal {
+    type Error = String;
+
+    fn try_from(value: &Value<'_>) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(v) => Ok(Self::String((*v).to_owned())),
+            Value::Boolean(v) => Ok(Self::Boolean(*v)),
+            Value::Scalar(Scalar::I64(v)) => Ok(Self::Integer(*v)),
+            Value::Scalar(Scalar::U64(v)) => Ok(Self::Unsigned(*v)),
+            Value::Scalar(Scalar::F64(v)) => Ok(Self::Float(*v)),
+            Value::Null | Value::Scalar(Scalar::Null) => {
+                Err("NULL literal not supported".to_owned())
+            }
+            Value::ByteArray(_) => Err("byte array literal not supported".to_owned()),
+        }
+    }
+}
+
+/// String literals are single-quoted, with any single quotes escaped.
+impl std::fmt::Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String(v) => write!(f, "'{}'", v.replace('\'', "''")),
+            Self::Integer(v) => write!(f, "{}", v),
+            Self::Unsigned(v) => write!(f, "{}", v),
+            Self::Float(v) => write!(f, "{}", v),
+            Self::Boolean(v) => write!(f, "{}", v),
+        }
+    }
+}
+
 /// An expression that contains a column name on the left side, an operator, and
 /// a literal value on the right side.
 #[derive(Clone, Debug, PartialEq)]
@@ -1049,6 +2113,7 @@ pub struct BinaryExpr {
     col: String,
     op: Operator,
     value: Literal,
+    case_insensitive: bool,
 }
 
 impl BinaryExpr {
@@ -1057,9 +2122,28 @@ impl BinaryExpr {
             col: column_name.into(),
             op,
             value,
+            case_insensitive: false,
         }
     }
 
+    /// Marks the expression as matching string values without regard to case.
+    ///
+    /// Case-insensitivity only applies to `=` and `!=` expressions on string
+    /// columns, and is ignored otherwise. Row groups can't be pruned using
+    /// column meta data for these expressions, and every distinct value in the
+    /// column must be case-folded when they are evaluated, so they are more
+    /// expensive than exact matches.
+    pub fn with_case_insensitive(mut self) -> Self {
+        self.case_insensitive = true;
+        self
+    }
+
+    /// Determines if the expression matches string values without regard to
+    /// case.
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
     pub fn column(&self) -> ColumnName<'_> {
         self.col.as_str()
     }
@@ -1072,6 +2156,19 @@ impl BinaryExpr {
         &self.value
     }
 
+    /// Determines if the expression's literal can be compared with values of
+    /// the provided logical type.
+    pub fn literal_compatible_with(&self, logical_data_type: LogicalDataType) -> bool {
+        match self.literal() {
+            Literal::String(_) => logical_data_type == LogicalDataType::String,
+            Literal::Integer(_) | Literal::Unsigned(_) | Literal::Float(_) => matches!(
+                logical_data_type,
+                LogicalDataType::Integer | LogicalDataType::Unsigned | LogicalDataType::Float
+            ),
+            Literal::Boolean(_) => false,
+        }
+    }
+
     fn literal_as_value(&self) -> Value<'_> {
         match self.literal() {
             Literal::String(v) => Value::String(v),
@@ -1083,6 +2180,17 @@ impl BinaryExpr {
     }
 }
 
+/// Displays the expression in a SQL-like form, e.g., `region = 'west'`.
+impl std::fmt::Display for BinaryExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.col, self.op, self.value)?;
+        if self.is_case_insensitive() {
+            write!(f, " (case insensitive)")?;
+        }
+        Ok(())
+    }
+}
+
 impl From<(&str, &str, &str)> for BinaryExpr {
     fn from(expr: (&str, &str, &str)) -> Self {
         Self::new(
@@ -1248,6 +2356,9 @@ pub struct ColumnMeta {
     pub typ: crate::schema::ColumnType,
     pub logical_data_type: LogicalDataType,
     pub range: (OwnedValue, OwnedValue),
+
+    // The number of NULL values in the column.
+    pub null_count: u64,
 }
 
 // column metadata is equivalent for two columns if their logical type and
@@ -1258,6 +2369,16 @@ impl PartialEq for ColumnMeta {
     }
 }
 
+// The logical data type of the results of aggregating a column. Counts are
+// unsigned whatever the column's type, and all other aggregates have the
+// column's logical data type.
+pub(crate) fn aggregate_data_type(meta: &ColumnMeta, agg_type: AggregateType) -> LogicalDataType {
+    match agg_type {
+        AggregateType::Count => LogicalDataType::Unsigned,
+        _ => meta.logical_data_type,
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct MetaData {
     // The total size of the table in bytes.
@@ -1289,8 +2410,19 @@ impl MetaData {
     //
     pub fn column_could_satisfy_binary_expr(&self, expr: &BinaryExpr) -> bool {
         let (column_min, column_max) = match self.columns.get(expr.column()) {
+            // Case-folded values don't map onto the raw min/max range of a
+            // string column, so it's not possible to rule the column out.
+            Some(schema)
+                if expr.is_case_insensitive()
+                    && schema.logical_data_type == LogicalDataType::String =>
+            {
+                return true
+            }
             Some(schema) => &schema.range,
-            None => return false, // column doesn't exist.
+
+            // The column doesn't exist, so all of its values are implicitly
+            // NULL, and NULL doesn't satisfy any comparison.
+            None => return false,
         };
 
         let (op, value) = (expr.op(), &expr.literal_as_value());
@@ -1327,6 +2459,7 @@ impl MetaData {
         col_type: schema::ColumnType,
         logical_data_type: LogicalDataType,
         range: (OwnedValue, OwnedValue),
+        null_count: u64,
     ) {
         self.columns.insert(
             name.to_owned(),
@@ -1334,6 +2467,7 @@ impl MetaData {
                 typ: col_type,
                 logical_data_type,
                 range,
+                null_count,
             },
         );
     }
@@ -1361,7 +2495,11 @@ impl MetaData {
             .iter()
             .map(|(name, agg_type)| {
                 let schema = self.columns.get(*name).unwrap();
-                (schema.typ.clone(), *agg_type, schema.logical_data_type)
+                (
+                    schema.typ.clone(),
+                    *agg_type,
+                    aggregate_data_type(schema, *agg_type),
+                )
             })
             .collect::<Vec<_>>()
     }
@@ -1392,6 +2530,17 @@ impl TryFrom<ReadFilterResult<'_>> for RecordBatch {
             .map_err(|source| Error::SchemaError { source })?;
         let arrow_schema: arrow_deps::arrow::datatypes::SchemaRef = schema.into();
 
+        // Catch columns whose values don't agree with the nullability of the
+        // schema here, rather than letting Arrow reject the record batch.
+        for (field, values) in arrow_schema.fields().iter().zip(&result.data) {
+            ensure!(
+                field.is_nullable() || !values.contains_null(),
+                ColumnNullabilityMismatch {
+                    column_name: field.name()
+                }
+            );
+        }
+
         let columns = result
             .data
             .into_iter()
@@ -1490,6 +2639,48 @@ impl<'row_group> ReadAggregateResult<'row_group> {
         self.group_keys.len()
     }
 
+    /// The group key and aggregates of each group in the results.
+    pub(crate) fn groups(
+        &self,
+    ) -> impl Iterator<Item = (&[Value<'row_group>], &[AggregateResult<'row_group>])> + '_ {
+        self.group_keys
+            .iter()
+            .map(|key| key.0.as_slice())
+            .zip(self.aggregates.iter().map(|aggs| aggs.0.as_slice()))
+    }
+
+    /// Removes the groups whose aggregates don't satisfy `having`, whose
+    /// expressions reference aggregate columns by their result column names,
+    /// e.g., `usage_sum`. Expressions on other columns are ignored, and a NULL
+    /// aggregate never satisfies an expression.
+    pub fn retain_having(&mut self, having: &Predicate) {
+        let exprs = having
+            .iter()
+            .filter_map(|expr| {
+                (0..self.schema.aggregate_columns.len())
+                    .find(|&i| self.schema.aggregate_result_column_name(i) == expr.column())
+                    .map(|i| (i, expr))
+            })
+            .collect::<Vec<_>>();
+        if exprs.is_empty() {
+            return;
+        }
+
+        let keep = self
+            .aggregates
+            .iter()
+            .map(|aggs| {
+                exprs
+                    .iter()
+                    .all(|(i, expr)| aggregate_satisfies_expr(&aggs.0[*i], expr))
+            })
+            .collect::<Vec<_>>();
+        let mut keep_iter = keep.iter();
+        self.group_keys.retain(|_| *keep_iter.next().unwrap());
+        let mut keep_iter = keep.iter();
+        self.aggregates.retain(|_| *keep_iter.next().unwrap());
+    }
+
     /// Merges `other` and self, returning a new set of results.
     ///
     /// NOTE: This is slow! Not expected to be the final type of implementation
@@ -1530,10 +2721,10 @@ impl<'row_group> ReadAggregateResult<'row_group> {
                 // drained other, add the rest of self
                 result
                     .group_keys
-                    .extend(self.group_keys.iter().skip(j).cloned());
+                    .extend(self.group_keys.iter().skip(i).cloned());
                 result
                     .aggregates
-                    .extend(self.aggregates.iter().skip(j).cloned());
+                    .extend(self.aggregates.iter().skip(i).cloned());
                 return result;
             }
 
@@ -1592,35 +2783,39 @@ impl TryFrom<ReadAggregateResult<'_>> for RecordBatch {
     type Error = Error;
 
     fn try_from(result: ReadAggregateResult<'_>) -> Result<Self, Self::Error> {
-        let schema = data_types::schema::Schema::try_from(result.schema())
-            .map_err(|source| Error::SchemaError { source })?;
-        let arrow_schema: arrow_deps::arrow::datatypes::SchemaRef = schema.into();
-
-        // Build the columns for the group keys. This involves pivoting the
-        // row-wise group keys into column-wise data.
-        let mut group_column_builders = (0..result.schema.group_columns.len())
-            .map(|_| {
-                arrow::array::StringBuilder::with_capacity(
-                    result.cardinality(),
-                    result.cardinality() * 8, // arbitrarily picked for now
-                )
-            })
-            .collect::<Vec<_>>();
-
-        // build each column for a group key value row by row.
-        for gk in result.group_keys.iter() {
-            for (i, v) in gk.0.iter().enumerate() {
-                group_column_builders[i]
-                    .append_value(v.string())
-                    .map_err(|source| Error::ArrowError { source })?;
-            }
-        }
+        let schema = data_types::schema::Schema::try_from(result.schema())
+            .map_err(|source| Error::SchemaError { source })?;
+        let arrow_schema: arrow_deps::arrow::datatypes::SchemaRef = schema.into();
 
-        // Add the group columns to the set of column data for the record batch.
+        // Build the columns for the group keys. This involves pivoting the
+        // row-wise group keys into column-wise data. Group columns are tag
+        // columns, apart from the window start time of windowed aggregates.
         let mut columns: Vec<Arc<dyn arrow::array::Array>> =
             Vec::with_capacity(result.schema.len());
-        for col in group_column_builders.iter_mut() {
-            columns.push(Arc::new(col.finish()));
+        for (i, (_, data_type)) in result.schema.group_columns.iter().enumerate() {
+            match data_type {
+                LogicalDataType::Integer => {
+                    let mut builder = array::Int64Builder::new(result.cardinality());
+                    for gk in result.group_keys.iter() {
+                        let v = match gk.0[i] {
+                            Value::Scalar(Scalar::I64(v)) => Some(v),
+                            _ => None,
+                        };
+                        builder.append_option(v).context(ArrowError)?;
+                    }
+                    columns.push(Arc::new(builder.finish()));
+                }
+                _ => {
+                    let mut builder = arrow::array::StringBuilder::with_capacity(
+                        result.cardinality(),
+                        result.cardinality() * 8, // arbitrarily picked for now
+                    );
+                    for gk in result.group_keys.iter() {
+                        builder.append_value(gk.0[i].string()).context(ArrowError)?;
+                    }
+                    columns.push(Arc::new(builder.finish()));
+                }
+            }
         }
 
         // For the aggregate columns, build one column at a time, repeatedly
@@ -1749,6 +2944,28 @@ impl std::fmt::Display for &ReadAggregateResult<'_> {
     }
 }
 
+/// Returns the start of the window of width `window` nanoseconds that the
+/// timestamp `t` falls into.
+///
+/// Windows are aligned to the epoch, and timestamps are floored toward negative
+/// infinity, so `-1` with a window of `10` falls into the window starting at
+/// `-10` rather than `0`. The computation is carried out in `i128` so that it
+/// cannot overflow; window starts that would be below `i64::MIN` are clamped to
+/// `i64::MIN`.
+///
+/// `window` must be positive.
+pub(crate) fn window_start(t: i64, window: i64) -> i64 {
+    assert!(window > 0, "window must be positive: {}", window);
+
+    let (t, window) = (t as i128, window as i128);
+    let start = t.div_euclid(window) * window;
+    if start < i64::MIN as i128 {
+        i64::MIN
+    } else {
+        start as i64
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1759,6 +2976,101 @@ mod test {
         Predicate::new(vec![expr])
     }
 
+    #[test]
+    fn estimated_matching_rows() {
+        let times = (0..1000_i64).collect::<Vec<_>>();
+        let regions = (0..1000)
+            .map(|i| ["north", "south", "east", "west"][i % 4])
+            .collect::<Vec<_>>();
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&times[..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&regions[..]));
+        columns.insert("region".to_string(), rc);
+        let row_group = RowGroup::new(1000, columns);
+
+        let cases = vec![
+            (vec![BinaryExpr::from(("time", "<", 250_i64))], 250),
+            (vec![BinaryExpr::from(("time", ">=", 900_i64))], 100),
+            (vec![BinaryExpr::from(("region", "=", "west"))], 250),
+            (vec![BinaryExpr::from(("region", "!=", "west"))], 750),
+            (
+                vec![
+                    BinaryExpr::from(("region", "=", "west")),
+                    BinaryExpr::from(("time", "<", 500_i64)),
+                ],
+                125,
+            ),
+            (vec![BinaryExpr::from(("time", ">", 2000_i64))], 0),
+            (vec![BinaryExpr::from(("region", "=", "zebra"))], 0),
+            (vec![BinaryExpr::from(("missing", "=", "west"))], 0),
+        ];
+
+        for (exprs, actual) in cases {
+            let predicate = Predicate::new(exprs);
+            let estimate = row_group.estimated_matching_rows(&predicate);
+
+            // estimates should be within 10% of the actual row count on a
+            // uniform dataset.
+            let diff = (estimate as i64 - actual).abs();
+            assert!(
+                diff <= actual / 10 + 1,
+                "estimate {} too far from {} for {}",
+                estimate,
+                actual,
+                predicate
+            );
+        }
+    }
+
+    #[test]
+    fn row_ids_from_predicates_case_insensitive() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[100_i64, 200, 500, 600, 300][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(
+            &["Host-A", "host-a", "host-b", "HOST-A", "host-c"][..],
+        ));
+        columns.insert("host".to_string(), rc);
+        let row_group = RowGroup::new(5, columns);
+
+        // An exact match only matches one of the values.
+        let row_ids =
+            row_group.row_ids_from_predicates(&col_pred(BinaryExpr::from(("host", "=", "host-a"))));
+        assert_eq!(row_ids.unwrap().to_vec(), vec![1]);
+
+        // A case-insensitive match matches all the values.
+        let expr = BinaryExpr::from(("host", "=", "hOsT-a")).with_case_insensitive();
+        assert!(row_group.could_satisfy_conjunctive_binary_expressions(&[expr.clone()]));
+        let row_ids = row_group.row_ids_from_predicates(&col_pred(expr));
+        assert_eq!(row_ids.unwrap().to_vec(), vec![0, 1, 3]);
+
+        // The value is outside of the column's range, but can still match.
+        let expr = BinaryExpr::from(("host", "=", "HOST-B")).with_case_insensitive();
+        assert!(row_group.could_satisfy_conjunctive_binary_expressions(&[expr.clone()]));
+        let row_ids = row_group.row_ids_from_predicates(&col_pred(expr));
+        assert_eq!(row_ids.unwrap().to_vec(), vec![2]);
+
+        // No values match.
+        let expr = BinaryExpr::from(("host", "=", "host-z")).with_case_insensitive();
+        let row_ids = row_group.row_ids_from_predicates(&col_pred(expr));
+        assert!(matches!(row_ids, RowIDsOption::None(_)));
+
+        // A case-insensitive != excludes all of the mixed-case values.
+        let expr = BinaryExpr::from(("host", "!=", "HOST-a")).with_case_insensitive();
+        let row_ids = row_group.row_ids_from_predicates(&col_pred(expr));
+        assert_eq!(row_ids.unwrap().to_vec(), vec![2, 4]);
+
+        // Combined with a time range.
+        let row_ids = row_group.row_ids_from_predicates(&Predicate::with_time_range(
+            &[BinaryExpr::from(("host", "=", "HOST-A")).with_case_insensitive()],
+            150,
+            700,
+        ));
+        assert_eq!(row_ids.unwrap().to_vec(), vec![1, 3]);
+    }
+
     #[test]
     fn row_ids_from_predicates() {
         let mut columns = BTreeMap::new();
@@ -1826,6 +3138,138 @@ mod test {
         assert!(matches!(row_ids, RowIDsOption::All(_)));
     }
 
+    #[test]
+    fn row_ids_from_time_range_sorted() {
+        let times = &[100_i64, 200, 300, 300, 500, 600];
+        let unsorted_times = &[500_i64, 200, 600, 300, 100, 300];
+
+        let mut columns = BTreeMap::new();
+        columns.insert(
+            "time".to_string(),
+            ColumnType::Time(Column::from(&times[..])),
+        );
+        let sorted = RowGroup::new(6, columns);
+        assert!(sorted.is_time_sorted());
+
+        let mut columns = BTreeMap::new();
+        columns.insert(
+            "time".to_string(),
+            ColumnType::Time(Column::from(&unsorted_times[..])),
+        );
+        let unsorted = RowGroup::new(6, columns);
+        assert!(!unsorted.is_time_sorted());
+
+        // Returns the sorted timestamps of the rows matching the time range.
+        let matching_times = |row_group: &RowGroup, from: i64, to: i64| -> Option<Vec<i64>> {
+            let exprs = Predicate::with_time_range(&[], from, to);
+            let row_ids =
+                row_group.row_ids_from_time_range(exprs.expressions(), RowIDs::new_bitmap());
+            let row_ids = match row_ids {
+                RowIDsOption::None(_) => return None,
+                RowIDsOption::All(_) => (0..row_group.rows()).collect::<Vec<_>>(),
+                RowIDsOption::Some(row_ids) => row_ids.to_vec(),
+            };
+
+            let mut times = row_ids
+                .into_iter()
+                .map(|id| match row_group.time_column().value(id) {
+                    Value::Scalar(Scalar::I64(t)) => t,
+                    v => panic!("unexpected value {:?}", v),
+                })
+                .collect::<Vec<_>>();
+            times.sort_unstable();
+            Some(times)
+        };
+
+        let cases = vec![
+            ((200, 600), Some(vec![200, 300, 300, 500])),
+            ((300, 301), Some(vec![300, 300])),
+            ((301, 500), None),
+            ((-10, 101), Some(vec![100])),
+            ((600, 1000), Some(vec![600])),
+            ((0, 1000), Some(vec![100, 200, 300, 300, 500, 600])),
+            ((700, 800), None),
+        ];
+
+        for ((from, to), exp) in cases {
+            assert_eq!(matching_times(&sorted, from, to), exp, "{}..{}", from, to);
+            assert_eq!(matching_times(&unsorted, from, to), exp, "{}..{}", from, to);
+        }
+
+        // the sorted fast path returns a contiguous range of row ids.
+        let exprs = Predicate::with_time_range(&[], 200, 600);
+        let row_ids = sorted.row_ids_from_time_range(exprs.expressions(), RowIDs::new_bitmap());
+        assert_eq!(row_ids.unwrap().to_vec(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn read_filter_result_nullability_mismatch() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let fc = ColumnType::Field(Column::from(arrow::array::Float64Array::from(vec![
+            Some(1.5),
+            None,
+            Some(3.5),
+        ])));
+        columns.insert("temp".to_string(), fc);
+        let row_group = RowGroup::new(3, columns);
+
+        // field columns are not nullable in the result schema.
+        let result = row_group.read_filter(&["temp", "time"], &Predicate::default());
+        assert!(matches!(
+            RecordBatch::try_from(result),
+            Err(Error::ColumnNullabilityMismatch { column_name }) if column_name == "temp"
+        ));
+
+        // no error when the materialised values contain no nulls.
+        let result = row_group.read_filter(
+            &["temp", "time"],
+            &Predicate::new(vec![BinaryExpr::from(("temp", ">", 2.0))]),
+        );
+        assert_eq!(RecordBatch::try_from(result).unwrap().num_rows(), 1);
+    }
+
+    #[test]
+    fn predicate_display() {
+        let predicate = Predicate::with_time_range(
+            &[
+                BinaryExpr::from(("region", "=", "west")),
+                BinaryExpr::from(("host", "!=", "o'brien")).with_case_insensitive(),
+                BinaryExpr::from(("temp", "<=", 10.5)),
+                BinaryExpr::from(("count", ">", 100_u64)),
+                BinaryExpr::from(("active", "=", true)),
+            ],
+            -100,
+            200,
+        );
+
+        assert_eq!(
+            predicate.to_string(),
+            "time >= -100 AND time < 200 AND region = 'west' AND \
+             host != 'o''brien' (case insensitive) AND temp <= 10.5 AND count > 100 AND \
+             active = true"
+        );
+
+        assert_eq!(Predicate::default().to_string(), "");
+
+        let aggregates = vec![
+            AggregateType::Count,
+            AggregateType::First,
+            AggregateType::Last,
+            AggregateType::Min,
+            AggregateType::Max,
+            AggregateType::Sum,
+        ];
+        assert_eq!(
+            aggregates
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["count", "first", "last", "min", "max", "sum"]
+        );
+    }
+
     #[test]
     fn read_filter() {
         let mut columns = BTreeMap::new();
@@ -1916,6 +3360,227 @@ west,4
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn matching_rows() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3, 4, 5, 6][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(
+            &["west", "west", "east", "west", "south", "north"][..],
+        ));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[100_u64, 101, 200, 203, 203, 10][..]));
+        columns.insert("count".to_string(), fc);
+        let row_group = RowGroup::new(6, columns);
+
+        let predicate =
+            Predicate::with_time_range(&[BinaryExpr::from(("region", "=", "west"))], 2, 6);
+        let row_ids = row_group.matching_rows(&predicate);
+        assert_eq!(row_ids.unwrap().to_vec(), vec![1, 3]);
+
+        // the row ids identify the rows that `read_filter` returns.
+        let results = row_group.read_filter(&["time", "region"], &predicate);
+        assert_eq!(format!("{:?}", &results), "time,region\n2,west\n4,west\n");
+        let results = row_group.read_filter_rows(&["time", "region"], &row_ids);
+        assert_eq!(format!("{:?}", &results), "time,region\n2,west\n4,west\n");
+
+        // and the same row ids can be used for aggregates.
+        let aggregates = [("count", AggregateType::Sum)];
+        assert_eq!(
+            row_group.read_aggregate_rows(
+                &row_ids,
+                &["region"],
+                &aggregates,
+                NanHandling::default()
+            ),
+            row_group.read_aggregate(&predicate, &["region"], &aggregates)
+        );
+        assert_eq!(
+            row_group.read_aggregate_rows(&row_ids, &[], &aggregates, NanHandling::default()),
+            row_group.read_aggregate(&predicate, &[], &aggregates)
+        );
+
+        let row_ids = row_group.matching_rows(&Predicate::default());
+        assert!(matches!(row_ids, RowIDsOption::All(_)));
+        assert_eq!(
+            row_group.read_aggregate_rows(
+                &row_ids,
+                &["region"],
+                &aggregates,
+                NanHandling::default()
+            ),
+            row_group.read_aggregate(&Predicate::default(), &["region"], &aggregates)
+        );
+
+        let row_ids = row_group.matching_rows(&Predicate::with_time_range(&[], 10, 20));
+        assert!(matches!(row_ids, RowIDsOption::None(_)));
+        assert!(row_group.read_filter_rows(&["time"], &row_ids).is_empty());
+    }
+
+    #[test]
+    fn column_encoding() {
+        let strategy =
+            EncodingStrategy::new().with_column_encoding("region", EncodingKind::Dictionary);
+
+        let mut columns = BTreeMap::new();
+        let times = array::Int64Array::from(vec![10, 20, 30]);
+        let tc = ColumnType::Time(strategy.time_column("time", &times));
+        columns.insert("time".to_string(), tc);
+        let regions = array::StringArray::from(vec!["west", "west", "east"]);
+        let rc = ColumnType::Tag(strategy.tag_column("region", &regions));
+        columns.insert("region".to_string(), rc);
+        let hosts = array::StringArray::from(vec!["a", "a", "b"]);
+        let hc = ColumnType::Tag(strategy.tag_column("host", &hosts));
+        columns.insert("host".to_string(), hc);
+        let counts = array::Int64Array::from(vec![3, 1, 2]);
+        let fc = ColumnType::Field(strategy.field_column("count", &counts));
+        columns.insert("count".to_string(), fc);
+        let row_group = RowGroup::new(3, columns);
+
+        assert_eq!(row_group.column_encoding("time"), Some(EncodingKind::Delta));
+        assert_eq!(
+            row_group.column_encoding("region"),
+            Some(EncodingKind::Dictionary)
+        );
+        assert_eq!(row_group.column_encoding("host"), Some(EncodingKind::RLE));
+        assert_eq!(
+            row_group.column_encoding("count"),
+            Some(EncodingKind::Plain)
+        );
+        assert_eq!(row_group.column_encoding("temp"), None);
+    }
+
+    #[test]
+    fn content_hash() {
+        let build = |counts: &[f64]| {
+            let mut columns = BTreeMap::new();
+            let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+            columns.insert("time".to_string(), tc);
+            let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
+            columns.insert("region".to_string(), rc);
+            let fc = ColumnType::Field(Column::from(counts));
+            columns.insert("count".to_string(), fc);
+            RowGroup::new(3, columns)
+        };
+
+        let row_group = build(&[1.0, 2.0, 3.0]);
+        assert_eq!(
+            row_group.content_hash(),
+            build(&[1.0, 2.0, 3.0]).content_hash()
+        );
+        assert_ne!(
+            row_group.content_hash(),
+            build(&[1.0, 2.0, 4.0]).content_hash()
+        );
+    }
+
+    #[test]
+    fn column_ranges() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&[Some("west"), None, Some("east")][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(arrow::array::Int64Array::from(vec![
+            None,
+            Some(-10),
+            Some(20),
+        ])));
+        columns.insert("count".to_string(), fc);
+        let row_group = RowGroup::new(3, columns);
+
+        let ranges = row_group.column_ranges();
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(
+            ranges["time"],
+            (
+                OwnedValue::Scalar(Scalar::I64(1)),
+                OwnedValue::Scalar(Scalar::I64(3))
+            )
+        );
+        // NULL values are excluded from the ranges.
+        assert_eq!(
+            ranges["region"],
+            (
+                OwnedValue::String("east".to_owned()),
+                OwnedValue::String("west".to_owned())
+            )
+        );
+        assert_eq!(
+            ranges["count"],
+            (
+                OwnedValue::Scalar(Scalar::I64(-10)),
+                OwnedValue::Scalar(Scalar::I64(20))
+            )
+        );
+    }
+
+    #[test]
+    fn read_filter_delta_time_column() {
+        // evenly spaced timestamps, stored without any per-row data.
+        let times = (0..1000).map(|i| i * 10).collect::<Vec<i64>>();
+        let time = crate::column::delta::Delta::try_from_values(&times).unwrap();
+
+        let mut columns = BTreeMap::new();
+        columns.insert("time".to_string(), ColumnType::Time(Column::from(time)));
+        let counts = (0..1000).collect::<Vec<u64>>();
+        let fc = ColumnType::Field(Column::from(counts.as_slice()));
+        columns.insert("count".to_string(), fc);
+        let row_group = RowGroup::new(1000, columns);
+
+        let results = row_group.read_filter(
+            &["count", "time"],
+            &Predicate::with_time_range(&[], 4995, 5030),
+        );
+        let expected = "count,time
+500,5000
+501,5010
+502,5020
+";
+        assert_eq!(format!("{:?}", &results), expected);
+
+        // the time column can still be pruned using its range.
+        assert!(row_group
+            .read_filter(&["time"], &Predicate::with_time_range(&[], 10_000, 20_000))
+            .is_empty());
+    }
+
+    #[test]
+    fn predicate_from_arrow_values() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3, 4][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "east", "west", "north"][..]));
+        columns.insert("region".to_string(), rc);
+        let cc = ColumnType::Field(Column::from(&[10_i64, 20, 30, 40][..]));
+        columns.insert("count".to_string(), cc);
+        let row_group = RowGroup::new(4, columns);
+
+        // values from the first row of a previous result.
+        let regions: arrow::array::ArrayRef =
+            Arc::new(array::StringArray::from(vec!["west", "east"]));
+        let counts: arrow::array::ArrayRef = Arc::new(array::Int64Array::from(vec![20, 40]));
+
+        let region = Value::from_arrow(&regions, 0).unwrap();
+        assert_eq!(region, Value::String("west"));
+        let count = Value::from_arrow(&counts, 0).unwrap();
+        assert_eq!(count, Value::Scalar(Scalar::I64(20)));
+
+        let predicate = Predicate::new(vec![
+            BinaryExpr::new(
+                "region",
+                Operator::Equal,
+                Literal::try_from(&region).unwrap(),
+            ),
+            BinaryExpr::new("count", Operator::GT, Literal::try_from(&count).unwrap()),
+        ]);
+        let results = row_group.read_filter(&["count", "time"], &predicate);
+        assert_eq!(format!("{:?}", &results), "count,time\n30,3\n");
+
+        assert!(Value::from_arrow(&counts, 2).is_err());
+        assert!(Literal::try_from(&Value::Null).is_err());
+    }
+
     #[test]
     fn read_group() {
         let mut columns = BTreeMap::new();
@@ -2316,6 +3981,87 @@ west,host-d,11,9
         );
     }
 
+    #[test]
+    fn read_group_result_retain_having() {
+        let schema = ResultSchema {
+            group_columns: vec![(
+                schema::ColumnType::Tag("region".to_owned()),
+                LogicalDataType::String,
+            )],
+            aggregate_columns: vec![
+                (
+                    schema::ColumnType::Field("temp".to_owned()),
+                    AggregateType::Sum,
+                    LogicalDataType::Integer,
+                ),
+                (
+                    schema::ColumnType::Field("temp".to_owned()),
+                    AggregateType::Count,
+                    LogicalDataType::Unsigned,
+                ),
+            ],
+            ..ResultSchema::default()
+        };
+
+        // 2^53 + 1 can't be represented by an f64.
+        let big = (1_i64 << 53) + 1;
+        let mut result = ReadAggregateResult {
+            schema,
+            ..Default::default()
+        };
+        result.add_row(
+            vec![Value::String("east")],
+            vec![
+                AggregateResult::Sum(Scalar::I64(big)),
+                AggregateResult::Count(3),
+            ],
+        );
+        result.add_row(
+            vec![Value::String("north")],
+            vec![
+                AggregateResult::Sum(Scalar::I64(big - 1)),
+                AggregateResult::Count(u64::MAX),
+            ],
+        );
+        result.add_row(
+            vec![Value::String("west")],
+            vec![
+                AggregateResult::Sum(Scalar::Null),
+                AggregateResult::Count(0),
+            ],
+        );
+
+        let retained = |exprs: Vec<BinaryExpr>| {
+            let mut result = result.clone();
+            result.retain_having(&Predicate::new(exprs));
+            format!("{}", &result)
+        };
+
+        // integers are compared exactly with integer and float literals.
+        assert_eq!(
+            retained(vec![BinaryExpr::from(("temp_sum", ">", big - 1))]),
+            format!("east,{},3\n", big)
+        );
+        assert_eq!(
+            retained(vec![BinaryExpr::from(("temp_sum", "=", (big - 1) as f64))]),
+            format!("north,{},{}\n", big - 1, u64::MAX)
+        );
+        assert_eq!(
+            retained(vec![BinaryExpr::from(("temp_count", ">", u64::MAX - 1))]),
+            format!("north,{},{}\n", big - 1, u64::MAX)
+        );
+
+        // NULL aggregates never satisfy an expression, and expressions on
+        // columns that aren't aggregates are ignored.
+        assert_eq!(
+            retained(vec![
+                BinaryExpr::from(("temp_sum", "!=", 0_i64)),
+                BinaryExpr::from(("region", "=", "north")),
+            ]),
+            format!("east,{},3\nnorth,{},{}\n", big, big - 1, u64::MAX)
+        );
+    }
+
     #[test]
     fn read_group_result_merge() {
         let schema = ResultSchema {
@@ -2482,6 +4228,7 @@ west,host-d,11,9
                 OwnedValue::String("east".to_owned()),
                 OwnedValue::String("west".to_owned()),
             ),
+            null_count: 0,
         };
 
         let col2 = ColumnMeta {
@@ -2491,6 +4238,7 @@ west,host-d,11,9
                 OwnedValue::String("north".to_owned()),
                 OwnedValue::String("west".to_owned()),
             ),
+            null_count: 0,
         };
 
         let col3 = ColumnMeta {
@@ -2500,10 +4248,94 @@ west,host-d,11,9
                 OwnedValue::String("east".to_owned()),
                 OwnedValue::String("west".to_owned()),
             ),
+            null_count: 0,
         };
 
         assert_eq!(col1, col2);
         assert_ne!(col1, col3);
         assert_ne!(col2, col3);
     }
+
+    #[test]
+    fn read_window_aggregate_ordered() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 11, 25, 3, 12, -4][..]));
+        columns.insert("time".to_string(), tc);
+        let fc = ColumnType::Field(Column::from(
+            &[
+                Some(1_i64),
+                Some(2),
+                None,
+                Some(4),
+                Some(5),
+                Some(6),
+                Some(7),
+            ][..],
+        ));
+        columns.insert("counter".to_string(), fc);
+        let row_group = RowGroup::new(7, columns);
+
+        let aggregates = [
+            ("counter", AggregateType::Count),
+            ("counter", AggregateType::Sum),
+        ];
+
+        // An empty predicate uses the single pass implementation whereas a
+        // time range covering all rows uses the general implementation.
+        let ordered = row_group.read_window_aggregate(&Predicate::default(), &[], &aggregates, 10);
+        let general = row_group.read_window_aggregate(
+            &Predicate::with_time_range(&[], -100, 100),
+            &[],
+            &aggregates,
+            10,
+        );
+        assert_eq!(&ordered, &general);
+
+        let windows = ordered
+            .group_keys
+            .iter()
+            .map(|key| key.0[0].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            windows,
+            vec![
+                Value::Scalar(Scalar::I64(-10)),
+                Value::Scalar(Scalar::I64(0)),
+                Value::Scalar(Scalar::I64(10)),
+                Value::Scalar(Scalar::I64(20)),
+            ]
+        );
+
+        // the NULL counter value in the window starting at 10 is not counted.
+        assert_eq!(
+            ordered.aggregates[2].0,
+            vec![
+                AggregateResult::Count(1),
+                AggregateResult::Sum(Scalar::I64(6))
+            ]
+        );
+    }
+
+    #[test]
+    fn window_start_bounds() {
+        // timestamps are floored toward negative infinity.
+        assert_eq!(window_start(-1, 10), -10);
+        assert_eq!(window_start(-10, 10), -10);
+        assert_eq!(window_start(-11, 10), -20);
+        assert_eq!(window_start(0, 10), 0);
+        assert_eq!(window_start(9, 10), 0);
+        assert_eq!(window_start(10, 10), 10);
+
+        // large windows near the bounds of i64 do not overflow.
+        let window = i64::MAX / 2;
+        assert_eq!(window_start(i64::MAX, window), window * 2);
+        assert_eq!(window_start(i64::MAX - 1, window), window * 2);
+        assert_eq!(window_start(i64::MAX, i64::MAX), i64::MAX);
+        assert_eq!(window_start(i64::MAX - 1, i64::MAX), 0);
+        assert_eq!(window_start(-1, i64::MAX), -i64::MAX);
+
+        // window starts below i64::MIN are clamped.
+        assert_eq!(window_start(i64::MIN, i64::MAX), i64::MIN);
+        assert_eq!(window_start(i64::MIN + 1, 3), i64::MIN);
+    }
 }
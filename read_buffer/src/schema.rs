@@ -10,6 +10,7 @@ use data_types::schema::InfluxFieldType;
 /// decorating Arrow record batches when results are converted before leaving
 /// the read buffer.
 #[derive(Default, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResultSchema {
     pub select_columns: Vec<(ColumnType, LogicalDataType)>,
     pub group_columns: Vec<(ColumnType, LogicalDataType)>,
@@ -59,7 +60,7 @@ impl ResultSchema {
     //
     // TODO(edd): support multiple instances of the same aggregation on the same
     // column? E.g., `temp_sum_1`, `temp_sum_2` etc??
-    fn aggregate_result_column_name(&self, i: usize) -> String {
+    pub(crate) fn aggregate_result_column_name(&self, i: usize) -> String {
         let (col_type, agg_type, _) = self.aggregate_columns.get(i).unwrap();
         format!("{}_{}", col_type, agg_type)
     }
@@ -104,6 +105,10 @@ impl TryFrom<&ResultSchema> for data_types::schema::Schema {
         for (col_type, data_type) in &rs.select_columns {
             match col_type {
                 ColumnType::Tag(name) => builder = builder.tag(name.as_str()),
+                // Binary fields have no InfluxDB field type.
+                ColumnType::Field(name) if data_type == &LogicalDataType::Binary => {
+                    builder = builder.field(name.as_str(), data_type.into())
+                }
                 ColumnType::Field(name) => {
                     builder = builder.influx_field(name.as_str(), data_type.into())
                 }
@@ -127,7 +132,9 @@ impl TryFrom<&ResultSchema> for data_types::schema::Schema {
             let col_name = rs.aggregate_result_column_name(i);
 
             match col_type {
-                ColumnType::Field(_) => {
+                // Aggregates of the time column, such as its minimum, are
+                // integer fields rather than timestamps.
+                ColumnType::Field(_) | ColumnType::Timestamp(_) => {
                     builder = builder.influx_field(col_name.as_str(), data_type.into())
                 }
                 ColumnType::Other(_) => {
@@ -142,6 +149,7 @@ impl TryFrom<&ResultSchema> for data_types::schema::Schema {
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The logical data-type for a column.
 pub enum LogicalDataType {
     Integer,  // Signed integer
@@ -183,6 +191,7 @@ impl From<&LogicalDataType> for InfluxFieldType {
 /// These variants describe supported aggregates that can applied to columnar
 /// data in the Read Buffer.
 #[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AggregateType {
     Count,
     First,
@@ -213,9 +222,33 @@ impl std::fmt::Display for AggregateType {
     }
 }
 
+/// Describes how NaN values in float columns are treated when aggregating.
+///
+/// By default NaN values are skipped, meaning they do not contribute to `Sum`,
+/// `Min` or `Max` aggregates. A group containing only NaN values has a NULL
+/// aggregate. NaN values are not NULL so they are always counted by `Count`.
+///
+/// Alternatively NaN values can be propagated, in which case a group containing
+/// any NaN value has a NaN aggregate.
+///
+/// Infinite values are always aggregated, and are ordered such that
+/// `-inf < finite values < +inf`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum NanHandling {
+    Skip,
+    Propagate,
+}
+
+impl Default for NanHandling {
+    fn default() -> Self {
+        Self::Skip
+    }
+}
+
 /// Describes the semantic meaning of the column in a set of results. That is,
 /// whether the column is a "tag", "field", "timestamp", or "other".
 #[derive(PartialEq, Debug, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColumnType {
     Tag(String),
     Field(String),
@@ -1,10 +1,30 @@
 use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryInto;
 use std::fmt::Display;
 use std::slice::Iter;
-
-use crate::column::{AggregateResult, Scalar, Value};
-use crate::row_group::{self, ColumnName, GroupKey, Predicate, RowGroup};
-use crate::schema::{AggregateType, ColumnType, LogicalDataType, ResultSchema};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use arrow_deps::arrow::{
+    array::{Array, ArrayRef, Float64Array, Int64Array, PrimitiveArray, UInt64Array},
+    compute::kernels::cast::cast,
+    datatypes::{
+        ArrowNumericType, DataType, Field, Float64Type, Int64Type, Schema as ArrowSchema,
+        UInt64Type,
+    },
+    record_batch::RecordBatch,
+};
+use snafu::{ensure, OptionExt, ResultExt};
+
+use crate::cache::{DecodeCache, DecodeCacheStats, PruneCache, PruneCacheStats};
+use crate::column::{strategy::EncodingKind, AggregateResult, OwnedValue, Scalar, Value, Values};
+use crate::row_group::{
+    self, ColumnName, Predicate, PredicatePushdown, ReadAggregateResult, RowGroup,
+};
+use crate::schema::{AggregateType, ColumnType, LogicalDataType, NanHandling, ResultSchema};
+use crate::{ColumnRole, Error};
 
 /// A Table represents data for a single measurement.
 ///
@@ -29,6 +49,20 @@ pub struct Table {
     meta: MetaData,
 
     row_groups: Vec<RowGroup>,
+
+    // An optional cache of recently materialised columns.
+    decode_cache: Option<Mutex<DecodeCache>>,
+
+    // An optional cache of the row groups that could satisfy recently
+    // evaluated predicates.
+    prune_cache: Option<Mutex<PruneCache>>,
+
+    // The number of distinct non-null values in a column, computed on demand
+    // by `column_statistics` and invalidated when row groups change.
+    distinct_counts: Mutex<BTreeMap<String, u64>>,
+
+    // The number of times column values have been scanned to build metadata.
+    metadata_scans: AtomicU64,
 }
 
 impl Table {
@@ -38,18 +72,199 @@ impl Table {
             name,
             meta: MetaData::new(rg.metadata()),
             row_groups: vec![rg],
+            decode_cache: None,
+            prune_cache: None,
+            distinct_counts: Mutex::new(BTreeMap::new()),
+            metadata_scans: AtomicU64::new(0),
+        }
+    }
+
+    /// Enables caching of materialised columns for this table, holding at
+    /// most `budget` bytes of decoded column data. Any previously cached
+    /// columns are discarded.
+    ///
+    /// When all rows in a row group satisfy the predicate of a `read_filter`
+    /// the decoded columns are served from, and stored in, the cache.
+    pub fn enable_decode_cache(&mut self, budget: usize) {
+        self.decode_cache = Some(Mutex::new(DecodeCache::new(budget)));
+    }
+
+    /// Hit and miss counters for the table's decode cache, if enabled.
+    pub fn decode_cache_stats(&self) -> Option<DecodeCacheStats> {
+        self.decode_cache
+            .as_ref()
+            .map(|cache| cache.lock().unwrap().stats())
+    }
+
+    /// Enables caching of the row groups that could satisfy a predicate, for
+    /// at most `capacity` distinct predicates. Any previously cached results
+    /// are discarded.
+    ///
+    /// When a query's predicate is found in the cache the row groups are not
+    /// checked against it again. The cache is cleared whenever row groups are
+    /// added to or removed from the table.
+    pub fn enable_prune_cache(&mut self, capacity: usize) {
+        self.prune_cache = Some(Mutex::new(PruneCache::new(capacity)));
+    }
+
+    /// Hit and miss counters for the table's prune cache, if enabled.
+    pub fn prune_cache_stats(&self) -> Option<PruneCacheStats> {
+        self.prune_cache
+            .as_ref()
+            .map(|cache| cache.lock().unwrap().stats())
+    }
+
+    /// Renames the table.
+    pub fn rename(&mut self, name: String) {
+        self.name = name;
+    }
+
+    /// Reclassifies a column across all of the table's row groups as the tag
+    /// or field column `column_type`, e.g., because a high cardinality column
+    /// was mistakenly ingested as a tag.
+    ///
+    /// Tag columns and string field columns share the same encodings, so the
+    /// column's data is not re-encoded. Data added to the table afterwards
+    /// must use the new classification.
+    ///
+    /// An error is returned if the column doesn't exist, if it is the time
+    /// column, or if a non-string column would become a tag.
+    pub fn reclassify_column(&mut self, column_type: ColumnType) -> Result<(), Error> {
+        let column_name = column_type.as_str();
+        let column_meta = self
+            .meta
+            .columns
+            .get(column_name)
+            .context(crate::ColumnNotFound {
+                column_name,
+                role: ColumnRole::Select,
+            })?;
+
+        ensure!(
+            !matches!(column_meta.typ, ColumnType::Timestamp(_)),
+            crate::UnsupportedOperation {
+                msg: format!("cannot reclassify time column {}", column_name),
+            }
+        );
+        match &column_type {
+            ColumnType::Tag(_) => ensure!(
+                column_meta.logical_data_type == LogicalDataType::String,
+                crate::UnsupportedOperation {
+                    msg: format!(
+                        "cannot reclassify non-string column {} as a tag",
+                        column_name
+                    ),
+                }
+            ),
+            ColumnType::Field(_) => {}
+            ColumnType::Timestamp(_) | ColumnType::Other(_) => {
+                return crate::UnsupportedOperation {
+                    msg: format!("column {} can only become a tag or field", column_name),
+                }
+                .fail()
+            }
         }
+
+        for rg in &mut self.row_groups {
+            rg.reclassify_column(column_type.clone());
+        }
+        self.meta.columns.get_mut(column_name).unwrap().typ = column_type.clone();
+        Ok(())
+    }
+
+    /// Checks that the row group can be added to this table. Row groups may
+    /// have columns that the table doesn't, and vice versa, but columns in both
+    /// must have the same type.
+    pub fn validate_row_group(&self, rg: &RowGroup) -> Result<(), Error> {
+        self.meta
+            .validate(rg.metadata())
+            .map_err(|column_name| Error::InvalidTableData {
+                table_name: self.name.clone(),
+                msg: format!(
+                    "column {} has a different type from the table's column",
+                    column_name
+                ),
+            })
+    }
+
+    /// Determines whether the table has a row group with the provided content
+    /// hash, i.e., one containing the same data.
+    pub fn has_row_group_content(&self, content_hash: u64) -> bool {
+        self.row_groups
+            .iter()
+            .any(|rg| rg.content_hash() == content_hash)
     }
 
-    /// Add a new row group to this table.
-    pub fn add_row_group(&mut self, rg: RowGroup) {
+    /// Add a new row group to this table. An error is returned, and the table
+    /// is unchanged, if the row group's schema conflicts with the table's.
+    pub fn add_row_group(&mut self, rg: RowGroup) -> Result<(), Error> {
+        self.validate_row_group(&rg)?;
         self.meta.update(rg.metadata());
         self.row_groups.push(rg);
+        self.distinct_counts.lock().unwrap().clear();
+        if let Some(cache) = &self.prune_cache {
+            cache.lock().unwrap().clear();
+        }
+        Ok(())
     }
 
     /// Remove the row group at `position` from table.
-    pub fn drop_segment(&mut self, position: usize) {
-        todo!();
+    ///
+    /// Returns an error if it is the table's only row group, because a table
+    /// must have at least one row group.
+    pub fn drop_segment(&mut self, position: usize) -> Result<(), Error> {
+        ensure!(
+            self.row_groups.len() > 1,
+            crate::UnsupportedOperation {
+                msg: "a table must have at least one row group",
+            }
+        );
+        self.row_groups.remove(position);
+
+        // Cached columns are keyed by row group position.
+        if let Some(cache) = &self.decode_cache {
+            cache.lock().unwrap().remove_row_group(position);
+        }
+        if let Some(cache) = &self.prune_cache {
+            cache.lock().unwrap().clear();
+        }
+        self.distinct_counts.lock().unwrap().clear();
+
+        // Rebuild the table meta data from the remaining row groups.
+        let mut meta = MetaData::new(self.row_groups[0].metadata());
+        for rg in &self.row_groups[1..] {
+            meta.update(rg.metadata());
+        }
+        self.meta = meta;
+        Ok(())
+    }
+
+    /// Replaces all of the table's row groups, e.g., with fewer larger row
+    /// groups containing the same rows.
+    ///
+    /// Returns an error if `row_groups` is empty, because a table must have
+    /// at least one row group.
+    pub fn replace_row_groups(&mut self, row_groups: Vec<RowGroup>) -> Result<(), Error> {
+        let (first, rest) = row_groups
+            .split_first()
+            .context(crate::UnsupportedOperation {
+                msg: "a table must have at least one row group",
+            })?;
+        let mut meta = MetaData::new(first.metadata());
+        for rg in rest {
+            meta.update(rg.metadata());
+        }
+        self.meta = meta;
+        self.row_groups = row_groups;
+
+        if let Some(cache) = &self.decode_cache {
+            cache.lock().unwrap().clear();
+        }
+        if let Some(cache) = &self.prune_cache {
+            cache.lock().unwrap().clear();
+        }
+        self.distinct_counts.lock().unwrap().clear();
+        Ok(())
     }
 
     /// Iterate over all row groups for the table.
@@ -72,6 +287,12 @@ impl Table {
         self.row_groups.len()
     }
 
+    /// The time range of the row group at `position` within the table, or
+    /// `None` if there is no such row group.
+    pub fn row_group_time_range(&self, position: usize) -> Option<(i64, i64)> {
+        self.row_groups.get(position).map(|rg| rg.time_range())
+    }
+
     /// The total size of the table in bytes.
     pub fn size(&self) -> u64 {
         self.meta.size
@@ -82,48 +303,280 @@ impl Table {
         self.meta.rows
     }
 
+    /// The schema of all columns in the table, ordered by column name.
+    pub fn schema_for_all_columns(&self) -> Vec<(ColumnType, LogicalDataType)> {
+        self.meta.schema_for_all_columns()
+    }
+
+    /// The logical data type of the column, or `None` if the column doesn't
+    /// exist in the table.
+    pub fn column_logical_data_type(&self, column_name: ColumnName<'_>) -> Option<LogicalDataType> {
+        self.meta
+            .columns
+            .get(column_name)
+            .map(|meta| meta.logical_data_type)
+    }
+
     /// The time range of all row groups within this table.
     pub fn time_range(&self) -> Option<(i64, i64)> {
         self.meta.time_range
     }
 
+    /// Statistics about the values in the column across all row groups, or
+    /// `None` if the column doesn't exist in the table.
+    ///
+    /// The row count, NULL count and range of values are maintained in the
+    /// table's meta data. The distinct count requires scanning the column the
+    /// first time it is requested, and is then cached until the table's row
+    /// groups change.
+    pub fn column_statistics(&self, column_name: ColumnName<'_>) -> Option<ColumnStatistics> {
+        let column_meta = self.meta.columns.get(column_name)?;
+
+        let mut distinct_counts = self.distinct_counts.lock().unwrap();
+        let distinct_count = match distinct_counts.get(column_name) {
+            Some(count) => *count,
+            None => {
+                let count = self.distinct_count(column_name);
+                distinct_counts.insert(column_name.to_owned(), count);
+                count
+            }
+        };
+
+        Some(ColumnStatistics {
+            rows: self.meta.rows,
+            null_count: column_meta.null_count,
+            distinct_count,
+            min: column_meta.range.0.clone(),
+            max: column_meta.range.1.clone(),
+        })
+    }
+
+    /// The kinds of encoding used to store each column across all row groups,
+    /// keyed by column name. Row groups encode their columns independently, so
+    /// a column may use several kinds of encoding within a table.
+    pub fn column_encodings(&self) -> BTreeMap<String, BTreeSet<EncodingKind>> {
+        let mut encodings = BTreeMap::new();
+        for column_name in self.meta.columns.keys() {
+            let kinds = self
+                .row_groups
+                .iter()
+                .filter_map(|rg| rg.column_encoding(column_name))
+                .collect::<BTreeSet<_>>();
+            encodings.insert(column_name.clone(), kinds);
+        }
+        encodings
+    }
+
+    /// Eagerly computes any metadata that is otherwise built on demand, so
+    /// that later queries don't pay for it. Column ranges and time ranges are
+    /// maintained as row groups are added, so only the distinct counts used
+    /// by `column_statistics` need computing.
+    ///
+    /// Returns the number of columns whose metadata was computed, which is
+    /// zero if the table is already warm.
+    pub fn warm_metadata(&self) -> usize {
+        let mut distinct_counts = self.distinct_counts.lock().unwrap();
+        let mut warmed = 0;
+        for column_name in self.meta.columns.keys() {
+            if !distinct_counts.contains_key(column_name) {
+                let count = self.distinct_count(column_name);
+                distinct_counts.insert(column_name.clone(), count);
+                warmed += 1;
+            }
+        }
+        warmed
+    }
+
+    /// The number of times the table has scanned column values to build
+    /// metadata on demand.
+    pub fn metadata_scans(&self) -> u64 {
+        self.metadata_scans.load(Ordering::Relaxed)
+    }
+
+    // The number of distinct non-null values in the column across all row
+    // groups. Scans every row group containing the column.
+    fn distinct_count(&self, column_name: ColumnName<'_>) -> u64 {
+        self.metadata_scans.fetch_add(1, Ordering::Relaxed);
+        let mut strings = BTreeSet::new();
+        let mut scalars = BTreeSet::new();
+        let mut bytes = BTreeSet::new();
+        let mut bools = BTreeSet::new();
+        for rg in &self.row_groups {
+            match rg.column_all_values(column_name) {
+                Some(Values::String(values)) => strings.extend(values.into_iter().flatten()),
+                Some(Values::I64(values)) => scalars.extend(values.into_iter().map(|v| v as u64)),
+                Some(Values::U64(values)) => scalars.extend(values),
+                Some(Values::F64(values)) => scalars.extend(values.into_iter().map(f64::to_bits)),
+                Some(Values::I64N(values)) => {
+                    scalars.extend(values.into_iter().flatten().map(|v| v as u64))
+                }
+                Some(Values::U64N(values)) => scalars.extend(values.into_iter().flatten()),
+                Some(Values::F64N(values)) => {
+                    scalars.extend(values.into_iter().flatten().map(f64::to_bits))
+                }
+                Some(Values::ByteArray(values)) => bytes.extend(values.into_iter().flatten()),
+                Some(Values::Bool(values)) => bools.extend(values.into_iter().flatten()),
+                None => {} // column not in row group
+            }
+        }
+        (strings.len() + scalars.len() + bytes.len() + bools.len()) as u64
+    }
+
+    /// Determines, using only row group meta data, whether any rows in the
+    /// table could satisfy the predicate. Expressions that can't be applied to
+    /// the table (see `predicate_pushdown`) are ignored.
+    pub fn could_pass_predicate(&self, predicate: &Predicate) -> bool {
+        let applied = self.predicate_pushdown(predicate).applied;
+        self.row_groups
+            .iter()
+            .any(|rg| rg.could_satisfy_conjunctive_binary_expressions(&applied))
+    }
+
+    /// The time range of the row groups that could contain rows satisfying the
+    /// predicate, determined using only row group meta data, or `None` if no
+    /// row groups could. Expressions that can't be applied to the table are
+    /// ignored, as for `could_pass_predicate`.
+    ///
+    /// The range contains the times of all rows satisfying the predicate, but
+    /// it may be wider than them.
+    pub fn time_range_for_predicate(&self, predicate: &Predicate) -> Option<(i64, i64)> {
+        let applied = self.predicate_pushdown(predicate).applied;
+        self.row_groups
+            .iter()
+            .filter(|rg| rg.could_satisfy_conjunctive_binary_expressions(&applied))
+            .map(|rg| rg.time_range())
+            .fold(None, |acc, (min, max)| match acc {
+                Some((acc_min, acc_max)) => Some((acc_min.min(min), acc_max.max(max))),
+                None => Some((min, max)),
+            })
+    }
+
     // Identify set of row groups that might satisfy the predicate.
     fn filter_row_groups(&self, predicate: &Predicate) -> Vec<&RowGroup> {
+        self.filter_row_groups_with_index(predicate)
+            .into_iter()
+            .map(|(_, rg)| rg)
+            .collect()
+    }
+
+    // As `filter_row_groups` but each row group is paired with its position
+    // in the table.
+    //
+    // The result is served from, and stored in, the prune cache if enabled.
+    fn filter_row_groups_with_index(&self, predicate: &Predicate) -> Vec<(usize, &RowGroup)> {
+        let cache = match &self.prune_cache {
+            Some(cache) => cache,
+            None => return self.prune_row_groups(predicate),
+        };
+
+        // The debug representation distinguishes all properties of the
+        // expressions, such as case-insensitivity.
+        let key = format!("{:?}", predicate.expressions());
+        if let Some(positions) = cache.lock().unwrap().get(&key) {
+            return positions
+                .into_iter()
+                .map(|i| (i, &self.row_groups[i]))
+                .collect();
+        }
+
+        let rgs = self.prune_row_groups(predicate);
+        cache
+            .lock()
+            .unwrap()
+            .insert(&key, rgs.iter().map(|(i, _)| *i).collect());
+        rgs
+    }
+
+    // Checks every row group against the predicate using its meta data.
+    fn prune_row_groups(&self, predicate: &Predicate) -> Vec<(usize, &RowGroup)> {
         let mut rgs = Vec::with_capacity(self.row_groups.len());
 
-        'rowgroup: for rg in &self.row_groups {
+        'rowgroup: for (i, rg) in self.row_groups.iter().enumerate() {
             // check all expressions in predicate
             if !rg.could_satisfy_conjunctive_binary_expressions(predicate.iter()) {
                 continue 'rowgroup;
             }
 
             // row group could potentially satisfy predicate
-            rgs.push(rg);
+            rgs.push((i, rg));
         }
 
         rgs
     }
 
+    /// Returns an estimate of the number of bytes that a `read_filter` with the
+    /// same arguments would materialise, computed from the meta data of the
+    /// row groups that could satisfy the predicate without reading any rows.
+    ///
+    /// See `RowGroup::estimated_select_size` for the assumptions made. The
+    /// estimate does not account for Arrow's buffer alignment or the overhead
+    /// of each record batch.
+    pub fn estimated_select_size(
+        &self,
+        columns: &ColumnSelection<'_>,
+        predicate: &Predicate,
+    ) -> u64 {
+        let column_names = match columns {
+            ColumnSelection::All => self.meta.columns.keys().map(|name| name.as_str()).collect(),
+            ColumnSelection::Some(names) => names.to_vec(),
+        };
+
+        let predicate = Predicate::new(self.predicate_pushdown(predicate).applied);
+        self.filter_row_groups(&predicate)
+            .iter()
+            .map(|rg| rg.estimated_select_size(&column_names, &predicate))
+            .sum()
+    }
+
     /// Select data for the specified column selections with the provided
     /// predicates applied.
     ///
-    /// All selection columns **must** exist within the schema.
+    /// All selection columns **must** exist within the schema. If none of them
+    /// do then the results are empty and no row groups are read.
     ///
     /// Results may be filtered by (currently only) conjunctive (AND)
     /// predicates, but can be ranged by time, which should be represented
     /// as nanoseconds since the epoch. Results are included if they satisfy
     /// the predicate and fall with the [min, max) time range domain.
+    ///
+    /// Expressions in the predicate that cannot be evaluated against the table
+    /// are not applied. They are reported via `ReadFilterResults::pushdown` and
+    /// must be re-checked by the caller.
     pub fn read_filter<'a>(
         &'a self,
         columns: &ColumnSelection<'_>,
         predicate: &Predicate,
     ) -> ReadFilterResults<'a> {
+        let pushdown = self.predicate_pushdown(predicate);
+        let predicate = Predicate::new(pushdown.applied.clone());
+        let schema = self.read_filter_schema(columns);
+
         // identify row groups where time range and predicates match could match
         // using row group meta data, and then execute against those row groups
-        // and merge results.
-        let rgs = self.filter_row_groups(predicate);
+        // and merge results. There is nothing to read if none of the selected
+        // columns exist in the table.
+        let rgs = if schema.select_columns.is_empty() {
+            vec![]
+        } else {
+            self.filter_row_groups_with_index(&predicate)
+        };
 
-        let schema = ResultSchema {
+        // temp I think I can remove `predicates` from the results
+        ReadFilterResults {
+            predicate,
+            schema,
+            row_groups_pruned: self.row_groups.len() - rgs.len(),
+            row_groups: rgs,
+            decode_cache: self.decode_cache.as_ref(),
+            pushdown,
+            rows_scanned: 0,
+        }
+    }
+
+    /// The schema of the results of `read_filter` for the selected columns.
+    /// Selected columns that don't exist in the table are not included.
+    pub fn read_filter_schema(&self, columns: &ColumnSelection<'_>) -> ResultSchema {
+        ResultSchema {
             select_columns: match columns {
                 ColumnSelection::All => self.meta.schema_for_all_columns(),
                 ColumnSelection::Some(column_names) => {
@@ -131,14 +584,222 @@ impl Table {
                 }
             },
             ..ResultSchema::default()
+        }
+    }
+
+    /// Determines which expressions in the predicate can be applied when
+    /// reading the table, and which cannot and must be re-checked by the
+    /// caller.
+    ///
+    /// An expression cannot be applied if its literal value can't be compared
+    /// with the type of the column it references. Expressions on columns that
+    /// don't exist in the table are applied: no rows can satisfy them.
+    pub fn predicate_pushdown(&self, predicate: &Predicate) -> PredicatePushdown {
+        let mut pushdown = PredicatePushdown::default();
+        for expr in predicate.iter() {
+            match self.meta.columns.get(expr.column()) {
+                Some(column_meta)
+                    if !expr.literal_compatible_with(column_meta.logical_data_type) =>
+                {
+                    pushdown.residual.push(expr.clone())
+                }
+                _ => pushdown.applied.push(expr.clone()),
+            }
+        }
+        pushdown
+    }
+
+    /// Select data for the specified column selections with the provided
+    /// predicates applied, from only the row group at position `index` within
+    /// the table.
+    ///
+    /// This allows row groups to be individually queried, for example by an
+    /// external scheduler distributing row groups across workers. As with
+    /// `read_filter`, selected columns that don't exist in the table are not
+    /// included, and those that don't exist in the row group are NULL.
+    ///
+    /// An error is returned if `index` is out of range.
+    pub fn select_row_group(
+        &self,
+        index: usize,
+        columns: &ColumnSelection<'_>,
+        predicate: &Predicate,
+    ) -> Result<row_group::ReadFilterResult<'_>, Error> {
+        let row_group = self
+            .row_groups
+            .get(index)
+            .context(crate::RowGroupNotFound {
+                index,
+                row_groups: self.row_groups.len(),
+            })?;
+
+        let schema = self.read_filter_schema(columns);
+        let row_ids = row_group.matching_rows(predicate);
+        Ok(row_group.read_filter_rows_with_schema(&schema, &row_ids))
+    }
+
+    /// Select data for the specified column selections with the provided
+    /// predicates applied, as `read_filter` does, along with a computed
+    /// column for each of the provided projections.
+    ///
+    /// Columns referenced only by projections are read but not included in
+    /// the results. An error is returned if a projection references a column
+    /// that doesn't exist or isn't numeric.
+    pub fn select(
+        &self,
+        columns: &ColumnSelection<'_>,
+        projections: &[Projection<'_>],
+        predicate: &Predicate,
+    ) -> Result<Vec<RecordBatch>, Error> {
+        let mut batches = vec![];
+        self.select_into(columns, projections, predicate, &mut batches)?;
+        Ok(batches)
+    }
+
+    /// As `select`, but the results are appended to `dst` rather than being
+    /// returned in a new vector.
+    ///
+    /// This allows callers running many queries to reuse the same vector for
+    /// their results. `dst` is never cleared, so callers should clear it
+    /// between queries once they are finished with the previous results. If
+    /// an error is returned then `dst` is left as it was.
+    pub fn select_into(
+        &self,
+        columns: &ColumnSelection<'_>,
+        projections: &[Projection<'_>],
+        predicate: &Predicate,
+        dst: &mut Vec<RecordBatch>,
+    ) -> Result<(), Error> {
+        let columns = match columns {
+            ColumnSelection::All => self
+                .meta
+                .columns
+                .keys()
+                .map(|name| (name.as_str(), name.as_str()))
+                .collect::<Vec<_>>(),
+            ColumnSelection::Some(column_names) => {
+                column_names.iter().map(|&name| (name, name)).collect()
+            }
         };
+        self.select_aliased_into(&columns, projections, predicate, dst)
+    }
 
-        // temp I think I can remove `predicates` from the results
-        ReadFilterResults {
-            predicate: predicate.clone(),
-            schema,
-            row_groups: rgs,
+    /// As `select`, but each selected column is paired with the name it is
+    /// given in the results. This allows the same column to be selected more
+    /// than once under different names.
+    ///
+    /// An error is returned if a selected column doesn't exist, or if the
+    /// results would contain duplicate column names.
+    pub fn select_aliased(
+        &self,
+        columns: &[(ColumnName<'_>, &str)],
+        projections: &[Projection<'_>],
+        predicate: &Predicate,
+    ) -> Result<Vec<RecordBatch>, Error> {
+        let mut batches = vec![];
+        self.select_aliased_into(columns, projections, predicate, &mut batches)?;
+        Ok(batches)
+    }
+
+    /// As `select_aliased`, but the results are appended to `dst`, as with
+    /// `select_into`.
+    pub fn select_aliased_into(
+        &self,
+        columns: &[(ColumnName<'_>, &str)],
+        projections: &[Projection<'_>],
+        predicate: &Predicate,
+        dst: &mut Vec<RecordBatch>,
+    ) -> Result<(), Error> {
+        let mut output_names = BTreeSet::new();
+        let aliases = columns.iter().map(|(_, alias)| *alias);
+        for name in aliases.chain(projections.iter().map(|p| p.name)) {
+            ensure!(
+                output_names.insert(name),
+                crate::UnsupportedOperation {
+                    msg: format!("duplicate output column name {}", name),
+                }
+            );
+        }
+
+        let mut column_names = vec![];
+        for &(name, _) in columns {
+            self.ensure_column_exists(name, ColumnRole::Select)?;
+            if !column_names.contains(&name) {
+                column_names.push(name);
+            }
+        }
+
+        for name in projections.iter().flat_map(|p| p.column_names()) {
+            self.ensure_column_exists(name, ColumnRole::Projection)?;
+            let logical_data_type = self.meta.columns[name].logical_data_type;
+            ensure!(
+                matches!(
+                    logical_data_type,
+                    LogicalDataType::Integer | LogicalDataType::Unsigned | LogicalDataType::Float
+                ),
+                crate::UnsupportedOperation {
+                    msg: format!("cannot project non-numeric column {}", name),
+                }
+            );
+
+            if !column_names.contains(&name) {
+                column_names.push(name);
+            }
+        }
+
+        let mut results = self.read_filter(&ColumnSelection::Some(&column_names), predicate);
+        let dst_len = dst.len();
+        while let Some(rb) = results.next_record_batch() {
+            match Self::select_batch(&rb, columns, projections) {
+                Ok(batch) => dst.push(batch),
+                Err(e) => {
+                    dst.truncate(dst_len);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Builds a `select` result from a batch of the columns read for it,
+    // renaming the selected columns and adding the projections.
+    fn select_batch(
+        rb: &RecordBatch,
+        columns: &[(ColumnName<'_>, &str)],
+        projections: &[Projection<'_>],
+    ) -> Result<RecordBatch, Error> {
+        let schema = rb.schema();
+
+        // Column meta-data is carried over to the column's output names.
+        // The meta-data of columns only read for projections is dropped.
+        let mut metadata = schema.metadata().clone();
+        metadata.retain(|name, _| schema.field_with_name(name).is_err());
+
+        let mut fields = vec![];
+        let mut arrays = vec![];
+        for &(name, alias) in columns {
+            let i = schema.index_of(name).context(crate::ArrowError)?;
+            let field = schema.field(i);
+            fields.push(Field::new(
+                alias,
+                field.data_type().clone(),
+                field.is_nullable(),
+            ));
+            arrays.push(Arc::clone(rb.column(i)));
+
+            if let Some(column_metadata) = schema.metadata().get(name) {
+                metadata.insert(alias.to_owned(), column_metadata.clone());
+            }
+        }
+
+        for projection in projections {
+            let arr = projection.evaluate(rb)?;
+            fields.push(Field::new(projection.name, arr.data_type().clone(), true));
+            arrays.push(arr);
         }
+
+        let schema = Arc::new(ArrowSchema::new_with_metadata(fields, metadata));
+        RecordBatch::try_new(schema, arrays).context(crate::ArrowError)
     }
 
     /// Returns an iterable collection of data in group columns and aggregate
@@ -149,13 +810,22 @@ impl Table {
     /// key", and each value in the same row for the aggregate columns contains
     /// aggregate values for those group keys.
     ///
+    /// An error is returned if any of the group columns, aggregate columns or
+    /// predicate columns do not exist within the table, or if any of the group
+    /// columns is not a tag column.
+    ///
+    /// Without any group columns the results contain a single row, holding
+    /// the aggregates of all rows satisfying the predicate.
+    ///
     /// Note: `read_aggregate` currently only supports "tag" columns.
     pub fn read_aggregate<'input>(
         &self,
         predicate: Predicate,
         group_columns: &'input ColumnSelection<'_>,
         aggregates: &'input [(ColumnName<'input>, AggregateType)],
-    ) -> ReadAggregateResults<'_> {
+    ) -> Result<ReadAggregateResults<'_>, Error> {
+        self.validate_aggregate(&predicate, group_columns, aggregates)?;
+
         // Filter out any column names that we do not have data for.
         let schema = ResultSchema {
             group_columns: match group_columns {
@@ -171,20 +841,146 @@ impl Table {
         let row_groups = self.filter_row_groups(&predicate);
 
         // return the iterator to build the results.
-        ReadAggregateResults {
+        Ok(ReadAggregateResults {
             schema,
             predicate,
+            row_groups_pruned: self.row_groups.len() - row_groups.len(),
             row_groups,
             ..Default::default()
+        })
+    }
+
+    /// Computes a single aggregate, without grouping, of the values in the
+    /// column from rows satisfying the predicate, across all row groups in the
+    /// table. Returns `None` if no rows satisfy the predicate.
+    ///
+    /// Only `Count`, `Sum`, `Min` and `Max` aggregates are supported, and the
+    /// column must exist within the table.
+    pub fn aggregate_scalar(
+        &self,
+        predicate: &Predicate,
+        column_name: ColumnName<'_>,
+        agg_type: AggregateType,
+    ) -> Option<AggregateResult<'_>> {
+        let mut result: Option<AggregateResult<'_>> = None;
+        for rg in self.filter_row_groups(predicate) {
+            let rg_result = match rg.aggregate_column(predicate, column_name, agg_type) {
+                Some(rg_result) => rg_result,
+                None => continue, // no matching rows in row group
+            };
+
+            let acc = match &mut result {
+                Some(acc) => acc,
+                None => {
+                    result = Some(rg_result);
+                    continue;
+                }
+            };
+
+            // NULL values have no effect when updating an aggregate, so the
+            // result of an earlier row group with no non-null values is
+            // replaced rather than merged.
+            match rg_result {
+                AggregateResult::Count(_) => acc.merge(&rg_result),
+                AggregateResult::Sum(Scalar::Null) => {}
+                AggregateResult::Sum(v) => acc.update(Value::Scalar(v)),
+                AggregateResult::Min(v) | AggregateResult::Max(v) => acc.update(v),
+                AggregateResult::First(_) | AggregateResult::Last(_) => {
+                    unimplemented!("{} aggregate not yet supported", agg_type)
+                }
+            }
+        }
+        result
+    }
+
+    /// Validates that all of the columns referenced by a `read_aggregate`
+    /// call exist within the table, returning an error describing the first
+    /// missing column and the role it plays in the query.
+    pub fn validate_aggregate(
+        &self,
+        predicate: &Predicate,
+        group_columns: &ColumnSelection<'_>,
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+    ) -> Result<(), Error> {
+        if let ColumnSelection::Some(column_names) = group_columns {
+            for &name in column_names.iter() {
+                self.ensure_column_exists(name, ColumnRole::Group)?;
+                ensure!(
+                    matches!(self.meta.columns[name].typ, ColumnType::Tag(_)),
+                    crate::UnsupportedOperation {
+                        msg: format!("cannot group by non-tag column {}", name),
+                    }
+                );
+            }
+        }
+
+        for (name, _) in aggregates {
+            self.ensure_column_exists(name, ColumnRole::Aggregate)?;
+        }
+
+        for expr in predicate.iter() {
+            self.ensure_column_exists(expr.column(), ColumnRole::Predicate)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the names of the table's columns that would be read by a query
+    /// with the provided predicate, selected columns, group columns and
+    /// aggregates. The time column is always included if the table has one.
+    ///
+    /// `ColumnSelection::All` selects all of the table's columns, or all of
+    /// its tag columns when grouping. Referenced columns that don't exist in
+    /// the table are not included. The query is not executed.
+    pub fn accessed_columns(
+        &self,
+        predicate: &Predicate,
+        select_columns: &ColumnSelection<'_>,
+        group_columns: &ColumnSelection<'_>,
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+    ) -> BTreeSet<String> {
+        let mut column_names: Vec<&str> = vec![row_group::TIME_COLUMN_NAME];
+        column_names.extend(predicate.iter().map(|expr| expr.column()));
+        column_names.extend(aggregates.iter().map(|(name, _)| *name));
+
+        match select_columns {
+            ColumnSelection::All => {
+                column_names.extend(self.meta.columns.keys().map(|name| name.as_str()))
+            }
+            ColumnSelection::Some(names) => column_names.extend(names.iter()),
+        }
+
+        match group_columns {
+            ColumnSelection::All => column_names.extend(
+                self.meta
+                    .columns
+                    .iter()
+                    .filter(|(_, meta)| matches!(meta.typ, ColumnType::Tag(_)))
+                    .map(|(name, _)| name.as_str()),
+            ),
+            ColumnSelection::Some(names) => column_names.extend(names.iter()),
         }
+
+        column_names
+            .into_iter()
+            .filter(|name| self.meta.columns.contains_key(*name))
+            .map(|name| name.to_owned())
+            .collect()
+    }
+
+    fn ensure_column_exists(&self, column_name: &str, role: ColumnRole) -> Result<(), Error> {
+        ensure!(
+            self.meta.columns.contains_key(column_name),
+            crate::ColumnNotFound { column_name, role }
+        );
+        Ok(())
     }
 
     /// Returns aggregates segmented by grouping keys and windowed by time.
     ///
-    /// The set of data to be aggregated may be filtered by (currently only)
-    /// equality predicates, but can be ranged by time, which should be
-    /// represented as nanoseconds since the epoch. Results are included if they
-    /// satisfy the predicate and fall with the [min, max) time range domain.
+    /// The set of data to be aggregated may be filtered by conjunctive
+    /// predicates, including a time range, which should be represented as
+    /// nanoseconds since the epoch.
     ///
     /// Group keys are determined according to the provided group column names
     /// (`group_columns`). Currently only grouping by string (tag key) columns
@@ -196,80 +992,75 @@ impl Table {
     ///
     /// Results are grouped and windowed according to the `window` parameter,
     /// which represents an interval in nanoseconds. For example, to window
-    /// results by one minute, window should be set to 600_000_000_000.
-    pub fn aggregate_window<'a>(
+    /// results by one minute, window should be set to 60_000_000_000. Each
+    /// timestamp is assigned to the window starting at `window_start(t,
+    /// window)`, which is safe for negative timestamps and timestamps close to
+    /// the bounds of `i64`.
+    ///
+    /// The time column is appended to the group columns in the results and
+    /// holds the start of each window. Results are ordered by group key
+    /// ascending and then by window start ascending, so each series is a
+    /// contiguous run of rows in time order.
+    ///
+    /// Without predicates or group columns, `Count` and `Sum` aggregates are
+    /// computed with a single ordered pass over each row group.
+    pub fn aggregate_window(
         &self,
-        time_range: (i64, i64),
-        predicates: &[(&str, &str)],
-        group_columns: Vec<ColumnName<'a>>,
-        aggregates: Vec<(ColumnName<'a>, AggregateType)>,
+        predicate: &Predicate,
+        group_columns: &[ColumnName<'_>],
+        aggregates: &[(ColumnName<'_>, AggregateType)],
         window: i64,
-    ) -> BTreeMap<GroupKey<'_>, Vec<(ColumnName<'a>, AggregateResult<'_>)>> {
-        // identify segments where time range and predicates match could match
-        // using segment meta data, and then execute against those segments and
-        // merge results.
-        todo!()
+    ) -> ReadAggregateResult<'_> {
+        let mut group_and_time_columns = group_columns.to_vec();
+        group_and_time_columns.push(row_group::TIME_COLUMN_NAME);
+
+        let mut result = ReadAggregateResult {
+            schema: ResultSchema {
+                select_columns: vec![],
+                group_columns: self.meta.schema_for_column_names(&group_and_time_columns),
+                aggregate_columns: self.meta.schema_for_aggregate_column_names(aggregates),
+            },
+            ..ReadAggregateResult::default()
+        };
+
+        // Merging results retains the ordering of group keys, and the window
+        // start is the last column in each group key.
+        for rg in self.filter_row_groups(predicate) {
+            let rg_result = rg.read_window_aggregate(predicate, group_columns, aggregates, window);
+            result = result.merge(rg_result);
+        }
+        result
     }
 
-    // Perform aggregates without any grouping. Filtering on optional predicates
-    // and time range is still supported.
+    // Perform aggregates without any grouping. Rows are filtered by the
+    // predicate, which may include a time range, in the same way as for all
+    // other aggregates.
+    //
+    // `First` and `Last` aggregates are only supported without predicates.
     fn read_aggregate_no_group<'a>(
         &self,
-        time_range: (i64, i64),
-        predicates: &[(&str, &str)],
+        predicate: &Predicate,
         aggregates: Vec<(ColumnName<'a>, AggregateType)>,
     ) -> Vec<(ColumnName<'a>, AggregateResult<'_>)> {
-        // The fast path where there are no predicates or a time range to apply.
-        // We just want the equivalent of column statistics.
-        if predicates.is_empty() {
-            let mut results = Vec::with_capacity(aggregates.len());
-            for (col_name, agg_type) in &aggregates {
-                match agg_type {
-                    AggregateType::Count => {
-                        results.push((
-                            col_name,
-                            AggregateResult::Count(self.count(col_name, time_range)),
-                        ));
-                    }
-                    AggregateType::First => {
-                        results.push((
-                            col_name,
-                            AggregateResult::First(self.first(col_name, time_range.0)),
-                        ));
-                    }
-                    AggregateType::Last => {
-                        results.push((
-                            col_name,
-                            AggregateResult::Last(self.last(col_name, time_range.1)),
-                        ));
-                    }
-                    AggregateType::Min => {
-                        results.push((
-                            col_name,
-                            AggregateResult::Min(self.min(col_name, time_range)),
-                        ));
-                    }
-                    AggregateType::Max => {
-                        results.push((
-                            col_name,
-                            AggregateResult::Max(self.max(col_name, time_range)),
-                        ));
-                    }
-                    AggregateType::Sum => {
-                        let res = match self.sum(col_name, time_range) {
-                            Some(x) => x,
-                            None => Scalar::Null,
-                        };
-
-                        results.push((col_name, AggregateResult::Sum(res)));
-                    }
+        let mut results = Vec::with_capacity(aggregates.len());
+        for (col_name, agg_type) in aggregates {
+            let result = match agg_type {
+                // The fast path where there are no predicates to apply. We
+                // just want the first or last rows according to the time
+                // column.
+                AggregateType::First if predicate.is_empty() => {
+                    AggregateResult::First(self.first(col_name, i64::MIN))
                 }
-            }
+                AggregateType::Last if predicate.is_empty() => {
+                    AggregateResult::Last(self.last(col_name, i64::MAX))
+                }
+                _ => self
+                    .aggregate_scalar(predicate, col_name, agg_type)
+                    .unwrap_or_else(|| AggregateResult::from(&agg_type)),
+            };
+            results.push((col_name, result));
         }
-
-        // Otherwise we have predicates so for each segment we will execute a
-        // generalised aggregation method and build up the result set.
-        todo!();
+        results
     }
 
     //
@@ -282,40 +1073,48 @@ impl Table {
     // The first value is based on the values in time column that best satisfy
     // the provided time lower bound. The first value returned may be NULL. If
     // the time column has multiple values that are all the minimum, then the
-    // value returned from this method will be stable but from one of the
-    // corresponding minimum-timestamp rows.
+    // value is taken from the row with the smallest row id within the
+    // lowest-indexed row group containing the minimum timestamp.
     //
     // Note: this returns an option at the moment because there is an assumption
     // that timestamps could be NULL. I think we could add a constraint to make
     // timestamps non-null.
     fn first(&self, column_name: &str, time_lower_bound: i64) -> Option<(i64, Value<'_>)> {
-        // Find the segment(s) that best satisfy the lower time bound. These will
-        // be the segments (or more likely, segment) that has the lowest min
-        // time-range.
-        //
-        // The segment(s) will provide the timestamp value and row_id from its
-        // zone map. This row_id can then be used to efficiently lookup the
-        // first value for the specified column_name.
-        //
-        // Tied values (multiple equivalent min timestamps) results in an
-        // arbitrary value from the result set being returned.
-        todo!();
+        let mut first: Option<(i64, &RowGroup, u32)> = None;
+        for rg in &self.row_groups {
+            if let Some((time, row_id)) = rg.first_row(time_lower_bound) {
+                match first {
+                    // an earlier row group wins ties.
+                    Some((first_time, _, _)) if first_time <= time => {}
+                    _ => first = Some((time, rg, row_id)),
+                }
+            }
+        }
+
+        first.map(|(time, rg, row_id)| (time, rg.column_value(column_name, row_id)))
     }
 
-    /// The inverse of `first`. Of note here is that the returned value must
-    /// have a
+    // The inverse of `first`: returns the last value for the specified column
+    // across the table where the corresponding value in the time column is
+    // < `time_upper_bound`.
+    //
+    // Ties are broken in the same way as `first`: if the time column has
+    // multiple values that are all the maximum, then the value is taken from
+    // the row with the smallest row id within the lowest-indexed row group
+    // containing the maximum timestamp.
     fn last(&self, column_name: &str, time_upper_bound: i64) -> Option<(i64, Value<'_>)> {
-        // Find the segment(s) that best satisfy the upper time bound. These will
-        // be the segments (or more likely, segment) that has the highest max
-        // time-range.
-        //
-        // The segment(s) will provide the timestamp value and row_id from its
-        // zone map. This row_id can then be used to efficiently lookup the last
-        // value for the specified column_name.
-        //
-        // Tied values (multiple equivalent min timestamps) results in an
-        // arbitrary value from the result set being returned.
-        todo!();
+        let mut last: Option<(i64, &RowGroup, u32)> = None;
+        for rg in &self.row_groups {
+            if let Some((time, row_id)) = rg.last_row(time_upper_bound) {
+                match last {
+                    // an earlier row group wins ties.
+                    Some((last_time, _, _)) if last_time >= time => {}
+                    _ => last = Some((time, rg, row_id)),
+                }
+            }
+        }
+
+        last.map(|(time, rg, row_id)| (time, rg.column_value(column_name, row_id)))
     }
 
     /// The minimum non-null value in the column for the table.
@@ -377,27 +1176,132 @@ impl Table {
         todo!();
     }
 
+    /// Returns the distinct set of field keys (field column names) in the
+    /// table that have not already been found, where the table has rows
+    /// matching the provided predicate, which may include a time range.
+    ///
+    /// Columns are classified as fields using the table's column type meta
+    /// data. Execution short-circuits if all of the table's field columns
+    /// are present in `found_keys`.
+    pub fn field_keys(
+        &self,
+        predicate: &Predicate,
+        found_keys: &BTreeSet<String>,
+    ) -> BTreeSet<ColumnName<'_>> {
+        let candidates = self
+            .meta
+            .columns
+            .iter()
+            .filter(|(name, meta)| {
+                matches!(meta.typ, ColumnType::Field(_)) && !found_keys.contains(name.as_str())
+            })
+            .map(|(name, _)| name.as_str())
+            .collect::<BTreeSet<_>>();
+
+        if candidates.is_empty() {
+            return candidates;
+        }
+
+        // Row groups may not have all of the table's columns, so a field key
+        // is only present if a row group with the column has a row matching
+        // the predicate.
+        let predicate = Predicate::new(self.predicate_pushdown(predicate).applied);
+        let mut keys = BTreeSet::new();
+        for rg in self.filter_row_groups(&predicate) {
+            let rg_keys = candidates
+                .iter()
+                .filter(|&&name| !keys.contains(name) && rg.metadata().columns.contains_key(name))
+                .copied()
+                .collect::<Vec<_>>();
+            if !rg_keys.is_empty() && rg.satisfies_predicate(&predicate) {
+                keys.extend(rg_keys);
+                if keys.len() == candidates.len() {
+                    break;
+                }
+            }
+        }
+        keys
+    }
+
+    /// Determines if any row in the table satisfies the predicate, which may
+    /// include a time range. Expressions that can't be applied to the table
+    /// (see `predicate_pushdown`) are ignored.
+    ///
+    /// Unlike `could_pass_predicate`, rows are read from the row groups whose
+    /// meta data can't rule out a match.
+    pub fn satisfies_predicate(&self, predicate: &Predicate) -> bool {
+        let predicate = Predicate::new(self.predicate_pushdown(predicate).applied);
+        self.filter_row_groups(&predicate)
+            .iter()
+            .any(|rg| rg.satisfies_predicate(&predicate))
+    }
+
+    /// Returns the distinct non-null values of a field column at rows matching
+    /// the provided predicate, which may include a time range. Values are in
+    /// the column's native type and in ascending order, with any NaN values
+    /// last. NULL values are excluded.
+    ///
+    /// As with `field_keys`, expressions in the predicate that can't be
+    /// applied to the table are ignored.
+    pub fn field_distinct_values(
+        &self,
+        column_name: ColumnName<'_>,
+        predicate: &Predicate,
+    ) -> Result<Values<'_>, Error> {
+        let column_meta = self
+            .meta
+            .columns
+            .get(column_name)
+            .context(crate::ColumnNotFound {
+                column_name,
+                role: ColumnRole::Select,
+            })?;
+        ensure!(
+            matches!(column_meta.typ, ColumnType::Field(_)),
+            crate::UnsupportedOperation {
+                msg: format!("distinct values of non-field column {}", column_name),
+            }
+        );
+
+        let predicate = Predicate::new(self.predicate_pushdown(predicate).applied);
+        let values = self
+            .filter_row_groups(&predicate)
+            .into_iter()
+            .filter_map(|rg| rg.column_values(column_name, &predicate))
+            .collect::<Vec<_>>();
+        distinct_values(values, column_meta.logical_data_type)
+    }
+
     /// Returns the distinct set of tag values (column values) for each provided
     /// tag key, where each returned value lives in a row matching the provided
-    /// optional predicates and time range.
+    /// predicate, which may include a time range.
     ///
     /// As a special case, if `tag_keys` is empty then all distinct values for
-    /// all columns (tag keys) are returned for the chunk.
+    /// all tag columns (tag keys) are returned for the table.
+    ///
+    /// Row groups where all rows match the predicate are answered from the
+    /// column dictionaries without scanning any rows.
     pub fn tag_values<'a>(
-        &self,
-        time_range: (i64, i64),
-        predicates: &[(&str, &str)],
-        tag_keys: &[String],
-        found_tag_values: &BTreeMap<String, BTreeSet<&String>>,
-    ) -> BTreeMap<ColumnName<'a>, BTreeSet<&String>> {
-        // identify segments where time range, predicates and tag keys match
-        // could match using segment meta data, and then execute against those
-        // segments and merge results.
-        //
-        // For each segment push the tag values that have already been found for
-        // the tag key down in an attempt to reduce execution against columns
-        // that only have values that have already been found.
-        todo!();
+        &'a self,
+        predicate: &Predicate,
+        tag_keys: &[ColumnName<'_>],
+    ) -> BTreeMap<ColumnName<'a>, BTreeSet<&'a str>> {
+        let tag_keys = if tag_keys.is_empty() {
+            self.meta
+                .columns
+                .iter()
+                .filter(|(_, meta)| matches!(meta.typ, ColumnType::Tag(_)))
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+        } else {
+            tag_keys.to_vec()
+        };
+
+        let mut results = BTreeMap::new();
+        for rg in self.filter_row_groups(predicate) {
+            rg.tag_values(predicate, &tag_keys, &mut results);
+        }
+        results
     }
 }
 
@@ -409,9 +1313,12 @@ struct MetaData {
     // The total number of rows in the table.
     rows: u64,
 
-    // The distinct set of columns for this table (all of these columns will
-    // appear in all of the table's row groups) and meta data about those
+    // The distinct set of columns for this table and meta data about those
     // columns including their schema and range.
+    //
+    // Not all of these columns necessarily appear in all of the table's row
+    // groups, e.g., when older row groups pre-date a column being added. Such
+    // row groups are treated as containing only NULL values for the column.
     columns: BTreeMap<String, row_group::ColumnMeta>,
 
     column_names: Vec<String>,
@@ -467,9 +1374,10 @@ impl MetaData {
         names
             .iter()
             .filter_map(|(name, agg_type)| {
-                self.columns
-                    .get(*name)
-                    .map(|schema| (schema.typ.clone(), *agg_type, schema.logical_data_type))
+                self.columns.get(*name).map(|schema| {
+                    let data_type = row_group::aggregate_data_type(schema, *agg_type);
+                    (schema.typ.clone(), *agg_type, data_type)
+                })
             })
             .collect::<Vec<_>>()
     }
@@ -478,23 +1386,59 @@ impl MetaData {
         self.column_names.iter().map(|name| name.as_str()).collect()
     }
 
+    // Checks that the columns in both the table and the row group have the
+    // same schema, returning the name of the first column that doesn't.
+    fn validate<'a>(&self, meta: &'a row_group::MetaData) -> Result<(), &'a str> {
+        for (column_name, column_meta) in &meta.columns {
+            match self.columns.get(column_name) {
+                Some(curr_meta) if curr_meta != column_meta => return Err(column_name),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
     pub fn update(&mut self, meta: &row_group::MetaData) {
+        // Columns missing from the incoming row group are NULL for all of its
+        // rows.
+        for (column_name, curr_meta) in &mut self.columns {
+            if !meta.columns.contains_key(column_name) {
+                curr_meta.null_count += meta.rows as u64;
+            }
+        }
+
         // update size, rows, column ranges, time range
+        let prev_rows = self.rows;
         self.size += meta.size;
         self.rows += meta.rows as u64;
 
-        // The incoming row group must have exactly the same schema as the
-        // existing row groups in the table.
-        assert_eq!(&self.columns, &meta.columns);
-
-        // Update the table schema using the incoming row group schema
+        // Update the table schema using the incoming row group schema. The
+        // incoming row group may have columns that the table doesn't, and vice
+        // versa, but columns in both must have the same schema.
         for (column_name, column_meta) in &meta.columns {
+            let curr_meta = match self.columns.get_mut(column_name) {
+                Some(curr_meta) => curr_meta,
+                None => {
+                    // The column is NULL for all rows in existing row groups.
+                    let mut column_meta = column_meta.clone();
+                    column_meta.null_count += prev_rows;
+                    self.columns.insert(column_name.to_owned(), column_meta);
+                    self.column_names = self.columns.keys().cloned().collect();
+                    continue;
+                }
+            };
+
+            // The values of a column whose schema differs from the table's
+            // can't be combined with the table's values, so they are treated
+            // as NULL. Row groups should be checked with `validate` first.
+            if curr_meta != column_meta {
+                curr_meta.null_count += meta.rows as u64;
+                continue;
+            }
+            curr_meta.null_count += column_meta.null_count;
+
             let (column_range_min, column_range_max) = &column_meta.range;
-            let mut curr_range = &mut self
-                .columns
-                .get_mut(&column_name.to_string())
-                .unwrap()
-                .range;
+            let mut curr_range = &mut curr_meta.range;
             if column_range_min < &curr_range.0 {
                 curr_range.0 = column_range_min.clone();
             }
@@ -502,16 +1446,16 @@ impl MetaData {
             if column_range_max > &curr_range.1 {
                 curr_range.1 = column_range_max.clone();
             }
+        }
 
-            match self.time_range {
-                Some(time_range) => {
-                    self.time_range = Some((
-                        time_range.0.min(meta.time_range.0),
-                        time_range.1.max(meta.time_range.1),
-                    ));
-                }
-                None => panic!("cannot call `update` on empty Metadata"),
+        match self.time_range {
+            Some(time_range) => {
+                self.time_range = Some((
+                    time_range.0.min(meta.time_range.0),
+                    time_range.1.max(meta.time_range.1),
+                ));
             }
+            None => panic!("cannot call `update` on empty Metadata"),
         }
     }
 
@@ -523,6 +1467,27 @@ impl MetaData {
     }
 }
 
+/// Statistics about the values in a table column, suitable for cost-based
+/// query planning.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStatistics {
+    /// The total number of rows in the table.
+    pub rows: u64,
+
+    /// The number of NULL values in the column. Rows in row groups that don't
+    /// contain the column are NULL.
+    pub null_count: u64,
+
+    /// The exact number of distinct non-null values in the column.
+    pub distinct_count: u64,
+
+    /// The minimum non-null value in the column.
+    pub min: OwnedValue,
+
+    /// The maximum non-null value in the column.
+    pub max: OwnedValue,
+}
+
 /// A collection of columns to include in query results.
 ///
 /// The `All` variant denotes that the caller wishes to include all table
@@ -533,18 +1498,227 @@ pub enum ColumnSelection<'a> {
     Some(&'a [&'a str]),
 }
 
+/// An arithmetic operator that can be applied in a `Projection`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArithmeticOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+/// An operand of a `Projection`: either a numeric column or a literal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand<'a> {
+    Column(ColumnName<'a>),
+    Literal(Scalar),
+}
+
+/// A computed column, produced by applying an arithmetic operator to two
+/// operands, e.g., `usage / 100` or `a + b`.
+///
+/// If the operands have different types then both are treated as floats.
+/// Operations involving a NULL value, and division by zero, produce NULL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projection<'a> {
+    pub name: &'a str,
+    pub left: Operand<'a>,
+    pub op: ArithmeticOperator,
+    pub right: Operand<'a>,
+}
+
+impl<'a> Projection<'a> {
+    pub fn new(
+        name: &'a str,
+        left: Operand<'a>,
+        op: ArithmeticOperator,
+        right: Operand<'a>,
+    ) -> Self {
+        Self {
+            name,
+            left,
+            op,
+            right,
+        }
+    }
+
+    // The names of the columns referenced by the projection.
+    fn column_names(&self) -> impl Iterator<Item = ColumnName<'a>> + '_ {
+        vec![&self.left, &self.right]
+            .into_iter()
+            .filter_map(|operand| match operand {
+                Operand::Column(name) => Some(*name),
+                Operand::Literal(_) => None,
+            })
+    }
+
+    // Evaluates the projection against the columns of `rb`.
+    fn evaluate(&self, rb: &RecordBatch) -> Result<ArrayRef, Error> {
+        let left = Self::operand_array(rb, &self.left)?;
+        let right = Self::operand_array(rb, &self.right)?;
+
+        let data_type = match (left.data_type(), right.data_type()) {
+            (l, r) if l == r => l.clone(),
+            _ => DataType::Float64,
+        };
+        let left = cast(&left, &data_type).context(crate::ArrowError)?;
+        let right = cast(&right, &data_type).context(crate::ArrowError)?;
+
+        let arr: ArrayRef = match data_type {
+            DataType::Int64 => Arc::new(Int64Array::from(apply(
+                primitive_values::<Int64Type>(&left),
+                primitive_values::<Int64Type>(&right),
+                self.op,
+            ))),
+            DataType::UInt64 => Arc::new(UInt64Array::from(apply(
+                primitive_values::<UInt64Type>(&left),
+                primitive_values::<UInt64Type>(&right),
+                self.op,
+            ))),
+            DataType::Float64 => Arc::new(Float64Array::from(apply(
+                primitive_values::<Float64Type>(&left),
+                primitive_values::<Float64Type>(&right),
+                self.op,
+            ))),
+            data_type => {
+                return crate::UnsupportedOperation {
+                    msg: format!("cannot project columns of type {:?}", data_type),
+                }
+                .fail()
+            }
+        };
+        Ok(arr)
+    }
+
+    // Materialises an operand as an array with the same number of rows as `rb`.
+    fn operand_array(rb: &RecordBatch, operand: &Operand<'_>) -> Result<ArrayRef, Error> {
+        let rows = rb.num_rows();
+        let arr: ArrayRef = match operand {
+            Operand::Column(name) => {
+                let i = rb.schema().index_of(name).context(crate::ArrowError)?;
+                Arc::clone(rb.column(i))
+            }
+            Operand::Literal(Scalar::I64(v)) => Arc::new(Int64Array::from(vec![*v; rows])),
+            Operand::Literal(Scalar::U64(v)) => Arc::new(UInt64Array::from(vec![*v; rows])),
+            Operand::Literal(Scalar::F64(v)) => Arc::new(Float64Array::from(vec![*v; rows])),
+            Operand::Literal(Scalar::Null) => Arc::new(Float64Array::from(vec![None; rows])),
+        };
+        Ok(arr)
+    }
+}
+
+// The values of a numeric array, with NULL values as `None`.
+fn primitive_values<T: ArrowNumericType>(arr: &ArrayRef) -> Vec<Option<T::Native>> {
+    let arr = arr.as_any().downcast_ref::<PrimitiveArray<T>>().unwrap();
+    (0..arr.len())
+        .map(|i| match arr.is_null(i) {
+            true => None,
+            false => Some(arr.value(i)),
+        })
+        .collect()
+}
+
+// Applies `op` to each pair of values. Pairs involving NULL, and division by
+// zero, produce NULL.
+fn apply<T>(left: Vec<Option<T>>, right: Vec<Option<T>>, op: ArithmeticOperator) -> Vec<Option<T>>
+where
+    T: Copy
+        + Default
+        + PartialEq
+        + std::ops::Add<Output = T>
+        + std::ops::Sub<Output = T>
+        + std::ops::Mul<Output = T>
+        + std::ops::Div<Output = T>,
+{
+    left.into_iter()
+        .zip(right.into_iter())
+        .map(|(l, r)| match (l, r) {
+            (Some(l), Some(r)) => match op {
+                ArithmeticOperator::Add => Some(l + r),
+                ArithmeticOperator::Subtract => Some(l - r),
+                ArithmeticOperator::Multiply => Some(l * r),
+                ArithmeticOperator::Divide if r == T::default() => None,
+                ArithmeticOperator::Divide => Some(l / r),
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+// Merges materialised column values into the distinct non-null values, in
+// ascending order with any NaN values last. All values must be from columns
+// with the provided logical data type. The merged values of several calls can
+// themselves be merged.
+pub(crate) fn distinct_values(
+    values: Vec<Values<'_>>,
+    logical_data_type: LogicalDataType,
+) -> Result<Values<'_>, Error> {
+    let mut strings = BTreeSet::new();
+    let mut signed = BTreeSet::new();
+    let mut unsigned = BTreeSet::new();
+    let mut floats = BTreeSet::new();
+    let mut bools = BTreeSet::new();
+    for v in values {
+        match v {
+            Values::String(v) => strings.extend(v.into_iter().flatten()),
+            Values::I64(v) => signed.extend(v),
+            Values::I64N(v) => signed.extend(v.into_iter().flatten()),
+            Values::U64(v) => unsigned.extend(v),
+            Values::U64N(v) => unsigned.extend(v.into_iter().flatten()),
+            Values::F64(v) => floats.extend(v.into_iter().map(f64::to_bits)),
+            Values::F64N(v) => floats.extend(v.into_iter().flatten().map(f64::to_bits)),
+            Values::Bool(v) => bools.extend(v.into_iter().flatten()),
+            Values::ByteArray(_) => {}
+        }
+    }
+
+    Ok(match logical_data_type {
+        LogicalDataType::String => Values::String(strings.into_iter().map(Some).collect()),
+        LogicalDataType::Integer => Values::I64(signed.into_iter().collect()),
+        LogicalDataType::Unsigned => Values::U64(unsigned.into_iter().collect()),
+        LogicalDataType::Float => {
+            let mut floats = floats.into_iter().map(f64::from_bits).collect::<Vec<_>>();
+            floats.sort_by(|a, b| {
+                a.partial_cmp(b)
+                    .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+            });
+            Values::F64(floats)
+        }
+        LogicalDataType::Boolean => Values::Bool(bools.into_iter().map(Some).collect()),
+        LogicalDataType::Binary => {
+            return crate::UnsupportedOperation {
+                msg: "distinct values of binary column".to_owned(),
+            }
+            .fail()
+        }
+    })
+}
+
 /// Results of a `read_filter` execution on the table. Execution is lazy -
 /// row groups are only queried when `ReadFilterResults` is iterated.
 pub struct ReadFilterResults<'table> {
     // schema of all columns in the query results
     schema: ResultSchema,
 
-    // These row groups passed the predicates and need to be queried.
-    row_groups: Vec<&'table RowGroup>,
+    // These row groups passed the predicates and need to be queried. Each
+    // row group is paired with its position in the table.
+    row_groups: Vec<(usize, &'table RowGroup)>,
 
     // TODO(edd): encapsulate this into a single executor function that just
     // executes on the next row group.
     predicate: Predicate,
+
+    // The table's cache of materialised columns, if enabled.
+    decode_cache: Option<&'table Mutex<DecodeCache>>,
+
+    // The expressions of the query's predicate that were and were not applied.
+    pushdown: PredicatePushdown,
+
+    // The number of row groups that could not satisfy the predicate.
+    row_groups_pruned: usize,
+
+    // The total number of rows in the row groups read so far.
+    rows_scanned: u64,
 }
 
 impl<'table> ReadFilterResults<'table> {
@@ -557,6 +1731,56 @@ impl<'table> ReadFilterResults<'table> {
     pub fn schema(&self) -> &ResultSchema {
         &self.schema
     }
+
+    /// Describes which expressions of the query's predicate were applied to
+    /// the results, and which must be re-checked by the caller.
+    pub fn pushdown(&self) -> &PredicatePushdown {
+        &self.pushdown
+    }
+
+    /// The number of row groups in the table that were not read because they
+    /// could not satisfy the predicate.
+    pub fn row_groups_pruned(&self) -> usize {
+        self.row_groups_pruned
+    }
+
+    /// The total number of rows in the row groups that have been read so far.
+    pub fn rows_scanned(&self) -> u64 {
+        self.rows_scanned
+    }
+
+    /// Executes against the next row group with results and returns them as
+    /// a record batch.
+    ///
+    /// If the table has a decode cache then materialised columns are served
+    /// from the cache where possible.
+    pub fn next_record_batch(&mut self) -> Option<RecordBatch> {
+        let cache = match self.decode_cache {
+            Some(cache) => cache,
+            // it should not be possible for the conversion to record batch to
+            // fail here.
+            None => return self.next().map(|result| result.try_into().unwrap()),
+        };
+
+        if self.is_empty() {
+            return None;
+        }
+
+        let (index, row_group) = self.row_groups.remove(0);
+        self.rows_scanned += u64::from(row_group.rows());
+        let rb = row_group
+            .read_filter_with_cache(
+                &self.schema,
+                &self.predicate,
+                index,
+                &mut cache.lock().unwrap(),
+            )
+            .unwrap();
+        match rb {
+            Some(rb) => Some(rb),
+            None => self.next_record_batch(), // try next row group
+        }
+    }
 }
 
 impl<'a> Iterator for ReadFilterResults<'a> {
@@ -567,15 +1791,13 @@ impl<'a> Iterator for ReadFilterResults<'a> {
             return None;
         }
 
-        let row_group = self.row_groups.remove(0);
-        let result = row_group.read_filter(
-            &self
-                .schema()
-                .select_column_names_iter()
-                .map(|name| name.as_str())
-                .collect::<Vec<_>>(),
-            &self.predicate,
-        );
+        let (_, row_group) = self.row_groups.remove(0);
+        self.rows_scanned += u64::from(row_group.rows());
+        // row groups created before columns were added to the table don't
+        // have those columns, so results are materialised with the table's
+        // schema.
+        let row_ids = row_group.matching_rows(&self.predicate);
+        let result = row_group.read_filter_rows_with_schema(&self.schema, &row_ids);
         if result.is_empty() {
             return self.next(); // try next row group
         }
@@ -619,6 +1841,18 @@ pub struct ReadAggregateResults<'table> {
     // aggregates to produce are determined by the `schema`.
     row_groups: Vec<&'table RowGroup>,
 
+    // how NaN values in float columns are treated when aggregating.
+    nan_handling: NanHandling,
+
+    // when set, no further row groups are executed against.
+    cancellation: Option<Arc<AtomicBool>>,
+
+    // the number of row groups that could not satisfy the predicate.
+    row_groups_pruned: usize,
+
+    // the total number of rows in the row groups executed against so far.
+    rows_scanned: u64,
+
     drained: bool, // currently this iterator only yields once.
 }
 
@@ -628,6 +1862,38 @@ impl<'a> ReadAggregateResults<'a> {
     pub fn schema(&self) -> &ResultSchema {
         &self.schema
     }
+
+    /// Sets how NaN values in float columns are treated when aggregating. By
+    /// default NaN values are skipped.
+    pub fn with_nan_handling(mut self, nan_handling: NanHandling) -> Self {
+        self.nan_handling = nan_handling;
+        self
+    }
+
+    /// Stops executing against row groups once `token` is set, in which case
+    /// no results are returned.
+    pub fn with_cancellation(mut self, token: Arc<AtomicBool>) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// The number of row groups in the table that were not executed against
+    /// because they could not satisfy the predicate.
+    pub fn row_groups_pruned(&self) -> usize {
+        self.row_groups_pruned
+    }
+
+    /// The total number of rows in the row groups that have been executed
+    /// against so far.
+    pub fn rows_scanned(&self) -> u64 {
+        self.rows_scanned
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .map_or(false, |token| token.load(Ordering::Relaxed))
+    }
 }
 
 /// Implements an iterator on the Table's results for `read_aggregate`. This
@@ -644,11 +1910,13 @@ impl<'a> Iterator for ReadAggregateResults<'a> {
     type Item = row_group::ReadAggregateResult<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.row_groups.is_empty() || self.drained {
+        if self.row_groups.is_empty() || self.drained || self.is_cancelled() {
             return None;
         }
 
-        let mut merged_results = self.row_groups.get(0).unwrap().read_aggregate(
+        let first = self.row_groups[0];
+        self.rows_scanned += u64::from(first.rows());
+        let mut merged_results = first.read_aggregate_with_nan_handling(
             &self.predicate,
             &self
                 .schema
@@ -661,13 +1929,20 @@ impl<'a> Iterator for ReadAggregateResults<'a> {
                 .iter()
                 .map(|(name, agg_type, _)| (name.as_str(), *agg_type))
                 .collect::<Vec<_>>(),
+            self.nan_handling,
         );
         assert_eq!(merged_results.schema(), self.schema()); // validate schema
 
         // Execute against remaining row groups, merging each into the merged
         // set.
-        for row_group in self.row_groups.iter().skip(1) {
-            let result = row_group.read_aggregate(
+        for &row_group in self.row_groups.iter().skip(1) {
+            if self.is_cancelled() {
+                self.drained = true;
+                return None;
+            }
+            self.rows_scanned += u64::from(row_group.rows());
+
+            let result = row_group.read_aggregate_with_nan_handling(
                 &self.predicate,
                 &self
                     .schema
@@ -680,6 +1955,7 @@ impl<'a> Iterator for ReadAggregateResults<'a> {
                     .iter()
                     .map(|(name, agg_type, _)| (name.as_str(), *agg_type))
                     .collect::<Vec<_>>(),
+                self.nan_handling,
             );
 
             if result.is_empty() {
@@ -726,6 +2002,7 @@ mod test {
     use crate::row_group::{BinaryExpr, ColumnType, ReadAggregateResult};
     use crate::schema;
     use crate::schema::LogicalDataType;
+    use arrow_deps::arrow::array::{Array, Int64Array};
 
     #[test]
     fn meta_data_update() {
@@ -741,6 +2018,7 @@ mod test {
                         column::OwnedValue::String("north".to_owned()),
                         column::OwnedValue::String("south".to_owned()),
                     ),
+                    null_count: 0,
                 },
             )]
             .into_iter()
@@ -772,6 +2050,7 @@ mod test {
                         column::OwnedValue::String("east".to_owned()),
                         column::OwnedValue::String("north".to_owned()),
                     ),
+                    null_count: 0,
                 },
             )]
             .into_iter()
@@ -792,86 +2071,750 @@ mod test {
     }
 
     #[test]
-    fn select() {
-        // Build first segment.
+    fn predicate_on_column_absent_from_row_group() {
         let mut columns = BTreeMap::new();
-        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3, 4, 5, 6][..]));
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
         columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
 
-        let rc = ColumnType::Tag(Column::from(
-            &["west", "west", "east", "west", "south", "north"][..],
-        ));
+        // A newer row group with an additional "env" column.
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[4_i64, 5, 6][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "east", "east"][..]));
         columns.insert("region".to_string(), rc);
+        let ec = ColumnType::Tag(Column::from(&["prod", "dev", "prod"][..]));
+        columns.insert("env".to_string(), ec);
+        table.add_row_group(RowGroup::new(3, columns)).unwrap();
 
-        let fc = ColumnType::Field(Column::from(&[100_u64, 101, 200, 203, 203, 10][..]));
-        columns.insert("count".to_string(), fc);
+        assert_eq!(table.meta.all_column_names(), vec!["env", "region", "time"]);
 
-        let rg = RowGroup::new(6, columns);
+        let read = |predicate: Predicate| {
+            let results =
+                table.read_filter(&ColumnSelection::Some(&["time", "region"]), &predicate);
+            format!("{}", DisplayReadFilterResults(results.collect()))
+        };
 
-        let mut table = Table::new("cpu".to_owned(), rg);
-        let exp_col_types = vec![
-            ("region", LogicalDataType::String),
-            ("count", LogicalDataType::Unsigned),
-            ("time", LogicalDataType::Integer),
-        ]
-        .into_iter()
-        .collect::<BTreeMap<_, _>>();
-        assert_eq!(
-            table
-                .meta
-                .columns
-                .iter()
-                .map(|(k, v)| (k.as_str(), v.logical_data_type))
-                .collect::<BTreeMap<_, _>>(),
-            exp_col_types
-        );
+        // The first row group has no "env" column, so all of its rows are
+        // considered NULL and never match.
+        let predicate = Predicate::new(vec![BinaryExpr::from(("env", "=", "prod"))]);
+        assert_eq!(read(predicate), "time,region\n4,west\n6,east\n");
 
-        // Build another segment.
+        let predicate = Predicate::new(vec![BinaryExpr::from(("env", "!=", "prod"))]);
+        assert_eq!(read(predicate), "time,region\n5,east\n");
+
+        // Predicates on columns in both row groups are unaffected.
+        let predicate = Predicate::new(vec![BinaryExpr::from(("region", "=", "west"))]);
+        assert_eq!(read(predicate), "time,region\n1,west\n2,west\n4,west\n");
+
+        let predicate = Predicate::new(vec![BinaryExpr::from(("env", "=", "prod"))]);
+        assert!(!table.row_groups[0].satisfies_predicate(&predicate));
+        assert!(table.row_groups[1].satisfies_predicate(&predicate));
+    }
+
+    #[test]
+    fn read_filter_column_absent_from_row_group() {
         let mut columns = BTreeMap::new();
-        let tc = ColumnType::Time(Column::from(&[10_i64, 20, 30][..]));
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
         columns.insert("time".to_string(), tc);
-        let rc = ColumnType::Tag(Column::from(&["south", "north", "east"][..]));
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
         columns.insert("region".to_string(), rc);
-        let fc = ColumnType::Field(Column::from(&[1000_u64, 1002, 1200][..]));
-        columns.insert("count".to_string(), fc);
-        let segment = RowGroup::new(3, columns);
-        table.add_row_group(segment);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
 
-        // Get all the results
-        let predicate = Predicate::with_time_range(&[], 1, 31);
-        let results = table.read_filter(
-            &ColumnSelection::Some(&["time", "count", "region"]),
-            &predicate,
-        );
+        // A newer row group with an additional "env" column.
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[4_i64, 5, 6][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "east", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let ec = ColumnType::Tag(Column::from(&["prod", "dev", "prod"][..]));
+        columns.insert("env".to_string(), ec);
+        table.add_row_group(RowGroup::new(3, columns)).unwrap();
 
-        // check the column types
-        let exp_schema = ResultSchema {
-            select_columns: vec![
-                (
-                    schema::ColumnType::Timestamp("time".to_owned()),
-                    LogicalDataType::Integer,
-                ),
-                (
-                    schema::ColumnType::Field("count".to_owned()),
-                    LogicalDataType::Unsigned,
-                ),
-                (
-                    schema::ColumnType::Tag("region".to_owned()),
-                    LogicalDataType::String,
-                ),
-            ],
-            ..ResultSchema::default()
+        let read = |table: &Table, columns: &ColumnSelection<'_>| {
+            let results = table.read_filter(columns, &Predicate::default());
+            format!("{}", DisplayReadFilterResults(results.collect()))
         };
 
-        assert_eq!(results.schema(), &exp_schema);
+        // The first row group has no "env" column, so its rows have NULL
+        // values for it.
+        assert_eq!(
+            read(&table, &ColumnSelection::All),
+            "env,region,time
+NULL,west,1
+NULL,west,2
+NULL,east,3
+prod,west,4
+dev,east,5
+prod,east,6
+"
+        );
+        assert_eq!(
+            read(&table, &ColumnSelection::Some(&["env"])),
+            "env\nNULL\nNULL\nNULL\nprod\ndev\nprod\n"
+        );
 
-        let mut all = vec![];
-        for result in results {
-            assert_eq!(result.schema(), &exp_schema);
-            all.push(result);
+        let result = table
+            .select_row_group(0, &ColumnSelection::All, &Predicate::default())
+            .unwrap();
+        assert_eq!(
+            format!("{}", DisplayReadFilterResults(vec![result])),
+            "env,region,time\nNULL,west,1\nNULL,west,2\nNULL,east,3\n"
+        );
+
+        // Results read via the decode cache are NULL filled too, whether or
+        // not all rows satisfy the predicate.
+        table.enable_decode_cache(1 << 20);
+        for predicate in vec![
+            Predicate::default(),
+            Predicate::new(vec![BinaryExpr::from(("region", "=", "west"))]),
+        ] {
+            let mut results = table.read_filter(&ColumnSelection::All, &predicate);
+            let rb = results.next_record_batch().unwrap();
+            let env = rb.column(rb.schema().index_of("env").unwrap());
+            assert_eq!(env.null_count(), rb.num_rows());
+
+            let rb = results.next_record_batch().unwrap();
+            let env = rb.column(rb.schema().index_of("env").unwrap());
+            assert_eq!(env.null_count(), 0);
+            assert!(results.next_record_batch().is_none());
         }
+    }
 
-        assert_eq!(
+    #[test]
+    fn add_row_group_with_conflicting_column_type() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        // "region" is now a field rather than a tag.
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[4_i64, 5, 6][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Field(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("region".to_string(), rc);
+        let row_group = RowGroup::new(3, columns);
+
+        assert!(matches!(
+            table.add_row_group(row_group),
+            Err(Error::InvalidTableData { .. })
+        ));
+        assert_eq!(table.rows(), 3);
+        assert_eq!(table.row_groups.len(), 1);
+        assert_eq!(table.meta.all_column_names(), vec!["region", "time"]);
+    }
+
+    #[test]
+    fn select_projections() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let fc = ColumnType::Field(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("a".to_string(), fc);
+        let fc = ColumnType::Field(Column::from(&[10_i64, 20, 0][..]));
+        columns.insert("b".to_string(), fc);
+        let table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        let projections = vec![
+            Projection::new(
+                "a_plus_b",
+                Operand::Column("a"),
+                ArithmeticOperator::Add,
+                Operand::Column("b"),
+            ),
+            Projection::new(
+                "a_div_b",
+                Operand::Column("a"),
+                ArithmeticOperator::Divide,
+                Operand::Column("b"),
+            ),
+            Projection::new(
+                "b_pct",
+                Operand::Column("b"),
+                ArithmeticOperator::Divide,
+                Operand::Literal(Scalar::F64(100.0)),
+            ),
+        ];
+        let results = table
+            .select(
+                &ColumnSelection::Some(&["time"]),
+                &projections,
+                &Predicate::default(),
+            )
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        let rb = &results[0];
+        let names = rb
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["time", "a_plus_b", "a_div_b", "b_pct"]);
+
+        let a_plus_b = rb.column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(a_plus_b.value(0), 11);
+        assert_eq!(a_plus_b.value(1), 22);
+        assert_eq!(a_plus_b.value(2), 3);
+
+        // integer division by zero is NULL.
+        let a_div_b = rb.column(2).as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(a_div_b.value(0), 0);
+        assert!(a_div_b.is_null(2));
+
+        let b_pct = rb
+            .column(3)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(b_pct.value(0), 0.1);
+        assert_eq!(b_pct.value(1), 0.2);
+        assert_eq!(b_pct.value(2), 0.0);
+
+        // projections can only reference numeric columns that exist.
+        let projections = vec![Projection::new(
+            "bad",
+            Operand::Column("c"),
+            ArithmeticOperator::Add,
+            Operand::Literal(Scalar::I64(1)),
+        )];
+        let result = table.select(&ColumnSelection::All, &projections, &Predicate::default());
+        assert!(matches!(
+            result,
+            Err(Error::ColumnNotFound {
+                role: ColumnRole::Projection,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn select_aliased() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let fc = ColumnType::Field(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("a".to_string(), fc);
+        let table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        let results = table
+            .select_aliased(
+                &[("time", "raw_time"), ("time", "time_copy"), ("a", "a")],
+                &[],
+                &Predicate::default(),
+            )
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        let rb = &results[0];
+        let names = rb
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["raw_time", "time_copy", "a"]);
+        for i in 0..2 {
+            let times = rb.column(i).as_any().downcast_ref::<Int64Array>().unwrap();
+            assert_eq!(
+                (0..3).map(|j| times.value(j)).collect::<Vec<_>>(),
+                vec![1, 2, 3]
+            );
+        }
+
+        // output names must be unique.
+        let result = table.select_aliased(&[("time", "t"), ("a", "t")], &[], &Predicate::default());
+        assert!(matches!(result, Err(Error::UnsupportedOperation { .. })));
+
+        let result = table.select_aliased(&[("b", "b")], &[], &Predicate::default());
+        assert!(matches!(
+            result,
+            Err(Error::ColumnNotFound {
+                role: ColumnRole::Select,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn select_into() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let fc = ColumnType::Field(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("a".to_string(), fc);
+        let table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        // results are appended to the existing contents.
+        let mut dst = table
+            .select(&ColumnSelection::Some(&["a"]), &[], &Predicate::default())
+            .unwrap();
+        table
+            .select_into(
+                &ColumnSelection::Some(&["time"]),
+                &[],
+                &Predicate::default(),
+                &mut dst,
+            )
+            .unwrap();
+        assert_eq!(dst.len(), 2);
+        assert_eq!(dst[0].schema().field(0).name(), "a");
+        assert_eq!(dst[1].schema().field(0).name(), "time");
+
+        // a cleared vector can be reused without reallocating.
+        let capacity = dst.capacity();
+        dst.clear();
+        table
+            .select_into(&ColumnSelection::All, &[], &Predicate::default(), &mut dst)
+            .unwrap();
+        assert_eq!(dst.len(), 1);
+        assert_eq!(dst.capacity(), capacity);
+
+        // dst is unchanged on error.
+        let result = table.select_into(
+            &ColumnSelection::Some(&["b"]),
+            &[],
+            &Predicate::default(),
+            &mut dst,
+        );
+        assert!(result.is_err());
+        assert_eq!(dst.len(), 1);
+    }
+
+    #[test]
+    fn first_last_tie_break() {
+        let row_group = |times: &[i64], values: &[&str]| {
+            let mut columns = BTreeMap::new();
+            let tc = ColumnType::Time(Column::from(times));
+            columns.insert("time".to_string(), tc);
+            let rc = ColumnType::Tag(Column::from(values));
+            columns.insert("region".to_string(), rc);
+            RowGroup::new(times.len() as u32, columns)
+        };
+
+        // rows with the minimum and maximum timestamps are duplicated within
+        // and across row groups.
+        let rg_a = || row_group(&[5, 1, 9, 1, 9], &["a0", "a1", "a2", "a3", "a4"]);
+        let rg_b = || row_group(&[1, 9, 1], &["b0", "b1", "b2"]);
+
+        let mut table = Table::new("cpu".to_owned(), rg_a());
+        table.add_row_group(rg_b()).unwrap();
+
+        for _ in 0..3 {
+            assert_eq!(
+                table.first("region", i64::MIN),
+                Some((1, Value::String("a1")))
+            );
+            assert_eq!(
+                table.last("region", i64::MAX),
+                Some((9, Value::String("a2")))
+            );
+        }
+
+        // the bounds exclude the tied rows.
+        assert_eq!(table.first("region", 2), Some((5, Value::String("a0"))));
+        assert_eq!(table.last("region", 9), Some((5, Value::String("a0"))));
+
+        // the lowest-indexed row group wins ties, regardless of which row
+        // group was inserted first.
+        let mut table = Table::new("cpu".to_owned(), rg_b());
+        table.add_row_group(rg_a()).unwrap();
+        assert_eq!(
+            table.first("region", i64::MIN),
+            Some((1, Value::String("b0")))
+        );
+        assert_eq!(
+            table.last("region", i64::MAX),
+            Some((9, Value::String("b1")))
+        );
+
+        assert_eq!(table.first("region", 10), None);
+    }
+
+    #[test]
+    fn column_statistics() {
+        let regions = vec![
+            Some("west"),
+            None,
+            Some("east"),
+            Some("north"),
+            Some("west"),
+        ];
+        let counters = vec![Some(1.5), Some(2.5), Some(1.5), None, None];
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&regions[..3]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[1.5, 2.5, 1.5][..]));
+        columns.insert("counter".to_string(), fc);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        // The second row group has no "counter" column.
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[4_i64, 5][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&regions[3..]));
+        columns.insert("region".to_string(), rc);
+        table.add_row_group(RowGroup::new(2, columns)).unwrap();
+
+        let distinct = regions.iter().flatten().collect::<BTreeSet<_>>();
+        assert_eq!(
+            table.column_statistics("region").unwrap(),
+            ColumnStatistics {
+                rows: regions.len() as u64,
+                null_count: regions.iter().filter(|v| v.is_none()).count() as u64,
+                distinct_count: distinct.len() as u64,
+                min: OwnedValue::String(distinct.iter().next().unwrap().to_string()),
+                max: OwnedValue::String(distinct.iter().last().unwrap().to_string()),
+            }
+        );
+
+        let non_null = counters.iter().flatten().copied().collect::<Vec<f64>>();
+        let mut distinct = non_null.clone();
+        distinct.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        distinct.dedup();
+        assert_eq!(
+            table.column_statistics("counter").unwrap(),
+            ColumnStatistics {
+                rows: counters.len() as u64,
+                null_count: (counters.len() - non_null.len()) as u64,
+                distinct_count: distinct.len() as u64,
+                min: OwnedValue::Scalar(Scalar::F64(distinct[0])),
+                max: OwnedValue::Scalar(Scalar::F64(distinct[distinct.len() - 1])),
+            }
+        );
+
+        assert!(table.column_statistics("host").is_none());
+
+        // Cached distinct counts are invalidated by new row groups.
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[6_i64][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["south"][..]));
+        columns.insert("region".to_string(), rc);
+        table.add_row_group(RowGroup::new(1, columns)).unwrap();
+
+        let stats = table.column_statistics("region").unwrap();
+        assert_eq!(stats.distinct_count, 4);
+        assert_eq!(stats.rows, 6);
+        assert_eq!(stats.null_count, 1);
+
+        // Binary columns have statistics too.
+        let payloads = vec![Some(&b"a"[..]), None, Some(&b"b"[..]), Some(&b"a"[..])];
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3, 4][..]));
+        columns.insert("time".to_string(), tc);
+        let arr = arrow_deps::arrow::array::BinaryArray::from(payloads);
+        columns.insert("payload".to_string(), ColumnType::Field(Column::from(&arr)));
+        let table = Table::new("blobs".to_owned(), RowGroup::new(4, columns));
+
+        let stats = table.column_statistics("payload").unwrap();
+        assert_eq!(stats.distinct_count, 2);
+        assert_eq!(stats.rows, 4);
+        assert_eq!(stats.null_count, 1);
+    }
+
+    #[test]
+    fn column_encodings() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        // The second row group delta encodes its time column and has an
+        // additional column.
+        let mut columns = BTreeMap::new();
+        let times = crate::column::delta::Delta::try_from_values(&[4, 5]).unwrap();
+        columns.insert("time".to_string(), ColumnType::Time(Column::from(times)));
+        let rc = ColumnType::Tag(Column::from(&["north", "south"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[1.5, 2.5][..]));
+        columns.insert("counter".to_string(), fc);
+        table.add_row_group(RowGroup::new(2, columns)).unwrap();
+
+        let encodings = table.column_encodings();
+        assert_eq!(
+            encodings.keys().collect::<Vec<_>>(),
+            vec!["counter", "region", "time"]
+        );
+        assert_eq!(
+            encodings["counter"],
+            vec![EncodingKind::Plain].into_iter().collect()
+        );
+        assert_eq!(
+            encodings["region"],
+            vec![EncodingKind::RLE].into_iter().collect()
+        );
+        assert_eq!(
+            encodings["time"],
+            vec![EncodingKind::Plain, EncodingKind::Delta]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn aggregate_window() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 12, 2, 25, 11][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "east", "east", "west", "west"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[1_i64, 2, 3, 4, 5][..]));
+        columns.insert("counter".to_string(), fc);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(5, columns));
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[21_i64, 3, 22][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["east", "west", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[6_i64, 7, 8][..]));
+        columns.insert("counter".to_string(), fc);
+        table.add_row_group(RowGroup::new(3, columns)).unwrap();
+
+        // Two series over three windows are ordered by series and then by
+        // window start.
+        let result = table.aggregate_window(
+            &Predicate::default(),
+            &["region"],
+            &[("counter", AggregateType::Sum)],
+            10,
+        );
+        assert_eq!(
+            format!("{}", DisplayReadAggregateResults(vec![result.clone()])),
+            "region,time,counter_sum
+east,0,3
+east,10,2
+east,20,14
+west,0,8
+west,10,5
+west,20,4
+"
+        );
+
+        let rb: RecordBatch = result.try_into().unwrap();
+        let time = rb.column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+        let time = (0..time.len()).map(|i| time.value(i)).collect::<Vec<_>>();
+        assert_eq!(time, vec![0, 10, 20, 0, 10, 20]);
+
+        // Windows without any rows satisfying the predicate are omitted.
+        let result = table.aggregate_window(
+            &Predicate::new(vec![BinaryExpr::from(("counter", ">", 4_i64))]),
+            &["region"],
+            &[("counter", AggregateType::Count)],
+            10,
+        );
+        assert_eq!(
+            format!("{}", DisplayReadAggregateResults(vec![result])),
+            "region,time,counter_count
+east,20,2
+west,0,1
+west,10,1
+"
+        );
+
+        // The first and last values are those at the earliest and latest times
+        // within each window, across row groups.
+        let result = table.aggregate_window(
+            &Predicate::default(),
+            &["region"],
+            &[
+                ("counter", AggregateType::First),
+                ("counter", AggregateType::Last),
+            ],
+            10,
+        );
+        assert_eq!(
+            format!("{}", DisplayReadAggregateResults(vec![result])),
+            "region,time,counter_first,counter_last
+east,0,3,3
+east,10,2,2
+east,20,6,8
+west,0,1,7
+west,10,5,5
+west,20,4,4
+"
+        );
+
+        // Tag predicates are supported as they are for other aggregates.
+        let result = table.aggregate_window(
+            &Predicate::new(vec![BinaryExpr::from(("region", "=", "east"))]),
+            &[],
+            &[("counter", AggregateType::Sum)],
+            10,
+        );
+        assert_eq!(
+            format!("{}", DisplayReadAggregateResults(vec![result])),
+            "time,counter_sum
+0,3
+10,2
+20,14
+"
+        );
+
+        let results = table.read_aggregate_no_group(
+            &Predicate::new(vec![BinaryExpr::from(("region", "=", "east"))]),
+            vec![
+                ("counter", AggregateType::Sum),
+                ("counter", AggregateType::Count),
+                ("counter", AggregateType::Max),
+            ],
+        );
+        assert_eq!(
+            results,
+            vec![
+                ("counter", AggregateResult::Sum(Scalar::I64(19))),
+                ("counter", AggregateResult::Count(4)),
+                ("counter", AggregateResult::Max(Value::from(8_i64))),
+            ]
+        );
+
+        // no rows satisfy the predicate.
+        let results = table.read_aggregate_no_group(
+            &Predicate::new(vec![BinaryExpr::from(("region", "=", "north"))]),
+            vec![("counter", AggregateType::Sum)],
+        );
+        assert_eq!(
+            results,
+            vec![("counter", AggregateResult::Sum(Scalar::Null))]
+        );
+    }
+
+    #[test]
+    fn aggregate_window_first_last_ties() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[5_i64, 5, 1][..]));
+        columns.insert("time".to_string(), tc);
+        let fc = ColumnType::Field(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("counter".to_string(), fc);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[5_i64, 1][..]));
+        columns.insert("time".to_string(), tc);
+        let fc = ColumnType::Field(Column::from(&[4_i64, 5][..]));
+        columns.insert("counter".to_string(), fc);
+        table.add_row_group(RowGroup::new(2, columns)).unwrap();
+
+        // ties are broken in the same way as for the table's first and last
+        // values: the smallest row id in the earliest row group wins.
+        let result = table.aggregate_window(
+            &Predicate::default(),
+            &[],
+            &[
+                ("counter", AggregateType::First),
+                ("counter", AggregateType::Last),
+            ],
+            10,
+        );
+        assert_eq!(
+            format!("{}", DisplayReadAggregateResults(vec![result])),
+            "time,counter_first,counter_last
+0,3,1
+"
+        );
+        assert_eq!(
+            table.first("counter", i64::MIN),
+            Some((1, Value::from(3_i64)))
+        );
+        assert_eq!(
+            table.last("counter", i64::MAX),
+            Some((5, Value::from(1_i64)))
+        );
+    }
+
+    #[test]
+    fn select() {
+        // Build first segment.
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3, 4, 5, 6][..]));
+        columns.insert("time".to_string(), tc);
+
+        let rc = ColumnType::Tag(Column::from(
+            &["west", "west", "east", "west", "south", "north"][..],
+        ));
+        columns.insert("region".to_string(), rc);
+
+        let fc = ColumnType::Field(Column::from(&[100_u64, 101, 200, 203, 203, 10][..]));
+        columns.insert("count".to_string(), fc);
+
+        let rg = RowGroup::new(6, columns);
+
+        let mut table = Table::new("cpu".to_owned(), rg);
+        let exp_col_types = vec![
+            ("region", LogicalDataType::String),
+            ("count", LogicalDataType::Unsigned),
+            ("time", LogicalDataType::Integer),
+        ]
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+        assert_eq!(
+            table
+                .meta
+                .columns
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.logical_data_type))
+                .collect::<BTreeMap<_, _>>(),
+            exp_col_types
+        );
+
+        // Build another segment.
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[10_i64, 20, 30][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["south", "north", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[1000_u64, 1002, 1200][..]));
+        columns.insert("count".to_string(), fc);
+        let segment = RowGroup::new(3, columns);
+        table.add_row_group(segment).unwrap();
+
+        // Get all the results
+        let predicate = Predicate::with_time_range(&[], 1, 31);
+        let results = table.read_filter(
+            &ColumnSelection::Some(&["time", "count", "region"]),
+            &predicate,
+        );
+
+        // check the column types
+        let exp_schema = ResultSchema {
+            select_columns: vec![
+                (
+                    schema::ColumnType::Timestamp("time".to_owned()),
+                    LogicalDataType::Integer,
+                ),
+                (
+                    schema::ColumnType::Field("count".to_owned()),
+                    LogicalDataType::Unsigned,
+                ),
+                (
+                    schema::ColumnType::Tag("region".to_owned()),
+                    LogicalDataType::String,
+                ),
+            ],
+            ..ResultSchema::default()
+        };
+
+        assert_eq!(results.schema(), &exp_schema);
+
+        let mut all = vec![];
+        for result in results {
+            assert_eq!(result.schema(), &exp_schema);
+            all.push(result);
+        }
+
+        assert_eq!(
             format!("{}", DisplayReadFilterResults(all)),
             "time,count,region
 1,100,west
@@ -910,6 +2853,558 @@ mod test {
         );
     }
 
+    #[test]
+    fn prune_cache() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[10_i64, 20][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["north", "south"][..]));
+        columns.insert("region".to_string(), rc);
+        table.add_row_group(RowGroup::new(2, columns)).unwrap();
+
+        assert!(table.prune_cache_stats().is_none());
+        table.enable_prune_cache(10);
+
+        let predicate = Predicate::new(vec![BinaryExpr::from(("region", "=", "east"))]);
+        let select = |table: &Table| {
+            let mut results = table.read_filter(&ColumnSelection::Some(&["time"]), &predicate);
+            let mut rows = 0;
+            while let Some(rb) = results.next_record_batch() {
+                rows += rb.num_rows();
+            }
+            rows
+        };
+
+        assert_eq!(select(&table), 1);
+        let stats = table.prune_cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (0, 1, 1));
+
+        // An identical query reuses the pruned set of row groups.
+        assert_eq!(select(&table), 1);
+        let stats = table.prune_cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+
+        // Case-insensitive expressions are cached separately.
+        let predicate = Predicate::new(vec![
+            BinaryExpr::from(("region", "=", "EAST")).with_case_insensitive()
+        ]);
+        let mut results = table.read_filter(&ColumnSelection::Some(&["time"]), &predicate);
+        assert_eq!(results.next_record_batch().unwrap().num_rows(), 1);
+        assert_eq!(table.prune_cache_stats().unwrap().entries, 2);
+
+        // Changing the table's row groups invalidates the cache.
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[30_i64][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["east"][..]));
+        columns.insert("region".to_string(), rc);
+        table.add_row_group(RowGroup::new(1, columns)).unwrap();
+        assert_eq!(table.prune_cache_stats().unwrap().entries, 0);
+        assert_eq!(select(&table), 2);
+    }
+
+    #[test]
+    fn select_decode_cache() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3, 4][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east", "north"][..]));
+        columns.insert("region".to_string(), rc);
+        let rg = RowGroup::new(4, columns);
+
+        let mut table = Table::new("cpu".to_owned(), rg);
+        assert!(table.decode_cache_stats().is_none());
+        table.enable_decode_cache(1 << 20);
+
+        let predicate = Predicate::with_time_range(&[], 1, 10);
+        let select = |table: &Table| {
+            let mut results =
+                table.read_filter(&ColumnSelection::Some(&["time", "region"]), &predicate);
+            let mut batches = vec![];
+            while let Some(rb) = results.next_record_batch() {
+                batches.push(rb);
+            }
+            batches
+        };
+
+        let first = select(&table);
+        let stats = table.decode_cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (0, 2, 2));
+
+        // An identical select is served from the cache.
+        let second = select(&table);
+        let stats = table.decode_cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (2, 2, 2));
+        assert!(std::sync::Arc::ptr_eq(
+            first[0].column(1),
+            second[0].column(1)
+        ));
+        assert_eq!(second[0].num_rows(), 4);
+
+        // Predicates not matching all rows bypass the cache.
+        let predicate = Predicate::with_time_range(&[], 1, 3);
+        let mut results = table.read_filter(&ColumnSelection::Some(&["time"]), &predicate);
+        assert_eq!(results.next_record_batch().unwrap().num_rows(), 2);
+        assert_eq!(table.decode_cache_stats().unwrap().hits, 2);
+
+        // Dropping a row group invalidates its cached columns.
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[5_i64, 6][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["south", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        table.add_row_group(RowGroup::new(2, columns)).unwrap();
+        table.drop_segment(0).unwrap();
+        assert_eq!(table.decode_cache_stats().unwrap().entries, 0);
+        assert_eq!(select(&table)[0].num_rows(), 2);
+
+        // The table's only row group can't be dropped.
+        assert!(matches!(
+            table.drop_segment(0),
+            Err(Error::UnsupportedOperation { .. })
+        ));
+        assert_eq!(select(&table)[0].num_rows(), 2);
+    }
+
+    #[test]
+    fn read_filter_pushdown() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[100_u64, 101, 200][..]));
+        columns.insert("count".to_string(), fc);
+        let table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        // A string literal can't be compared with the numeric "count" column,
+        // so that expression is not applied.
+        let region = BinaryExpr::from(("region", "=", "west"));
+        let count = BinaryExpr::from(("count", "=", "100"));
+        let predicate = Predicate::with_time_range(&[region.clone(), count.clone()], 1, 3);
+
+        let results = table.read_filter(&ColumnSelection::Some(&["time", "count"]), &predicate);
+        let pushdown = results.pushdown().clone();
+        assert!(!pushdown.is_complete());
+        assert_eq!(pushdown.residual, vec![count]);
+        assert_eq!(pushdown.applied.len(), 3);
+        assert!(pushdown.applied.contains(&region));
+
+        assert_eq!(
+            format!("{}", DisplayReadFilterResults(results.collect())),
+            "time,count
+1,100
+2,101
+",
+        );
+
+        // All expressions are applied.
+        let predicate = Predicate::new(vec![BinaryExpr::from(("count", ">", 100_u64))]);
+        let results = table.read_filter(&ColumnSelection::Some(&["count"]), &predicate);
+        assert!(results.pushdown().is_complete());
+        assert_eq!(
+            format!("{}", DisplayReadFilterResults(results.collect())),
+            "count
+101
+200
+",
+        );
+    }
+
+    #[test]
+    fn read_filter_no_matching_columns() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[4_i64, 5][..]));
+        columns.insert("time".to_string(), tc);
+        table.add_row_group(RowGroup::new(2, columns)).unwrap();
+
+        let mut results = table.read_filter(
+            &ColumnSelection::Some(&["host", "temp"]),
+            &Predicate::default(),
+        );
+        assert!(results.is_empty());
+        assert!(results.schema().select_columns.is_empty());
+        assert_eq!(results.row_groups_pruned(), 2);
+        assert!(results.next().is_none());
+        assert_eq!(results.rows_scanned(), 0);
+
+        // Columns that don't exist are ignored when others do.
+        let results = table.read_filter(
+            &ColumnSelection::Some(&["host", "time"]),
+            &Predicate::default(),
+        );
+        assert_eq!(
+            format!("{}", DisplayReadFilterResults(results.collect())),
+            "time
+1
+2
+3
+4
+5
+",
+        );
+    }
+
+    #[test]
+    fn field_keys() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[100_u64, 101, 200][..]));
+        columns.insert("count".to_string(), fc);
+        let fc = ColumnType::Field(Column::from(&[1.2, 3.3, 45.0][..]));
+        columns.insert("temp".to_string(), fc);
+        let table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        let exp = vec!["count", "temp"].into_iter().collect::<BTreeSet<_>>();
+        assert_eq!(
+            table.field_keys(&Predicate::default(), &BTreeSet::new()),
+            exp
+        );
+
+        let predicate =
+            Predicate::with_time_range(&[BinaryExpr::from(("region", "=", "east"))], 1, 10);
+        assert_eq!(table.field_keys(&predicate, &BTreeSet::new()), exp);
+
+        // Already found keys are not returned.
+        let found = vec!["count".to_owned()].into_iter().collect();
+        let exp = vec!["temp"].into_iter().collect::<BTreeSet<_>>();
+        assert_eq!(table.field_keys(&Predicate::default(), &found), exp);
+
+        // No rows match the predicate.
+        let predicate = Predicate::with_time_range(&[], 10, 20);
+        assert!(table.field_keys(&predicate, &BTreeSet::new()).is_empty());
+        let predicate = Predicate::new(vec![BinaryExpr::from(("region", "=", "north"))]);
+        assert!(table.field_keys(&predicate, &BTreeSet::new()).is_empty());
+
+        // Field keys are only present in the row groups with the column.
+        let mut table = table;
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[4_i64][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["north"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[0.5][..]));
+        columns.insert("load".to_string(), fc);
+        table.add_row_group(RowGroup::new(1, columns)).unwrap();
+
+        let exp = vec!["load"].into_iter().collect::<BTreeSet<_>>();
+        assert_eq!(table.field_keys(&predicate, &BTreeSet::new()), exp);
+        let predicate = Predicate::new(vec![BinaryExpr::from(("region", "=", "east"))]);
+        let exp = vec!["count", "temp"].into_iter().collect::<BTreeSet<_>>();
+        assert_eq!(table.field_keys(&predicate, &BTreeSet::new()), exp);
+    }
+
+    #[test]
+    fn field_distinct_values() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3, 4][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[Some(30_i64), None, Some(10), Some(30)][..]));
+        columns.insert("count".to_string(), fc);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(4, columns));
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[10_i64, 20][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "north"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[Some(-5_i64), Some(10)][..]));
+        columns.insert("count".to_string(), fc);
+        table.add_row_group(RowGroup::new(2, columns)).unwrap();
+
+        // NULL values are excluded.
+        let values = table
+            .field_distinct_values("count", &Predicate::default())
+            .unwrap();
+        assert_eq!(values, Values::I64(vec![-5, 10, 30]));
+
+        let predicate =
+            Predicate::with_time_range(&[BinaryExpr::from(("region", "=", "west"))], 1, 15);
+        let values = table.field_distinct_values("count", &predicate).unwrap();
+        assert_eq!(values, Values::I64(vec![-5, 30]));
+
+        // No rows match the predicate.
+        let predicate = Predicate::with_time_range(&[], 100, 200);
+        let values = table.field_distinct_values("count", &predicate).unwrap();
+        assert_eq!(values, Values::I64(vec![]));
+
+        assert!(matches!(
+            table.field_distinct_values("region", &Predicate::default()),
+            Err(Error::UnsupportedOperation { .. })
+        ));
+        assert!(matches!(
+            table.field_distinct_values("temp", &Predicate::default()),
+            Err(Error::ColumnNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn select_row_group() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[10_i64, 20][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["south", "north"][..]));
+        columns.insert("region".to_string(), rc);
+        table.add_row_group(RowGroup::new(2, columns)).unwrap();
+
+        let result = table
+            .select_row_group(
+                1,
+                &ColumnSelection::Some(&["time", "region"]),
+                &Predicate::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            format!("{}", DisplayReadFilterResults(vec![result])),
+            "time,region
+10,south
+20,north
+",
+        );
+
+        let result = table
+            .select_row_group(
+                1,
+                &ColumnSelection::All,
+                &Predicate::new(vec![BinaryExpr::from(("region", "=", "north"))]),
+            )
+            .unwrap();
+        assert_eq!(
+            format!("{}", DisplayReadFilterResults(vec![result])),
+            "region,time
+north,20
+",
+        );
+
+        match table.select_row_group(2, &ColumnSelection::All, &Predicate::default()) {
+            Err(Error::RowGroupNotFound { index, row_groups }) => {
+                assert_eq!((index, row_groups), (2, 2));
+            }
+            _ => panic!("expected out of range error"),
+        }
+    }
+
+    #[test]
+    fn read_aggregate_missing_columns() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[100_u64, 101, 200][..]));
+        columns.insert("count".to_string(), fc);
+        let table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        // missing group column
+        let result = table.read_aggregate(
+            Predicate::default(),
+            &ColumnSelection::Some(&["region", "host"]),
+            &[("count", AggregateType::Sum)],
+        );
+        match result {
+            Err(Error::ColumnNotFound { column_name, role }) => {
+                assert_eq!(column_name, "host");
+                assert_eq!(role, ColumnRole::Group);
+            }
+            _ => panic!("expected a missing group column error"),
+        }
+
+        // missing aggregate column
+        let result = table.read_aggregate(
+            Predicate::default(),
+            &ColumnSelection::Some(&["region"]),
+            &[("count", AggregateType::Sum), ("temp", AggregateType::Min)],
+        );
+        match result {
+            Err(Error::ColumnNotFound { column_name, role }) => {
+                assert_eq!(column_name, "temp");
+                assert_eq!(role, ColumnRole::Aggregate);
+            }
+            _ => panic!("expected a missing aggregate column error"),
+        }
+
+        // missing predicate column
+        let result = table.read_aggregate(
+            Predicate::new(vec![BinaryExpr::from(("env", "=", "prod"))]),
+            &ColumnSelection::Some(&["region"]),
+            &[("count", AggregateType::Sum)],
+        );
+        match result {
+            Err(e @ Error::ColumnNotFound { .. }) => {
+                assert_eq!(e.to_string(), "predicate column does not exist: env");
+            }
+            _ => panic!("expected a missing predicate column error"),
+        }
+
+        // all columns exist
+        assert!(table
+            .read_aggregate(
+                Predicate::new(vec![BinaryExpr::from(("region", "=", "west"))]),
+                &ColumnSelection::Some(&["region"]),
+                &[("count", AggregateType::Sum)],
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn reclassify_column() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[100_u64, 101, 200][..]));
+        columns.insert("count".to_string(), fc);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[4_i64][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["north"][..]));
+        columns.insert("region".to_string(), rc);
+        table.add_row_group(RowGroup::new(1, columns)).unwrap();
+
+        table
+            .reclassify_column(schema::ColumnType::Field("region".to_owned()))
+            .unwrap();
+        assert_eq!(
+            table.field_keys(&Predicate::default(), &BTreeSet::new()),
+            vec!["count", "region"].into_iter().collect::<BTreeSet<_>>()
+        );
+        for rg in table.iter() {
+            assert_eq!(
+                rg.metadata().columns["region"].typ,
+                schema::ColumnType::Field("region".to_owned())
+            );
+        }
+
+        // grouping on the column is rejected now that it is a field.
+        let result = table.read_aggregate(
+            Predicate::default(),
+            &ColumnSelection::Some(&["region"]),
+            &[("count", AggregateType::Sum)],
+        );
+        assert!(matches!(result, Err(Error::UnsupportedOperation { .. })));
+
+        // the time column can't be reclassified, and non-string columns can't
+        // become tags.
+        assert!(matches!(
+            table.reclassify_column(schema::ColumnType::Field("time".to_owned())),
+            Err(Error::UnsupportedOperation { .. })
+        ));
+        assert!(matches!(
+            table.reclassify_column(schema::ColumnType::Tag("count".to_owned())),
+            Err(Error::UnsupportedOperation { .. })
+        ));
+        assert!(matches!(
+            table.reclassify_column(schema::ColumnType::Tag("temp".to_owned())),
+            Err(Error::ColumnNotFound { .. })
+        ));
+
+        // the column can be reclassified back into a tag.
+        table
+            .reclassify_column(schema::ColumnType::Tag("region".to_owned()))
+            .unwrap();
+        assert!(table
+            .read_aggregate(
+                Predicate::default(),
+                &ColumnSelection::Some(&["region"]),
+                &[("count", AggregateType::Sum)],
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn read_aggregate_no_group_columns() {
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["west", "west", "east"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[100_u64, 101, 200][..]));
+        columns.insert("count".to_string(), fc);
+        let mut table = Table::new("cpu".to_owned(), RowGroup::new(3, columns));
+
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[4_i64, 5][..]));
+        columns.insert("time".to_string(), tc);
+        let rc = ColumnType::Tag(Column::from(&["east", "north"][..]));
+        columns.insert("region".to_string(), rc);
+        let fc = ColumnType::Field(Column::from(&[300_u64, 2][..]));
+        columns.insert("count".to_string(), fc);
+        table.add_row_group(RowGroup::new(2, columns)).unwrap();
+
+        let aggregates = [
+            ("count", AggregateType::Count),
+            ("count", AggregateType::Sum),
+        ];
+
+        // A single row aggregates all rows in the table.
+        let results = table
+            .read_aggregate(
+                Predicate::default(),
+                &ColumnSelection::Some(&[]),
+                &aggregates,
+            )
+            .unwrap();
+        assert_eq!(
+            format!("{}", DisplayReadAggregateResults(results.collect())),
+            "count_count,count_sum
+5,703
+"
+        );
+
+        let results = table
+            .read_aggregate(
+                Predicate::new(vec![BinaryExpr::from(("region", "!=", "west"))]),
+                &ColumnSelection::Some(&[]),
+                &aggregates,
+            )
+            .unwrap();
+        assert_eq!(
+            format!("{}", DisplayReadAggregateResults(results.collect())),
+            "count_count,count_sum
+3,502
+"
+        );
+
+        // No rows satisfy the predicate.
+        let results = table
+            .read_aggregate(
+                Predicate::new(vec![BinaryExpr::from(("region", "=", "south"))]),
+                &ColumnSelection::Some(&[]),
+                &aggregates,
+            )
+            .unwrap();
+        assert!(results.collect::<Vec<_>>().iter().all(|r| r.is_empty()));
+    }
+
     #[test]
     fn read_group_result() {
         let mut result_a = ReadAggregateResult {
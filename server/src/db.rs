@@ -61,6 +61,9 @@ pub enum Error {
 
     #[snafu(display("Error dropping data from read buffer: {}", source))]
     ReadBufferDrop { source: read_buffer::Error },
+
+    #[snafu(display("Error writing to read buffer: {}", source))]
+    ReadBufferWrite { source: read_buffer::Error },
 }
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -225,7 +228,9 @@ impl Db {
                 // until all reads to the read buffer to complete and
                 // then will block all reads while the insert is occuring
                 let mut read_buffer = self.read_buffer.write().expect("mutex poisoned");
-                read_buffer.upsert_partition(partition_key, mb_chunk.id(), &stats.name, batch)
+                read_buffer
+                    .upsert_partition(partition_key, mb_chunk.id(), &stats.name, batch)
+                    .context(ReadBufferWrite)?;
             }
         }
 